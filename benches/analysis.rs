@@ -0,0 +1,71 @@
+//! Throughput of diff parsing and of each analyzer over synthetic diffs.
+//!
+//! `cargo bench` runs a small (100 files) and a large (10k files, 1M diff
+//! lines) case; filter with e.g. `cargo bench -- large`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pr_analyzer::analysis;
+use pr_analyzer::config::Config;
+use pr_analyzer::pr::diff::parse_diff;
+use pr_analyzer::pr::PullRequest;
+
+/// (label, files, lines per file)
+const SIZES: &[(&str, usize, usize)] = &[("small", 100, 100), ("large", 10_000, 100)];
+
+/// A diff of `files` Rust files with `lines` hunk lines each: half added,
+/// a quarter removed, a quarter context, with a few lines that trip rules.
+fn synthetic_diff(files: usize, lines: usize) -> String {
+    let mut diff = String::new();
+    for f in 0..files {
+        let path = format!("src/module_{}/file_{}.rs", f % 50, f);
+        diff.push_str(&format!("diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n", path));
+        diff.push_str(&format!("@@ -1,{} +1,{} @@\n", lines / 2, lines * 3 / 4));
+        for i in 0..lines {
+            let line = match i % 20 {
+                0 => "+    let q = format!(\"SELECT * FROM users WHERE id = {}\", id);".to_string(),
+                1 => "+    let value = map.get(&key).unwrap().clone();".to_string(),
+                2 => "+        if ready { for item in items { if item.ok() { process(item); } } }".to_string(),
+                n if n % 4 == 3 => format!("-    let old_{} = compute({});", i, i),
+                n if n % 4 == 1 => format!("     let kept_{} = {};", i, i),
+                _ => format!("+    let value_{} = transform(input_{});", i, i),
+            };
+            diff.push_str(&line);
+            diff.push('\n');
+        }
+    }
+    diff
+}
+
+fn pull_request(raw: &str) -> PullRequest {
+    let files = parse_diff(raw).expect("synthetic diff parses");
+    PullRequest::builder("Synthetic").number(1).author("bench").files(files).build().expect("synthetic diff has unique paths")
+}
+
+fn bench_parse_diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_diff");
+    group.sample_size(10);
+    for &(label, files, lines) in SIZES {
+        let raw = synthetic_diff(files, lines);
+        group.throughput(Throughput::Elements((files * lines) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(label), &raw, |b, raw| b.iter(|| parse_diff(raw)));
+    }
+    group.finish();
+}
+
+fn bench_analyzers(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let config = Config::default();
+    for &(label, files, lines) in SIZES {
+        let pr = pull_request(&synthetic_diff(files, lines));
+        let mut group = c.benchmark_group(format!("analyze/{}", label));
+        group.sample_size(10);
+        group.throughput(Throughput::Elements((files * lines) as u64));
+        for analyzer in analysis::analyzers(&config) {
+            group.bench_function(analyzer.name().to_string(), |b| b.iter(|| runtime.block_on(analyzer.analyze(&pr))));
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse_diff, bench_analyzers);
+criterion_main!(benches);
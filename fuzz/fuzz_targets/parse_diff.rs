@@ -0,0 +1,26 @@
+//! Arbitrary bytes into the diff parser: it must neither panic nor report
+//! counts that disagree with the hunks it kept.
+//!
+//!     cargo +nightly fuzz run parse_diff
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pr_analyzer::pr::diff::{parse_diff_bytes, strip_signature, MAX_LINE_NUMBER};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(files) = parse_diff_bytes(data) else {
+        return;
+    };
+    for file in &files {
+        let lines = file.hunks().iter().flat_map(|h| &h.lines);
+        assert_eq!(lines.clone().filter(|l| l.starts_with('+')).count(), file.additions());
+        assert_eq!(lines.filter(|l| l.starts_with('-')).count(), file.deletions());
+        for hunk in file.hunks() {
+            assert!(hunk.old_start.max(hunk.old_count).max(hunk.new_start).max(hunk.new_count) <= MAX_LINE_NUMBER);
+        }
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = strip_signature(text);
+    }
+});
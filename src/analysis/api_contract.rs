@@ -0,0 +1,351 @@
+use async_trait::async_trait;
+
+use super::{Analyzer, AnalysisError, RuleSpec};
+use crate::paths::file_name;
+use crate::pr::types::DiffFile;
+use crate::pr::PullRequest;
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel};
+
+const HTTP_METHODS: &[&str] = &["get", "put", "post", "delete", "patch", "head", "options", "trace"];
+
+/// API Contract Analyzer
+///
+/// Classifies changes to OpenAPI/Swagger and GraphQL schema files:
+/// - Breaking (HIGH): removed paths, operations, types, fields, or enum values;
+///   changed field types; new non-null input fields
+/// - Additive (LOW): new paths, operations, types, or fields
+#[derive(Default)]
+pub struct ApiContractAnalyzer;
+
+/// A schema element seen on an added or removed line.
+struct Element {
+    key: String,
+    detail: String,
+    line: usize,
+}
+
+impl ApiContractAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Classify changes in an OpenAPI/Swagger document.
+    fn check_openapi(&self, file: &DiffFile) -> Vec<Finding> {
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for hunk in file.hunks() {
+            let mut current_path = String::new();
+            let mut current_field = String::new();
+            for (i, line) in hunk.lines.iter().enumerate() {
+                let (prefix, content) = line.split_at(line.len().min(1));
+                let element = if let Some(path) = openapi_path(content) {
+                    current_path = path.to_string();
+                    Some((format!("path {}", path), String::new()))
+                } else if let Some(ty) = yaml_type(content) {
+                    Some((format!("type of '{}' under {}", current_field, current_path), ty.to_string()))
+                } else if let Some((indent, key)) = yaml_key(content) {
+                    if HTTP_METHODS.contains(&key) && !current_path.is_empty() {
+                        Some((format!("operation {} {}", key.to_uppercase(), current_path), String::new()))
+                    } else if indent > 4 && !is_openapi_keyword(key) {
+                        current_field = key.to_string();
+                        Some((format!("field '{}' under {}", key, current_path), String::new()))
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
+                if let Some((key, detail)) = element {
+                    let element = Element { key, detail, line: hunk.new_start + i };
+                    match prefix {
+                        "-" => removed.push(element),
+                        "+" => added.push(element),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        classify(file, removed, added, |_| false)
+    }
+
+    /// Classify changes in a GraphQL schema.
+    fn check_graphql(&self, file: &DiffFile) -> Vec<Finding> {
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        let mut input_types = Vec::new();
+        for hunk in file.hunks() {
+            let mut current_type = String::new();
+            let mut current_kind = String::new();
+            for (i, line) in hunk.lines.iter().enumerate() {
+                let (prefix, content) = line.split_at(line.len().min(1));
+                let element = if let Some((kind, name)) = graphql_type(content) {
+                    current_kind = kind.to_string();
+                    current_type = name.to_string();
+                    if current_kind == "input" {
+                        input_types.push(current_type.clone());
+                    }
+                    Some((format!("{} {}", current_kind, current_type), String::new()))
+                } else if content.trim() == "}" {
+                    current_type.clear();
+                    None
+                } else if let Some((name, ty)) = graphql_field(content) {
+                    Some((format!("field {}.{}", current_type, name), ty.to_string()))
+                } else if current_kind == "enum" && !current_type.is_empty() {
+                    let value = content.trim();
+                    (!value.is_empty() && value.chars().all(|c| c.is_alphanumeric() || c == '_'))
+                        .then(|| (format!("enum value {}.{}", current_type, value), String::new()))
+                } else {
+                    None
+                };
+                if let Some((key, detail)) = element {
+                    let element = Element { key, detail, line: hunk.new_start + i };
+                    match prefix {
+                        "-" => removed.push(element),
+                        "+" => added.push(element),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        // A new non-null field on an input type breaks every client that omits it.
+        classify(file, removed, added, |element| {
+            element.detail.ends_with('!')
+                && input_types.iter().any(|t| element.key.starts_with(&format!("field {}.", t)))
+        })
+    }
+}
+
+/// Pair removed and added elements by key: removed-only is breaking, re-added with a
+/// different detail is a changed type, added-only is additive unless `breaking_addition` says otherwise.
+fn classify(file: &DiffFile, removed: Vec<Element>, added: Vec<Element>, breaking_addition: impl Fn(&Element) -> bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for old in &removed {
+        match added.iter().find(|new| new.key == old.key) {
+            None => findings.push(Finding::new("api_contract.removed", RiskLevel::High).param("item", &old.key).in_file(file.path()).at_line(old.line)),
+            Some(new) if new.detail != old.detail => findings.push(
+                Finding::new("api_contract.changed", RiskLevel::High)
+                    .param("item", &new.key)
+                    .param("from", &old.detail)
+                    .param("to", &new.detail)
+                    .in_file(file.path())
+                    .at_line(new.line),
+            ),
+            Some(_) => {}
+        }
+    }
+    for new in added.iter().filter(|new| !removed.iter().any(|old| old.key == new.key)) {
+        let finding = if breaking_addition(new) {
+            Finding::new("api_contract.added_required", RiskLevel::High)
+        } else {
+            Finding::new("api_contract.added", RiskLevel::Low)
+        };
+        findings.push(finding.param("item", &new.key).in_file(file.path()).at_line(new.line));
+    }
+    findings
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// A path key: `  /users/{id}:` in YAML (two-space indent under `paths:`) or `"/users": {` in JSON.
+fn openapi_path(content: &str) -> Option<&str> {
+    if let Some(key) = content.strip_prefix("  /").and_then(|rest| rest.trim_end().strip_suffix(':')) {
+        return (!key.contains(char::is_whitespace)).then(|| &content[2..3 + key.len()]);
+    }
+    let rest = content.trim().strip_prefix("\"/")?;
+    let (key, tail) = rest.split_once('"')?;
+    let tail = tail.trim_start().strip_prefix(':')?.trim();
+    (tail.is_empty() || tail == "{").then(|| &content.trim()[1..2 + key.len()])
+}
+
+/// A bare mapping key such as `      email:`, returned with its indentation width.
+fn yaml_key(content: &str) -> Option<(usize, &str)> {
+    let indent = content.len() - content.trim_start().len();
+    let key = content.trim().strip_suffix(':')?;
+    (indent > 0 && is_identifier(key)).then_some((indent, key))
+}
+
+/// The value of an indented `type: <name>` line.
+fn yaml_type(content: &str) -> Option<&str> {
+    let ty = content.trim().strip_prefix("type:")?.trim();
+    (content.starts_with(char::is_whitespace) && is_identifier(ty)).then_some(ty)
+}
+
+/// A GraphQL definition header such as `type User {` or `extend input Filter`.
+fn graphql_type(content: &str) -> Option<(&str, &str)> {
+    let mut tokens = content.split_whitespace();
+    let mut kind = tokens.next()?;
+    if kind == "extend" {
+        kind = tokens.next()?;
+    }
+    if !matches!(kind, "type" | "input" | "interface" | "enum") {
+        return None;
+    }
+    let name = tokens.next()?.trim_end_matches('{');
+    is_identifier(name).then_some((kind, name))
+}
+
+/// An indented GraphQL field such as `  user(id: ID!): User!`.
+fn graphql_field(content: &str) -> Option<(&str, &str)> {
+    if !content.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let trimmed = content.trim();
+    let name_end = trimmed.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    let name = &trimmed[..name_end];
+    let mut rest = trimmed[name_end..].trim_start();
+    if rest.starts_with('(') {
+        rest = &rest[rest.find(')')? + 1..];
+    }
+    let ty = rest.trim_start().strip_prefix(':')?.split_whitespace().next()?;
+    (!name.is_empty() && ty.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '!' | '[' | ']'))).then_some((name, ty))
+}
+
+/// Structural OpenAPI keys that are not user-defined fields.
+fn is_openapi_keyword(key: &str) -> bool {
+    matches!(
+        key,
+        "properties" | "items" | "schema" | "schemas" | "content" | "responses" | "parameters" | "requestBody"
+            | "components" | "allOf" | "oneOf" | "anyOf" | "headers" | "examples" | "example" | "required"
+            | "application/json" | "security" | "tags"
+    ) || key.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_openapi_file(path: &str) -> bool {
+    let name = file_name(path).to_lowercase();
+    let is_document = name.ends_with(".yaml") || name.ends_with(".yml") || name.ends_with(".json");
+    is_document && (name.starts_with("openapi") || name.starts_with("swagger"))
+}
+
+fn is_graphql_file(path: &str) -> bool {
+    path.ends_with(".graphql") || path.ends_with(".graphqls") || path.ends_with(".gql")
+}
+
+#[async_trait]
+impl Analyzer for ApiContractAnalyzer {
+    fn name(&self) -> &str {
+        "API Contract Assessment"
+    }
+
+    fn rules_version(&self) -> u32 {
+        1
+    }
+
+    fn rules(&self) -> Vec<RuleSpec> {
+        vec![RuleSpec::file(
+            "API001", "api_contract.breaking_changes", Category::Compatibility,
+            &["api_contract.removed", "api_contract.changed", "api_contract.added_required", "api_contract.added", "api_contract.schema_deleted"],
+        )]
+    }
+
+    async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError> {
+        let mut findings = Vec::new();
+        for file in pr.files() {
+            if file.is_deleted() && (is_openapi_file(file.path()) || is_graphql_file(file.path())) {
+                findings.push(Finding::new("api_contract.schema_deleted", RiskLevel::High).in_file(file.path()));
+            } else if is_openapi_file(file.path()) {
+                findings.extend(self.check_openapi(file));
+            } else if is_graphql_file(file.path()) {
+                findings.extend(self.check_graphql(file));
+            }
+        }
+
+        let risk_level = if findings.iter().any(|f| f.severity == RiskLevel::High) {
+            RiskLevel::High
+        } else if findings.iter().any(|f| f.severity == RiskLevel::Medium) {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        };
+
+        Ok(AnalysisResult {
+            analyzer_name: self.name().to_string(),
+            risk_level,
+            findings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+
+    fn lines(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_empty_pr_returns_low_api_risk() {
+        let result = ApiContractAnalyzer::new().analyze(&test_pull_request()).await.unwrap();
+        assert_eq!(result.risk_level, RiskLevel::Low);
+        assert!(result.findings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_removed_openapi_path_is_breaking() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "api/openapi.yaml",
+            lines(&[" paths:", "-  /users/{id}:", "-    get:", "+  /accounts:", "+    post:"]),
+        )]);
+        let result = ApiContractAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().any(|f| f.message == "Breaking API change: removed path /users/{id}"));
+        assert!(result.findings.iter().any(|f| f.message == "Additive API change: added path /accounts"));
+        assert_eq!(result.risk_level, RiskLevel::High);
+    }
+
+    #[tokio::test]
+    async fn test_changed_openapi_type_is_breaking() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "openapi.yml",
+            lines(&["   /users:", "             id:", "-              type: string", "+              type: integer"]),
+        )]);
+        let result = ApiContractAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().any(|f| f.message.contains("changed from string to integer")));
+    }
+
+    #[tokio::test]
+    async fn test_graphql_field_changes() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "schema/user.graphql",
+            lines(&[
+                " type User {",
+                "-  email: String",
+                "+  nickname: String",
+                "-  age: Int",
+                "+  age: String",
+                " }",
+                " input CreateUser {",
+                "+  tenant: ID!",
+                " }",
+            ]),
+        )]);
+        let result = ApiContractAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().any(|f| f.message == "Breaking API change: removed field User.email"));
+        assert!(result.findings.iter().any(|f| f.message == "Breaking API change: field User.age changed from Int to String"));
+        assert!(result.findings.iter().any(|f| f.message == "Additive API change: added field User.nickname" && f.severity == RiskLevel::Low));
+        assert!(result.findings.iter().any(|f| f.message == "Breaking API change: added required field CreateUser.tenant"));
+    }
+
+    #[tokio::test]
+    async fn test_additive_only_graphql_is_low() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("api.gql", lines(&["+type Query {", "+  health: Boolean", "+}"]))]);
+        let result = ApiContractAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(!result.findings.is_empty());
+        assert_eq!(result.risk_level, RiskLevel::Low);
+    }
+
+    #[test]
+    fn test_schema_file_detection() {
+        assert!(is_openapi_file("docs/openapi.yaml"));
+        assert!(is_openapi_file("swagger.json"));
+        assert!(!is_openapi_file("config.yaml"));
+        assert!(is_graphql_file("schema.graphql"));
+        assert!(!is_graphql_file("schema.rs"));
+    }
+}
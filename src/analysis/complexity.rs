@@ -1,8 +1,48 @@
 use async_trait::async_trait;
 
-use super::{Analyzer, AnalysisError};
+use super::scan::{FileVisitor, Line, LineFindings, LineHits, LinePass, LineRules};
+use super::syntax::Syntax;
+use super::{added_lines, is_lockfile, Analyzer, AnalysisError, RuleSpec};
+use crate::config::Profile;
+use crate::paths::file_name;
+use crate::pr::types::DiffFile;
 use crate::pr::PullRequest;
-use crate::report::types::{AnalysisResult, Finding, RiskLevel};
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel};
+
+/// Report section name; the runner also files orchestration notes here.
+pub const NAME: &str = "Complexity Assessment";
+
+/// Files longer than this (whole file, not diff) are flagged when a checkout is available.
+const LONG_FILE_LINES: usize = 1000;
+
+/// Inline base64 assets above this decoded size belong in a static file.
+const INLINE_ASSET_LIMIT_KB: usize = 10;
+
+/// Extensions of data files that usually belong in fixtures storage, a
+/// release asset or a download step rather than in source.
+const DATA_FILE_EXTENSIONS: &[&str] = &["csv", "tsv", "json", "jsonl", "ndjson", "xml", "parquet", "avro", "sqlite", "db"];
+
+/// npm packages with a large bundle footprint, and the usual lighter alternative.
+const HEAVY_NPM_PACKAGES: &[(&str, &str)] = &[
+    ("moment", "date-fns or dayjs"),
+    ("lodash", "lodash-es or per-method imports"),
+    ("jquery", "native DOM APIs"),
+    ("aws-sdk", "modular @aws-sdk/client-* packages"),
+    ("core-js", "targeted polyfills"),
+    ("rxjs", "native promises or a smaller event library"),
+    ("antd", "per-component imports"),
+    ("@mui/material", "per-component imports"),
+    ("three", "a lighter rendering library if 3D is incidental"),
+    ("chart.js", "a lighter charting library"),
+];
+
+/// Renames at least this similar (in percent) to the old file are moves
+/// with a small edit: their edited lines count, but the file does not count
+/// toward the number of files changed.
+const MOVED_FILE_SIMILARITY: u8 = 90;
+
+/// Declarations that add public API surface; also the line rules' keywords.
+const PUB_ITEMS: &[&str] = &["pub fn ", "pub struct ", "pub enum ", "pub trait ", "pub type "];
 
 /// Complexity Risk Analyzer
 ///
@@ -12,24 +52,72 @@ use crate::report::types::{AnalysisResult, Finding, RiskLevel};
 /// - Number of files changed
 /// - New public API surface (exported types, functions)
 /// - Nesting depth increases
-pub struct ComplexityAnalyzer;
+/// - Frontend weight: large inline data URIs and heavyweight npm packages
+/// - Whole-file length (only with a local checkout)
+/// - Large added files and data files committed into source
+///
+/// Size, dependency, API-surface and nesting limits come from the active profile.
+pub struct ComplexityAnalyzer {
+    thresholds: Profile,
+}
+
+impl Default for ComplexityAnalyzer {
+    fn default() -> Self {
+        Self::with_config(&Profile::default())
+    }
+}
 
 impl ComplexityAnalyzer {
+    #[cfg(test)]
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn with_config(thresholds: &Profile) -> Self {
+        Self {
+            thresholds: thresholds.clone(),
+        }
+    }
+
+    /// Flag added files over the profile's size limits, and data files
+    /// (CSV, JSON, ...) over `max_data_file_kb` separately. Sizes come from
+    /// the checkout when there is one, since the diff of a large file may be
+    /// truncated, and from the added lines otherwise. Lockfiles are expected
+    /// to be large and are left out.
+    fn check_added_files(&self, pr: &PullRequest) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for file in pr.files().iter().filter(|f| f.is_new() && !f.is_deleted() && !is_lockfile(f.path())) {
+            let (lines, bytes) = match pr.checkout().and_then(|c| c.read_file(file.path()).ok()) {
+                Some(contents) => (contents.lines().count(), contents.len()),
+                None => {
+                    let added = file.hunks().iter().flat_map(|h| &h.lines).filter(|l| l.starts_with('+'));
+                    (file.additions(), added.map(|l| l.len()).sum())
+                }
+            };
+            let size_kb = bytes.div_ceil(1024);
+            let extension = file_name(file.path()).rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+            if extension.is_some_and(|ext| DATA_FILE_EXTENSIONS.contains(&ext.as_str())) {
+                if size_kb > self.thresholds.max_data_file_kb {
+                    findings.push(Finding::new("complexity.data_file", RiskLevel::Medium).param("file", file.path()).param("size_kb", size_kb).in_file(file.path()));
+                }
+            } else if lines > self.thresholds.max_added_file_lines || size_kb > self.thresholds.max_added_file_kb {
+                findings.push(Finding::new("complexity.large_added_file", RiskLevel::Medium).param("file", file.path()).param("lines", lines).param("size_kb", size_kb).in_file(file.path()));
+            }
+        }
+        findings
     }
 
     /// Check how many new dependencies are being added.
     fn check_dependency_count(&self, pr: &PullRequest) -> Vec<Finding> {
         let manifest_files = ["Cargo.toml", "package.json", "requirements.txt", "go.mod"];
         let mut findings = Vec::new();
-        for file in &pr.files {
-            let is_manifest = manifest_files.iter().any(|m| file.path.ends_with(m));
+        for file in pr.files() {
+            let is_manifest = manifest_files.iter().any(|m| file.path().ends_with(m));
             if !is_manifest {
                 continue;
             }
             let mut dep_count = 0usize;
-            for hunk in &file.hunks {
+            for hunk in file.hunks() {
                 for line in &hunk.lines {
                     if !line.starts_with('+') {
                         continue;
@@ -43,138 +131,226 @@ impl ComplexityAnalyzer {
                     }
                 }
             }
-            if dep_count >= 3 {
-                let severity = if dep_count >= 5 { RiskLevel::High } else { RiskLevel::Medium };
-                findings.push(Finding {
-                    message: format!("{} new dependencies added in {}", dep_count, file.path),
-                    file: Some(file.path.clone()),
-                    line: None,
-                    severity,
-                });
+            if dep_count >= self.thresholds.medium_new_dependencies {
+                let severity = if dep_count >= self.thresholds.high_new_dependencies { RiskLevel::High } else { RiskLevel::Medium };
+                let reason = format!("{} new dependencies (MEDIUM from {}, HIGH from {})", dep_count, self.thresholds.medium_new_dependencies, self.thresholds.high_new_dependencies);
+                findings.push(Finding::new("complexity.new_dependencies", severity).param("count", dep_count).param("file", file.path()).in_file(file.path()).because(reason));
             }
         }
         findings
     }
 
-    /// Evaluate the change size (lines added/removed, files changed).
+    /// Evaluate the change size (lines added/removed, files changed). Files
+    /// moved with a small edit are not counted, so a rename-heavy refactor
+    /// is sized by what it edits.
     fn check_change_size(&self, pr: &PullRequest) -> Vec<Finding> {
         let mut findings = Vec::new();
-        let total_changed = pr.additions + pr.deletions;
-
-        if total_changed > 500 {
-            findings.push(Finding {
-                message: format!("Very large change: {} lines modified (+{} -{})", total_changed, pr.additions, pr.deletions),
-                file: None,
-                line: None,
-                severity: RiskLevel::High,
-            });
-        } else if total_changed > 200 {
-            findings.push(Finding {
-                message: format!("Large change: {} lines modified (+{} -{})", total_changed, pr.additions, pr.deletions),
-                file: None,
-                line: None,
-                severity: RiskLevel::Medium,
-            });
+        let total_changed = pr.additions() + pr.deletions();
+
+        let size = if total_changed > self.thresholds.high_change_lines {
+            Some(Finding::new("complexity.very_large_change", RiskLevel::High))
+        } else if total_changed > self.thresholds.medium_change_lines {
+            Some(Finding::new("complexity.large_change", RiskLevel::Medium))
+        } else {
+            None
+        };
+        if let Some(finding) = size {
+            findings.push(finding.param("lines", total_changed).param("additions", pr.additions()).param("deletions", pr.deletions()));
         }
 
-        if pr.files_changed > 20 {
-            findings.push(Finding {
-                message: format!("Very high number of files changed: {}", pr.files_changed),
-                file: None,
-                line: None,
-                severity: RiskLevel::High,
-            });
-        } else if pr.files_changed > 10 {
-            findings.push(Finding {
-                message: format!("High number of files changed: {}", pr.files_changed),
-                file: None,
-                line: None,
-                severity: RiskLevel::Medium,
-            });
+        let moved = pr.files().iter().filter(|f| f.previous_path().is_some() && f.similarity().is_some_and(|s| s >= MOVED_FILE_SIMILARITY)).count();
+        let files = pr.files_changed().saturating_sub(moved);
+        let count = if files > self.thresholds.high_change_files {
+            Some(Finding::new("complexity.very_many_files", RiskLevel::High))
+        } else if files > self.thresholds.medium_change_files {
+            Some(Finding::new("complexity.many_files", RiskLevel::Medium))
+        } else {
+            None
+        };
+        if let Some(finding) = count {
+            let finding = finding.param("files", files);
+            findings.push(if moved > 0 { finding.because(format!("{} renamed files with {}%+ similarity not counted", moved, MOVED_FILE_SIMILARITY)) } else { finding });
         }
 
         findings
     }
 
-    /// Detect new public API surface introduced.
-    fn check_api_surface(&self, pr: &PullRequest) -> Vec<Finding> {
+    /// Flag heavyweight npm packages added to package.json.
+    fn check_heavy_npm_dependencies(&self, pr: &PullRequest) -> Vec<Finding> {
         let mut findings = Vec::new();
-        let pub_patterns = ["pub fn ", "pub struct ", "pub enum ", "pub trait ", "pub type "];
-        let mut total_pub = 0usize;
-
-        for file in &pr.files {
-            for hunk in &file.hunks {
+        for file in pr.files().iter().filter(|f| f.path().ends_with("package.json")) {
+            for hunk in file.hunks() {
                 for (i, line) in hunk.lines.iter().enumerate() {
                     if !line.starts_with('+') {
                         continue;
                     }
-                    let content = line[1..].trim_start();
-                    if pub_patterns.iter().any(|p| content.starts_with(p)) {
-                        total_pub += 1;
-                        findings.push(Finding {
-                            message: format!("New public API: {}", content.trim()),
-                            file: Some(file.path.clone()),
-                            line: Some(hunk.new_start + i),
-                            severity: RiskLevel::Low,
-                        });
+                    let Some(name) = line[1..].trim().strip_prefix('"').and_then(|rest| rest.split('"').next()) else {
+                        continue;
+                    };
+                    if let Some((_, alternative)) = HEAVY_NPM_PACKAGES.iter().find(|(heavy, _)| *heavy == name) {
+                        findings.push(
+                            Finding::new("complexity.heavy_npm_package", RiskLevel::Medium)
+                                .param("package", name)
+                                .param("alternative", alternative)
+                                .in_file(file.path())
+                                .at_line(hunk.new_start + i),
+                        );
                     }
                 }
             }
         }
+        findings
+    }
 
-        if total_pub > 10 {
-            findings.push(Finding {
-                message: format!("{} new public API items introduced — consider if all need to be public", total_pub),
-                file: None,
-                line: None,
-                severity: RiskLevel::Medium,
-            });
+    /// With a checkout, flag changed files that have grown past LONG_FILE_LINES.
+    fn check_file_length(&self, pr: &PullRequest) -> Vec<Finding> {
+        let Some(checkout) = &pr.checkout() else {
+            return Vec::new();
+        };
+        let mut findings = Vec::new();
+        for file in pr.files().iter().filter(|f| !f.is_deleted() && f.additions() > 0) {
+            let Ok(contents) = checkout.read_file(file.path()) else {
+                continue;
+            };
+            let total = contents.lines().count();
+            if total > LONG_FILE_LINES {
+                findings.push(Finding::new("complexity.long_file", RiskLevel::Low).param("lines", total).in_file(file.path()));
+            }
         }
-
         findings
     }
+}
 
-    /// Detect increases in nesting depth (deeply nested code).
-    fn check_nesting_depth(&self, pr: &PullRequest) -> Vec<Finding> {
-        let mut findings = Vec::new();
-        for file in &pr.files {
-            for hunk in &file.hunks {
-                for (i, line) in hunk.lines.iter().enumerate() {
-                    if !line.starts_with('+') {
-                        continue;
-                    }
-                    let content = &line[1..];
-                    // Count leading whitespace to estimate nesting
-                    let leading_spaces = content.len() - content.trim_start().len();
-                    // 4 spaces per level, >4 levels = deeply nested
-                    let indent_level = leading_spaces / 4;
-                    if indent_level > 4 && !content.trim().is_empty() {
-                        findings.push(Finding {
-                            message: format!("Deeply nested code (indent level {}): consider refactoring", indent_level),
-                            file: Some(file.path.clone()),
-                            line: Some(hunk.new_start + i),
-                            severity: RiskLevel::Medium,
-                        });
-                    }
+/// Line rules, in report order; `LineFindings` groups findings by these.
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    ApiSurface,
+    Nesting,
+    InlineAsset,
+}
+
+/// New public API items, nesting depth, and large inline base64 assets.
+///
+/// With a checkout, supported languages count enclosing blocks in the
+/// syntax tree for nesting depth; otherwise indentation stands in for it.
+impl LineRules for ComplexityAnalyzer {
+    fn keywords(&self) -> &'static [&'static str] {
+        PUB_ITEMS
+    }
+
+    fn visit_file<'a>(&'a self, pr: &'a PullRequest, file: &'a DiffFile, out: &'a mut LineFindings) -> Option<Box<dyn FileVisitor + 'a>> {
+        let syntax = Syntax::parse(file, pr.checkout()).filter(|s| s.whole_file);
+        let mut visitor = ComplexityFile { path: file.path(), has_syntax: syntax.is_some(), max_nesting_depth: self.thresholds.max_nesting_depth, out };
+        if let Some(syntax) = &syntax {
+            for line in added_lines(file) {
+                let Some(depth) = syntax.block_depth(line) else {
+                    continue;
+                };
+                if depth > visitor.max_nesting_depth {
+                    visitor.push(Rule::Nesting, Finding::new("complexity.nesting_depth", RiskLevel::Medium).param("depth", depth), line);
                 }
             }
         }
-        findings
+        Some(Box::new(visitor))
+    }
+
+    fn finish(&self, out: &mut LineFindings) {
+        let total_pub = out.count(Rule::ApiSurface as usize);
+        if total_pub > self.thresholds.max_public_items {
+            out.push(Rule::ApiSurface as usize, Finding::new("complexity.public_api_items", RiskLevel::Medium).param("count", total_pub));
+        }
+    }
+}
+
+/// `ComplexityAnalyzer` line rules applied to one file.
+struct ComplexityFile<'a> {
+    path: &'a str,
+    /// Whether nesting depth already came from the syntax tree
+    has_syntax: bool,
+    max_nesting_depth: usize,
+    out: &'a mut LineFindings,
+}
+
+impl ComplexityFile<'_> {
+    fn push(&mut self, rule: Rule, finding: Finding, line: usize) {
+        self.out.push(rule as usize, finding.in_file(self.path).at_line(line));
+    }
+}
+
+impl FileVisitor for ComplexityFile<'_> {
+    fn line(&mut self, line: &Line, hits: LineHits) {
+        if !line.added {
+            return;
+        }
+        let bare = line.masked.bare.trim_start();
+
+        if !hits.bare.is_empty() && PUB_ITEMS.iter().any(|p| bare.starts_with(p)) {
+            self.push(Rule::ApiSurface, Finding::new("complexity.public_api_item", RiskLevel::Low).param("item", line.text.trim()), line.number);
+        }
+
+        // 4 spaces of indentation per level, beyond max_nesting_depth = deeply nested
+        let indent_level = (line.text.len() - line.text.trim_start().len()) / 4;
+        if !self.has_syntax && indent_level > self.max_nesting_depth && !bare.is_empty() {
+            self.push(Rule::Nesting, Finding::new("complexity.indent_level", RiskLevel::Medium).param("level", indent_level), line.number);
+        }
+
+        for (pos, _) in line.text.match_indices(";base64,") {
+            if !line.text[..pos].contains("data:") {
+                continue;
+            }
+            let payload = &line.text[pos + ";base64,".len()..];
+            let encoded = payload
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '='))
+                .unwrap_or(payload.len());
+            let size_kb = encoded * 3 / 4 / 1024;
+            if size_kb > INLINE_ASSET_LIMIT_KB {
+                self.push(Rule::InlineAsset, Finding::new("complexity.inline_asset", RiskLevel::Medium).param("size_kb", size_kb), line.number);
+            }
+        }
     }
 }
 
 #[async_trait]
 impl Analyzer for ComplexityAnalyzer {
     fn name(&self) -> &str {
-        "Complexity Assessment"
+        NAME
+    }
+
+    fn rules_version(&self) -> u32 {
+        3
+    }
+
+    fn rules(&self) -> Vec<RuleSpec> {
+        vec![
+            RuleSpec::file("CPX001", "complexity.new_dependencies", Category::SupplyChain, &["complexity.new_dependencies"]),
+            RuleSpec::file("CPX002", "complexity.change_size", Category::Maintainability, &["complexity.very_large_change", "complexity.large_change", "complexity.very_many_files", "complexity.many_files"]),
+            RuleSpec::line("CPX003", "complexity.public_api", Category::Maintainability, &["complexity.public_api_items", "complexity.public_api_item"]),
+            RuleSpec::line("CPX004", "complexity.nesting", Category::Maintainability, &["complexity.nesting_depth", "complexity.indent_level"]),
+            RuleSpec::file("CPX005", "complexity.long_file", Category::Maintainability, &["complexity.long_file"]).needs_checkout(),
+            RuleSpec::file("CPX006", "complexity.large_files", Category::Performance, &["complexity.large_added_file", "complexity.data_file"]),
+            RuleSpec::line("CPX007", "complexity.inline_asset", Category::Performance, &["complexity.inline_asset"]),
+            RuleSpec::file("CPX008", "complexity.heavy_npm_package", Category::Performance, &["complexity.heavy_npm_package"]),
+        ]
     }
 
     async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError> {
+        self.analyze_scanned(pr, LinePass::run_one(self, pr)).await
+    }
+
+    fn line_rules(&self) -> Option<&dyn LineRules> {
+        Some(self)
+    }
+
+    async fn analyze_scanned(&self, pr: &PullRequest, mut lines: LineFindings) -> Result<AnalysisResult, AnalysisError> {
         let mut findings = Vec::new();
         findings.extend(self.check_dependency_count(pr));
         findings.extend(self.check_change_size(pr));
-        findings.extend(self.check_api_surface(pr));
-        findings.extend(self.check_nesting_depth(pr));
+        findings.extend(lines.take(Rule::ApiSurface as usize));
+        findings.extend(lines.take(Rule::Nesting as usize));
+        findings.extend(self.check_file_length(pr));
+        findings.extend(self.check_added_files(pr));
+        findings.extend(lines.take(Rule::InlineAsset as usize));
+        findings.extend(self.check_heavy_npm_dependencies(pr));
 
         let risk_level = if findings.iter().any(|f| f.severity == RiskLevel::High) {
             RiskLevel::High
@@ -205,11 +381,87 @@ mod tests {
         assert_eq!(result.risk_level, RiskLevel::Low);
     }
 
+    #[tokio::test]
+    async fn test_profile_thresholds_change_severity() {
+        let mut pr = test_pull_request();
+        pr.set_totals(pr.files_changed(), 350, pr.deletions());
+        let standard = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        assert_eq!(standard.risk_level, RiskLevel::Medium);
+        let strict = ComplexityAnalyzer::with_config(&Profile::builtin("strict").unwrap()).analyze(&pr).await.unwrap();
+        assert_eq!(strict.risk_level, RiskLevel::High);
+        let lenient = ComplexityAnalyzer::with_config(&Profile::builtin("lenient").unwrap()).analyze(&pr).await.unwrap();
+        assert_eq!(lenient.risk_level, RiskLevel::Low);
+    }
+
+    #[tokio::test]
+    async fn test_long_file_flagged_only_with_checkout() {
+        let root = std::env::temp_dir().join("pr-analyzer-test-long-file");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/big.rs"), "fn f() {}\n".repeat(LONG_FILE_LINES + 1)).unwrap();
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/big.rs", vec!["+fn f() {}".to_string()])]);
+
+        let without = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(!without.findings.iter().any(|f| f.message.starts_with("Long file")));
+        pr.set_checkout(crate::repo::Checkout::unchecked(&root));
+        let with = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(with.findings.iter().any(|f| f.message.starts_with("Long file: 1001 lines")));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[tokio::test]
+    async fn test_large_added_and_data_files() {
+        let added = |path: &str, lines: usize, width: usize| {
+            let mut file = test_diff_file(path, vec![format!("+{}", "x".repeat(width)); lines]);
+            file.set_new(true);
+            file
+        };
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            added("src/generated.rs", 1200, 10),
+            added("src/small.rs", 50, 10),
+            added("fixtures/users.csv", 600, 100),
+            added("fixtures/tiny.json", 10, 10),
+            added("package-lock.json", 5000, 40),
+        ]);
+        let result = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        let messages: Vec<_> = result.findings.iter().filter(|f| f.file.is_some() && f.message_id.as_deref() != Some("complexity.new_dependencies")).map(|f| f.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Large file added: src/generated.rs (1200 lines, 13 KB) — it stays in every clone",
+                "Data file committed into source: fixtures/users.csv (60 KB) — consider fixtures storage or a download step",
+            ]
+        );
+    }
+
+    #[cfg(feature = "syntax")]
+    #[tokio::test]
+    async fn test_nesting_uses_syntax_tree_with_checkout() {
+        let root = std::env::temp_dir().join("pr-analyzer-test-nesting");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        // Deep indentation from a wrapped argument list, but only two blocks deep.
+        let source = "fn f() {\n    if a {\n        call(\n                                x,\n        );\n    }\n}\n";
+        std::fs::write(root.join("src/wrapped.rs"), source).unwrap();
+        let mut pr = test_pull_request();
+        let mut file = test_diff_file("src/wrapped.rs", vec!["+                                x,".to_string()]);
+        file.hunks_mut()[0].new_start = 4;
+        pr.set_files(vec![file]);
+
+        let without = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(without.findings.iter().any(|f| f.message.contains("Deeply nested")));
+        pr.set_checkout(crate::repo::Checkout::unchecked(&root));
+        let with = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(!with.findings.iter().any(|f| f.message.contains("Deeply nested")));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
     #[tokio::test]
     async fn test_large_pr_flags_high_complexity() {
         let mut pr = test_pull_request();
-        pr.additions = 600;
-        pr.deletions = 50;
+        pr.set_totals(pr.files_changed(), 600, 50);
         let analyzer = ComplexityAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert_eq!(result.risk_level, RiskLevel::High);
@@ -219,8 +471,7 @@ mod tests {
     #[tokio::test]
     async fn test_medium_pr_flags_medium_complexity() {
         let mut pr = test_pull_request();
-        pr.additions = 250;
-        pr.deletions = 50;
+        pr.set_totals(pr.files_changed(), 250, 50);
         let analyzer = ComplexityAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert_eq!(result.risk_level, RiskLevel::Medium);
@@ -229,23 +480,47 @@ mod tests {
     #[tokio::test]
     async fn test_many_files_flags_complexity() {
         let mut pr = test_pull_request();
-        pr.files_changed = 15;
+        pr.set_totals(15, pr.additions(), pr.deletions());
         let analyzer = ComplexityAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert_eq!(result.risk_level, RiskLevel::Medium);
     }
 
+    #[tokio::test]
+    async fn test_renames_with_small_edits_do_not_count_as_changed_files() {
+        let mut pr = test_pull_request();
+        pr.set_totals(15, pr.additions(), pr.deletions());
+        pr.set_files((0..15)
+            .map(|i| {
+                let mut file = test_diff_file(&format!("src/moved/f{}.rs", i), vec!["-use old::X;".to_string(), "+use new::X;".to_string()]);
+                file.set_rename(Some(format!("src/old/f{}.rs", i)), Some(if i < 8 { 96 } else { 70 }));
+                file
+            })
+            .collect());
+        let analyzer = ComplexityAnalyzer::new();
+        let result = analyzer.analyze(&pr).await.unwrap();
+        assert!(!result.findings.iter().any(|f| f.message_id.as_deref() == Some("complexity.many_files")), "{:?}", result.findings);
+
+        // Renames with larger edits still count
+        for file in pr.files_mut() {
+            file.set_rename(file.previous_path().map(str::to_string), Some(70));
+        }
+        let result = analyzer.analyze(&pr).await.unwrap();
+        let many = result.findings.iter().find(|f| f.message_id.as_deref() == Some("complexity.many_files")).unwrap();
+        assert_eq!(many.severity_reason, None);
+    }
+
     #[tokio::test]
     async fn test_detects_new_public_api() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "src/api.rs",
             vec![
                 "+pub fn create_user(name: &str) -> User {".to_string(),
                 "+pub struct User {".to_string(),
                 "+pub enum Role {".to_string(),
             ],
-        )];
+        )]);
         let analyzer = ComplexityAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert_eq!(result.findings.iter().filter(|f| f.message.contains("New public API")).count(), 3);
@@ -254,14 +529,44 @@ mod tests {
     #[tokio::test]
     async fn test_detects_deep_nesting() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "src/logic.rs",
             vec![
                 "+                        deeply_nested_call();".to_string(), // 24 spaces = 6 levels
             ],
-        )];
+        )]);
         let analyzer = ComplexityAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert!(result.findings.iter().any(|f| f.message.contains("Deeply nested")));
     }
+
+    #[tokio::test]
+    async fn test_detects_large_inline_data_uri() {
+        let mut pr = test_pull_request();
+        let payload = "A".repeat(20 * 1024);
+        pr.set_files(vec![test_diff_file(
+            "web/src/logo.css",
+            vec![
+                format!("+.logo {{ background: url(data:image/png;base64,{}); }}", payload),
+                "+.icon { background: url(data:image/png;base64,iVBORw0KGgo=); }".to_string(),
+            ],
+        )]);
+        let result = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        let inline: Vec<_> = result.findings.iter().filter(|f| f.message.contains("Large inline asset")).collect();
+        assert_eq!(inline.len(), 1);
+        assert!(inline[0].message.contains("~15 KB"));
+    }
+
+    #[tokio::test]
+    async fn test_detects_heavy_npm_dependency() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "web/package.json",
+            vec!["+    \"moment\": \"^2.29.4\",".to_string(), "+    \"clsx\": \"^2.0.0\",".to_string()],
+        )]);
+        let result = ComplexityAnalyzer::new().analyze(&pr).await.unwrap();
+        let heavy: Vec<_> = result.findings.iter().filter(|f| f.message.contains("Heavyweight npm")).collect();
+        assert_eq!(heavy.len(), 1);
+        assert!(heavy[0].message.contains("'moment'"));
+    }
 }
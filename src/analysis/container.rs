@@ -0,0 +1,340 @@
+use async_trait::async_trait;
+
+use super::{Analyzer, AnalysisError, RuleSpec};
+use crate::paths::file_name;
+use crate::pr::types::DiffFile;
+use crate::pr::PullRequest;
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel};
+
+/// Build-argument and environment names that indicate a secret is being baked into the image.
+const SECRET_NAME_HINTS: &[&str] = &["PASSWORD", "PASSWD", "SECRET", "TOKEN", "API_KEY", "APIKEY", "PRIVATE_KEY", "CREDENTIAL", "ACCESS_KEY"];
+
+/// Container Risk Analyzer
+///
+/// Checks Dockerfile changes against common best practices:
+/// - Base images pinned by digest rather than a mutable tag
+/// - `COPY` preferred over `ADD`
+/// - No secrets passed through build `ARG`s or `ENV`
+/// - A non-root `USER` declared in new images
+/// - `apt-get install` with `--no-install-recommends` and list cleanup
+#[derive(Default)]
+pub struct ContainerAnalyzer;
+
+/// A single Dockerfile instruction assembled from added lines, with `\` continuations joined.
+struct Instruction {
+    line: usize,
+    keyword: String,
+    args: String,
+}
+
+impl ContainerAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Flag base images that are unpinned, use `latest`, or are pinned by tag only.
+    fn check_base_images(&self, file: &DiffFile) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let stages = stage_names(file);
+        for ins in instructions(file) {
+            if ins.keyword != "FROM" {
+                continue;
+            }
+            let Some(image) = ins.args.split_whitespace().find(|t| !t.starts_with("--")) else {
+                continue;
+            };
+            if image == "scratch" || image.starts_with('$') || stages.iter().any(|s| s.eq_ignore_ascii_case(image)) {
+                continue;
+            }
+            if image.contains("@sha256:") {
+                continue;
+            }
+            let name = image.rsplit('/').next().unwrap_or(image);
+            let finding = match name.split_once(':') {
+                None => Finding::new("container.base_image_untagged", RiskLevel::Medium),
+                Some((_, "latest")) => Finding::new("container.base_image_latest", RiskLevel::Medium),
+                Some(_) => Finding::new("container.base_image_tag_only", RiskLevel::Low),
+            };
+            findings.push(finding.param("image", image).in_file(file.path()).at_line(ins.line));
+        }
+        findings
+    }
+
+    /// Flag `ADD` where `COPY` would do, and `ADD` of remote URLs.
+    fn check_add_instruction(&self, file: &DiffFile) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for ins in instructions(file) {
+            if ins.keyword != "ADD" {
+                continue;
+            }
+            let remote = ins.args.split_whitespace().any(|t| t.starts_with("http://") || t.starts_with("https://"));
+            let finding = if remote {
+                Finding::new("container.add_remote_url", RiskLevel::Medium)
+            } else {
+                Finding::new("container.add_instead_of_copy", RiskLevel::Low)
+            };
+            findings.push(finding.in_file(file.path()).at_line(ins.line));
+        }
+        findings
+    }
+
+    /// Flag secret-looking build arguments and environment variables, which persist in image history.
+    fn check_build_secrets(&self, file: &DiffFile) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for ins in instructions(file) {
+            if ins.keyword != "ARG" && ins.keyword != "ENV" {
+                continue;
+            }
+            let Some(name) = ins.args.split(|c: char| c == '=' || c.is_whitespace()).next() else {
+                continue;
+            };
+            let upper = name.to_uppercase();
+            if SECRET_NAME_HINTS.iter().any(|hint| upper.contains(hint)) {
+                findings.push(
+                    Finding::new("container.build_secret", RiskLevel::High)
+                        .param("name", name)
+                        .param("instruction", &ins.keyword)
+                        .in_file(file.path())
+                        .at_line(ins.line),
+                );
+            }
+        }
+        findings
+    }
+
+    /// Flag new images that never drop root, and explicit switches back to root.
+    fn check_user(&self, file: &DiffFile) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let users: Vec<Instruction> = instructions(file).into_iter().filter(|i| i.keyword == "USER").collect();
+        for ins in &users {
+            if ins.args.trim() == "root" || ins.args.trim() == "0" {
+                findings.push(Finding::new("container.user_root", RiskLevel::Medium).in_file(file.path()).at_line(ins.line));
+            }
+        }
+        if file.is_new() && users.is_empty() {
+            findings.push(Finding::new("container.no_user", RiskLevel::Medium).in_file(file.path()));
+        }
+        findings
+    }
+
+    /// Flag `apt-get install` without `--no-install-recommends` or without cleaning package lists.
+    fn check_apt_get(&self, file: &DiffFile) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for ins in instructions(file) {
+            if ins.keyword != "RUN" || !ins.args.contains("apt-get install") {
+                continue;
+            }
+            if !ins.args.contains("--no-install-recommends") {
+                findings.push(Finding::new("container.apt_recommends", RiskLevel::Low).in_file(file.path()).at_line(ins.line));
+            }
+            if !ins.args.contains("/var/lib/apt/lists") {
+                findings.push(Finding::new("container.apt_lists", RiskLevel::Low).in_file(file.path()).at_line(ins.line));
+            }
+        }
+        findings
+    }
+}
+
+/// Whether a path names a Dockerfile (`Dockerfile`, `Dockerfile.prod`, `api.dockerfile`, `Containerfile`).
+fn is_dockerfile(path: &str) -> bool {
+    let name = file_name(path);
+    name == "Dockerfile" || name.starts_with("Dockerfile.") || name == "Containerfile" || name.to_lowercase().ends_with(".dockerfile")
+}
+
+/// Collect instructions from added lines, joining `\` line continuations.
+fn instructions(file: &DiffFile) -> Vec<Instruction> {
+    let mut result = Vec::new();
+    for hunk in file.hunks() {
+        let mut pending: Option<(usize, String)> = None;
+        for (i, line) in hunk.lines.iter().enumerate() {
+            let Some(content) = line.strip_prefix('+') else {
+                pending = None;
+                continue;
+            };
+            let content = content.trim();
+            let (start, mut text) = match pending.take() {
+                Some((start, text)) => (start, text),
+                None => {
+                    if content.is_empty() || content.starts_with('#') {
+                        continue;
+                    }
+                    (hunk.new_start + i, String::new())
+                }
+            };
+            if let Some(continued) = content.strip_suffix('\\') {
+                text.push_str(continued);
+                text.push(' ');
+                pending = Some((start, text));
+                continue;
+            }
+            text.push_str(content);
+            if let Some((keyword, args)) = text.split_once(char::is_whitespace) {
+                result.push(Instruction {
+                    line: start,
+                    keyword: keyword.to_uppercase(),
+                    args: args.trim().to_string(),
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Names of build stages (`FROM image AS name`) visible in the diff, so later `FROM name` lines are not treated as images.
+fn stage_names(file: &DiffFile) -> Vec<String> {
+    let mut stages = Vec::new();
+    for hunk in file.hunks() {
+        for line in &hunk.lines {
+            let content = line.get(1..).unwrap_or("").trim();
+            let tokens: Vec<&str> = content.split_whitespace().collect();
+            if tokens.len() >= 4 && tokens[0].eq_ignore_ascii_case("FROM") && tokens[tokens.len() - 2].eq_ignore_ascii_case("AS") {
+                stages.push(tokens[tokens.len() - 1].to_string());
+            }
+        }
+    }
+    stages
+}
+
+#[async_trait]
+impl Analyzer for ContainerAnalyzer {
+    fn name(&self) -> &str {
+        "Container Risk Assessment"
+    }
+
+    fn rules_version(&self) -> u32 {
+        1
+    }
+
+    fn rules(&self) -> Vec<RuleSpec> {
+        vec![
+            RuleSpec::file("CTR001", "container.base_image", Category::SupplyChain, &["container.base_image_untagged", "container.base_image_latest", "container.base_image_tag_only"]),
+            RuleSpec::file("CTR002", "container.add", Category::SupplyChain, &["container.add_remote_url", "container.add_instead_of_copy"]),
+            RuleSpec::file("CTR003", "container.build_secret", Category::Secrets, &["container.build_secret"]),
+            RuleSpec::file("CTR004", "container.root_user", Category::AccessControl, &["container.user_root", "container.no_user"]),
+            RuleSpec::file("CTR005", "container.apt_hygiene", Category::Maintainability, &["container.apt_recommends", "container.apt_lists"]),
+        ]
+    }
+
+    async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError> {
+        let mut findings = Vec::new();
+        for file in pr.files().iter().filter(|f| is_dockerfile(f.path()) && !f.is_deleted()) {
+            findings.extend(self.check_base_images(file));
+            findings.extend(self.check_add_instruction(file));
+            findings.extend(self.check_build_secrets(file));
+            findings.extend(self.check_user(file));
+            findings.extend(self.check_apt_get(file));
+        }
+
+        let risk_level = if findings.iter().any(|f| f.severity == RiskLevel::High) {
+            RiskLevel::High
+        } else if findings.iter().any(|f| f.severity == RiskLevel::Medium) {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        };
+
+        Ok(AnalysisResult {
+            analyzer_name: self.name().to_string(),
+            risk_level,
+            findings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+
+    fn dockerfile(lines: &[&str]) -> DiffFile {
+        test_diff_file("Dockerfile", lines.iter().map(|l| l.to_string()).collect())
+    }
+
+    #[tokio::test]
+    async fn test_empty_pr_returns_low_container_risk() {
+        let analyzer = ContainerAnalyzer::new();
+        let result = analyzer.analyze(&test_pull_request()).await.unwrap();
+        assert_eq!(result.risk_level, RiskLevel::Low);
+        assert!(result.findings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ignores_non_dockerfiles() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("docs/build.md", vec!["+FROM ubuntu".to_string()])]);
+        let result = ContainerAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_detects_unpinned_base_image() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![dockerfile(&["+FROM ubuntu", "+FROM node:latest"])]);
+        let result = ContainerAnalyzer::new().analyze(&pr).await.unwrap();
+        assert_eq!(result.findings.iter().filter(|f| f.severity == RiskLevel::Medium).count(), 2);
+        assert_eq!(result.risk_level, RiskLevel::Medium);
+    }
+
+    #[tokio::test]
+    async fn test_digest_pinned_and_stage_images_are_accepted() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![dockerfile(&[
+            "+FROM rust@sha256:0123abcd AS builder",
+            "+FROM builder",
+        ])]);
+        let result = ContainerAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().all(|f| !f.message.contains("Base image")));
+    }
+
+    #[tokio::test]
+    async fn test_detects_add_and_remote_add() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![dockerfile(&["+ADD . /app", "+ADD https://example.com/tool.tgz /opt/"])]);
+        let result = ContainerAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().any(|f| f.message.contains("instead of COPY") && f.severity == RiskLevel::Low));
+        assert!(result.findings.iter().any(|f| f.message.contains("remote URL") && f.severity == RiskLevel::Medium));
+    }
+
+    #[tokio::test]
+    async fn test_detects_secret_build_arg() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![dockerfile(&["+ARG NPM_TOKEN", "+ENV DB_PASSWORD=changeme"])]);
+        let result = ContainerAnalyzer::new().analyze(&pr).await.unwrap();
+        assert_eq!(result.findings.iter().filter(|f| f.message.contains("persisted in image history")).count(), 2);
+        assert_eq!(result.risk_level, RiskLevel::High);
+    }
+
+    #[tokio::test]
+    async fn test_new_dockerfile_without_user_flags_root() {
+        let mut pr = test_pull_request();
+        let mut file = dockerfile(&["+FROM alpine:3.19@sha256:abc", "+CMD [\"/app\"]"]);
+        file.set_new(true);
+        pr.set_files(vec![file]);
+        let result = ContainerAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().any(|f| f.message.contains("run as root")));
+    }
+
+    #[tokio::test]
+    async fn test_apt_get_joins_continuation_lines() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![dockerfile(&[
+            "+RUN apt-get update && \\",
+            "+    apt-get install -y --no-install-recommends curl && \\",
+            "+    rm -rf /var/lib/apt/lists/*",
+            "+RUN apt-get install -y git",
+        ])]);
+        let result = ContainerAnalyzer::new().analyze(&pr).await.unwrap();
+        let apt: Vec<&Finding> = result.findings.iter().filter(|f| f.message.contains("apt-get")).collect();
+        assert_eq!(apt.len(), 2);
+        assert!(apt.iter().all(|f| f.line == Some(4)));
+    }
+
+    #[test]
+    fn test_is_dockerfile() {
+        assert!(is_dockerfile("Dockerfile"));
+        assert!(is_dockerfile("deploy/Dockerfile.prod"));
+        assert!(is_dockerfile("images/api.dockerfile"));
+        assert!(is_dockerfile("Containerfile"));
+        assert!(!is_dockerfile("src/docker.rs"));
+    }
+}
@@ -0,0 +1,58 @@
+//! Rule documentation for `rules docs`: one markdown page with every rule of
+//! the built-in analyzers, what it reports and what it needs to run.
+//!
+//! The page is committed as `docs/rules.md`, which findings link to unless
+//! `[report] docs_url` points elsewhere. Each rule has an anchor named by
+//! its id, so `rules.md#security.hardcoded_secret` lands on it.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::{analyzer_key, analyzers};
+use crate::config::Config;
+use crate::report::Catalog;
+
+/// The rules page for `config`, with messages and labels from `catalog`.
+pub fn render(config: &Config, catalog: &Catalog) -> String {
+    let mut out = String::from("# Rules\n\n");
+    out.push_str("<!-- Generated by `pr-analyzer rules docs`; do not edit. -->\n\n");
+    out.push_str("Every rule of the built-in analyzers. Finding messages show their placeholders in braces.\n");
+    for analyzer in analyzers(config) {
+        let _ = write!(out, "\n## {} (`{}`)\n", analyzer.name(), analyzer_key(analyzer.as_ref()));
+        for spec in analyzer.rules() {
+            let _ = write!(out, "\n<a id=\"{}\"></a>\n\n### `{}`\n\n", spec.id, spec.id);
+            let mut facts = vec![format!("`{}`", spec.code), catalog.chrome(spec.category.label_key()).to_string()];
+            facts.push(if spec.line_pass { "checks added lines" } else { "checks whole files or the change" }.to_string());
+            if spec.needs_checkout {
+                facts.push("needs --repo or --clone".to_string());
+            }
+            if spec.runs_pr_code {
+                facts.push("skipped on PRs from forks".to_string());
+            }
+            if let Some(reason) = spec.disabled {
+                facts.push(format!("off by default ({})", reason));
+            }
+            let _ = writeln!(out, "{}\n", facts.join(" · "));
+            if spec.messages.is_empty() {
+                out.push_str("Reports nothing yet.\n");
+            }
+            for id in spec.messages {
+                let text = catalog.render(id, &BTreeMap::new()).unwrap_or_default();
+                let _ = writeln!(out, "- `{}`: {}", id, text);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_committed_rule_docs_are_current() {
+        let docs = render(&Config::default(), Catalog::english());
+        assert!(docs.contains("<a id=\"security.custom_patterns\"></a>"));
+        assert_eq!(docs, include_str!("../../docs/rules.md"), "docs/rules.md is stale: run `pr-analyzer rules docs > docs/rules.md`");
+    }
+}
@@ -0,0 +1,118 @@
+//! Review effort: about how many minutes a reviewer needs for the change,
+//! so reviews can be assigned by load rather than by eye.
+//!
+//! The estimate reads changed lines at a pace that depends on the kind of
+//! file (code is slow, docs fast, lockfiles skipped), adds time per file
+//! and per complexity finding, and adds a quarter when a sizeable code
+//! change comes with almost no test changes. It is a rough guide, rounded
+//! to 5 minutes, not a measurement.
+
+use super::{complexity, is_lockfile, is_test_path};
+use crate::paths::file_name;
+use crate::pr::PullRequest;
+use crate::report::types::{AnalysisResult, ReviewEffort};
+
+/// Weighted lines a reviewer reads per minute (about 300 lines of code an hour).
+const LINES_PER_MINUTE: f64 = 5.0;
+
+/// Fixed cost of any review: reading the description and loading context.
+const BASE_MINUTES: f64 = 5.0;
+
+const MINUTES_PER_FILE: f64 = 1.0;
+
+const MINUTES_PER_COMPLEXITY_FINDING: f64 = 3.0;
+
+/// Below this share of changed lines in tests, a code change of more than
+/// `UNTESTED_MIN_LINES` lines costs `UNTESTED_FACTOR` times as much.
+const UNTESTED_RATIO: f64 = 0.1;
+const UNTESTED_MIN_LINES: usize = 50;
+const UNTESTED_FACTOR: f64 = 1.25;
+
+/// How closely a changed line of `path` is read, relative to code.
+fn weight(path: &str, test_paths: &[String]) -> f64 {
+    if is_lockfile(path) {
+        return 0.0;
+    }
+    if is_test_path(path, test_paths) {
+        return 0.5;
+    }
+    let extension = file_name(path).rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("md" | "markdown" | "rst" | "txt" | "adoc") => 0.3,
+        Some("json" | "yaml" | "yml" | "toml" | "ini" | "cfg" | "xml" | "csv" | "svg") => 0.5,
+        Some("sql" | "sh" | "bash" | "tf" | "hcl") => 1.2,
+        _ => 1.0,
+    }
+}
+
+/// The review effort for `pr`, given the analyzers' `results`.
+pub fn estimate(pr: &PullRequest, results: &[AnalysisResult], test_paths: &[String]) -> ReviewEffort {
+    let mut weighted = 0.0;
+    let (mut code_lines, mut test_lines) = (0, 0);
+    for file in pr.files() {
+        let lines = file.additions() + file.deletions();
+        let weight = weight(file.path(), test_paths);
+        weighted += lines as f64 * weight;
+        if is_test_path(file.path(), test_paths) {
+            test_lines += lines;
+        } else if weight >= 1.0 {
+            code_lines += lines;
+        }
+    }
+    let complexity_findings = results.iter().filter(|r| r.analyzer_name == complexity::NAME).map(|r| r.findings.len()).sum();
+    let test_ratio = if code_lines + test_lines == 0 { 0.0 } else { test_lines as f64 / (code_lines + test_lines) as f64 };
+    let mut minutes = BASE_MINUTES + weighted / LINES_PER_MINUTE + pr.files().len() as f64 * MINUTES_PER_FILE + complexity_findings as f64 * MINUTES_PER_COMPLEXITY_FINDING;
+    if code_lines > UNTESTED_MIN_LINES && test_ratio < UNTESTED_RATIO {
+        minutes *= UNTESTED_FACTOR;
+    }
+    ReviewEffort {
+        minutes: ((minutes / 5.0).round() as u32 * 5).max(5),
+        weighted_lines: weighted.round() as usize,
+        complexity_findings,
+        test_ratio: (test_ratio * 100.0).round() / 100.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+    use crate::config::StyleConfig;
+    use crate::pr::types::DiffFile;
+
+    fn file(path: &str, additions: usize) -> DiffFile {
+        let mut file = test_diff_file(path, vec![]);
+        file.set_counts(additions, 0);
+        file
+    }
+
+    #[test]
+    fn test_estimate_weights_files_and_tests() {
+        let test_paths = StyleConfig::default().test_paths;
+        let mut pr = test_pull_request();
+        pr.set_files(vec![file("src/a.rs", 200), file("Cargo.lock", 900), file("README.md", 100)]);
+        let untested = estimate(&pr, &[], &test_paths);
+        // 5 + (200 + 30) / 5 + 3 files = 54, a quarter more without tests
+        assert_eq!(untested.weighted_lines, 230);
+        assert_eq!(untested.test_ratio, 0.0);
+        assert_eq!(untested.minutes, 70);
+
+        pr.files_mut().push(file("tests/a.rs", 100));
+        let tested = estimate(&pr, &[], &test_paths);
+        assert_eq!(tested.test_ratio, 0.33);
+        assert_eq!(tested.minutes, 65);
+    }
+
+    #[test]
+    fn test_estimate_counts_complexity_findings() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![file("src/a.rs", 10)]);
+        let results = vec![AnalysisResult {
+            analyzer_name: complexity::NAME.to_string(),
+            risk_level: crate::report::RiskLevel::Medium,
+            findings: vec![crate::report::Finding::text("deep nesting", crate::report::RiskLevel::Medium); 5],
+        }];
+        assert_eq!(estimate(&pr, &[], &[]).minutes, 10);
+        assert_eq!(estimate(&pr, &results, &[]).minutes, 25);
+    }
+}
@@ -0,0 +1,208 @@
+//! Why lines did or did not match, for `--only-analyzer` / `--only-rule`.
+//!
+//! Walks the diff again for the selected analyzers and, for every added line
+//! one of their keywords or `[security] patterns` occurs in, says where the
+//! line pass saw it: in code, only inside a string literal, or only inside a
+//! comment (which the line pass never sees). A keyword hit only makes a rule
+//! look at a line; the findings listed under it show whether the rule's own
+//! checks then reported it.
+//!
+//! `explain_findings` goes the other way, for `--explain-matches`: from each
+//! finding to the rule that reported it, what matched on its line, and why
+//! it has its severity.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use regex::Regex;
+
+use super::scan::{for_each_line, Keywords};
+use super::{analyzer_key, analyzers, select, AnalysisError, AnalysisRun};
+use crate::config::Config;
+use crate::pr::PullRequest;
+use crate::report::types::Finding;
+
+/// Diagnostics for the analyzers `config.only` selects, given the run they
+/// were part of.
+pub fn explain(pr: &PullRequest, config: &Config, run: &AnalysisRun) -> Result<String, AnalysisError> {
+    let mut out = String::new();
+    for analyzer in select(analyzers(config), &config.only, &config.policy.required_analyzers)? {
+        let key = analyzer_key(analyzer.as_ref());
+        let findings: &[Finding] = run.results.iter().find(|r| r.analyzer_name == analyzer.name()).map_or(&[], |r| &r.findings);
+        let _ = writeln!(out, "== {} ({}) ==", analyzer.name(), key);
+        for rule in run.rules.iter().filter(|r| r.analyzer == analyzer.name()) {
+            let status = format!("{:?}", rule.status).to_lowercase();
+            let _ = match &rule.reason {
+                Some(reason) => writeln!(out, "rule {}: {} ({})", rule.rule, status, reason),
+                None => writeln!(out, "rule {}: {}, {} finding(s)", rule.rule, status, rule.findings),
+            };
+        }
+
+        let keywords = analyzer.line_rules().map_or(&[][..], |r| r.keywords());
+        let searcher = Keywords::new(keywords.iter().copied());
+        let patterns = if key == "security" && config.only.rule.as_deref().is_none_or(|r| r.eq_ignore_ascii_case("security.custom_patterns")) {
+            custom_patterns(config)
+        } else {
+            Vec::new()
+        };
+        let mut quiet = 0;
+        for file in pr.files() {
+            for_each_line(file, |line| {
+                if !line.added {
+                    return;
+                }
+                let (raw, code, bare) = (searcher.matches(line.text), searcher.matches(&line.masked.code), searcher.matches(&line.masked.bare));
+                let matching: Vec<&Regex> = patterns.iter().filter(|re| re.is_match(line.text)).collect();
+                let here: Vec<&Finding> = findings.iter().filter(|f| f.file.as_deref() == Some(file.path()) && f.line == Some(line.number)).collect();
+                if raw.is_empty() && code.is_empty() && matching.is_empty() && here.is_empty() {
+                    quiet += 1;
+                    return;
+                }
+                let _ = writeln!(out, "{}:{}: {}", file.path(), line.number, line.text.trim());
+                for (i, keyword) in keywords.iter().enumerate() {
+                    let seen = if bare.contains(i) {
+                        "in code"
+                    } else if code.contains(i) {
+                        "only in a string literal"
+                    } else if raw.contains(i) {
+                        "only in a comment, ignored"
+                    } else {
+                        continue;
+                    };
+                    let _ = writeln!(out, "  keyword `{}`: {}", keyword, seen);
+                }
+                for re in matching {
+                    let seen = if re.is_match(&line.masked.code) { "matches" } else { "matches only with comments included, ignored" };
+                    let _ = writeln!(out, "  pattern `{}`: {}", re.as_str(), seen);
+                }
+                if here.is_empty() {
+                    let _ = writeln!(out, "  no finding");
+                }
+                for f in here {
+                    let _ = writeln!(out, "  -> [{}] {}", f.severity, f.message);
+                }
+            });
+        }
+        let _ = writeln!(out, "{} other added line(s) without keyword or pattern hits", quiet);
+        for f in findings.iter().filter(|f| f.line.is_none()) {
+            let _ = writeln!(out, "{}: [{}] {}", f.file.as_deref().unwrap_or("(change)"), f.severity, f.message);
+        }
+    }
+    Ok(out)
+}
+
+/// For every finding of the analyzers `config.only` selects (all of them by
+/// default): its rule and category, its line with the analyzer keywords and
+/// the `[security] patterns` match (with capture groups) found there, and
+/// the reason for its severity when the rule gave one.
+pub fn explain_findings(pr: &PullRequest, config: &Config, run: &AnalysisRun) -> Result<String, AnalysisError> {
+    let mut lines: HashMap<(&str, usize), String> = HashMap::new();
+    for file in pr.files() {
+        for_each_line(file, |line| {
+            if line.added {
+                lines.insert((file.path(), line.number), line.masked.code.to_string());
+            }
+        });
+    }
+    let patterns = custom_patterns(config);
+    let mut out = String::new();
+    for analyzer in select(analyzers(config), &config.only, &config.policy.required_analyzers)? {
+        let Some(result) = run.results.iter().find(|r| r.analyzer_name == analyzer.name()) else {
+            continue;
+        };
+        let specs = analyzer.rules();
+        let keywords = analyzer.line_rules().map_or(&[][..], |r| r.keywords());
+        let searcher = Keywords::new(keywords.iter().copied());
+        for f in &result.findings {
+            let location = match (&f.file, f.line) {
+                (Some(file), Some(line)) => format!("{}:{}", file, line),
+                (Some(file), None) => file.clone(),
+                (None, _) => "(change)".to_string(),
+            };
+            let _ = writeln!(out, "{} [{}] {}", location, f.severity, f.message);
+            let _ = match f.message_id.as_deref().and_then(|id| specs.iter().find(|s| s.messages.contains(&id) || s.failure == Some(id))) {
+                Some(spec) => writeln!(out, "  rule: {} ({:?}) in {}", spec.id, spec.category, analyzer.name()),
+                None => writeln!(out, "  rule: none, a note from {}", analyzer.name()),
+            };
+            let code = f.file.as_deref().zip(f.line).and_then(|(file, line)| lines.get(&(file, line)));
+            match code {
+                Some(code) => {
+                    let _ = writeln!(out, "  line: {}", code.trim());
+                    let hits = searcher.matches(code);
+                    let found: Vec<String> = keywords.iter().enumerate().filter(|(i, _)| hits.contains(*i)).map(|(_, k)| format!("`{}`", k)).collect();
+                    if !found.is_empty() {
+                        let _ = writeln!(out, "  keywords: {}", found.join(", "));
+                    }
+                    let pattern = f.params.get("pattern").and_then(|p| patterns.iter().find(|re| re.as_str() == p));
+                    if let Some(captures) = pattern.and_then(|re| re.captures(code)) {
+                        let _ = writeln!(out, "  pattern `{}` matched `{}`", pattern.map_or("", |re| re.as_str()), &captures[0]);
+                        for (i, group) in captures.iter().enumerate().skip(1) {
+                            let _ = writeln!(out, "    group {}: {}", i, group.map_or("(no match)".to_string(), |g| format!("`{}`", g.as_str())));
+                        }
+                    }
+                }
+                None => {
+                    let _ = writeln!(out, "  line: none, the rule looks at the file or the change as a whole");
+                }
+            }
+            let _ = writeln!(out, "  severity: {}", f.severity_reason.as_deref().unwrap_or("fixed for this rule"));
+        }
+    }
+    Ok(out)
+}
+
+/// The `[security] patterns` that compile, as the security analyzer uses them.
+fn custom_patterns(config: &Config) -> Vec<Regex> {
+    config.security.patterns.iter().filter_map(|p| Regex::new(&p.pattern).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::run_all;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+    use crate::config::Selection;
+
+    #[tokio::test]
+    async fn test_explains_keyword_and_pattern_hits() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "src/db.rs",
+            vec!["+// TODO: audit this".to_string(), "+let q = \"TODO: audit\";".to_string(), "+let x = 1;".to_string()],
+        )]);
+        let mut config = Config::default();
+        config.apply_profile(None).unwrap();
+        config.security.patterns = vec!["TODO: audit".into()];
+        config.only = Selection { rule: Some("security.custom_patterns".to_string()), ..Selection::default() };
+        let run = run_all(&pr, &config, std::future::pending()).await.unwrap();
+        let text = explain(&pr, &config, &run).unwrap();
+
+        assert!(text.starts_with("== Security Risk Assessment (security) ==\n"), "{text}");
+        assert!(text.contains("rule security.custom_patterns: executed, 1 finding(s)"), "{text}");
+        assert!(text.contains("rule security.sql_injection: skipped (not selected (--only-rule))"), "{text}");
+        assert!(text.contains("src/db.rs:1: // TODO: audit this\n  pattern `TODO: audit`: matches only with comments included, ignored\n  no finding\n"), "{text}");
+        assert!(text.contains("src/db.rs:2: let q = \"TODO: audit\";\n  pattern `TODO: audit`: matches\n  -> [MEDIUM]"), "{text}");
+        assert!(text.contains("1 other added line(s) without keyword or pattern hits"), "{text}");
+    }
+
+    #[tokio::test]
+    async fn test_explains_each_finding() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file("src/api.rs", vec!["+let host = \"db.corp.internal:5432\";".to_string()]),
+            test_diff_file("CODEOWNERS", vec!["-* @org/team".to_string()]),
+        ]);
+        pr.files_mut()[1].set_deleted(true);
+        let mut config = Config::default();
+        config.apply_profile(None).unwrap();
+        config.security.patterns = vec!["([a-z]+)\\.corp\\.internal(:\\d+)?".into()];
+        let run = run_all(&pr, &config, std::future::pending()).await.unwrap();
+        let text = explain_findings(&pr, &config, &run).unwrap();
+
+        assert!(text.contains("src/api.rs:1 [MEDIUM] Custom security pattern match"), "{text}");
+        assert!(text.contains("  rule: security.custom_patterns (Custom) in Security Risk Assessment\n"), "{text}");
+        assert!(text.contains("  pattern `([a-z]+)\\.corp\\.internal(:\\d+)?` matched `db.corp.internal:5432`\n    group 1: `db`\n    group 2: `:5432`\n  severity: fixed for this rule\n"), "{text}");
+        assert!(text.contains("CODEOWNERS [HIGH] Code owners changed"), "{text}");
+        assert!(text.contains("  line: none, the rule looks at the file or the change as a whole\n  severity: file deleted\n"), "{text}");
+    }
+}
@@ -0,0 +1,121 @@
+//! CI gating: whether a report should fail the build.
+//!
+//! `--fail-on` sets one threshold for every analyzer; a `[ci]` entry for an
+//! analyzer (by rule table, e.g. `security = "medium"`) replaces it for that
+//! analyzer, and `off` means it never fails the run. An analyzer fails the
+//! gate when its risk level reaches its threshold.
+
+use std::fmt::Write;
+
+use super::{analyzer_key, analyzers, is_analyzer, AnalysisError};
+use crate::config::{Config, Gate};
+use crate::report::{Report, RiskLevel};
+
+/// The gate outcome for one analyzer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Verdict {
+    pub analyzer: String,
+    pub risk: RiskLevel,
+    pub findings: usize,
+    /// Lowest risk level that fails; None when the gate is off
+    pub threshold: Option<RiskLevel>,
+}
+
+impl Verdict {
+    pub fn fails(&self) -> bool {
+        self.threshold.is_some_and(|t| self.risk >= t)
+    }
+}
+
+/// `[ci]` keys that name no analyzer are an error, so a typo does not
+/// silently leave an analyzer ungated.
+pub fn check(config: &Config) -> Result<(), AnalysisError> {
+    let all = analyzers(config);
+    for name in config.ci.keys() {
+        if !all.iter().any(|a| is_analyzer(a.as_ref(), name)) {
+            let known = all.iter().map(|a| analyzer_key(a.as_ref())).collect::<Vec<_>>().join(", ");
+            return Err(AnalysisError::UnknownSelection { kind: "[ci] analyzer", name: name.clone(), known });
+        }
+    }
+    Ok(())
+}
+
+/// A verdict for every analyzer in `report` that has a gate, from `[ci]`
+/// or else `fail_on`. Without either, nothing is gated.
+pub fn evaluate(report: &Report, config: &Config, fail_on: Option<Gate>) -> Result<Vec<Verdict>, AnalysisError> {
+    check(config)?;
+    let all = analyzers(config);
+    let mut verdicts = Vec::new();
+    for result in &report.results {
+        let Some(analyzer) = all.iter().find(|a| a.name() == result.analyzer_name) else {
+            continue;
+        };
+        let gate = config.ci.iter().find(|(name, _)| is_analyzer(analyzer.as_ref(), name)).map(|(_, gate)| *gate).or(fail_on);
+        if let Some(gate) = gate {
+            verdicts.push(Verdict { analyzer: result.analyzer_name.clone(), risk: result.risk_level, findings: result.findings.len(), threshold: gate.threshold() });
+        }
+    }
+    Ok(verdicts)
+}
+
+/// One line per verdict: `[FAIL]` blocks the run, `[warn]` has findings
+/// but does not block, `[ok  ]` otherwise.
+pub fn render(verdicts: &[Verdict]) -> String {
+    let mut out = String::new();
+    for v in verdicts {
+        let tag = if v.fails() {
+            "FAIL"
+        } else if v.findings > 0 {
+            "warn"
+        } else {
+            "ok"
+        };
+        let gate = v.threshold.map_or("gate off".to_string(), |t| format!("fails at {}", t));
+        let _ = writeln!(out, "[{:<4}] {}: {} ({})", tag, v.analyzer, v.risk, gate);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::test_pull_request;
+    use crate::report::{build, AnalysisResult, Finding};
+
+    fn result(analyzer: &str, risk: RiskLevel) -> AnalysisResult {
+        AnalysisResult { analyzer_name: analyzer.to_string(), risk_level: risk, findings: vec![Finding::text("x", risk)] }
+    }
+
+    #[test]
+    fn test_per_analyzer_gates_override_fail_on() {
+        let report = build(
+            vec![
+                result("Security Risk Assessment", RiskLevel::Medium),
+                result("Complexity Assessment", RiskLevel::Medium),
+                result("Style & Architecture Assessment", RiskLevel::High),
+            ],
+            &test_pull_request(),
+        );
+        let mut config = Config::default();
+        config.ci.insert("security".to_string(), Gate::Medium);
+        config.ci.insert("style".to_string(), Gate::Off);
+
+        let verdicts = evaluate(&report, &config, Some(Gate::High)).unwrap();
+        let fails: Vec<(&str, bool)> = verdicts.iter().map(|v| (v.analyzer.as_str(), v.fails())).collect();
+        assert_eq!(fails, [("Security Risk Assessment", true), ("Complexity Assessment", false), ("Style & Architecture Assessment", false)]);
+        let text = render(&verdicts);
+        assert!(text.contains("[FAIL] Security Risk Assessment: MEDIUM (fails at MEDIUM)\n"), "{text}");
+        assert!(text.contains("[warn] Style & Architecture Assessment: HIGH (gate off)\n"), "{text}");
+
+        // Without --fail-on only the [ci] analyzers are gated.
+        assert_eq!(evaluate(&report, &config, None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_ci_analyzer_is_an_error() {
+        let mut config = Config::default();
+        config.ci.insert("secrity".to_string(), Gate::High);
+        let err = check(&config).unwrap_err();
+        assert!(err.to_string().starts_with("Unknown [ci] analyzer 'secrity' (known: security, complexity"), "{err}");
+    }
+}
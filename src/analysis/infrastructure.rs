@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+
+use super::{Analyzer, AnalysisError, RuleSpec};
+use crate::pr::PullRequest;
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel};
+
+/// Resource type prefixes that control identity, network exposure, or key material.
+const SENSITIVE_RESOURCE_PREFIXES: &[&str] = &[
+    "aws_iam_",
+    "aws_security_group",
+    "aws_vpc_security_group_",
+    "aws_network_acl",
+    "aws_kms_",
+    "aws_secretsmanager_",
+    "google_project_iam_",
+    "google_service_account",
+    "google_compute_firewall",
+    "google_kms_",
+    "azurerm_role_",
+    "azurerm_key_vault",
+    "azurerm_network_security_",
+];
+
+/// Plan-style classification of a resource touched by the diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Add,
+    Update,
+    Destroy,
+}
+
+impl Change {
+    fn symbol(self) -> &'static str {
+        match self {
+            Change::Add => "+",
+            Change::Update => "~",
+            Change::Destroy => "-",
+        }
+    }
+
+    /// Message id of a sensitive resource with this change.
+    fn sensitive_message_id(self) -> &'static str {
+        match self {
+            Change::Add => "infrastructure.sensitive_added",
+            Change::Update => "infrastructure.sensitive_changed",
+            Change::Destroy => "infrastructure.sensitive_removed",
+        }
+    }
+}
+
+/// A Terraform resource touched by the diff.
+struct ResourceChange {
+    resource_type: String,
+    name: String,
+    change: Change,
+    file: String,
+    /// First changed line of the block; `None` while only a context header has been seen.
+    line: Option<usize>,
+}
+
+/// Infrastructure Analyzer
+///
+/// Summarizes Terraform (`.tf`) changes the way `terraform plan` would:
+/// - Resources added, changed, and removed, grouped by type
+/// - Sensitive resource types (IAM, security groups, KMS keys) at HIGH severity
+/// - Variable file (`.tfvars`) changes
+#[derive(Default)]
+pub struct InfrastructureAnalyzer;
+
+impl InfrastructureAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Classify every `resource` block touched by added or removed lines.
+    fn collect_changes(&self, pr: &PullRequest) -> Vec<ResourceChange> {
+        let mut changes: Vec<ResourceChange> = Vec::new();
+        for file in pr.files().iter().filter(|f| f.path().ends_with(".tf")) {
+            for hunk in file.hunks() {
+                // Index into `changes` of the block the current line belongs to.
+                let mut current: Option<usize> = None;
+                for (i, line) in hunk.lines.iter().enumerate() {
+                    let (prefix, content) = line.split_at(line.len().min(1));
+                    if let Some((resource_type, name)) = resource_header(content) {
+                        let change = match prefix {
+                            "+" => Some(Change::Add),
+                            "-" => Some(Change::Destroy),
+                            _ => None,
+                        };
+                        let existing = changes
+                            .iter()
+                            .position(|c| c.file == file.path() && c.resource_type == resource_type && c.name == name);
+                        current = match (existing, change) {
+                            (Some(idx), Some(change)) => {
+                                if changes[idx].change != change {
+                                    changes[idx].change = Change::Update;
+                                }
+                                Some(idx)
+                            }
+                            (Some(idx), None) => Some(idx),
+                            (None, change) => {
+                                changes.push(ResourceChange {
+                                    resource_type: resource_type.to_string(),
+                                    name: name.to_string(),
+                                    change: change.unwrap_or(Change::Update),
+                                    file: file.path().to_string(),
+                                    line: change.map(|_| hunk.new_start + i),
+                                });
+                                Some(changes.len() - 1)
+                            }
+                        };
+                        continue;
+                    }
+                    if content.trim_end() == "}" && !content.starts_with(char::is_whitespace) {
+                        current = None;
+                        continue;
+                    }
+                    if let Some(idx) = current {
+                        if (prefix == "+" || prefix == "-") && changes[idx].line.is_none() {
+                            changes[idx].line = Some(hunk.new_start + i);
+                        }
+                    }
+                }
+            }
+        }
+        // Drop context-only headers whose block had no changed lines.
+        changes.retain(|c| c.line.is_some());
+        changes
+    }
+
+    /// One plan-style summary finding plus a HIGH finding per sensitive resource.
+    fn check_resources(&self, pr: &PullRequest) -> Vec<Finding> {
+        let changes = self.collect_changes(pr);
+        if changes.is_empty() {
+            return Vec::new();
+        }
+        let mut findings = Vec::new();
+        let count = |kind: Change| changes.iter().filter(|c| c.change == kind).count();
+        let mut by_type: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for c in &changes {
+            *by_type.entry((c.resource_type.as_str(), c.change.symbol())).or_default() += 1;
+        }
+        let breakdown: Vec<String> = by_type.iter().map(|((ty, symbol), n)| format!("{}: {}{}", ty, symbol, n)).collect();
+        let destroys = count(Change::Destroy);
+        findings.push(
+            Finding::new("infrastructure.plan_summary", if destroys > 0 { RiskLevel::Medium } else { RiskLevel::Low })
+                .because(if destroys > 0 { "the plan destroys resources" } else { "the plan destroys nothing" })
+                .param("add", count(Change::Add))
+                .param("change", count(Change::Update))
+                .param("destroy", destroys)
+                .param("resources", breakdown.join(", ")),
+        );
+
+        for c in changes.iter().filter(|c| is_sensitive(&c.resource_type)) {
+            findings.push(
+                Finding::new(c.change.sensitive_message_id(), RiskLevel::High)
+                    .param("resource", format!("{}.{}", c.resource_type, c.name))
+                    .in_file(&c.file)
+                    .at_line(c.line),
+            );
+        }
+        findings
+    }
+
+    /// Note variable file changes, which alter every environment that loads them.
+    fn check_tfvars(&self, pr: &PullRequest) -> Vec<Finding> {
+        pr.files()
+            .iter()
+            .filter(|f| f.path().ends_with(".tfvars") || f.path().ends_with(".tfvars.json"))
+            .map(|f| Finding::new("infrastructure.tfvars_changed", RiskLevel::Low).param("additions", f.additions()).param("deletions", f.deletions()).in_file(f.path()))
+            .collect()
+    }
+}
+
+/// Parse `resource "aws_s3_bucket" "logs" {` into `("aws_s3_bucket", "logs")`.
+fn resource_header(content: &str) -> Option<(&str, &str)> {
+    let rest = content.trim_start().strip_prefix("resource")?;
+    let mut quoted = rest.split('"');
+    quoted.next().filter(|gap| gap.trim().is_empty())?;
+    let resource_type = quoted.next()?;
+    quoted.next().filter(|gap| gap.trim().is_empty())?;
+    let name = quoted.next()?;
+    (!resource_type.is_empty() && !name.is_empty()).then_some((resource_type, name))
+}
+
+fn is_sensitive(resource_type: &str) -> bool {
+    SENSITIVE_RESOURCE_PREFIXES.iter().any(|p| resource_type.starts_with(p))
+}
+
+#[async_trait]
+impl Analyzer for InfrastructureAnalyzer {
+    fn name(&self) -> &str {
+        "Infrastructure Assessment"
+    }
+
+    fn rules_version(&self) -> u32 {
+        1
+    }
+
+    fn rules(&self) -> Vec<RuleSpec> {
+        vec![
+            RuleSpec::file("INF001", "infrastructure.plan_summary", Category::Infrastructure, &["infrastructure.plan_summary"]),
+            RuleSpec::file("INF002", "infrastructure.sensitive_resources", Category::AccessControl, &["infrastructure.sensitive_added", "infrastructure.sensitive_changed", "infrastructure.sensitive_removed"]),
+            RuleSpec::file("INF003", "infrastructure.tfvars", Category::Infrastructure, &["infrastructure.tfvars_changed"]),
+        ]
+    }
+
+    async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError> {
+        let mut findings = Vec::new();
+        findings.extend(self.check_resources(pr));
+        findings.extend(self.check_tfvars(pr));
+
+        let risk_level = if findings.iter().any(|f| f.severity == RiskLevel::High) {
+            RiskLevel::High
+        } else if findings.iter().any(|f| f.severity == RiskLevel::Medium) {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        };
+
+        Ok(AnalysisResult {
+            analyzer_name: self.name().to_string(),
+            risk_level,
+            findings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+
+    fn lines(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_empty_pr_returns_low_infrastructure_risk() {
+        let result = InfrastructureAnalyzer::new().analyze(&test_pull_request()).await.unwrap();
+        assert_eq!(result.risk_level, RiskLevel::Low);
+        assert!(result.findings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_summarizes_added_changed_and_removed_resources() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "infra/main.tf",
+            lines(&[
+                "+resource \"aws_s3_bucket\" \"logs\" {",
+                "+  bucket = \"logs\"",
+                "+}",
+                " resource \"aws_instance\" \"web\" {",
+                "-  instance_type = \"t3.micro\"",
+                "+  instance_type = \"t3.large\"",
+                " }",
+                " resource \"aws_instance\" \"untouched\" {",
+                "   ami = \"ami-123\"",
+                " }",
+                "-resource \"aws_sqs_queue\" \"jobs\" {",
+                "-}",
+            ]),
+        )]);
+        let result = InfrastructureAnalyzer::new().analyze(&pr).await.unwrap();
+        let summary = &result.findings[0];
+        assert!(summary.message.starts_with("Terraform plan summary: 1 to add, 1 to change, 1 to destroy"));
+        assert!(summary.message.contains("aws_instance: ~1"));
+        assert_eq!(summary.severity, RiskLevel::Medium);
+        assert_eq!(result.risk_level, RiskLevel::Medium);
+    }
+
+    #[tokio::test]
+    async fn test_sensitive_resources_are_high() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "iam.tf",
+            lines(&["+resource \"aws_iam_policy\" \"admin\" {", "+  policy = data.aws_iam_policy_document.admin.json", "+}"]),
+        )]);
+        let result = InfrastructureAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().any(|f| f.message == "Sensitive resource aws_iam_policy.admin added" && f.severity == RiskLevel::High));
+        assert_eq!(result.risk_level, RiskLevel::High);
+    }
+
+    #[tokio::test]
+    async fn test_tfvars_change_is_noted() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("env/prod.tfvars", lines(&["-replicas = 2", "+replicas = 6"]))]);
+        let result = InfrastructureAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(result.findings.iter().any(|f| f.message.contains("Terraform variables changed")));
+    }
+
+    #[test]
+    fn test_resource_header_parsing() {
+        assert_eq!(resource_header("resource \"aws_kms_key\" \"main\" {"), Some(("aws_kms_key", "main")));
+        assert_eq!(resource_header("data \"aws_ami\" \"ubuntu\" {"), None);
+        assert_eq!(resource_header("resources = []"), None);
+    }
+}
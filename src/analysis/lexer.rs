@@ -0,0 +1,312 @@
+//! Line-level tokenizer that separates code from comments and string literals.
+//!
+//! Cheaper and broader than `syntax`: it knows the comment and quote syntax of
+//! many languages without parsing them, so substring checks can skip matches
+//! inside comments, docs, and (where it matters) string literals. Prose files
+//! such as Markdown are treated as one long comment.
+//!
+//! Lines without any comment or quote delimiter (most of them) are returned
+//! borrowed, so masking costs no allocation.
+
+use std::borrow::Cow;
+
+/// Comment and quote syntax of a language family.
+struct Grammar {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Quote delimiters, longest first
+    quotes: &'static [&'static str],
+    /// Quotes whose literals may span lines
+    multiline_quotes: &'static [&'static str],
+}
+
+const C_LIKE: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &["\"", "'"],
+    multiline_quotes: &[],
+};
+
+const RUST: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    // `'` also starts lifetimes, so char literals are left as code.
+    quotes: &["\""],
+    multiline_quotes: &["\""],
+};
+
+const JS_GO: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &["`", "\"", "'"],
+    multiline_quotes: &["`"],
+};
+
+const PYTHON: Grammar = Grammar {
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &["\"\"\"", "'''", "\"", "'"],
+    multiline_quotes: &["\"\"\"", "'''"],
+};
+
+const HASH: Grammar = Grammar {
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &["\"", "'"],
+    multiline_quotes: &[],
+};
+
+const HCL: Grammar = Grammar {
+    line_comments: &["#", "//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &["\""],
+    multiline_quotes: &[],
+};
+
+const SQL: Grammar = Grammar {
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &["'"],
+    multiline_quotes: &["'"],
+};
+
+impl Grammar {
+    /// Whether `b` is the first byte of any comment or quote delimiter.
+    fn opens_with(&self, b: u8) -> bool {
+        let first = |s: &&str| s.as_bytes().first() == Some(&b);
+        self.line_comments.iter().any(first)
+            || self.block_comment.is_some_and(|(open, _)| first(&open))
+            || self.quotes.iter().any(first)
+    }
+}
+
+fn grammar_for(path: &str) -> Option<&'static Grammar> {
+    let name = crate::paths::file_name(path);
+    if name == "Dockerfile" || name.starts_with("Dockerfile.") || name == "Gemfile" {
+        return Some(&HASH);
+    }
+    let ext = name.rsplit_once('.')?.1.to_ascii_lowercase();
+    match ext.as_str() {
+        "rs" => Some(&RUST),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" | "go" => Some(&JS_GO),
+        "java" | "kt" | "kts" | "swift" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "scala" | "dart" | "php" => Some(&C_LIKE),
+        "py" | "pyi" => Some(&PYTHON),
+        "rb" | "sh" | "bash" | "zsh" | "yml" | "yaml" | "toml" | "r" | "pl" => Some(&HASH),
+        "tf" | "hcl" => Some(&HCL),
+        "sql" => Some(&SQL),
+        _ => None,
+    }
+}
+
+fn is_prose(path: &str) -> bool {
+    let name = crate::paths::file_name(path).to_ascii_lowercase();
+    [".md", ".markdown", ".mdx", ".rst", ".txt", ".adoc"].iter().any(|ext| name.ends_with(ext))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+    BlockComment,
+    Str(&'static str),
+}
+
+/// One diff line with comments (and optionally string contents) blanked out.
+///
+/// Blanked characters become spaces, so byte offsets into the masked text
+/// still line up with the original for ASCII input.
+pub struct MaskedLine<'a> {
+    /// Code and string literals; comments blanked
+    pub code: Cow<'a, str>,
+    /// Code only; comments and string contents blanked, quotes kept
+    pub bare: Cow<'a, str>,
+}
+
+/// Streams the lines of one hunk, carrying block-comment and multi-line
+/// string state from line to line.
+pub struct Lexer {
+    grammar: Option<&'static Grammar>,
+    prose: bool,
+    state: State,
+}
+
+impl Lexer {
+    pub fn for_path(path: &str) -> Self {
+        Self {
+            grammar: grammar_for(path),
+            prose: is_prose(path),
+            state: State::Code,
+        }
+    }
+
+    /// Tokenize the next diff line (with its `+`/`-`/` ` prefix).
+    ///
+    /// Removed lines are masked but don't advance the state, since they are
+    /// not part of the new file.
+    pub fn next_line<'a>(&mut self, line: &'a str) -> MaskedLine<'a> {
+        let content = match line.as_bytes().first() {
+            Some(b'+' | b'-' | b' ') => &line[1..],
+            _ => line,
+        };
+        if self.prose {
+            let blank = " ".repeat(content.chars().count());
+            return MaskedLine { code: Cow::Owned(blank.clone()), bare: Cow::Owned(blank) };
+        }
+        let Some(grammar) = self.grammar else {
+            return MaskedLine { code: Cow::Borrowed(content), bare: Cow::Borrowed(content) };
+        };
+        if self.state == State::Code && !content.bytes().any(|b| grammar.opens_with(b)) {
+            return MaskedLine { code: Cow::Borrowed(content), bare: Cow::Borrowed(content) };
+        }
+        let saved = self.state;
+        let masked = self.scan(grammar, content);
+        if line.starts_with('-') {
+            self.state = saved;
+        }
+        masked
+    }
+
+    fn scan(&mut self, grammar: &'static Grammar, content: &str) -> MaskedLine<'static> {
+        let mut code = String::with_capacity(content.len());
+        let mut bare = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(c) = rest.chars().next() {
+            match self.state {
+                State::Code => {
+                    if grammar.line_comments.iter().any(|open| rest.starts_with(open)) {
+                        let blank = " ".repeat(rest.chars().count());
+                        code.push_str(&blank);
+                        bare.push_str(&blank);
+                        break;
+                    }
+                    if let Some((open, _)) = grammar.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+                        self.state = State::BlockComment;
+                        push_blank(&mut code, &mut bare, open.len());
+                        rest = &rest[open.len()..];
+                        continue;
+                    }
+                    if let Some(quote) = grammar.quotes.iter().find(|q| rest.starts_with(**q)) {
+                        self.state = State::Str(quote);
+                        code.push_str(quote);
+                        bare.push_str(quote);
+                        rest = &rest[quote.len()..];
+                        continue;
+                    }
+                    code.push(c);
+                    bare.push(c);
+                }
+                State::BlockComment => {
+                    if let Some((_, close)) = grammar.block_comment.filter(|(_, close)| rest.starts_with(close)) {
+                        self.state = State::Code;
+                        push_blank(&mut code, &mut bare, close.len());
+                        rest = &rest[close.len()..];
+                        continue;
+                    }
+                    push_blank(&mut code, &mut bare, 1);
+                }
+                State::Str(quote) => {
+                    if rest.starts_with(quote) {
+                        self.state = State::Code;
+                        code.push_str(quote);
+                        bare.push_str(quote);
+                        rest = &rest[quote.len()..];
+                        continue;
+                    }
+                    if c == '\\' && quote != "`" {
+                        if let Some(escaped) = rest[1..].chars().next() {
+                            code.push(c);
+                            code.push(escaped);
+                            bare.push_str("  ");
+                            rest = &rest[1 + escaped.len_utf8()..];
+                            continue;
+                        }
+                    }
+                    code.push(c);
+                    bare.push(' ');
+                }
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+        if let State::Str(quote) = self.state {
+            if !grammar.multiline_quotes.contains(&quote) {
+                self.state = State::Code;
+            }
+        }
+        MaskedLine { code: Cow::Owned(code), bare: Cow::Owned(bare) }
+    }
+}
+
+fn push_blank(code: &mut String, bare: &mut String, n: usize) {
+    for _ in 0..n {
+        code.push(' ');
+        bare.push(' ');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(path: &str, lines: &[&str]) -> Vec<(String, String)> {
+        let mut lexer = Lexer::for_path(path);
+        lines
+            .iter()
+            .map(|l| {
+                let m = lexer.next_line(l);
+                (m.code.trim_end().to_string(), m.bare.trim_end().to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_comments_are_blanked() {
+        let out = mask("src/lib.rs", &["+let x = y.unwrap(); // never .unwrap() here"]);
+        assert_eq!(out[0].0, "let x = y.unwrap();");
+    }
+
+    #[test]
+    fn test_string_contents_only_blanked_in_bare() {
+        let out = mask("app.py", &["+password = \"hunter2\"  # example"]);
+        assert_eq!(out[0].0, "password = \"hunter2\"");
+        assert_eq!(out[0].1, "password = \"       \"");
+    }
+
+    #[test]
+    fn test_comment_markers_inside_strings_are_code() {
+        let out = mask("main.go", &["+url := \"http://example.com\" // link"]);
+        assert_eq!(out[0].0, "url := \"http://example.com\"");
+    }
+
+    #[test]
+    fn test_block_comment_spans_lines_but_removed_lines_do_not_count() {
+        let out = mask("a.ts", &["+/* start", "-*/", "+eval(x) */ run(y)"]);
+        assert_eq!(out[0].0, "");
+        assert_eq!(out[2].0.trim_start(), "run(y)");
+    }
+
+    #[test]
+    fn test_python_docstring_spans_lines() {
+        let out = mask("m.py", &["+\"\"\"Usage:", "+    eval(expr)", "+\"\"\"", "+eval(expr)"]);
+        assert_eq!(out[1].1, "");
+        assert_eq!(out[3].1, "eval(expr)");
+    }
+
+    #[test]
+    fn test_escaped_quote_does_not_close_string() {
+        let out = mask("x.js", &["+s = \"a \\\" // b\"; t()"]);
+        assert!(out[0].0.ends_with("t()"));
+    }
+
+    #[test]
+    fn test_plain_code_is_borrowed() {
+        let mut lexer = Lexer::for_path("src/lib.rs");
+        let masked = lexer.next_line("+let total = a + b;");
+        assert!(matches!(masked.code, Cow::Borrowed("let total = a + b;")));
+        assert!(matches!(lexer.next_line("+let s = \"x\";").bare, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_prose_is_all_comment_and_unknown_is_all_code() {
+        assert_eq!(mask("README.md", &["+password = \"x\""])[0].0, "");
+        assert_eq!(mask("data.csv", &["+a,# b"])[0].0, "a,# b");
+    }
+}
@@ -1,133 +1,1172 @@
+pub mod api_contract;
 pub mod complexity;
+pub mod container;
+pub mod docs;
+pub mod effort;
+pub mod explain;
+pub mod gate;
+pub mod infrastructure;
+pub mod lexer;
+mod normalize;
+pub mod process;
+pub mod rules;
+mod scan;
 pub mod security;
+pub mod split;
 pub mod style;
+pub mod surface;
+#[cfg(feature = "syntax")]
+pub mod syntax;
+#[cfg(not(feature = "syntax"))]
+#[path = "syntax_disabled.rs"]
+pub mod syntax;
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::pin;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use futures::future::FutureExt;
+use futures::stream::{self, StreamExt};
 use thiserror::Error;
-use tracing::{debug, info_span, Instrument};
+use tracing::{debug, info, info_span, warn, Instrument};
 
-use crate::config::Config;
+use self::scan::{LineFindings, LinePass, LineRules};
+use crate::config::{Config, Profile, RuntimeConfig, Selection};
+use crate::events::{self, AnalysisEvent, Events};
+use crate::paths::{self, file_name};
+use crate::pr::types::DiffFile;
 use crate::pr::PullRequest;
-use crate::report::types::AnalysisResult;
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel, RuleRun, RuleStatus, Warning};
+use crate::report::{Layout, Part};
 
 #[derive(Debug, Error)]
 pub enum AnalysisError {
+    #[allow(dead_code)] // No built-in analyzer fails yet; kept so analyzers have a typed failure path
     #[error("Analysis failed for {analyzer}: {reason}")]
     Failed {
         analyzer: String,
         reason: String,
     },
+
+    #[error("Unknown {kind} '{name}' (known: {known})")]
+    UnknownSelection {
+        kind: &'static str,
+        name: String,
+        known: String,
+    },
+
+    #[error("Rule '{rule}' does not belong to analyzer '{analyzer}'")]
+    SelectionMismatch {
+        rule: String,
+        analyzer: String,
+    },
+
+    #[error("Analyzer '{analyzer}' is required by [policy] and cannot be left out")]
+    RequiredAnalyzer { analyzer: String },
 }
 
 /// Core trait that all risk analyzers must implement.
-/// Analyzers must be Send + Sync to run concurrently via tokio::join!.
+/// Analyzers must be Send + Sync so run_all can drive them concurrently.
 #[async_trait]
 pub trait Analyzer: Send + Sync {
     /// Human-readable name of this analyzer (e.g., "Security Risk Assessment")
     fn name(&self) -> &str;
 
+    /// Version of this analyzer's rules. Bump it whenever detection logic
+    /// or messages change, so results stored by an older version (history,
+    /// cached reports) are recognizably stale even if the config is unchanged.
+    fn rules_version(&self) -> u32;
+
+    /// Every check this analyzer runs, for the report's rules appendix.
+    fn rules(&self) -> Vec<RuleSpec>;
+
     /// Run the analysis on the given pull request and return structured results.
     /// Must not print to stdout — return findings via AnalysisResult.
     async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError>;
+
+    /// Per-line rules this analyzer contributes to the shared line pass.
+    fn line_rules(&self) -> Option<&dyn LineRules> {
+        None
+    }
+
+    /// `analyze`, with this analyzer's `line_rules` already run by the
+    /// shared line pass in `run_all`. Analyzers with line rules override it
+    /// (and implement `analyze` by running their rules alone).
+    async fn analyze_scanned(&self, pr: &PullRequest, lines: LineFindings) -> Result<AnalysisResult, AnalysisError> {
+        let _ = lines;
+        self.analyze(pr).await
+    }
+}
+
+/// One check of an analyzer, as listed in the report's rules appendix.
+#[derive(Debug, Clone)]
+pub struct RuleSpec {
+    /// Short code that stays the same when the rule is renamed, e.g.
+    /// `SEC002`: the analyzer's prefix and the rule's number in it. Numbers
+    /// are never reused; new rules take the next one
+    pub code: &'static str,
+    /// `<table>.<rule>`, e.g. `security.hardcoded_secret`
+    pub id: &'static str,
+    /// Category of the findings it reports
+    pub category: Category,
+    /// Message ids of the findings it reports
+    pub messages: &'static [&'static str],
+    /// Runs in the shared line pass, which a too-large change skips
+    pub line_pass: bool,
+    /// Needs a checkout (`--repo` / `--clone`)
+    pub needs_checkout: bool,
+    /// Executes code from the checkout (build scripts, proc macros), so it
+    /// does not run on PRs from forks
+    pub runs_pr_code: bool,
+    /// Why the rule is off in this configuration
+    pub disabled: Option<&'static str>,
+    /// Message id of the finding that says the rule could not run
+    pub failure: Option<&'static str>,
 }
 
-/// Run all three analyzers concurrently and collect their results.
+impl RuleSpec {
+    /// A rule of the shared line pass.
+    pub fn line(code: &'static str, id: &'static str, category: Category, messages: &'static [&'static str]) -> Self {
+        Self { code, id, category, messages, line_pass: true, needs_checkout: false, runs_pr_code: false, disabled: None, failure: None }
+    }
+
+    /// A rule that reads whole files or the PR, outside the line pass.
+    pub fn file(code: &'static str, id: &'static str, category: Category, messages: &'static [&'static str]) -> Self {
+        Self { line_pass: false, ..Self::line(code, id, category, messages) }
+    }
+
+    pub fn needs_checkout(mut self) -> Self {
+        self.needs_checkout = true;
+        self
+    }
+
+    pub fn runs_pr_code(mut self) -> Self {
+        self.runs_pr_code = true;
+        self
+    }
+
+    /// Mark the rule off, with `reason`, when `off` holds.
+    pub fn disabled_if(mut self, off: bool, reason: &'static str) -> Self {
+        if off {
+            self.disabled = Some(reason);
+        }
+        self
+    }
+
+    pub fn fails_with(mut self, message_id: &'static str) -> Self {
+        self.failure = Some(message_id);
+        self
+    }
+}
+
+/// Every built-in analyzer, in report order.
+pub fn analyzers(config: &Config) -> Vec<Box<dyn Analyzer>> {
+    vec![
+        Box::new(security::SecurityAnalyzer::with_config(&config.security)),
+        Box::new(complexity::ComplexityAnalyzer::with_config(&config.thresholds)),
+        Box::new(style::StyleAnalyzer::with_config(&config.style)),
+        Box::new(container::ContainerAnalyzer::new()),
+        Box::new(api_contract::ApiContractAnalyzer::new()),
+        Box::new(infrastructure::InfrastructureAnalyzer::new()),
+        Box::new(process::ProcessAnalyzer::with_config(&config.process)),
+        Box::new(rules::RulesAnalyzer::with_config(&config.rules)),
+    ]
+}
+
+/// Short name of an analyzer for `--only-analyzer`: the table its rule ids
+/// start with, e.g. `security` or `api_contract`.
+pub fn analyzer_key(analyzer: &dyn Analyzer) -> &'static str {
+    analyzer.rules().first().and_then(|r| r.id.split_once('.')).map_or("", |(table, _)| table)
+}
+
+fn is_analyzer(analyzer: &dyn Analyzer, name: &str) -> bool {
+    name.eq_ignore_ascii_case(analyzer_key(analyzer)) || name.eq_ignore_ascii_case(analyzer.name())
+}
+
+fn has_rule(analyzer: &dyn Analyzer, rule: &str) -> bool {
+    analyzer.rules().iter().any(|s| s.id.eq_ignore_ascii_case(rule))
+}
+
+/// The analyzers of `all` that `only` selects. Names that match nothing are
+/// an error listing the valid ones, so a typo does not silently run nothing.
+/// A selection that leaves out a `required` analyzer (`[policy]
+/// required_analyzers`) is an error too.
+pub(crate) fn select(all: Vec<Box<dyn Analyzer>>, only: &Selection, required: &[String]) -> Result<Vec<Box<dyn Analyzer>>, AnalysisError> {
+    if let Some(name) = &only.analyzer {
+        if !all.iter().any(|a| is_analyzer(a.as_ref(), name)) {
+            let known = all.iter().map(|a| analyzer_key(a.as_ref())).collect::<Vec<_>>().join(", ");
+            return Err(AnalysisError::UnknownSelection { kind: "analyzer", name: name.clone(), known });
+        }
+    }
+    if let Some(rule) = &only.rule {
+        let Some(owner) = all.iter().find(|a| has_rule(a.as_ref(), rule)) else {
+            let known = all.iter().flat_map(|a| a.rules()).map(|s| s.id).collect::<Vec<_>>().join(", ");
+            return Err(AnalysisError::UnknownSelection { kind: "rule", name: rule.clone(), known });
+        };
+        if let Some(name) = only.analyzer.as_ref().filter(|name| !is_analyzer(owner.as_ref(), name)) {
+            return Err(AnalysisError::SelectionMismatch { rule: rule.clone(), analyzer: name.clone() });
+        }
+    }
+    if let Some(name) = required.iter().find(|name| !all.iter().any(|a| is_analyzer(a.as_ref(), name))) {
+        let known = all.iter().map(|a| analyzer_key(a.as_ref())).collect::<Vec<_>>().join(", ");
+        return Err(AnalysisError::UnknownSelection { kind: "[policy] required analyzer", name: name.clone(), known });
+    }
+    let selected: Vec<_> = all
+        .into_iter()
+        .filter(|a| only.analyzer.as_ref().is_none_or(|name| is_analyzer(a.as_ref(), name)))
+        .filter(|a| only.rule.as_ref().is_none_or(|rule| has_rule(a.as_ref(), rule)))
+        .collect();
+    if let Some(name) = required.iter().find(|name| !selected.iter().any(|a| is_analyzer(a.as_ref(), name))) {
+        return Err(AnalysisError::RequiredAnalyzer { analyzer: name.clone() });
+    }
+    Ok(selected)
+}
+
+/// Check `config.only` (and the policy's required analyzers) before any work
+/// is done; `run_all` would reject it too.
+pub fn check_selection(config: &Config) -> Result<(), AnalysisError> {
+    select(analyzers(config), &config.only, &config.policy.required_analyzers).map(|_| ())
+}
+
+/// `[report]` as a `Layout`, with analyzer sections resolved to analyzer
+/// names. An unknown section name is an error, listing the valid ones.
+pub fn report_layout(config: &Config) -> Result<Layout, AnalysisError> {
+    let all = analyzers(config);
+    let sections = config
+        .report
+        .sections
+        .iter()
+        .map(|name| {
+            if let Some(part) = Part::named(name) {
+                return Ok(part);
+            }
+            match all.iter().find(|a| is_analyzer(a.as_ref(), name)) {
+                Some(analyzer) => Ok(Part::Analyzer(analyzer.name().to_string())),
+                None => {
+                    let known = Part::NAMES.iter().copied().chain(all.iter().map(|a| analyzer_key(a.as_ref()))).collect::<Vec<_>>().join(", ");
+                    Err(AnalysisError::UnknownSelection { kind: "[report] section", name: name.clone(), known })
+                }
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Layout { sections, hide_empty: config.report.hide_empty })
+}
+
+/// Rules version of every built-in analyzer, keyed by analyzer name.
+/// Together with `Config::fingerprint` this identifies the exact rule set a
+/// report was produced with.
+pub fn rule_versions(config: &Config) -> BTreeMap<String, u32> {
+    analyzers(config).iter().map(|a| (a.name().to_string(), a.rules_version())).collect()
+}
+
+/// Name of the shared line pass in `AnalysisRun::timings`.
+pub const LINE_PASS: &str = "line pass";
+
+/// Results of one `run_all` call.
+#[derive(Debug)]
+pub struct AnalysisRun {
+    /// One entry per analyzer that finished, in report order
+    pub results: Vec<AnalysisResult>,
+    /// True when `cancel` fired before every analyzer finished
+    pub interrupted: bool,
+    /// Wall time of the shared line pass, then of each finished analyzer in report order
+    pub timings: Vec<(String, Duration)>,
+    /// Every rule of every analyzer, with whether it ran
+    pub rules: Vec<RuleRun>,
+    /// Parts of the change the analysis could not see (see `diff_warnings`)
+    pub warnings: Vec<Warning>,
+}
+
+/// Run all analyzers concurrently and collect their results.
 ///
-/// Claude: Implement using tokio::join! to run SecurityAnalyzer,
-/// ComplexityAnalyzer, and StyleAnalyzer in parallel.
+/// The per-line rules of all analyzers run first, in one pass over the diff
+/// (see `scan`); each analyzer then gets its share of the line findings.
+/// Formatting-only changes and code moved between files are turned into
+/// context first (see `normalize`) and noted in the complexity result.
+/// An analyzer with many MEDIUM or LOW findings is raised one risk level
+/// (see `escalate_by_volume`); then findings below the active profile's
+/// `min_severity` are dropped.
+/// Enforces the `[runtime]` budget: at most `max_concurrent_analyzers` run at
+/// once, files larger than `max_file_size_kb` are skipped (and reported as
+/// complexity findings), and the report keeps at most `max_total_findings`.
+/// A change over `max_diff_bytes` or `max_files` skips normalization and the
+/// line pass; only file-level checks run, plus a HIGH complexity finding.
 ///
-/// Returns a Vec<AnalysisResult> with one entry per analyzer,
-/// or propagates the first error encountered.
-pub async fn run_all(pr: &PullRequest, config: &Config) -> Result<Vec<AnalysisResult>, AnalysisError> {
-    let security = security::SecurityAnalyzer::with_config(&config.security);
-    let complexity = complexity::ComplexityAnalyzer::new();
-    let style = style::StyleAnalyzer::new();
-
-    let (sec_result, comp_result, style_result) = tokio::join!(
-        security.analyze(pr).instrument(info_span!("analyze", analyzer = "security")),
-        complexity.analyze(pr).instrument(info_span!("analyze", analyzer = "complexity")),
-        style.analyze(pr).instrument(info_span!("analyze", analyzer = "style")),
-    );
+/// `cancel` is checked before each preprocessing step, before each file of
+/// the line pass, and while analyzers run. When it resolves, the work under
+/// way is dropped and the results finished so far are returned with
+/// `interrupted` set; before the analyzers start that is none. Otherwise
+/// returns one result per analyzer, in report order, or propagates the first
+/// error.
+///
+/// `config.only` narrows the run to one analyzer, or to one rule: the
+/// rule's analyzer runs and only that rule's findings are kept. Its other
+/// rules are listed as skipped; other analyzers are left out altogether.
+pub async fn run_all(pr: &PullRequest, config: &Config, cancel: impl Future<Output = ()>) -> Result<AnalysisRun, AnalysisError> {
+    run_all_with_events(pr, config, cancel, &events::ignore).await
+}
+
+/// `run_all`, reporting its progress to `events` (see `events`): the
+/// analyzers selected, the end of the line pass, and each analyzer's start
+/// and finish with its findings, so a front end can show them before the
+/// whole run is done.
+pub async fn run_all_with_events(pr: &PullRequest, config: &Config, cancel: impl Future<Output = ()>, events: Events<'_>) -> Result<AnalysisRun, AnalysisError> {
+    let runtime = &config.runtime;
+    let mut cancel = pin!(cancel.fuse());
+    let mut cancelled = false;
+    let mut fired = || {
+        cancelled = cancelled || cancel.as_mut().now_or_never().is_some();
+        cancelled
+    };
+    let warnings = diff_warnings(pr);
+    let (budgeted, skipped) = apply_file_budget(pr, runtime.max_file_size_kb);
+    let pr = budgeted.as_ref().unwrap_or(pr);
+    let too_large = exceeds_line_budget(pr, runtime);
+    let line_pass_skipped = too_large.is_some();
+    if let Some(finding) = &too_large {
+        warn!(reason = %finding.message, "change too large; skipping line-level analysis");
+    }
+    let (reformatted, formatting_only) = match too_large {
+        None if !fired() => normalize::strip_formatting_only(pr),
+        _ => (None, 0),
+    };
+    let pr = reformatted.as_ref().unwrap_or(pr);
+    let (unmoved, moves) = match too_large {
+        None if !fired() => normalize::detect_moves(pr),
+        _ => (None, Vec::new()),
+    };
+    let pr = unmoved.as_ref().unwrap_or(pr);
+
+    let analyzers = select(analyzers(config), &config.only, &config.policy.required_analyzers)?;
+    let specs: Vec<Vec<RuleSpec>> = analyzers.iter().map(|a| a.rules()).collect();
+    let mut results = Vec::with_capacity(analyzers.len());
+    let mut timings = Vec::with_capacity(analyzers.len() + 1);
+    events(AnalysisEvent::AnalysisStarted { analyzers: analyzers.iter().map(|a| a.name().to_string()).collect() });
+
+    let start = Instant::now();
+    let line_rules: Vec<_> = analyzers.iter().map(|a| a.line_rules().filter(|_| too_large.is_none())).collect();
+    let scanned = info_span!("line_pass").in_scope(|| LinePass::new(line_rules.iter().flatten().copied().collect()).run_until(pr, &mut fired));
+    let stopped = scanned.is_none() || fired();
+    let mut line_findings = scanned.unwrap_or_default().into_iter();
+    let line_findings: Vec<LineFindings> = line_rules.iter().map(|r| r.and_then(|_| line_findings.next()).unwrap_or_default()).collect();
+    let line_pass = start.elapsed();
+    timings.push((LINE_PASS.to_string(), line_pass));
+    events(AnalysisEvent::LinePassFinished { duration: line_pass });
+
+    let outcomes: Vec<_> = if stopped {
+        Vec::new()
+    } else {
+        stream::iter(analyzers.iter().zip(&specs).zip(line_findings).map(|((a, specs), lines)| {
+            let span = info_span!("analyze", analyzer = %a.name());
+            async move {
+                events(AnalysisEvent::AnalyzerStarted { name: a.name().to_string() });
+                let start = Instant::now();
+                let mut outcome = a.analyze_scanned(pr, lines).instrument(span).await;
+                let elapsed = start.elapsed();
+                if let Ok(result) = &mut outcome {
+                    categorize(&mut result.findings, specs);
+                    events(AnalysisEvent::AnalyzerFinished { name: a.name().to_string(), duration: elapsed, findings: result.findings.clone() });
+                }
+                (outcome, a.name(), elapsed)
+            }
+        }))
+        .buffered(runtime.max_concurrent_analyzers.max(1))
+        .take_until(cancel)
+        .collect()
+        .await
+    };
+    let interrupted = outcomes.len() < analyzers.len();
+    if interrupted {
+        warn!(completed = outcomes.len(), total = analyzers.len(), "analysis interrupted");
+    }
+    for (outcome, name, elapsed) in outcomes {
+        results.push(outcome?);
+        timings.push((name.to_string(), elapsed));
+    }
+    for (result, specs) in results.iter_mut().zip(&specs) {
+        if let Some(spec) = config.only.rule.as_ref().and_then(|rule| specs.iter().find(|s| s.id.eq_ignore_ascii_case(rule))) {
+            result.findings.retain(|f| f.message_id.as_deref().is_some_and(|id| spec.messages.contains(&id) || spec.failure == Some(id)));
+            result.risk_level = result.findings.iter().map(|f| f.severity).max().unwrap_or(RiskLevel::Low);
+        }
+    }
+
+    if let Some(complexity) = results.iter_mut().find(|r| r.analyzer_name == complexity::NAME) {
+        if let Some(finding) = too_large {
+            complexity.findings.push(finding);
+            complexity.risk_level = RiskLevel::High;
+        }
+        for (path, size_kb) in skipped {
+            complexity.findings.push(
+                Finding::new("analysis.oversized_file", RiskLevel::Medium)
+                    .param("size_kb", size_kb)
+                    .param("max", runtime.max_file_size_kb)
+                    .in_file(path),
+            );
+            complexity.risk_level = complexity.risk_level.max(RiskLevel::Medium);
+        }
+    }
+    let raised: Vec<BTreeMap<String, usize>> = results.iter().map(|r| count_by_message(&r.findings)).collect();
+    for result in &mut results {
+        escalate_by_volume(result, &config.thresholds);
+        result.findings.retain(|f| f.severity >= config.thresholds.min_severity);
+    }
+    if let Some(complexity) = results.iter_mut().find(|r| r.analyzer_name == complexity::NAME) {
+        if formatting_only > 0 {
+            complexity.findings.push(Finding::new("analysis.formatting_only", RiskLevel::Low).param("lines", formatting_only));
+        }
+        for m in moves {
+            complexity.findings.push(Finding::new("analysis.moved_lines", RiskLevel::Low).param("lines", m.lines).param("from", m.from).in_file(m.to));
+        }
+    }
+    apply_findings_budget(&mut results, runtime.max_total_findings);
 
-    let results = vec![sec_result?, comp_result?, style_result?];
     for r in &results {
         debug!(analyzer = %r.analyzer_name, risk = %r.risk_level, findings = r.findings.len(), "analyzer result");
     }
-    Ok(results)
+    let rules = analyzers
+        .iter()
+        .zip(&specs)
+        .enumerate()
+        .flat_map(|(i, (analyzer, specs))| {
+            let result = results.get(i).map(|r| (r, &raised[i]));
+            specs.iter().map(move |spec| {
+                let selected = config.only.rule.as_ref().is_none_or(|rule| spec.id.eq_ignore_ascii_case(rule));
+                rule_run(analyzer.name(), spec, result, selected, pr, line_pass_skipped)
+            })
+        })
+        .collect();
+    Ok(AnalysisRun { results, interrupted, timings, rules, warnings })
+}
+
+/// Set each finding's category, rule id and rule code from the rule that
+/// reports its message id.
+fn categorize(findings: &mut [Finding], specs: &[RuleSpec]) {
+    for finding in findings {
+        let spec = finding.message_id.as_deref().and_then(|id| specs.iter().find(|s| s.messages.contains(&id)));
+        finding.category = spec.map(|s| s.category);
+        finding.rule = spec.map(|s| s.id.to_string());
+        finding.rule_code = spec.map(|s| s.code.to_string());
+    }
+}
+
+/// Number of findings per message id; findings without one are not counted.
+fn count_by_message(findings: &[Finding]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for id in findings.iter().filter_map(|f| f.message_id.as_deref()) {
+        *counts.entry(id.to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Whether `spec` ran, given its analyzer's final result and the findings it
+/// raised before `min_severity` and the findings budget dropped any (None
+/// when the run was interrupted before the analyzer finished). `selected`
+/// is false when `--only-rule` picked another rule.
+fn rule_run(analyzer: &str, spec: &RuleSpec, result: Option<(&AnalysisResult, &BTreeMap<String, usize>)>, selected: bool, pr: &PullRequest, line_pass_skipped: bool) -> RuleRun {
+    let skipped = |reason: &str| RuleRun {
+        analyzer: analyzer.to_string(),
+        rule: spec.id.to_string(),
+        status: RuleStatus::Skipped,
+        findings: 0,
+        suppressed: 0,
+        reason: Some(reason.to_string()),
+    };
+    if !selected {
+        return skipped("not selected (--only-rule)");
+    }
+    if let Some(reason) = spec.disabled {
+        return skipped(reason);
+    }
+    if spec.runs_pr_code && pr.is_fork() {
+        return skipped("would run code from a fork");
+    }
+    if spec.needs_checkout && pr.checkout().is_none() {
+        return skipped("needs --repo or --clone");
+    }
+    if spec.line_pass && line_pass_skipped {
+        return skipped("change too large for line-level checks");
+    }
+    let Some((result, raised)) = result else {
+        return skipped("analysis interrupted");
+    };
+    let ours = |f: &&Finding| f.message_id.as_deref().is_some_and(|id| spec.messages.contains(&id));
+    let findings = result.findings.iter().filter(ours).count();
+    let raised: usize = spec.messages.iter().filter_map(|id| raised.get(*id)).sum();
+    let failure = spec.failure.and_then(|id| result.findings.iter().find(|f| f.message_id.as_deref() == Some(id)));
+    RuleRun {
+        analyzer: analyzer.to_string(),
+        rule: spec.id.to_string(),
+        status: if failure.is_some() { RuleStatus::Errored } else { RuleStatus::Executed },
+        findings,
+        suppressed: raised.saturating_sub(findings),
+        reason: failure.map(|f| f.message.clone()),
+    }
+}
+
+/// Raise an analyzer's risk one level (LOW to MEDIUM, MEDIUM to HIGH) when
+/// it has at least `escalate_medium_findings` MEDIUM or `escalate_low_findings`
+/// LOW findings: many small issues together are riskier than any one of
+/// them. Counted before `min_severity` filtering, so hidden findings still
+/// count; a LOW note says why the level went up.
+fn escalate_by_volume(result: &mut AnalysisResult, thresholds: &Profile) {
+    let count = |severity| result.findings.iter().filter(|f| f.severity == severity).count();
+    let (medium, low) = (count(RiskLevel::Medium), count(RiskLevel::Low));
+    let (severity, findings, threshold) = if medium >= thresholds.escalate_medium_findings {
+        (RiskLevel::Medium, medium, thresholds.escalate_medium_findings)
+    } else if low >= thresholds.escalate_low_findings {
+        (RiskLevel::Low, low, thresholds.escalate_low_findings)
+    } else {
+        return;
+    };
+    let escalated = match result.risk_level {
+        RiskLevel::Low => RiskLevel::Medium,
+        RiskLevel::Medium | RiskLevel::High => RiskLevel::High,
+    };
+    if escalated == result.risk_level {
+        return;
+    }
+    debug!(analyzer = %result.analyzer_name, from = %result.risk_level, to = %escalated, medium, low, "risk escalated by finding volume");
+    result.findings.push(
+        Finding::new("analysis.risk_escalated", RiskLevel::Low)
+            .param("risk", escalated)
+            .param("count", findings)
+            .param("severity", severity)
+            .param("threshold", threshold),
+    );
+    result.risk_level = escalated;
+}
+
+/// Package manager lockfiles and checksum lists: generated, large and full
+/// of hashes by design.
+const LOCKFILES: &[&str] = &["Cargo.lock", "package-lock.json", "npm-shrinkwrap.json", "yarn.lock", "pnpm-lock.yaml", "go.sum", "poetry.lock", "Pipfile.lock", "Gemfile.lock", "composer.lock"];
+
+pub(crate) fn is_lockfile(path: &str) -> bool {
+    LOCKFILES.contains(&file_name(path))
+}
+
+/// Whether a path is test code according to the `[style] test_paths` globs.
+pub(crate) fn is_test_path(path: &str, test_paths: &[String]) -> bool {
+    paths::matches_any(test_paths, path)
+}
+
+/// ASCII case-insensitive substring test that doesn't allocate, unlike
+/// `to_uppercase().contains(..)`.
+pub(crate) fn contains_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    needle.is_empty() || haystack.as_bytes().windows(needle.len()).any(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// The module path of an import line: `use`, `import`, `from`, `#include`
+/// and `require(...)` forms. None for any other line.
+pub(crate) fn import_path(text: &str) -> Option<&str> {
+    let trimmed = text.trim_start();
+    let trimmed = trimmed.strip_prefix("pub(crate) ").or_else(|| trimmed.strip_prefix("pub ")).unwrap_or(trimmed);
+    ["use ", "import ", "from ", "#include "]
+        .iter()
+        .find_map(|keyword| trimmed.strip_prefix(keyword))
+        .or_else(|| trimmed.split_once("require(").map(|(_, rest)| rest))
+}
+
+/// Segments of an import or file path: split on `::`, `.`, `/` and anything else
+/// that cannot be part of a module name.
+pub(crate) fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-').filter(|s| !s.is_empty())
+}
+
+/// New-file line numbers of the lines a diff adds.
+pub(crate) fn added_lines(file: &DiffFile) -> Vec<usize> {
+    let mut added = Vec::new();
+    for hunk in file.hunks() {
+        let mut line_no = hunk.new_start;
+        for line in &hunk.lines {
+            if line.starts_with('+') {
+                added.push(line_no);
+            }
+            if !line.starts_with('-') {
+                line_no += 1;
+            }
+        }
+    }
+    added
+}
+
+/// Size of a file's diff content in KB (rounded up).
+fn diff_size_kb(file: &DiffFile) -> usize {
+    let bytes: usize = file.hunks().iter().flat_map(|h| &h.lines).map(|l| l.len() + 1).sum();
+    bytes.div_ceil(1024)
+}
+
+/// The HIGH finding for a change over the `max_diff_bytes` / `max_files` limits, if it is.
+fn exceeds_line_budget(pr: &PullRequest, runtime: &RuntimeConfig) -> Option<Finding> {
+    if pr.files().len() > runtime.max_files {
+        return Some(Finding::new("analysis.too_many_files", RiskLevel::High).param("files", pr.files().len()).param("max", runtime.max_files));
+    }
+    let bytes: usize = pr.files().iter().flat_map(|f| f.hunks()).flat_map(|h| &h.lines).map(|l| l.len() + 1).sum();
+    (bytes > runtime.max_diff_bytes).then(|| Finding::new("analysis.diff_too_large", RiskLevel::High).param("bytes", bytes).param("max", runtime.max_diff_bytes))
+}
+
+/// Drop the files matching one of the `ignore` globs (vendored or generated
+/// code) from `pr`, before anything looks at it, and from its totals, so
+/// they neither count towards its size nor read as missing (see `paths`
+/// for how globs match). Returns how many files were dropped.
+pub fn ignore_files(pr: &mut PullRequest, patterns: &[String]) -> usize {
+    let dropped = pr.remove_files(|f| paths::matches_any(patterns, f.path()));
+    if !dropped.is_empty() {
+        info!(count = dropped.len(), "ignoring files matching the ignore globs");
+    }
+    dropped.len()
+}
+
+/// Drop files whose diff exceeds `max_file_size_kb`, returning the trimmed PR
+/// (only when something was dropped) and the skipped paths with their sizes.
+fn apply_file_budget(pr: &PullRequest, max_file_size_kb: usize) -> (Option<PullRequest>, Vec<(String, usize)>) {
+    let skipped: Vec<(String, usize)> = pr
+        .files()
+        .iter()
+        .map(|f| (f, diff_size_kb(f)))
+        .filter(|(_, size_kb)| *size_kb > max_file_size_kb)
+        .map(|(f, size_kb)| (f.path().to_string(), size_kb))
+        .collect();
+    if skipped.is_empty() {
+        return (None, skipped);
+    }
+    warn!(count = skipped.len(), max_file_size_kb, "skipping oversized files");
+    let mut trimmed = pr.clone();
+    trimmed.files_mut().retain(|f| !skipped.iter().any(|(path, _)| *path == f.path()));
+    (Some(trimmed), skipped)
+}
+
+/// Signs that the diff does not hold the whole change: fewer files than
+/// the PR's metadata counts (GitHub cuts off very large diffs), and hunks
+/// whose lines do not add up to their `@@` header (a truncated or mangled
+/// patch, whose unreadable lines the parser drops).
+fn diff_warnings(pr: &PullRequest) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    if pr.files().len() < pr.files_changed() {
+        warnings.push(Warning::new("warning.files_missing").param("listed", pr.files().len()).param("files", pr.files_changed()));
+    }
+    for file in pr.files() {
+        // Diffs are decoded lossily, so undecodable bytes show up as U+FFFD
+        let undecodable = file.hunks().iter().flat_map(|h| &h.lines).filter(|l| !l.starts_with('-') && l.contains('\u{fffd}')).count();
+        if undecodable > 0 {
+            warnings.push(Warning::new("warning.undecodable_lines").param("lines", undecodable).in_file(file.path()));
+        }
+        for hunk in file.hunks() {
+            let count = |prefix: char| hunk.lines.iter().filter(|l| l.starts_with(prefix)).count();
+            let (old, new) = (count(' ') + count('-'), count(' ') + count('+'));
+            if old == hunk.old_count && new == hunk.new_count {
+                continue;
+            }
+            let line = if hunk.new_count == 0 { hunk.old_start } else { hunk.new_start };
+            warnings.push(
+                Warning::new("warning.incomplete_hunk")
+                    .param("line", line)
+                    .param("old_found", old)
+                    .param("new_found", new)
+                    .param("old", hunk.old_count)
+                    .param("new", hunk.new_count)
+                    .in_file(file.path()),
+            );
+        }
+    }
+    for warning in &warnings {
+        warn!(file = warning.file.as_deref().unwrap_or(""), "{}", warning.message);
+    }
+    warnings
+}
+
+/// Cap the total number of findings across results. Truncated results keep
+/// their most severe findings; one slot of the budget is reserved for a note,
+/// on the first truncated result, saying how many were omitted overall, so
+/// the total never exceeds `max_total_findings`. Risk levels are left as
+/// computed so truncation never lowers them.
+fn apply_findings_budget(results: &mut [AnalysisResult], max_total_findings: usize) {
+    let total: usize = results.iter().map(|r| r.findings.len()).sum();
+    if total <= max_total_findings {
+        return;
+    }
+    let mut remaining = max_total_findings.saturating_sub(1);
+    let omitted = total - remaining;
+    let mut noted = max_total_findings == 0;
+    for result in results.iter_mut() {
+        if result.findings.len() <= remaining {
+            remaining -= result.findings.len();
+            continue;
+        }
+        warn!(analyzer = %result.analyzer_name, omitted = result.findings.len() - remaining, "findings truncated by max_total_findings");
+        result.findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        result.findings.truncate(remaining);
+        remaining = 0;
+        if !noted {
+            result.findings.push(Finding::new("analysis.findings_omitted", RiskLevel::Low).param("omitted", omitted).param("max", max_total_findings));
+            noted = true;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pr::types::{DiffFile, PullRequest};
+    use std::collections::BTreeSet;
+    use std::future::{pending, ready};
 
     /// Helper to create a minimal PullRequest for testing.
     pub fn test_pull_request() -> PullRequest {
-        PullRequest {
-            number: 1,
-            title: "Test PR".to_string(),
-            author: "testuser".to_string(),
-            files_changed: 0,
-            additions: 0,
-            deletions: 0,
-            files: vec![],
-        }
+        PullRequest::builder("Test PR").number(1).author("testuser").build().unwrap()
     }
 
     /// Helper to create a DiffFile with custom content for testing.
     pub fn test_diff_file(path: &str, lines: Vec<String>) -> DiffFile {
         use crate::pr::types::Hunk;
-        DiffFile {
-            path: path.to_string(),
-            is_new: false,
-            is_deleted: false,
-            additions: lines.iter().filter(|l| l.starts_with('+')).count(),
-            deletions: lines.iter().filter(|l| l.starts_with('-')).count(),
-            hunks: vec![Hunk {
-                old_start: 1,
-                old_count: 10,
-                new_start: 1,
-                new_count: 10,
-                lines,
-            }],
-        }
+        let mut file = DiffFile::empty(path);
+        file.set_counts(lines.iter().filter(|l| l.starts_with('+')).count(), lines.iter().filter(|l| l.starts_with('-')).count());
+        file.hunks_mut().push(Hunk {
+            old_start: 1,
+            old_count: 10,
+            new_start: 1,
+            new_count: 10,
+            lines,
+        });
+        file
+    }
+
+    #[test]
+    fn test_diff_warnings_flag_truncated_diffs() {
+        let mut pr = test_pull_request();
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,2 +1,3 @@\n fn a() {\n+    b();\n }\n@@ -20,3 +21,4 @@\n fn c() {\n+    d();\n";
+        pr.set_files(crate::pr::diff::parse_diff(diff).unwrap());
+        pr.set_totals(1, pr.additions(), pr.deletions());
+        let warnings = diff_warnings(&pr);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "Hunk at line 21 holds 1 old and 2 new lines where its header announces 3 and 4; the diff may be cut off or malformed, so some lines were not analyzed");
+        assert_eq!(warnings[0].file.as_deref(), Some("src/a.rs"));
+
+        pr.files_mut().truncate(0);
+        pr.set_totals(3, pr.additions(), pr.deletions());
+        assert_eq!(diff_warnings(&pr)[0].message, "The diff lists 0 of 3 changed files; the others were not analyzed");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_encoded_diff_is_analyzed_with_a_warning() {
+        let mut pr = test_pull_request();
+        // Latin-1 "é" and Shift-JIS "日本" in comments next to the code
+        let diff = b"diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,1 +1,3 @@\n fn a() {}\n+// caf\xe9\n+let v = x.unwrap(); // \x93\xfa\x96\x7b\n";
+        pr.set_files(crate::pr::diff::parse_diff_bytes(diff).unwrap());
+        pr.set_totals(1, pr.additions(), pr.deletions());
+        let run = run_all(&pr, &Config::default(), pending()).await.unwrap();
+        assert_eq!(run.warnings.len(), 1);
+        assert_eq!(run.warnings[0].message_id, "warning.undecodable_lines");
+        assert!(run.warnings[0].message.starts_with("2 changed lines are not valid UTF-8"), "{}", run.warnings[0].message);
+        let style = run.results.iter().find(|r| r.analyzer_name == "Style & Architecture Assessment").unwrap();
+        assert!(style.findings.iter().any(|f| f.message_id.as_deref() == Some("style.unwrap") && f.line == Some(3)), "{:?}", style.findings);
     }
 
     #[tokio::test]
-    async fn test_run_all_returns_three_results() {
+    async fn test_run_all_returns_one_result_per_analyzer() {
         let pr = test_pull_request();
         let config = Config::default();
-        let results = run_all(&pr, &config).await.unwrap();
-        assert_eq!(results.len(), 3);
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
+        assert_eq!(results.len(), 8);
     }
 
     #[tokio::test]
     async fn test_run_all_analyzer_names() {
         let pr = test_pull_request();
         let config = Config::default();
-        let results = run_all(&pr, &config).await.unwrap();
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
         let names: Vec<&str> = results.iter().map(|r| r.analyzer_name.as_str()).collect();
         assert!(names.contains(&"Security Risk Assessment"));
         assert!(names.contains(&"Complexity Assessment"));
         assert!(names.contains(&"Style & Architecture Assessment"));
+        assert!(names.contains(&"Container Risk Assessment"));
+        assert!(names.contains(&"API Contract Assessment"));
+        assert!(names.contains(&"Infrastructure Assessment"));
+        assert!(names.contains(&"Process Risk Assessment"));
+        assert!(names.contains(&"Custom Rules"));
+    }
+
+    #[tokio::test]
+    async fn test_rule_versions_cover_every_analyzer() {
+        let config = Config::default();
+        let versions = rule_versions(&config);
+        let results = run_all(&test_pull_request(), &config, pending()).await.unwrap().results;
+        assert_eq!(versions.len(), results.len());
+        assert!(results.iter().all(|r| versions.get(&r.analyzer_name).is_some_and(|v| *v >= 1)));
     }
 
     #[tokio::test]
     async fn test_run_all_with_dirty_pr() {
         let mut pr = test_pull_request();
         let config = Config::default();
-        pr.additions = 600;
-        pr.files = vec![test_diff_file(
+        pr.set_totals(pr.files_changed(), 600, pr.deletions());
+        pr.set_files(vec![test_diff_file(
             "src/main.rs",
             vec![
                 "+    let password = \"secret123\";".to_string(),
                 "+    unsafe {".to_string(),
                 "+        todo!(\"fix this\")".to_string(),
             ],
-        )];
-        let results = run_all(&pr, &config).await.unwrap();
-        assert_eq!(results.len(), 3);
+        )]);
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
+        assert_eq!(results.len(), 8);
         // At least one analyzer should flag something
         assert!(results.iter().any(|r| !r.findings.is_empty()));
     }
+
+    #[tokio::test]
+    async fn test_run_all_reports_progress_events() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/main.rs", vec!["+    let x = y.unwrap();".to_string()])]);
+        let mut config = Config::default();
+        config.runtime.max_concurrent_analyzers = 1;
+        let seen = std::sync::Mutex::new(Vec::new());
+        let run = run_all_with_events(&pr, &config, pending(), &|event| seen.lock().unwrap().push(event)).await.unwrap();
+        let seen = seen.into_inner().unwrap();
+
+        let AnalysisEvent::AnalysisStarted { analyzers } = &seen[0] else { panic!("{:?}", seen[0]) };
+        assert_eq!(analyzers.len(), run.results.len());
+        assert!(matches!(seen[1], AnalysisEvent::LinePassFinished { .. }));
+        // One at a time: each analyzer starts, then finishes, in report order
+        for (i, result) in run.results.iter().enumerate() {
+            assert!(matches!(&seen[2 + 2 * i], AnalysisEvent::AnalyzerStarted { name } if *name == result.analyzer_name));
+            let AnalysisEvent::AnalyzerFinished { name, findings, .. } = &seen[3 + 2 * i] else { panic!("{:?}", seen[3 + 2 * i]) };
+            assert_eq!(name, &result.analyzer_name);
+            assert!(findings.iter().all(|f| f.rule.is_some()), "{findings:?}");
+        }
+        assert_eq!(seen.len(), 2 + 2 * run.results.len());
+        assert!(seen.iter().any(|e| matches!(e, AnalysisEvent::AnalyzerFinished { findings, .. } if findings.iter().any(|f| f.rule.as_deref() == Some("style.unwrap")))));
+    }
+
+    #[tokio::test]
+    async fn test_run_all_respects_concurrency_limit_of_one() {
+        let pr = test_pull_request();
+        let mut config = Config::default();
+        config.runtime.max_concurrent_analyzers = 1;
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
+        assert_eq!(results.len(), 8);
+        assert_eq!(results[0].analyzer_name, "Security Risk Assessment");
+    }
+
+    #[tokio::test]
+    async fn test_run_all_skips_oversized_files() {
+        let mut pr = test_pull_request();
+        let mut config = Config::default();
+        config.runtime.max_file_size_kb = 1;
+        let big_line = format!("+    let password = \"{}\";", "x".repeat(2048));
+        pr.set_files(vec![test_diff_file("src/generated.rs", vec![big_line])]);
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
+        let security = results.iter().find(|r| r.analyzer_name == "Security Risk Assessment").unwrap();
+        assert!(security.findings.is_empty());
+        let complexity = results.iter().find(|r| r.analyzer_name == complexity::NAME).unwrap();
+        assert!(complexity.findings.iter().any(|f| f.message.contains("Oversized file skipped") && f.file.as_deref() == Some("src/generated.rs")));
+    }
+
+    #[tokio::test]
+    async fn test_run_all_skips_line_checks_when_change_is_too_large() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file("src/a.rs", vec!["+let x = y.unwrap();".to_string()]),
+            test_diff_file("src/b.rs", vec!["+let password = \"hunter2\";".to_string()]),
+        ]);
+        let mut config = Config::default();
+        config.runtime.max_files = 1;
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
+        let complexity = results.iter().find(|r| r.analyzer_name == complexity::NAME).unwrap();
+        assert_eq!(complexity.risk_level, RiskLevel::High);
+        assert!(complexity.findings.iter().any(|f| f.message.starts_with("PR too large to analyze line-by-line: 2 files exceed max_files (1)")));
+        assert!(results.iter().filter(|r| r.analyzer_name != complexity::NAME).all(|r| r.findings.is_empty()));
+
+        config.runtime.max_files = 10;
+        config.runtime.max_diff_bytes = 16;
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
+        assert!(results.iter().flat_map(|r| &r.findings).any(|f| f.message.contains("exceed max_diff_bytes (16)")));
+    }
+
+    #[test]
+    fn test_rules_cover_every_finding_message() {
+        let catalog = crate::report::Catalog::english();
+        let specs: Vec<RuleSpec> = analyzers(&Config::default()).iter().flat_map(|a| a.rules()).collect();
+        let ids: BTreeSet<&str> = specs.iter().map(|s| s.id).collect();
+        assert_eq!(ids.len(), specs.len(), "rule ids must be unique");
+        let codes: BTreeSet<&str> = specs.iter().map(|s| s.code).collect();
+        assert_eq!(codes.len(), specs.len(), "rule codes must be unique");
+        for analyzer in analyzers(&Config::default()) {
+            let prefix = &analyzer.rules()[0].code[..3];
+            for spec in analyzer.rules() {
+                let (letters, number) = spec.code.split_at(3);
+                assert!(letters == prefix && number.len() == 3 && number.bytes().all(|b| b.is_ascii_digit()), "{}: bad code {}", spec.id, spec.code);
+            }
+        }
+        for spec in &specs {
+            assert!(spec.messages.iter().chain(&spec.failure).all(|id| catalog.has_message(id)), "{} names an unknown message", spec.id);
+        }
+        let covered: BTreeSet<&str> = specs.iter().flat_map(|s| s.messages.iter().chain(&s.failure).copied()).collect();
+        let uncovered: Vec<&str> = catalog.message_ids().filter(|id| !id.starts_with("analysis.") && !id.starts_with("warning.") && !covered.contains(id)).collect();
+        assert!(uncovered.is_empty(), "messages no rule reports: {:?}", uncovered);
+    }
+
+    #[tokio::test]
+    async fn test_run_all_categorizes_findings_by_rule() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/a.rs", vec!["+let password = \"hunter2\";".to_string(), "+let z = w.unwrap();".to_string()])]);
+        let results = run_all(&pr, &Config::default(), pending()).await.unwrap().results;
+        let category = |id: &str| results.iter().flat_map(|r| &r.findings).find(|f| f.message_id.as_deref() == Some(id)).map(|f| f.category);
+        assert_eq!(category("security.hardcoded_password"), Some(Some(Category::Secrets)));
+        assert_eq!(category("style.unwrap"), Some(Some(Category::Maintainability)));
+        let rule = results.iter().flat_map(|r| &r.findings).find(|f| f.message_id.as_deref() == Some("security.hardcoded_password")).and_then(|f| f.rule.as_deref());
+        assert_eq!(rule, Some("security.hardcoded_secret"));
+        let code = results.iter().flat_map(|r| &r.findings).find(|f| f.message_id.as_deref() == Some("style.unwrap")).and_then(|f| f.rule_code.as_deref());
+        assert_eq!(code, Some("STY001"));
+    }
+
+    #[tokio::test]
+    async fn test_run_all_lists_rule_status() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/a.rs", vec!["+let x = y.unwrap();".to_string(), "+let z = w.unwrap();".to_string()])]);
+        let mut config = Config::default();
+        config.security.patterns = vec!["TODO.*security".into()];
+        let rules = run_all(&pr, &config, pending()).await.unwrap().rules;
+        let status = |id: &str| rules.iter().find(|r| r.rule == id).map(|r| (r.status, r.findings, r.reason.clone())).unwrap();
+        assert_eq!(status("style.unwrap"), (RuleStatus::Executed, 2, None));
+        assert_eq!(status("security.custom_patterns"), (RuleStatus::Executed, 0, None));
+        assert_eq!(status("style.clippy"), (RuleStatus::Skipped, 0, Some("[style] clippy = false".to_string())));
+        assert_eq!(status("complexity.long_file").2.as_deref(), Some("needs --repo or --clone"));
+
+        config.runtime.max_files = 0;
+        let rules = run_all(&pr, &config, pending()).await.unwrap().rules;
+        let unwrap = rules.iter().find(|r| r.rule == "style.unwrap").unwrap();
+        assert_eq!((unwrap.status, unwrap.reason.as_deref()), (RuleStatus::Skipped, Some("change too large for line-level checks")));
+        assert!(rules.iter().any(|r| r.rule == "security.new_dependencies" && r.status == RuleStatus::Executed));
+
+        pr.set_fork(true);
+        config.style.clippy = true;
+        let rules = run_all(&pr, &config, pending()).await.unwrap().rules;
+        let status = |id: &str| rules.iter().find(|r| r.rule == id).map(|r| (r.status, r.reason.clone())).unwrap();
+        assert_eq!(status("style.clippy"), (RuleStatus::Skipped, Some("would run code from a fork".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_run_all_only_selected_analyzer_or_rule() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/a.rs", vec!["+let x = y.unwrap();".to_string(), "+// TODO: fix".to_string()])]);
+        let mut config = Config::default();
+        config.only.analyzer = Some("Style".to_string());
+        let run = run_all(&pr, &config, pending()).await.unwrap();
+        assert_eq!(run.results.len(), 1);
+        assert_eq!(run.results[0].analyzer_name, "Style & Architecture Assessment");
+        assert!(run.rules.iter().all(|r| r.analyzer == "Style & Architecture Assessment"));
+
+        config.only = Selection { analyzer: None, rule: Some("style.unwrap".to_string()) };
+        let run = run_all(&pr, &config, pending()).await.unwrap();
+        assert!(run.results[0].findings.iter().all(|f| f.message_id.as_deref() == Some("style.unwrap")));
+        assert!(!run.results[0].findings.is_empty());
+        let unfinished = run.rules.iter().find(|r| r.rule == "style.unfinished_code").unwrap();
+        assert_eq!((unfinished.status, unfinished.reason.as_deref()), (RuleStatus::Skipped, Some("not selected (--only-rule)")));
+    }
+
+    #[test]
+    fn test_unknown_selection_lists_valid_names() {
+        let mut config = Config::default();
+        config.only.analyzer = Some("secruity".to_string());
+        let err = check_selection(&config).unwrap_err().to_string();
+        assert!(err.starts_with("Unknown analyzer 'secruity' (known: security, complexity, style"), "{err}");
+
+        config.only = Selection { analyzer: None, rule: Some("security.nope".to_string()) };
+        assert!(matches!(check_selection(&config), Err(AnalysisError::UnknownSelection { kind: "rule", .. })));
+        config.only = Selection { analyzer: Some("style".to_string()), rule: Some("security.xss".to_string()) };
+        assert!(matches!(check_selection(&config), Err(AnalysisError::SelectionMismatch { .. })));
+        config.only.analyzer = Some("security".to_string());
+        assert!(check_selection(&config).is_ok());
+    }
+
+    #[test]
+    fn test_policy_required_analyzers_cannot_be_deselected() {
+        let mut config = Config::default();
+        config.policy.required_analyzers = vec!["security".to_string()];
+        assert!(check_selection(&config).is_ok());
+        config.only.analyzer = Some("security".to_string());
+        assert!(check_selection(&config).is_ok());
+
+        config.only.analyzer = Some("style".to_string());
+        let err = check_selection(&config).unwrap_err();
+        assert!(matches!(&err, AnalysisError::RequiredAnalyzer { analyzer } if analyzer == "security"), "{err}");
+        config.only = Selection { analyzer: None, rule: Some("style.unwrap".to_string()) };
+        assert!(matches!(check_selection(&config), Err(AnalysisError::RequiredAnalyzer { .. })));
+
+        config.only = Selection::default();
+        config.policy.required_analyzers = vec!["secrets".to_string()];
+        assert!(matches!(check_selection(&config), Err(AnalysisError::UnknownSelection { kind: "[policy] required analyzer", .. })));
+    }
+
+    #[test]
+    fn test_report_layout_resolves_section_names() {
+        let mut config = Config::default();
+        config.report.sections = vec!["summary".to_string(), "complexity".to_string(), "Security Risk Assessment".to_string()];
+        let layout = report_layout(&config).unwrap();
+        assert_eq!(layout.sections, [Part::Summary, Part::Analyzer("Complexity Assessment".to_string()), Part::Analyzer("Security Risk Assessment".to_string())]);
+
+        config.report.sections.push("overview".to_string());
+        let err = report_layout(&config).unwrap_err().to_string();
+        assert!(err.starts_with("Unknown [report] section 'overview' (known: summary, resolved, patches, policy, surface, split, reviewers, rules, security,"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_run_all_drops_findings_below_profile_min_severity() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/lib.rs", vec!["+pub fn exported() {}".to_string()])]);
+        let mut config = Config::default();
+        config.apply_profile(Some("lenient")).unwrap();
+        let results = run_all(&pr, &config, pending()).await.unwrap().results;
+        assert!(results.iter().flat_map(|r| &r.findings).all(|f| f.severity >= RiskLevel::Medium));
+    }
+
+    #[tokio::test]
+    async fn test_run_all_ignores_formatting_only_changes() {
+        let mut pr = test_pull_request();
+        let removed: Vec<String> = (0..300).map(|i| format!("-let  x{} = 1;", i)).collect();
+        let added: Vec<String> = (0..300).map(|i| format!("+let x{} = 1;", i)).collect();
+        pr.set_totals(pr.files_changed(), 300, 300);
+        pr.set_files(vec![test_diff_file("src/lib.rs", removed.into_iter().chain(added).collect())]);
+        let results = run_all(&pr, &Config::default(), pending()).await.unwrap().results;
+        let complexity = results.iter().find(|r| r.analyzer_name == complexity::NAME).unwrap();
+        assert_eq!(complexity.risk_level, RiskLevel::Low);
+        assert!(complexity.findings.iter().any(|f| f.message == "300 lines were formatting-only and were excluded from analysis"));
+    }
+
+    #[tokio::test]
+    async fn test_run_all_treats_moved_code_as_unchanged() {
+        let mut pr = test_pull_request();
+        let body: Vec<String> = (0..20).map(|i| format!("    let value_{} = compute({}).unwrap();", i, i)).collect();
+        pr.set_files(vec![
+            test_diff_file("src/old.rs", body.iter().map(|l| format!("-{}", l)).collect()),
+            test_diff_file("src/new.rs", body.iter().map(|l| format!("+{}", l)).collect()),
+        ]);
+        pr.set_totals(2, 20, 20);
+        let results = run_all(&pr, &Config::default(), pending()).await.unwrap().results;
+        let style = results.iter().find(|r| r.analyzer_name == "Style & Architecture Assessment").unwrap();
+        assert!(!style.findings.iter().any(|f| f.message.contains("unwrap()")));
+        let complexity = results.iter().find(|r| r.analyzer_name == complexity::NAME).unwrap();
+        assert!(complexity.findings.iter().any(|f| f.message == "20 lines moved from src/old.rs — treated as unchanged" && f.file.as_deref() == Some("src/new.rs")));
+    }
+
+    #[test]
+    fn test_findings_budget_keeps_most_severe() {
+        let finding = |severity| Finding::text("x", severity);
+        let mut results = vec![AnalysisResult {
+            analyzer_name: "A".to_string(),
+            risk_level: RiskLevel::High,
+            findings: vec![finding(RiskLevel::Low), finding(RiskLevel::High), finding(RiskLevel::Low)],
+        }];
+        apply_findings_budget(&mut results, 2);
+        assert_eq!(results[0].findings.len(), 2);
+        assert_eq!(results[0].findings[0].severity, RiskLevel::High);
+        assert!(results[0].findings[1].message.contains("2 more findings omitted"));
+        assert_eq!(results[0].risk_level, RiskLevel::High);
+    }
+
+    #[test]
+    fn test_findings_budget_total_includes_note() {
+        let result = |name: &str, count| AnalysisResult {
+            analyzer_name: name.to_string(),
+            risk_level: RiskLevel::Low,
+            findings: (0..count).map(|_| Finding::text("x", RiskLevel::Low)).collect(),
+        };
+        let mut results = vec![result("A", 3), result("B", 4), result("C", 2)];
+        apply_findings_budget(&mut results, 5);
+        let counts: Vec<usize> = results.iter().map(|r| r.findings.len()).collect();
+        assert_eq!(counts, vec![3, 2, 0]);
+        assert_eq!(counts.iter().sum::<usize>(), 5);
+        assert!(results[1].findings[1].message.contains("5 more findings omitted: max_total_findings (5) reached"));
+
+        let mut exact = vec![result("A", 3), result("B", 2)];
+        apply_findings_budget(&mut exact, 5);
+        assert_eq!(exact.iter().map(|r| r.findings.len()).sum::<usize>(), 5);
+        assert!(!exact.iter().flat_map(|r| &r.findings).any(|f| f.message.contains("omitted")));
+
+        let mut none = vec![result("A", 3)];
+        apply_findings_budget(&mut none, 0);
+        assert!(none[0].findings.is_empty());
+    }
+
+    #[test]
+    fn test_many_low_findings_escalate_one_level() {
+        let result = |count, severity| AnalysisResult {
+            analyzer_name: "Style".to_string(),
+            risk_level: severity,
+            findings: (0..count).map(|_| Finding::text("x", severity)).collect(),
+        };
+        let profile = Profile::default();
+
+        let mut below = result(39, RiskLevel::Low);
+        escalate_by_volume(&mut below, &profile);
+        assert_eq!((below.risk_level, below.findings.len()), (RiskLevel::Low, 39));
+
+        let mut lows = result(40, RiskLevel::Low);
+        escalate_by_volume(&mut lows, &profile);
+        assert_eq!(lows.risk_level, RiskLevel::Medium);
+        assert_eq!(lows.findings[40].message, "Risk raised to MEDIUM: 40 LOW findings reach the escalation threshold (40)");
+
+        let mut mediums = result(15, RiskLevel::Medium);
+        escalate_by_volume(&mut mediums, &profile);
+        assert_eq!(mediums.risk_level, RiskLevel::High);
+    }
+
+    #[tokio::test]
+    async fn test_run_all_cancelled_returns_partial_interrupted_run() {
+        let pr = test_pull_request();
+        let config = Config::default();
+        let run = run_all(&pr, &config, ready(())).await.unwrap();
+        assert!(run.interrupted);
+        assert!(run.results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_all_uncancelled_is_not_interrupted() {
+        let run = run_all(&test_pull_request(), &Config::default(), pending()).await.unwrap();
+        assert!(!run.interrupted);
+    }
+
+    #[test]
+    fn test_path_helpers_accept_either_separator() {
+        assert_eq!(file_name("src\\pr\\diff.rs"), "diff.rs");
+        assert_eq!(file_name("src/pr/diff.rs"), "diff.rs");
+        let defaults = crate::config::StyleConfig::default().test_paths;
+        assert!(is_test_path("tests\\integration.rs", &defaults));
+        assert!(is_test_path("crates/core/tests/parse.rs", &defaults));
+        assert!(is_test_path("src\\parser_test.rs", &defaults));
+        assert!(!is_test_path("src/tests.rs", &defaults));
+    }
+
+    #[test]
+    fn test_added_lines_skip_removed_and_count_context() {
+        let mut file = test_diff_file("src/lib.rs", vec![" ctx".to_string(), "-old".to_string(), "+new".to_string(), " ctx".to_string(), "+tail".to_string()]);
+        file.hunks_mut()[0].new_start = 10;
+        assert_eq!(added_lines(&file), vec![11, 13]);
+    }
+
+    #[test]
+    fn test_contains_ignore_ascii_case() {
+        assert!(contains_ignore_ascii_case("let q = \"select * from t\";", "SELECT"));
+        assert!(contains_ignore_ascii_case("anything", ""));
+        assert!(!contains_ignore_ascii_case("sel", "SELECT"));
+    }
+
+    #[test]
+    fn test_ignored_files_leave_the_change_and_its_totals() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file("vendor/lib/a.rs", vec!["+a".to_string(), "+b".to_string()]),
+            test_diff_file("web/dist/app.min.js", vec!["+x".to_string(), "-y".to_string()]),
+            test_diff_file("src/main.rs", vec!["+fn main() {}".to_string()]),
+        ]);
+        // One more file than listed: the diff was cut off
+        pr.set_totals(4, 9, 1);
+        let patterns = ["vendor/**".to_string(), "*.min.js".to_string()];
+        assert_eq!(ignore_files(&mut pr, &patterns), 2);
+        assert_eq!(pr.files().iter().map(|f| f.path()).collect::<Vec<_>>(), ["src/main.rs"]);
+        assert_eq!((pr.files_changed(), pr.additions(), pr.deletions()), (2, 6, 0));
+        let missing = diff_warnings(&pr).into_iter().find(|w| w.message_id == "warning.files_missing").unwrap();
+        assert_eq!(missing.params["listed"], "1");
+    }
 }
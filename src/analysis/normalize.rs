@@ -0,0 +1,300 @@
+//! Diff normalization applied before analyzers run.
+//!
+//! Rewrites the PR so that changes with no semantic content (reformatting,
+//! code moved between files) stop counting as additions. Analyzers see the
+//! rewritten diff only.
+
+use std::collections::{HashMap, HashSet};
+
+use tracing::debug;
+
+use crate::pr::types::Hunk;
+use crate::pr::PullRequest;
+
+/// Smallest run of added lines, counting only lines with letters or digits,
+/// that can be classified as a move.
+const MIN_MOVE_LINES: usize = 5;
+/// Share of a run's non-blank lines that must match removed lines elsewhere.
+const MOVE_MATCH_RATIO: f64 = 0.9;
+
+/// Lines moved from one file to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Move {
+    pub from: String,
+    pub to: String,
+    pub lines: usize,
+}
+
+/// Turn formatting-only changes into context lines.
+///
+/// A run of removed lines followed by a run of added lines is
+/// formatting-only when both are identical once all whitespace is removed.
+/// This covers re-indentation, trailing whitespace, and rustfmt/prettier
+/// re-wrapping that changes the line count. Its added lines become context,
+/// its removed lines are dropped, and the file and PR totals shrink
+/// accordingly. Returns the rewritten PR (only when something changed) and
+/// the number of added lines that were formatting-only.
+pub(crate) fn strip_formatting_only(pr: &PullRequest) -> (Option<PullRequest>, usize) {
+    let mut rewritten = pr.clone();
+    let (mut total_added, mut total_removed) = (0, 0);
+    for index in 0..rewritten.files().len() {
+        let (mut added, mut removed) = (0, 0);
+        for hunk in rewritten.files_mut()[index].hunks_mut() {
+            let (a, r) = strip_hunk(hunk);
+            added += a;
+            removed += r;
+        }
+        discount(&mut rewritten, index, added, removed);
+        total_added += added;
+        total_removed += removed;
+    }
+    if total_added == 0 && total_removed == 0 {
+        return (None, 0);
+    }
+    debug!(added = total_added, "formatting-only lines excluded");
+    (Some(rewritten), total_added)
+}
+
+/// Turn code moved between files into context.
+///
+/// A run of added lines is a move when at least `MOVE_MATCH_RATIO` of its
+/// non-blank lines match (ignoring whitespace) lines removed from one other
+/// file. The added lines become context and the matched removed lines are
+/// dropped, so the move counts toward neither size nor findings. Returns the
+/// rewritten PR (only when something moved) and the moves, one per file pair.
+pub(crate) fn detect_moves(pr: &PullRequest) -> (Option<PullRequest>, Vec<Move>) {
+    // Removed lines of each file, keyed by normalized text.
+    let mut removed: Vec<HashMap<String, Vec<(usize, usize)>>> = vec![HashMap::new(); pr.files().len()];
+    for (f, file) in pr.files().iter().enumerate() {
+        for (h, hunk) in file.hunks().iter().enumerate() {
+            for (l, line) in hunk.lines.iter().enumerate() {
+                let key = normalize_line(line);
+                if line.starts_with('-') && !key.is_empty() {
+                    removed[f].entry(key).or_default().push((h, l));
+                }
+            }
+        }
+    }
+
+    // (file, hunk, line) of each added line that becomes context / removed line that is dropped
+    let mut moved_added = HashSet::new();
+    let mut moved_removed = HashSet::new();
+    let mut moves: Vec<Move> = Vec::new();
+    for (f, file) in pr.files().iter().enumerate() {
+        for (h, hunk) in file.hunks().iter().enumerate() {
+            let mut i = 0;
+            while i < hunk.lines.len() {
+                let len = hunk.lines[i..].iter().take_while(|l| l.starts_with('+')).count();
+                if len == 0 {
+                    i += 1;
+                    continue;
+                }
+                let run = i..i + len;
+                i += len;
+                let keys: Vec<String> = hunk.lines[run.clone()].iter().map(|l| normalize_line(l)).collect();
+                if keys.iter().filter(|k| k.chars().any(char::is_alphanumeric)).count() < MIN_MOVE_LINES {
+                    continue;
+                }
+                let non_blank = keys.iter().filter(|k| !k.is_empty()).count();
+                let best = (0..pr.files().len())
+                    .filter(|src| *src != f)
+                    .map(|src| (src, match_run(&keys, &removed[src])))
+                    .max_by_key(|(_, matched)| matched.len());
+                let Some((src, matched)) = best else {
+                    continue;
+                };
+                if (matched.len() as f64) < non_blank as f64 * MOVE_MATCH_RATIO {
+                    continue;
+                }
+                for key in keys.iter().filter(|k| !k.is_empty()) {
+                    if let Some(positions) = removed[src].get_mut(key) {
+                        positions.pop();
+                    }
+                }
+                moved_added.extend(run.map(|l| (f, h, l)));
+                moved_removed.extend(matched.into_iter().map(|(mh, ml)| (src, mh, ml)));
+                let (from, to) = (pr.files()[src].path(), file.path());
+                match moves.iter_mut().find(|m| m.from == from && m.to == to) {
+                    Some(m) => m.lines += len,
+                    None => moves.push(Move { from: from.to_string(), to: to.to_string(), lines: len }),
+                }
+            }
+        }
+    }
+    if moves.is_empty() {
+        return (None, moves);
+    }
+
+    let mut rewritten = pr.clone();
+    for index in 0..rewritten.files().len() {
+        let (mut added, mut dropped) = (0, 0);
+        for (h, hunk) in rewritten.files_mut()[index].hunks_mut().iter_mut().enumerate() {
+            let lines = std::mem::take(&mut hunk.lines);
+            let mut hunk_dropped = 0;
+            for (l, line) in lines.into_iter().enumerate() {
+                if moved_removed.contains(&(index, h, l)) {
+                    hunk_dropped += 1;
+                } else if moved_added.contains(&(index, h, l)) {
+                    hunk.lines.push(format!(" {}", &line[1..]));
+                    added += 1;
+                } else {
+                    hunk.lines.push(line);
+                }
+            }
+            hunk.old_count = hunk.old_count.saturating_sub(hunk_dropped);
+            dropped += hunk_dropped;
+        }
+        discount(&mut rewritten, index, added, dropped);
+    }
+    debug!(moves = moves.len(), "moved code excluded");
+    (Some(rewritten), moves)
+}
+
+/// Match each non-blank key against a file's removed lines (each removed
+/// line used at most once), returning the (hunk, line) positions matched.
+fn match_run(keys: &[String], removed: &HashMap<String, Vec<(usize, usize)>>) -> Vec<(usize, usize)> {
+    let mut used: HashMap<&str, usize> = HashMap::new();
+    let mut matched = Vec::new();
+    for key in keys.iter().filter(|k| !k.is_empty()) {
+        let Some(positions) = removed.get(key) else {
+            continue;
+        };
+        let taken = used.entry(key.as_str()).or_default();
+        if let Some(position) = positions.len().checked_sub(*taken + 1).map(|i| positions[i]) {
+            *taken += 1;
+            matched.push(position);
+        }
+    }
+    matched
+}
+
+/// A diff line without its prefix, with runs of whitespace collapsed.
+fn normalize_line(line: &str) -> String {
+    line.get(1..).unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Subtract stripped lines from a file's and the PR's totals. A file left
+/// with no changes stops counting toward `files_changed`.
+fn discount(pr: &mut PullRequest, index: usize, added: usize, removed: usize) {
+    if added == 0 && removed == 0 {
+        return;
+    }
+    let file = &mut pr.files_mut()[index];
+    let had_changes = file.additions() + file.deletions() > 0;
+    file.set_counts(file.additions().saturating_sub(added), file.deletions().saturating_sub(removed));
+    let emptied = had_changes && file.additions() + file.deletions() == 0;
+    let files_changed = if emptied { pr.files_changed().saturating_sub(1) } else { pr.files_changed() };
+    pr.set_totals(files_changed, pr.additions().saturating_sub(added), pr.deletions().saturating_sub(removed));
+}
+
+/// Rewrite one hunk in place, returning the (added, removed) lines stripped.
+fn strip_hunk(hunk: &mut Hunk) -> (usize, usize) {
+    let mut lines = Vec::with_capacity(hunk.lines.len());
+    let (mut added, mut removed) = (0, 0);
+    let mut i = 0;
+    while i < hunk.lines.len() {
+        let run = |from: usize, prefix: char| hunk.lines[from..].iter().take_while(|l| l.starts_with(prefix)).count();
+        let minus = run(i, '-');
+        let plus = run(i + minus, '+');
+        if minus == 0 || plus == 0 {
+            lines.push(hunk.lines[i].clone());
+            i += 1;
+            continue;
+        }
+        let old = &hunk.lines[i..i + minus];
+        let new = &hunk.lines[i + minus..i + minus + plus];
+        if squash(old) == squash(new) {
+            lines.extend(new.iter().map(|l| format!(" {}", &l[1..])));
+            added += plus;
+            removed += minus;
+        } else {
+            lines.extend_from_slice(old);
+            lines.extend_from_slice(new);
+        }
+        i += minus + plus;
+    }
+    hunk.lines = lines;
+    // The rewritten context lines now exist on the old side too.
+    hunk.old_count = (hunk.old_count + added).saturating_sub(removed);
+    (added, removed)
+}
+
+/// Diff lines without their prefixes and without any whitespace.
+fn squash(lines: &[String]) -> String {
+    lines.iter().flat_map(|l| l[1..].chars()).filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+
+    fn lines(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reindent_and_rewrap_are_formatting_only() {
+        let mut pr = test_pull_request();
+        pr.set_totals(1, 3, 2);
+        pr.set_files(vec![test_diff_file(
+            "src/lib.rs",
+            lines(&["-fn f(a: u32, b: u32) {}", "-  let x = 1;", "+fn f(", "+    a: u32, b: u32) {}", "+    let x = 1;"]),
+        )]);
+        let (rewritten, count) = strip_formatting_only(&pr);
+        let rewritten = rewritten.unwrap();
+        assert_eq!(count, 3);
+        assert_eq!((rewritten.additions(), rewritten.deletions(), rewritten.files_changed()), (0, 0, 0));
+        assert_eq!(rewritten.files()[0].hunks()[0].lines, lines(&[" fn f(", "     a: u32, b: u32) {}", "     let x = 1;"]));
+    }
+
+    #[test]
+    fn test_block_moved_between_files_becomes_context() {
+        let body = ["fn helper(a: u32) -> u32 {", "    let b = a * 2;", "    let c = b + 1;", "    log(c);", "    c", "}"];
+        let mut pr = test_pull_request();
+        pr.set_totals(2, 7, 6);
+        pr.set_files(vec![
+            test_diff_file("src/a.rs", body.iter().map(|l| format!("-{}", l)).collect()),
+            // Re-indented on the way, plus one genuinely new line after it
+            test_diff_file("src/b.rs", body.iter().map(|l| format!("+  {}", l)).chain([" ".to_string(), "+fn new_code() {}".to_string()]).collect()),
+        ]);
+        let (rewritten, moves) = detect_moves(&pr);
+        let rewritten = rewritten.unwrap();
+        assert_eq!(moves, vec![Move { from: "src/a.rs".to_string(), to: "src/b.rs".to_string(), lines: 6 }]);
+        assert_eq!((rewritten.additions(), rewritten.deletions(), rewritten.files_changed()), (1, 0, 1));
+        assert!(rewritten.files()[0].hunks()[0].lines.is_empty());
+        assert_eq!(rewritten.files()[1].hunks()[0].lines.iter().filter(|l| l.starts_with('+')).count(), 1);
+    }
+
+    #[test]
+    fn test_small_or_dissimilar_blocks_are_not_moves() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file("a.py", lines(&["-x = 1", "-y = 2", "-z = 3", "-w = 4", "-v = 5"])),
+            test_diff_file("b.py", lines(&["+x = 1", "+y = 2", "+z = 30", "+w = 40", "+v = 50"])),
+            test_diff_file("c.py", lines(&["+x = 1", "+y = 2"])),
+        ]);
+        assert!(detect_moves(&pr).0.is_none());
+    }
+
+    #[test]
+    fn test_semantic_change_is_kept() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/lib.rs", lines(&["-let x = 1;", "+let x = 2;", " ctx", "+let y = 3;"]))]);
+        let (rewritten, count) = strip_formatting_only(&pr);
+        assert!(rewritten.is_none());
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_mixed_hunk_strips_only_formatting_runs() {
+        let mut pr = test_pull_request();
+        pr.set_totals(pr.files_changed(), 2, 2);
+        pr.set_files(vec![test_diff_file("a.js", lines(&["-foo( 1 );", "+foo(1);", " ctx", "-bar(1);", "+bar(2);"]))]);
+        let (rewritten, count) = strip_formatting_only(&pr);
+        let rewritten = rewritten.unwrap();
+        assert_eq!(count, 1);
+        assert_eq!((rewritten.additions(), rewritten.deletions()), (1, 1));
+        assert_eq!(rewritten.files()[0].hunks()[0].lines, lines(&[" foo(1);", " ctx", "-bar(1);", "+bar(2);"]));
+    }
+}
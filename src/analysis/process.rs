@@ -0,0 +1,427 @@
+use async_trait::async_trait;
+use regex::Regex;
+
+use super::{Analyzer, AnalysisError, RuleSpec};
+use crate::config::ProcessConfig;
+use crate::paths::{file_name, matches_any};
+use crate::pr::types::DiffFile;
+use crate::pr::PullRequest;
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel};
+
+/// Directories GitHub reads CODEOWNERS and SECURITY.md from.
+const GOVERNANCE_DIRS: &[&str] = &["", ".github/", "docs/"];
+
+/// Dependency manifests and the lockfiles generated from them. A lockfile
+/// lives next to its manifest or in a parent directory (workspace root).
+const LOCKFILES: &[(&str, &[&str])] = &[
+    ("Cargo.toml", &["Cargo.lock"]),
+    ("package.json", &["package-lock.json", "npm-shrinkwrap.json", "yarn.lock", "pnpm-lock.yaml"]),
+    ("pyproject.toml", &["poetry.lock", "uv.lock", "pdm.lock"]),
+    ("Pipfile", &["Pipfile.lock"]),
+    ("Gemfile", &["Gemfile.lock"]),
+    ("composer.json", &["composer.lock"]),
+    ("go.mod", &["go.sum"]),
+];
+
+/// Process Risk Analyzer
+///
+/// Flags changes to files that govern how code is reviewed and shipped
+/// rather than what it does; they rarely get the scrutiny of code:
+/// - CODEOWNERS and branch protection exports (`.github/settings.yml`, rulesets)
+/// - SECURITY.md
+/// - Release, publish and deploy workflows (`[process] release_workflows`)
+/// - The Dockerfile of the deployed image (`[process] deploy_images`)
+/// - Manifests whose dependencies change without their lockfile, and
+///   lockfiles that change without their manifest
+///
+/// It also checks the PR itself against naming conventions, as LOW
+/// findings: a conventional-commit title (`[process] title_types`) and the
+/// branch name (`[process] branch_pattern`), and that changes to
+/// public-facing code (`[process] public_paths`) come with a changelog entry.
+pub struct ProcessAnalyzer {
+    release_workflows: Vec<String>,
+    deploy_images: Vec<String>,
+    title_types: Vec<String>,
+    /// None when unset or not a valid regex
+    branch_pattern: Option<Regex>,
+    branch_pattern_invalid: bool,
+    public_paths: Vec<String>,
+    changelog_paths: Vec<String>,
+    changelog_skip_labels: Vec<String>,
+}
+
+impl Default for ProcessAnalyzer {
+    fn default() -> Self {
+        Self::with_config(&ProcessConfig::default())
+    }
+}
+
+impl ProcessAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: &ProcessConfig) -> Self {
+        let branch_pattern = config.branch_pattern.as_deref().map(Regex::new);
+        Self {
+            release_workflows: config.release_workflows.clone(),
+            deploy_images: config.deploy_images.clone(),
+            title_types: config.title_types.clone(),
+            branch_pattern_invalid: branch_pattern.as_ref().is_some_and(|re| re.is_err()),
+            branch_pattern: branch_pattern.and_then(Result::ok),
+            public_paths: config.public_paths.clone(),
+            changelog_paths: config.changelog_paths.clone(),
+            changelog_skip_labels: config.changelog_skip_labels.clone(),
+        }
+    }
+
+    /// Message id and severity for a governance file, or None for any other file.
+    fn classify(&self, path: &str) -> Option<(&'static str, RiskLevel)> {
+        let in_governance_dir = |name: &str| GOVERNANCE_DIRS.iter().any(|dir| path.strip_prefix(dir).is_some_and(|rest| rest.eq_ignore_ascii_case(name)));
+        let name = file_name(path).to_ascii_lowercase();
+        if in_governance_dir("CODEOWNERS") {
+            Some(("process.codeowners", RiskLevel::High))
+        } else if path == ".github/settings.yml" || path.starts_with(".github/rulesets/") || name.contains("branch-protection") || name.contains("branch_protection") {
+            Some(("process.branch_protection", RiskLevel::High))
+        } else if matches_any(&self.release_workflows, path) {
+            Some(("process.release_workflow", RiskLevel::High))
+        } else if matches_any(&self.deploy_images, path) {
+            Some(("process.deploy_image", RiskLevel::Medium))
+        } else if in_governance_dir("SECURITY.md") {
+            Some(("process.security_policy", RiskLevel::Medium))
+        } else {
+            None
+        }
+    }
+
+    /// One finding per changed governance file; deleting one is always HIGH.
+    fn check_file(&self, file: &DiffFile) -> Option<Finding> {
+        let (id, severity) = self.classify(file.path())?;
+        let finding = Finding::new(id, severity).param("additions", file.additions()).param("deletions", file.deletions()).in_file(file.path());
+        Some(if file.is_deleted() { Finding { severity: RiskLevel::High, ..finding }.because("file deleted") } else { finding })
+    }
+
+    /// A LOW finding when the title does not start with `<type>[(scope)][!]: `
+    /// and a description, for one of the configured types.
+    fn check_title(&self, title: &str) -> Option<Finding> {
+        if self.title_types.is_empty() || follows_conventional_commit(title, &self.title_types) {
+            return None;
+        }
+        Some(Finding::new("process.title_convention", RiskLevel::Low).param("title", title).param("types", self.title_types.join("|")))
+    }
+
+    /// A LOW finding when the source branch is known and does not match.
+    fn check_branch(&self, branch: Option<&str>) -> Option<Finding> {
+        let (re, branch) = (self.branch_pattern.as_ref()?, branch?);
+        if re.is_match(branch) {
+            return None;
+        }
+        Some(Finding::new("process.branch_convention", RiskLevel::Low).param("branch", branch).param("pattern", re.as_str()))
+    }
+
+    /// A LOW finding when public-facing code changed but no changelog file
+    /// did, unless the PR carries one of the skip labels.
+    fn check_changelog(&self, pr: &PullRequest) -> Option<Finding> {
+        let public: Vec<&str> = pr.files().iter().map(|f| f.path()).filter(|path| matches_any(&self.public_paths, path)).collect();
+        if public.is_empty() || pr.files().iter().any(|f| matches_any(&self.changelog_paths, f.path())) {
+            return None;
+        }
+        if pr.labels().iter().any(|label| self.changelog_skip_labels.iter().any(|skip| skip.eq_ignore_ascii_case(label))) {
+            return None;
+        }
+        Some(
+            Finding::new("process.changelog_missing", RiskLevel::Low)
+                .param("count", public.len())
+                .param("files", public.join(", "))
+                .param("changelog", self.changelog_paths.join(", "))
+                .param("labels", self.changelog_skip_labels.join(" or ")),
+        )
+    }
+}
+
+/// A manifest whose dependency lines changed with no lockfile of its kind
+/// changed beside it or above it is MEDIUM: a locked install (`cargo build
+/// --locked`, `npm ci`) fails after merge. With a checkout, manifests that
+/// have no lockfile at all are left alone. A lockfile changed with no
+/// manifest below it is LOW, since `cargo update` and the like do that on
+/// purpose.
+fn check_lockfiles(pr: &PullRequest) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let changed = |names: &[&str]| pr.files().iter().filter(|f| names.contains(&file_name(f.path()))).map(|f| f.path()).collect::<Vec<_>>();
+    for (manifest, lockfiles) in LOCKFILES {
+        let manifests = changed(&[manifest]);
+        let locks = changed(lockfiles);
+        for file in pr.files().iter().filter(|f| manifests.contains(&f.path()) && !f.is_deleted()) {
+            let dir = parent_dir(file.path());
+            if locks.iter().any(|lock| is_ancestor(parent_dir(lock), dir)) || !changes_dependencies(file) {
+                continue;
+            }
+            if let Some(checkout) = &pr.checkout() {
+                let ancestors = std::iter::successors(Some(dir), |d| (!d.is_empty()).then(|| parent_dir(d)));
+                if !ancestors.flat_map(|d| lockfiles.iter().map(move |lock| join(d, lock))).any(|path| checkout.has_file(&path)) {
+                    continue;
+                }
+            }
+            findings.push(Finding::new("process.lockfile_stale", RiskLevel::Medium).param("manifest", file.path()).param("lockfiles", lockfiles.join(" or ")).in_file(file.path()));
+        }
+        for lock in &locks {
+            if !manifests.iter().any(|m| is_ancestor(parent_dir(lock), parent_dir(m))) {
+                findings.push(Finding::new("process.lockfile_without_manifest", RiskLevel::Low).param("lockfile", *lock).param("manifest", *manifest).in_file(*lock));
+            }
+        }
+    }
+    findings
+}
+
+/// Whether a manifest diff adds or removes a line in a dependency section,
+/// as far as the hunk shows: a TOML `[*dependencies*]` (Pipfile
+/// `[packages]`) table, a JSON `"*dependencies"`/`"require*"` object, a
+/// go.mod `require`/`replace` directive, or a Gemfile `gem` line.
+fn changes_dependencies(file: &DiffFile) -> bool {
+    let name = file_name(file.path());
+    file.hunks().iter().any(|hunk| {
+        let mut section = "";
+        hunk.lines.iter().any(|line| {
+            let changed = line.starts_with('+') || line.starts_with('-');
+            let content = line.get(1..).unwrap_or_default().trim();
+            match name {
+                "package.json" | "composer.json" => {
+                    if let Some((key, "{")) = content.split_once(':').map(|(k, v)| (k.trim().trim_matches('"'), v.trim())) {
+                        section = key;
+                    } else if content.starts_with('}') {
+                        section = "";
+                    }
+                    changed && !content.is_empty() && (section.ends_with("ependencies") || section.starts_with("require"))
+                }
+                "go.mod" => {
+                    if content.ends_with('(') {
+                        section = content;
+                    } else if content == ")" {
+                        section = "";
+                    }
+                    changed && !content.is_empty() && ["require", "replace"].iter().any(|d| content.starts_with(d) || section.starts_with(d))
+                }
+                "Gemfile" => changed && content.starts_with("gem "),
+                _ => {
+                    if content.starts_with('[') {
+                        section = content;
+                    }
+                    changed && !content.is_empty() && !content.starts_with('#') && (section.contains("dependencies") || section == "[packages]" || section == "[dev-packages]")
+                }
+            }
+        })
+    })
+}
+
+/// Directory part of a repository path; "" at the root.
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+fn is_ancestor(dir: &str, of: &str) -> bool {
+    dir.is_empty() || of == dir || of.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn follows_conventional_commit(title: &str, types: &[String]) -> bool {
+    let Some((prefix, description)) = title.split_once(": ") else {
+        return false;
+    };
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let kind = match prefix.split_once('(') {
+        Some((kind, scope)) if scope.ends_with(')') && scope.len() > 1 => kind,
+        Some(_) => return false,
+        None => prefix,
+    };
+    types.iter().any(|t| t == kind) && !description.trim().is_empty()
+}
+
+#[async_trait]
+impl Analyzer for ProcessAnalyzer {
+    fn name(&self) -> &str {
+        "Process Risk Assessment"
+    }
+
+    fn rules_version(&self) -> u32 {
+        5
+    }
+
+    fn rules(&self) -> Vec<RuleSpec> {
+        vec![
+            RuleSpec::file("PRC001", "process.codeowners", Category::Process, &["process.codeowners"]),
+            RuleSpec::file("PRC002", "process.branch_protection", Category::Process, &["process.branch_protection"]),
+            RuleSpec::file("PRC003", "process.security_policy", Category::Process, &["process.security_policy"]),
+            RuleSpec::file("PRC004", "process.release_workflow", Category::Process, &["process.release_workflow"]).disabled_if(self.release_workflows.is_empty(), "[process] release_workflows is empty"),
+            RuleSpec::file("PRC005", "process.deploy_image", Category::Process, &["process.deploy_image"]).disabled_if(self.deploy_images.is_empty(), "[process] deploy_images is empty"),
+            RuleSpec::file("PRC006", "process.lockfiles", Category::Process, &["process.lockfile_stale", "process.lockfile_without_manifest"]),
+            RuleSpec::file("PRC007", "process.title_convention", Category::Process, &["process.title_convention"]).disabled_if(self.title_types.is_empty(), "[process] title_types is empty"),
+            RuleSpec::file("PRC008", "process.branch_convention", Category::Process, &["process.branch_convention"])
+                .disabled_if(self.branch_pattern.is_none(), "[process] branch_pattern is not set")
+                .disabled_if(self.branch_pattern_invalid, "[process] branch_pattern is not a valid regex"),
+            RuleSpec::file("PRC009", "process.changelog", Category::Process, &["process.changelog_missing"]).disabled_if(self.public_paths.is_empty(), "[process] public_paths is empty"),
+        ]
+    }
+
+    async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError> {
+        let mut findings: Vec<Finding> = pr.files().iter().filter_map(|f| self.check_file(f)).collect();
+        findings.extend(check_lockfiles(pr));
+        findings.extend(self.check_title(pr.title()));
+        findings.extend(self.check_branch(pr.branch()));
+        findings.extend(self.check_changelog(pr));
+        let risk_level = findings.iter().map(|f| f.severity).max().unwrap_or(RiskLevel::Low);
+
+        Ok(AnalysisResult {
+            analyzer_name: self.name().to_string(),
+            risk_level,
+            findings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+
+    fn changed(path: &str) -> DiffFile {
+        test_diff_file(path, vec!["+x".to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_flags_governance_files_only() {
+        let mut pr = test_pull_request();
+        let mut security_md = changed("SECURITY.md");
+        security_md.set_deleted(true);
+        pr.set_files(vec![
+            changed(".github/CODEOWNERS"),
+            changed(".github/settings.yml"),
+            changed(".github/workflows/release.yml"),
+            changed(".github/workflows/ci.yml"),
+            changed("Dockerfile"),
+            changed("dev/Dockerfile"),
+            changed("docs/security/overview.md"),
+            security_md,
+        ]);
+        let result = ProcessAnalyzer::new().analyze(&pr).await.unwrap();
+        let flagged: Vec<(&str, RiskLevel)> = result.findings.iter().map(|f| (f.file.as_deref().unwrap(), f.severity)).collect();
+        assert_eq!(
+            flagged,
+            vec![
+                (".github/CODEOWNERS", RiskLevel::High),
+                (".github/settings.yml", RiskLevel::High),
+                (".github/workflows/release.yml", RiskLevel::High),
+                ("Dockerfile", RiskLevel::Medium),
+                ("SECURITY.md", RiskLevel::High),
+            ]
+        );
+        assert_eq!(result.findings[0].message, "Code owners changed (+1 -0) — this changes who must approve which paths");
+        assert_eq!(result.risk_level, RiskLevel::High);
+    }
+
+    #[tokio::test]
+    async fn test_configured_deploy_images() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![changed("Dockerfile"), changed("deploy/api/Dockerfile")]);
+        let config = ProcessConfig { deploy_images: vec!["deploy/**/Dockerfile".to_string()], ..ProcessConfig::default() };
+        let result = ProcessAnalyzer::with_config(&config).analyze(&pr).await.unwrap();
+        assert_eq!(result.findings.len(), 1);
+        assert_eq!(result.findings[0].file.as_deref(), Some("deploy/api/Dockerfile"));
+    }
+
+    #[test]
+    fn test_conventional_commit_titles() {
+        let types = ["feat".to_string(), "fix".to_string()];
+        for title in ["feat: add login", "fix(api): handle 404", "feat(ui)!: drop IE"] {
+            assert!(follows_conventional_commit(title, &types), "{title}");
+        }
+        for title in ["Add login", "docs: readme", "feat:add", "feat(): x", "feat: ", "Feat: add login"] {
+            assert!(!follows_conventional_commit(title, &types), "{title}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_title_and_branch_conventions() {
+        let mut pr = test_pull_request();
+        pr.set_title("Add login".to_string());
+        pr.set_branch(Some("alice/login".to_string()));
+        let config = ProcessConfig { title_types: vec!["feat".to_string(), "fix".to_string()], branch_pattern: Some("^feature/JIRA-\\d+".to_string()), ..ProcessConfig::default() };
+        let analyzer = ProcessAnalyzer::with_config(&config);
+        let result = analyzer.analyze(&pr).await.unwrap();
+        let ids: Vec<&str> = result.findings.iter().filter_map(|f| f.message_id.as_deref()).collect();
+        assert_eq!(ids, ["process.title_convention", "process.branch_convention"]);
+        assert_eq!(result.findings[0].message, "PR title \"Add login\" does not follow the conventional-commit format (feat|fix: description)");
+        assert_eq!(result.findings[1].message, "Branch \"alice/login\" does not match the naming convention `^feature/JIRA-\\d+`");
+        assert_eq!(result.risk_level, RiskLevel::Low);
+
+        pr.set_title("feat(auth): add login".to_string());
+        pr.set_branch(Some("feature/JIRA-123-login".to_string()));
+        assert!(analyzer.analyze(&pr).await.unwrap().findings.is_empty());
+        pr.set_branch(None);
+        assert!(analyzer.analyze(&pr).await.unwrap().findings.is_empty());
+
+        let invalid = ProcessAnalyzer::with_config(&ProcessConfig { branch_pattern: Some("(".to_string()), ..ProcessConfig::default() });
+        let rule = invalid.rules().into_iter().find(|r| r.id == "process.branch_convention").unwrap();
+        assert_eq!(rule.disabled, Some("[process] branch_pattern is not a valid regex"));
+    }
+
+    #[tokio::test]
+    async fn test_manifest_and_lockfile_change_together() {
+        let lines = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let cargo_dep = test_diff_file("crates/core/Cargo.toml", lines(&[" [dependencies]", "+serde = \"1\""]));
+        let cargo_meta = test_diff_file("crates/cli/Cargo.toml", lines(&[" [package]", "-description = \"old\"", "+description = \"new\""]));
+        let npm_dep = test_diff_file("web/package.json", lines(&["   \"devDependencies\": {", "+    \"vitest\": \"^1.0.0\","]));
+        let npm_scripts = test_diff_file("docs/package.json", lines(&["   \"scripts\": {", "+    \"lint\": \"eslint .\","]));
+        let mut pr = test_pull_request();
+        pr.set_files(vec![cargo_dep.clone(), cargo_meta, npm_dep, npm_scripts, changed("go.sum")]);
+        let result = ProcessAnalyzer::new().analyze(&pr).await.unwrap();
+        let found: Vec<_> = result.findings.iter().map(|f| (f.message_id.as_deref().unwrap(), f.file.as_deref().unwrap())).collect();
+        assert_eq!(
+            found,
+            [("process.lockfile_stale", "crates/core/Cargo.toml"), ("process.lockfile_stale", "web/package.json"), ("process.lockfile_without_manifest", "go.sum")]
+        );
+        assert_eq!(result.findings[1].message, "web/package.json changes dependencies but package-lock.json or npm-shrinkwrap.json or yarn.lock or pnpm-lock.yaml was not updated — a locked install will fail after merge");
+
+        // The workspace lockfile at the root covers member manifests.
+        pr.set_files(vec![cargo_dep.clone(), changed("Cargo.lock")]);
+        assert!(ProcessAnalyzer::new().analyze(&pr).await.unwrap().findings.is_empty());
+
+        // With a checkout, a manifest without any lockfile is not flagged.
+        let root = std::env::temp_dir().join("pr-analyzer-test-lockfiles");
+        std::fs::create_dir_all(&root).unwrap();
+        pr.set_files(vec![cargo_dep]);
+        pr.set_checkout(crate::repo::Checkout::unchecked(&root));
+        assert!(ProcessAnalyzer::new().analyze(&pr).await.unwrap().findings.is_empty());
+        std::fs::write(root.join("Cargo.lock"), "").unwrap();
+        assert_eq!(ProcessAnalyzer::new().analyze(&pr).await.unwrap().findings.len(), 1);
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[tokio::test]
+    async fn test_public_changes_need_a_changelog() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![changed("src/api/users.rs"), changed("tests/users.rs")]);
+        let config = ProcessConfig { public_paths: vec!["src/api/**".to_string()], changelog_paths: vec!["CHANGELOG.md".to_string(), "changes/*.md".to_string()], ..ProcessConfig::default() };
+        let analyzer = ProcessAnalyzer::with_config(&config);
+        let result = analyzer.analyze(&pr).await.unwrap();
+        assert_eq!(result.findings.len(), 1);
+        assert_eq!(
+            result.findings[0].message,
+            "Public-facing code changed in 1 file(s) (src/api/users.rs) without a changelog entry (CHANGELOG.md, changes/*.md); add one, or label the PR skip-changelog if none is needed"
+        );
+
+        pr.set_labels(vec!["Skip-Changelog".to_string()]);
+        assert!(analyzer.analyze(&pr).await.unwrap().findings.is_empty());
+        pr.set_labels(Vec::new());
+        pr.files_mut().push(changed("changes/123.md"));
+        assert!(analyzer.analyze(&pr).await.unwrap().findings.is_empty());
+        pr.set_files(vec![changed("tests/users.rs")]);
+        assert!(analyzer.analyze(&pr).await.unwrap().findings.is_empty());
+    }
+}
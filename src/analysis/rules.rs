@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use regex::Regex;
+
+use super::scan::{FileVisitor, Line, LineFindings, LineHits, LinePass, LineRules};
+use super::{Analyzer, AnalysisError, RuleSpec};
+use crate::config::UserRule;
+use crate::paths;
+use crate::pr::types::DiffFile;
+use crate::pr::PullRequest;
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel};
+
+/// Custom Rules Analyzer
+///
+/// Runs the `[[rules]]` of the config (and its `rules_file`): each is a
+/// regex matched against the code of added lines, with comments blanked and
+/// string literals kept, in the files its `files` globs select. A match is
+/// a finding with the rule's message, severity and tags.
+///
+/// Rules whose regex does not compile are reported (LOW) instead of
+/// silently not running.
+pub struct RulesAnalyzer {
+    /// Rules that compile, with their regex
+    rules: Vec<(Regex, UserRule)>,
+    /// Ids of rules that do not compile, with the regex error
+    invalid: Vec<(String, String)>,
+}
+
+impl RulesAnalyzer {
+    pub fn with_config(rules: &[UserRule]) -> Self {
+        let mut compiled = Vec::new();
+        let mut invalid = Vec::new();
+        for rule in rules {
+            match Regex::new(&rule.pattern) {
+                Ok(re) => compiled.push((re, rule.clone())),
+                Err(e) => invalid.push((rule.id.clone(), e.to_string())),
+            }
+        }
+        Self { rules: compiled, invalid }
+    }
+}
+
+/// Whether `rule` checks the file at `path`.
+fn applies_to(rule: &UserRule, path: &str) -> bool {
+    rule.files.is_empty() || paths::matches_any(&rule.files, path)
+}
+
+/// Rules match with regexes of their own, so the shared pass searches no
+/// keywords for them; the visitor sees each line of the files they select.
+impl LineRules for RulesAnalyzer {
+    fn keywords(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn visit_file<'a>(&'a self, _pr: &'a PullRequest, file: &'a DiffFile, out: &'a mut LineFindings) -> Option<Box<dyn FileVisitor + 'a>> {
+        let rules: Vec<usize> = (0..self.rules.len()).filter(|&i| applies_to(&self.rules[i].1, file.path())).collect();
+        if rules.is_empty() {
+            return None;
+        }
+        Some(Box::new(RulesFile { path: file.path(), all: &self.rules, rules, out }))
+    }
+}
+
+/// The rules that apply to one file, by index into `all`.
+struct RulesFile<'a> {
+    path: &'a str,
+    all: &'a [(Regex, UserRule)],
+    rules: Vec<usize>,
+    out: &'a mut LineFindings,
+}
+
+impl FileVisitor for RulesFile<'_> {
+    fn line(&mut self, line: &Line, _hits: LineHits) {
+        if !line.added {
+            return;
+        }
+        for &i in &self.rules {
+            let (re, rule) = &self.all[i];
+            if re.is_match(&line.masked.code) {
+                let finding = Finding::new("rules.match", rule.severity).param("rule", &rule.id).param("message", &rule.message);
+                let finding = rule.tags.iter().fold(finding, |f, tag| f.tag(tag));
+                self.out.push(i, finding.in_file(self.path).at_line(line.number));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Analyzer for RulesAnalyzer {
+    fn name(&self) -> &str {
+        "Custom Rules"
+    }
+
+    fn rules_version(&self) -> u32 {
+        1
+    }
+
+    fn rules(&self) -> Vec<RuleSpec> {
+        vec![RuleSpec::line("RUL001", "rules.custom", Category::Custom, &["rules.match"])
+            .disabled_if(self.rules.is_empty() && self.invalid.is_empty(), "no [[rules]] configured")
+            .fails_with("rules.invalid")]
+    }
+
+    async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError> {
+        self.analyze_scanned(pr, LinePass::run_one(self, pr)).await
+    }
+
+    fn line_rules(&self) -> Option<&dyn LineRules> {
+        Some(self)
+    }
+
+    async fn analyze_scanned(&self, _pr: &PullRequest, mut lines: LineFindings) -> Result<AnalysisResult, AnalysisError> {
+        let mut findings: Vec<Finding> = (0..self.rules.len()).flat_map(|i| lines.take(i)).collect();
+        findings.extend(self.invalid.iter().map(|(rule, error)| Finding::new("rules.invalid", RiskLevel::Low).param("rule", rule).param("error", error)));
+        let risk_level = findings.iter().map(|f| f.severity).max().unwrap_or(RiskLevel::Low);
+
+        Ok(AnalysisResult {
+            analyzer_name: self.name().to_string(),
+            risk_level,
+            findings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+
+    fn rule(id: &str, pattern: &str, files: &[&str]) -> UserRule {
+        UserRule {
+            id: id.to_string(),
+            pattern: pattern.to_string(),
+            files: files.iter().map(|f| f.to_string()).collect(),
+            message: format!("{id} matched"),
+            severity: RiskLevel::High,
+            tags: vec!["team-policy".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rules_match_added_code_in_selected_files() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file("src/main.rs", vec!["+    println!(\"hi\");".to_string(), "+    // println!(\"old\");".to_string(), "-    println!(\"gone\");".to_string()]),
+            test_diff_file("tools/gen.py", vec!["+print('println!')".to_string()]),
+        ]);
+        let analyzer = RulesAnalyzer::with_config(&[rule("no-println", r"println!\(", &["*.rs"]), rule("broken", "(", &[])]);
+        let result = analyzer.analyze(&pr).await.unwrap();
+
+        assert_eq!(result.risk_level, RiskLevel::High);
+        let matches: Vec<&Finding> = result.findings.iter().filter(|f| f.message_id.as_deref() == Some("rules.match")).collect();
+        assert_eq!(matches.len(), 1, "{:?}", result.findings);
+        assert_eq!((matches[0].file.as_deref(), matches[0].line), (Some("src/main.rs"), Some(1)));
+        assert_eq!(matches[0].message, "no-println matched (no-println)");
+        assert_eq!(matches[0].tags, ["team-policy"]);
+        let invalid = result.findings.iter().find(|f| f.message_id.as_deref() == Some("rules.invalid")).unwrap();
+        assert!(invalid.message.contains("`broken`"), "{}", invalid.message);
+    }
+}
@@ -0,0 +1,351 @@
+//! One pass over the diff lines for every line-based rule.
+//!
+//! Analyzers with per-line checks implement `LineRules`. A `LinePass` walks
+//! each file once, masks each line once, and searches it once for the
+//! keywords of all registered rule sets through a combined Aho-Corasick
+//! automaton. It then hands the line and its keyword hits to each rule set's
+//! `FileVisitor`. Adding rules therefore adds patterns to one automaton, not
+//! another walk over the diff.
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
+
+use super::contains_ignore_ascii_case;
+use super::lexer::{Lexer, MaskedLine};
+use crate::pr::types::DiffFile;
+use crate::pr::PullRequest;
+use crate::report::types::Finding;
+
+/// Line-based rules of one analyzer, run by a `LinePass`.
+pub trait LineRules: Send + Sync {
+    /// Substrings the rules look for, at most 64. `LineHits` reports them by
+    /// index into this list.
+    fn keywords(&self) -> &'static [&'static str];
+
+    /// Start a file. The visitor sees every line of the file in diff order
+    /// and records findings in `out`; `None` skips the file.
+    fn visit_file<'a>(&'a self, pr: &'a PullRequest, file: &'a DiffFile, out: &'a mut LineFindings) -> Option<Box<dyn FileVisitor + 'a>>;
+
+    /// Called after the last file, for findings about the change as a whole.
+    fn finish(&self, out: &mut LineFindings) {
+        let _ = out;
+    }
+}
+
+/// Per-file state of one `LineRules` implementation.
+pub trait FileVisitor {
+    /// `hits` is empty for lines the diff does not add.
+    fn line(&mut self, line: &Line, hits: LineHits);
+}
+
+/// Keyword hits of one line, indexed like `LineRules::keywords`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineHits {
+    /// Found in `MaskedLine::code` (string literals kept)
+    pub code: Hits,
+    /// Found in `MaskedLine::bare` (string contents blanked)
+    pub bare: Hits,
+}
+
+/// Findings of one analyzer's line rules, grouped by rule so the analyzer
+/// can place each group in its usual report order.
+#[derive(Debug, Default)]
+pub struct LineFindings {
+    by_rule: Vec<Vec<Finding>>,
+}
+
+impl LineFindings {
+    pub fn push(&mut self, rule: usize, finding: Finding) {
+        if self.by_rule.len() <= rule {
+            self.by_rule.resize_with(rule + 1, Vec::new);
+        }
+        self.by_rule[rule].push(finding);
+    }
+
+    pub fn count(&self, rule: usize) -> usize {
+        self.by_rule.get(rule).map_or(0, Vec::len)
+    }
+
+    /// Remove and return the findings of `rule`.
+    pub fn take(&mut self, rule: usize) -> Vec<Finding> {
+        self.by_rule.get_mut(rule).map(std::mem::take).unwrap_or_default()
+    }
+}
+
+/// Registered rule sets and the automaton over all of their keywords.
+pub struct LinePass<'r> {
+    rules: Vec<&'r dyn LineRules>,
+    keywords: Keywords,
+    /// Rule set and local keyword index of each combined pattern
+    owners: Vec<(usize, usize)>,
+}
+
+impl<'r> LinePass<'r> {
+    pub fn new(rules: Vec<&'r dyn LineRules>) -> Self {
+        let mut owners = Vec::new();
+        let mut patterns = Vec::new();
+        for (set, r) in rules.iter().enumerate() {
+            debug_assert!(r.keywords().len() <= 64, "Hits holds at most 64 patterns");
+            for (local, pattern) in r.keywords().iter().enumerate() {
+                owners.push((set, local));
+                patterns.push(*pattern);
+            }
+        }
+        Self { rules, keywords: Keywords::build(patterns, false), owners }
+    }
+
+    /// Run `rules` alone; what `Analyzer::analyze` does outside `run_all`.
+    pub fn run_one(rules: &dyn LineRules, pr: &PullRequest) -> LineFindings {
+        LinePass::new(vec![rules]).run(pr).pop().unwrap_or_default()
+    }
+
+    /// Walk every file once. Returns one `LineFindings` per rule set, in
+    /// registration order.
+    pub fn run(&self, pr: &PullRequest) -> Vec<LineFindings> {
+        self.run_until(pr, || false).unwrap_or_default()
+    }
+
+    /// `run`, asking `stop` before each file. Returns `None` as soon as it
+    /// says yes; findings of a partial walk would be wrong, not just short.
+    pub fn run_until(&self, pr: &PullRequest, mut stop: impl FnMut() -> bool) -> Option<Vec<LineFindings>> {
+        let mut outs: Vec<LineFindings> = self.rules.iter().map(|_| LineFindings::default()).collect();
+        let mut hits = vec![LineHits::default(); self.rules.len()];
+        for file in pr.files() {
+            if stop() {
+                return None;
+            }
+            let mut visitors: Vec<(usize, Box<dyn FileVisitor + '_>)> = self
+                .rules
+                .iter()
+                .zip(outs.iter_mut())
+                .enumerate()
+                .filter_map(|(set, (r, out))| r.visit_file(pr, file, out).map(|v| (set, v)))
+                .collect();
+            if visitors.is_empty() {
+                continue;
+            }
+            for_each_line(file, |line| {
+                hits.fill(LineHits::default());
+                if line.added {
+                    self.search(&line.masked, &mut hits);
+                }
+                for (set, visitor) in &mut visitors {
+                    visitor.line(line, hits[*set]);
+                }
+            });
+        }
+        for (r, out) in self.rules.iter().zip(outs.iter_mut()) {
+            r.finish(out);
+        }
+        Some(outs)
+    }
+
+    fn search(&self, masked: &MaskedLine, hits: &mut [LineHits]) {
+        let (code, bare) = (&*masked.code, &*masked.bare);
+        self.keywords.for_each_match(code, |i| {
+            let (set, local) = self.owners[i];
+            hits[set].code.0 |= 1 << local;
+        });
+        // Lines without comments or strings borrow the same text for both.
+        if std::ptr::eq(code, bare) {
+            for h in hits.iter_mut() {
+                h.bare = h.code;
+            }
+            return;
+        }
+        self.keywords.for_each_match(bare, |i| {
+            let (set, local) = self.owners[i];
+            hits[set].bare.0 |= 1 << local;
+        });
+    }
+}
+
+/// A diff line as seen by the line-based checks.
+pub struct Line<'a> {
+    /// `hunk.new_start` plus the line's index in the hunk
+    pub number: usize,
+    /// Whether the diff adds this line (`+`)
+    pub added: bool,
+    /// The line without its `+`/`-`/` ` prefix
+    pub text: &'a str,
+    pub masked: MaskedLine<'a>,
+}
+
+/// Call `f` for every line of every hunk in `file`, in diff order.
+pub(crate) fn for_each_line<'a>(file: &'a DiffFile, mut f: impl FnMut(&Line<'a>)) {
+    for hunk in file.hunks() {
+        let mut lexer = Lexer::for_path(file.path());
+        for (i, line) in hunk.lines.iter().enumerate() {
+            let masked = lexer.next_line(line);
+            let text = match line.as_bytes().first() {
+                Some(b'+' | b'-' | b' ') => &line[1..],
+                _ => line.as_str(),
+            };
+            f(&Line { number: hunk.new_start + i, added: line.starts_with('+'), text, masked });
+        }
+    }
+}
+
+/// A fixed set of substrings searched for in one pass. `matches` reports
+/// them by their index in the set, so it supports at most 64 patterns.
+pub struct Keywords {
+    patterns: Vec<&'static str>,
+    ignore_case: bool,
+    /// `None` only if the automaton could not be built; searches then fall
+    /// back to one substring check per pattern.
+    automaton: Option<AhoCorasick>,
+}
+
+impl Keywords {
+    /// Case-sensitive set.
+    pub fn new(patterns: impl IntoIterator<Item = &'static str>) -> Self {
+        Self::build(patterns, false)
+    }
+
+    /// Set that matches regardless of ASCII case (`select` finds `SELECT`).
+    pub fn ignore_ascii_case(patterns: impl IntoIterator<Item = &'static str>) -> Self {
+        Self::build(patterns, true)
+    }
+
+    fn build(patterns: impl IntoIterator<Item = &'static str>, ignore_case: bool) -> Self {
+        let patterns: Vec<_> = patterns.into_iter().collect();
+        let automaton = AhoCorasickBuilder::new().ascii_case_insensitive(ignore_case).build(&patterns).ok();
+        Self { patterns, ignore_case, automaton }
+    }
+
+    /// Which patterns occur anywhere in `haystack` (overlapping matches included).
+    pub fn matches(&self, haystack: &str) -> Hits {
+        debug_assert!(self.patterns.len() <= 64, "Hits holds at most 64 patterns");
+        let mut hits = Hits::default();
+        self.for_each_match(haystack, |i| hits.0 |= 1 << i);
+        hits
+    }
+
+    /// Call `f` with the index of every pattern occurrence in `haystack`.
+    /// A pattern found several times is reported several times.
+    fn for_each_match(&self, haystack: &str, mut f: impl FnMut(usize)) {
+        match &self.automaton {
+            Some(automaton) => {
+                for m in automaton.find_overlapping_iter(haystack) {
+                    f(m.pattern().as_usize());
+                }
+            }
+            None => {
+                for (i, pattern) in self.patterns.iter().enumerate() {
+                    let found = if self.ignore_case { contains_ignore_ascii_case(haystack, pattern) } else { haystack.contains(pattern) };
+                    if found {
+                        f(i);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pattern indices found by `Keywords::matches`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Hits(u64);
+
+impl Hits {
+    pub fn contains(self, index: usize) -> bool {
+        self.0 & (1 << index) != 0
+    }
+
+    pub fn any(self, indices: &[usize]) -> bool {
+        indices.iter().any(|&i| self.contains(i))
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// `text` starts with `prefix`, ignoring ASCII case.
+pub(crate) fn starts_with_ignore_ascii_case(text: &str, prefix: &str) -> bool {
+    text.as_bytes().get(..prefix.len()).is_some_and(|head| head.eq_ignore_ascii_case(prefix.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+    use crate::report::types::RiskLevel;
+
+    /// Records one finding per added line whose code has its last keyword.
+    struct LastKeyword(&'static [&'static str]);
+
+    struct Recorder<'a>(usize, &'a mut LineFindings);
+
+    impl FileVisitor for Recorder<'_> {
+        fn line(&mut self, line: &Line, hits: LineHits) {
+            if hits.code.contains(self.0) {
+                let finding = Finding::text(line.text, RiskLevel::Low).at_line(line.number);
+                self.1.push(0, finding);
+            }
+        }
+    }
+
+    impl LineRules for LastKeyword {
+        fn keywords(&self) -> &'static [&'static str] {
+            self.0
+        }
+
+        fn visit_file<'a>(&'a self, _pr: &'a PullRequest, _file: &'a DiffFile, out: &'a mut LineFindings) -> Option<Box<dyn FileVisitor + 'a>> {
+            Some(Box::new(Recorder(self.0.len() - 1, out)))
+        }
+    }
+
+    #[test]
+    fn test_line_pass_dispatches_hits_per_rule_set() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/lib.rs", vec!["+let a = b.unwrap();".to_string(), "+// todo!()".to_string(), "-todo!()".to_string(), "+todo!()".to_string()])]);
+        let (unwrap, todo) = (LastKeyword(&["unwrap"]), LastKeyword(&["clone", "todo!"]));
+        let mut outs = LinePass::new(vec![&unwrap, &todo]).run(&pr);
+        let todo_lines: Vec<_> = outs[1].take(0).iter().map(|f| f.line).collect();
+        assert_eq!(todo_lines, vec![Some(4)]);
+        assert_eq!(outs[0].count(0), 1);
+        assert_eq!(LinePass::run_one(&todo, &pr).count(0), 1);
+    }
+
+    #[test]
+    fn test_line_pass_stops_between_files() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/a.rs", vec!["+a.unwrap()".to_string()]), test_diff_file("src/b.rs", vec!["+b.unwrap()".to_string()])]);
+        let unwrap = LastKeyword(&["unwrap"]);
+        let pass = LinePass::new(vec![&unwrap]);
+        let mut asked = 0;
+        let stopped = pass.run_until(&pr, || {
+            asked += 1;
+            asked > 1
+        });
+        assert!(stopped.is_none());
+        assert_eq!(asked, 2);
+        assert_eq!(pass.run_until(&pr, || false).unwrap()[0].count(0), 2);
+    }
+
+    #[test]
+    fn test_keywords_report_overlapping_matches() {
+        let keywords = Keywords::new(["secret", "secret_key_", "token"]);
+        let hits = keywords.matches("let secret_key_a = 1;");
+        assert!(hits.contains(0) && hits.contains(1));
+        assert!(!hits.contains(2));
+        assert!(keywords.matches("SECRET").is_empty());
+    }
+
+    #[test]
+    fn test_keywords_ignore_ascii_case() {
+        let keywords = Keywords::ignore_ascii_case(["SELECT", "WHERE"]);
+        assert!(keywords.matches("select * from t").any(&[0]));
+        assert!(!keywords.matches("select * from t").contains(1));
+        assert!(starts_with_ignore_ascii_case("// fixme later", "// FIXME"));
+        assert!(!starts_with_ignore_ascii_case("//", "// FIXME"));
+    }
+
+    #[test]
+    fn test_for_each_line_numbers_and_masks() {
+        let file = test_diff_file("src/lib.rs", vec!["+let a = 1; // note".to_string(), "-gone".to_string(), " kept".to_string()]);
+        let mut seen = Vec::new();
+        for_each_line(&file, |line| seen.push((line.number, line.added, line.text.to_string(), line.masked.code.trim_end().to_string())));
+        assert_eq!(seen[0], (1, true, "let a = 1; // note".to_string(), "let a = 1;".to_string()));
+        assert_eq!((seen[1].0, seen[1].1, seen[2].0), (2, false, 3));
+    }
+}
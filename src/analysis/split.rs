@@ -0,0 +1,167 @@
+//! Split suggestions for changes the complexity analyzer calls too large:
+//! groups of files that could land as separate PRs ("Suggested Split").
+//!
+//! Files are grouped by component, the first directory under the
+//! repository root or a source root such as `src/` or `crates/`. A test
+//! file joins the component its name mentions. Components are then merged
+//! when an added import line in one names another, since those changes
+//! depend on each other. Like the attack surface this is a hint: it sees
+//! only the diff, not the code's real dependency graph.
+
+use std::collections::BTreeMap;
+
+use super::{complexity, import_path, is_test_path, path_segments};
+use crate::paths::file_name;
+use crate::pr::PullRequest;
+use crate::report::types::{AnalysisResult, SplitGroup};
+
+/// Complexity findings that call the change too large to review as one.
+const SIZE_FINDINGS: &[&str] = &["complexity.very_large_change", "complexity.large_change", "complexity.very_many_files", "complexity.many_files"];
+
+/// Directories that hold components rather than being one.
+const SOURCE_ROOTS: &[&str] = &["src", "lib", "app", "pkg", "packages", "crates", "internal", "cmd", "source"];
+
+/// Name of the group of files directly in the repository root.
+const ROOT: &str = "(root)";
+
+/// The component a file belongs to, e.g. `src/auth` for `src/auth/login.rs`.
+fn component(path: &str) -> String {
+    let dirs: Vec<&str> = path.split(['/', '\\']).collect();
+    let dirs = &dirs[..dirs.len() - 1];
+    match dirs {
+        [] => ROOT.to_string(),
+        [root, component, ..] if SOURCE_ROOTS.contains(root) => format!("{}/{}", root, component),
+        [first, ..] => first.to_string(),
+    }
+}
+
+/// Last segment of a component, as it appears in import paths.
+fn short_name(component: &str) -> &str {
+    component.rsplit('/').next().unwrap_or(component)
+}
+
+/// Suggested groups when a complexity finding says the change is too large
+/// and it spans at least two independent groups; otherwise empty.
+pub fn suggest(pr: &PullRequest, results: &[AnalysisResult], test_paths: &[String]) -> Vec<SplitGroup> {
+    let too_large = results
+        .iter()
+        .filter(|r| r.analyzer_name == complexity::NAME)
+        .flat_map(|r| &r.findings)
+        .any(|f| f.message_id.as_deref().is_some_and(|id| SIZE_FINDINGS.contains(&id)));
+    if !too_large {
+        return Vec::new();
+    }
+
+    let mut components: Vec<String> = pr.files().iter().filter(|f| !is_test_path(f.path(), test_paths)).map(|f| component(f.path())).collect();
+    components.sort();
+    components.dedup();
+    // Names that import paths and test file names can refer to
+    let names: BTreeMap<&str, usize> = components
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.as_str() != ROOT && !SOURCE_ROOTS.contains(&c.as_str()))
+        .map(|(i, c)| (short_name(c), i))
+        .collect();
+    let file_component = |path: &str| -> String {
+        if is_test_path(path, test_paths) {
+            let stem = file_name(path).split('.').next().unwrap_or_default();
+            if let Some((_, i)) = names.iter().find(|(name, _)| stem.split(['_', '-']).any(|part| part == **name)) {
+                return components[*i].clone();
+            }
+        }
+        component(path)
+    };
+
+    let mut groups: BTreeMap<String, (Vec<String>, usize)> = BTreeMap::new();
+    let mut parent: BTreeMap<String, String> = BTreeMap::new();
+    fn find(parent: &BTreeMap<String, String>, key: &str) -> String {
+        let mut key = key.to_string();
+        while let Some(next) = parent.get(&key).filter(|next| **next != key) {
+            key = next.clone();
+        }
+        key
+    }
+    for file in pr.files() {
+        let own = file_component(file.path());
+        let group = groups.entry(own.clone()).or_default();
+        group.0.push(file.path().to_string());
+        group.1 += file.additions() + file.deletions();
+        let imported = file
+            .hunks()
+            .iter()
+            .flat_map(|h| &h.lines)
+            .filter_map(|line| line.strip_prefix('+'))
+            .filter_map(import_path)
+            .flat_map(path_segments)
+            .filter_map(|segment| names.get(segment).map(|i| components[*i].clone()));
+        for other in imported.collect::<Vec<_>>() {
+            let (a, b) = (find(&parent, &own), find(&parent, &other));
+            // The smaller name becomes the root, so merging is order-independent
+            match a.cmp(&b) {
+                std::cmp::Ordering::Less => parent.insert(b, a),
+                std::cmp::Ordering::Greater => parent.insert(a, b),
+                std::cmp::Ordering::Equal => None,
+            };
+        }
+    }
+
+    let mut merged: BTreeMap<String, SplitGroup> = BTreeMap::new();
+    for (key, (files, lines)) in groups {
+        let group = merged.entry(find(&parent, &key)).or_insert_with(|| SplitGroup { components: Vec::new(), files: Vec::new(), lines: 0 });
+        group.components.push(key);
+        group.files.extend(files);
+        group.lines += lines;
+    }
+    if merged.len() < 2 {
+        return Vec::new();
+    }
+    let mut suggestions: Vec<SplitGroup> = merged.into_values().collect();
+    suggestions.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.components.cmp(&b.components)));
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+    use crate::config::StyleConfig;
+    use crate::report::{Finding, RiskLevel};
+
+    fn too_large() -> Vec<AnalysisResult> {
+        vec![AnalysisResult {
+            analyzer_name: complexity::NAME.to_string(),
+            risk_level: RiskLevel::High,
+            findings: vec![Finding::new("complexity.very_many_files", RiskLevel::High).param("files", 40)],
+        }]
+    }
+
+    #[test]
+    fn test_component() {
+        assert_eq!(component("src/auth/login.rs"), "src/auth");
+        assert_eq!(component("crates/core/src/lib.rs"), "crates/core");
+        assert_eq!(component("src/main.rs"), "src");
+        assert_eq!(component("docs/guide.md"), "docs");
+        assert_eq!(component("Cargo.toml"), "(root)");
+    }
+
+    #[test]
+    fn test_suggest_groups_by_component_and_imports() {
+        let test_paths = StyleConfig::default().test_paths;
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file("src/auth/login.rs", vec!["+use crate::session::Token;".to_string(), "+fn login() {}".to_string()]),
+            test_diff_file("src/session/token.rs", vec!["+pub struct Token;".to_string()]),
+            test_diff_file("src/billing/invoice.rs", vec!["+fn total() {}".to_string(); 3]),
+            test_diff_file("tests/billing_test.rs", vec!["+#[test] fn t() {}".to_string()]),
+            test_diff_file("docs/billing.md", vec!["+# Billing".to_string()]),
+        ]);
+        for file in pr.files_mut() {
+            file.set_counts(file.hunks()[0].lines.len(), file.deletions());
+        }
+        assert!(suggest(&pr, &[], &test_paths).is_empty());
+
+        let groups = suggest(&pr, &too_large(), &test_paths);
+        let shape: Vec<(Vec<&str>, usize, usize)> = groups.iter().map(|g| (g.components.iter().map(String::as_str).collect(), g.files.len(), g.lines)).collect();
+        assert_eq!(shape, [(vec!["src/billing"], 2, 4), (vec!["src/auth", "src/session"], 2, 3), (vec!["docs"], 1, 1)]);
+    }
+}
@@ -1,8 +1,12 @@
 use async_trait::async_trait;
 
-use super::{Analyzer, AnalysisError};
+use super::scan::{starts_with_ignore_ascii_case, FileVisitor, Line, LineFindings, LineHits, LinePass, LineRules};
+use super::{added_lines, import_path, is_test_path, path_segments, Analyzer, AnalysisError, RuleSpec};
+use crate::config::StyleConfig;
+use crate::paths::{self, file_name};
+use crate::pr::types::DiffFile;
 use crate::pr::PullRequest;
-use crate::report::types::{AnalysisResult, Finding, RiskLevel};
+use crate::report::types::{AnalysisResult, Category, Finding, RiskLevel};
 
 /// Style & Architecture Risk Analyzer
 ///
@@ -13,185 +17,230 @@ use crate::report::types::{AnalysisResult, Finding, RiskLevel};
 /// - Import organization
 /// - Architectural boundary violations
 /// - Lint-style checks (unnecessary clone, todo! macros, missing #[must_use])
+/// - Real `cargo clippy` diagnostics on added lines (opt-in, needs a checkout)
 pub struct StyleAnalyzer {
-    // TODO (third-party agent): Add configurable layers from Config.style.layers
+    /// `[style] layers`, from the top layer down
+    layers: Vec<String>,
+    test_paths: Vec<String>,
+    clippy: bool,
+}
+
+impl Default for StyleAnalyzer {
+    fn default() -> Self {
+        Self::with_config(&StyleConfig::default())
+    }
 }
 
 impl StyleAnalyzer {
+    #[cfg(test)]
     pub fn new() -> Self {
-        Self {}
+        Self::default()
     }
 
-    /// Check for unwrap() usage in non-test code.
-    fn check_unwrap_usage(&self, pr: &PullRequest) -> Vec<Finding> {
-        let mut findings = Vec::new();
-        for file in &pr.files {
-            // Skip test files
-            if file.path.starts_with("tests/") || file.path.contains("/tests/") || file.path.ends_with("_test.rs") {
-                continue;
-            }
-            // Check if file contains #[cfg(test)] — we can only heuristically check lines
-            let mut in_test_section = false;
-            for hunk in &file.hunks {
-                for (i, line) in hunk.lines.iter().enumerate() {
-                    let raw = if line.starts_with('+') || line.starts_with('-') || line.starts_with(' ') {
-                        &line[1..]
-                    } else {
-                        line.as_str()
-                    };
-                    if raw.contains("#[cfg(test)]") {
-                        in_test_section = true;
-                    }
-                    if !line.starts_with('+') || in_test_section {
-                        continue;
-                    }
-                    let content = &line[1..];
-                    if content.contains(".unwrap()") {
-                        findings.push(Finding {
-                            message: format!("Use of .unwrap() — prefer ? operator or .expect() with context"),
-                            file: Some(file.path.clone()),
-                            line: Some(hunk.new_start + i),
-                            severity: RiskLevel::Medium,
-                        });
-                    }
-                }
-            }
+    pub fn with_config(config: &StyleConfig) -> Self {
+        Self {
+            layers: config.layers.clone(),
+            test_paths: config.test_paths.clone(),
+            clippy: config.clippy,
         }
-        findings
     }
 
-    /// Check for todo!() and unimplemented!() macros left in production code.
-    fn check_todo_macros(&self, pr: &PullRequest) -> Vec<Finding> {
-        let mut findings = Vec::new();
-        for file in &pr.files {
-            for hunk in &file.hunks {
-                for (i, line) in hunk.lines.iter().enumerate() {
-                    if !line.starts_with('+') {
-                        continue;
-                    }
-                    let content = &line[1..];
-                    if content.contains("todo!()") || content.contains("todo!(\"") {
-                        findings.push(Finding {
-                            message: "todo!() macro found — should not ship to production".to_string(),
-                            file: Some(file.path.clone()),
-                            line: Some(hunk.new_start + i),
-                            severity: RiskLevel::Medium,
-                        });
-                    }
-                    if content.contains("unimplemented!()") || content.contains("unimplemented!(\"") {
-                        findings.push(Finding {
-                            message: "unimplemented!() macro found — should not ship to production".to_string(),
-                            file: Some(file.path.clone()),
-                            line: Some(hunk.new_start + i),
-                            severity: RiskLevel::Medium,
-                        });
-                    }
-                    let trimmed = content.trim().to_uppercase();
-                    if trimmed.starts_with("// FIXME") || trimmed.starts_with("# FIXME") {
-                        findings.push(Finding {
-                            message: "FIXME comment found — indicates known issue".to_string(),
-                            file: Some(file.path.clone()),
-                            line: Some(hunk.new_start + i),
-                            severity: RiskLevel::Low,
-                        });
-                    }
-                }
+    /// Import `cargo clippy` diagnostics that land on lines this PR added.
+    /// Needs `[style] clippy = true` and a checkout; otherwise does nothing.
+    /// Never runs on a fork's PR: build scripts and proc macros would run
+    /// the fork's code with this job's environment and secrets.
+    async fn check_clippy(&self, pr: &PullRequest) -> Vec<Finding> {
+        let Some(checkout) = pr.checkout().filter(|_| self.clippy && !pr.is_fork()) else {
+            return Vec::new();
+        };
+        let changed: Vec<&str> = pr.files().iter().filter(|f| !f.is_deleted()).map(|f| f.path()).collect();
+        let diagnostics = match checkout.clippy(&changed).await {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                return vec![Finding::new("style.clippy_failed", RiskLevel::Low).param("error", e)]
             }
-        }
-        findings
+        };
+        diagnostics
+            .into_iter()
+            .filter(|d| pr.files().iter().any(|f| f.path() == d.file && added_lines(f).contains(&d.line)))
+            .map(|d| {
+                let severity = if d.level == "error" { RiskLevel::Medium } else { RiskLevel::Low };
+                let reason = format!("clippy {}", d.level);
+                let finding = match &d.code {
+                    Some(code) => Finding::new("style.clippy", severity).param("code", code).param("message", &d.message),
+                    None => Finding::text(d.message, severity),
+                };
+                finding.in_file(d.file).at_line(d.line).because(reason)
+            })
+            .collect()
     }
 
-    /// Check for unnecessary clone() calls (heuristic).
-    fn check_unnecessary_clone(&self, pr: &PullRequest) -> Vec<Finding> {
-        let mut findings = Vec::new();
-        for file in &pr.files {
-            if !file.path.ends_with(".rs") {
-                continue;
-            }
-            for hunk in &file.hunks {
-                for (i, line) in hunk.lines.iter().enumerate() {
-                    if !line.starts_with('+') {
-                        continue;
-                    }
-                    let content = &line[1..];
-                    // Heuristic: .clone() on a &str or &String pattern, or .to_string().clone()
-                    if content.contains(".to_string().clone()") || content.contains(".to_owned().clone()") {
-                        findings.push(Finding {
-                            message: "Redundant clone: .to_string().clone() or .to_owned().clone()".to_string(),
-                            file: Some(file.path.clone()),
-                            line: Some(hunk.new_start + i),
-                            severity: RiskLevel::Low,
-                        });
-                    }
-                }
+    /// Index in `layers` of the first path segment a layer glob matches.
+    fn layer_of<'a>(&self, segments: impl IntoIterator<Item = &'a str>) -> Option<usize> {
+        segments.into_iter().find_map(|segment| self.layers.iter().position(|layer| paths::matches(layer, segment)))
+    }
+}
+
+/// Keywords of the line rules, matched in masked code; the constants below index into it.
+static LINE_TOKENS: &[&str] = &[
+    ".unwrap()",
+    "todo!()",
+    "todo!(\"",
+    "unimplemented!()",
+    "unimplemented!(\"",
+    ".to_string().clone()",
+    ".to_owned().clone()",
+];
+const UNWRAP: usize = 0;
+const TODO: usize = 1;
+const TODO_MESSAGE: usize = 2;
+const UNIMPLEMENTED: usize = 3;
+const UNIMPLEMENTED_MESSAGE: usize = 4;
+const TO_STRING_CLONE: usize = 5;
+const TO_OWNED_CLONE: usize = 6;
+
+/// Line rules, in report order; `LineFindings` groups findings by these.
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    Unwrap,
+    UnfinishedCode,
+    RedundantClone,
+    Architecture,
+    Naming,
+}
+
+/// `.unwrap()` outside tests, `todo!`/`unimplemented!` macros, FIXME
+/// comments, redundant clones, and file and type names in new files.
+impl LineRules for StyleAnalyzer {
+    fn keywords(&self) -> &'static [&'static str] {
+        LINE_TOKENS
+    }
+
+    fn visit_file<'a>(&'a self, _pr: &'a PullRequest, file: &'a DiffFile, out: &'a mut LineFindings) -> Option<Box<dyn FileVisitor + 'a>> {
+        let is_rust = file.path().ends_with(".rs");
+        // New Rust files are named in snake_case, and so are renamed ones
+        // whose name changed; a rename's content is not new, so type names
+        // are only checked in new files
+        let renamed = file.previous_path().is_some_and(|previous| file_name(previous) != file_name(file.path()));
+        if (file.is_new() || renamed) && is_rust {
+            let filename = file_name(file.path());
+            let stem = filename.trim_end_matches(".rs");
+            if stem != "mod" && stem != "lib" && stem != "main" && !is_snake_case(stem) {
+                out.push(Rule::Naming as usize, Finding::new("style.file_name_case", RiskLevel::Low).param("name", filename).in_file(file.path()));
             }
         }
-        findings
+        let is_test_file = is_test_path(file.path(), &self.test_paths);
+        Some(Box::new(StyleFile {
+            file,
+            is_rust,
+            is_test_file,
+            // Tests may reach into any layer
+            layer: if is_test_file { None } else { self.layer_of(path_segments(file.path())) },
+            analyzer: self,
+            in_test_section: false,
+            out,
+        }))
     }
+}
+
+/// `StyleAnalyzer` line rules applied to one file.
+struct StyleFile<'a> {
+    file: &'a DiffFile,
+    is_rust: bool,
+    /// Test files are skipped by the unwrap rule
+    is_test_file: bool,
+    /// Index of the file's layer in `[style] layers`
+    layer: Option<usize>,
+    analyzer: &'a StyleAnalyzer,
+    /// Everything after a `#[cfg(test)]` line (on any side of the diff) counts as test code
+    in_test_section: bool,
+    out: &'a mut LineFindings,
+}
 
-    /// Check architectural boundary violations.
-    fn check_architecture_boundaries(&self, _pr: &PullRequest) -> Vec<Finding> {
-        // Without configured layers, we can't check boundaries
-        // This would require Config.style.layers to be populated
-        // For now, return empty — the check is a no-op without layer configuration
-        vec![]
+impl StyleFile<'_> {
+    fn push(&mut self, rule: Rule, finding: Finding, line: usize) {
+        self.out.push(rule as usize, finding.in_file(self.file.path()).at_line(line));
     }
+}
 
-    /// Check naming conventions in new files and types.
-    fn check_naming_conventions(&self, pr: &PullRequest) -> Vec<Finding> {
-        let mut findings = Vec::new();
-        for file in &pr.files {
-            if !file.is_new {
-                continue;
+impl FileVisitor for StyleFile<'_> {
+    fn line(&mut self, line: &Line, hits: LineHits) {
+        if line.text.contains("#[cfg(test)]") {
+            self.in_test_section = true;
+        }
+        if !line.added {
+            return;
+        }
+        let hits = hits.bare;
+
+        if hits.contains(UNWRAP) && !self.is_test_file && !self.in_test_section {
+            self.push(Rule::Unwrap, Finding::new("style.unwrap", RiskLevel::Medium), line.number);
+        }
+
+        if hits.any(&[TODO, TODO_MESSAGE]) {
+            self.push(Rule::UnfinishedCode, Finding::new("style.todo", RiskLevel::Medium), line.number);
+        }
+        if hits.any(&[UNIMPLEMENTED, UNIMPLEMENTED_MESSAGE]) {
+            self.push(Rule::UnfinishedCode, Finding::new("style.unimplemented", RiskLevel::Medium), line.number);
+        }
+        let trimmed = line.text.trim();
+        if starts_with_ignore_ascii_case(trimmed, "// FIXME") || starts_with_ignore_ascii_case(trimmed, "# FIXME") {
+            self.push(Rule::UnfinishedCode, Finding::new("style.fixme", RiskLevel::Low), line.number);
+        }
+
+        // A layer may use the layers below it, never the ones above
+        if let Some(from) = self.layer {
+            let target = import_path(line.text).and_then(|path| self.analyzer.layer_of(path_segments(path)));
+            if let Some(to) = target.filter(|to| *to < from) {
+                let layers = &self.analyzer.layers;
+                self.push(Rule::Architecture, Finding::new("style.layer_violation", RiskLevel::Medium).param("from", &layers[from]).param("to", &layers[to]), line.number);
             }
-            // Check file name is snake_case (for Rust files)
-            if file.path.ends_with(".rs") {
-                if let Some(filename) = file.path.rsplit('/').next() {
-                    let stem = filename.trim_end_matches(".rs");
-                    if stem != "mod" && stem != "lib" && stem != "main" && !is_snake_case(stem) {
-                        findings.push(Finding {
-                            message: format!("File name '{}' does not follow snake_case convention", filename),
-                            file: Some(file.path.clone()),
-                            line: None,
-                            severity: RiskLevel::Low,
-                        });
-                    }
-                }
+        }
+
+        // Heuristic: .clone() on a &str or &String pattern, or .to_string().clone()
+        if self.is_rust && hits.any(&[TO_STRING_CLONE, TO_OWNED_CLONE]) {
+            let mut finding = Finding::new("style.redundant_clone", RiskLevel::Low);
+            if let Some(fixed) = without_redundant_clones(line.text, &line.masked.bare) {
+                finding = finding.suggest(fixed);
             }
-            // Check type definitions are PascalCase
-            for hunk in &file.hunks {
-                for (i, line) in hunk.lines.iter().enumerate() {
-                    if !line.starts_with('+') {
-                        continue;
-                    }
-                    let content = line[1..].trim_start();
-                    for keyword in &["struct ", "enum ", "trait "] {
-                        let prefix = format!("pub {}", keyword);
-                        let name = if content.starts_with(&prefix) {
-                            content[prefix.len()..].split(|c: char| !c.is_alphanumeric() && c != '_').next()
-                        } else if content.starts_with(keyword) {
-                            content[keyword.len()..].split(|c: char| !c.is_alphanumeric() && c != '_').next()
-                        } else {
-                            None
-                        };
-                        if let Some(name) = name {
-                            if !name.is_empty() && !is_pascal_case(name) {
-                                findings.push(Finding {
-                                    message: format!("Type '{}' does not follow PascalCase convention", name),
-                                    file: Some(file.path.clone()),
-                                    line: Some(hunk.new_start + i),
-                                    severity: RiskLevel::Low,
-                                });
-                            }
-                        }
-                    }
+            self.push(Rule::RedundantClone, finding, line.number);
+        }
+
+        // Type definitions in new files are PascalCase
+        if self.file.is_new() {
+            let content = line.text.trim_start();
+            let declaration = content.strip_prefix("pub ").unwrap_or(content);
+            for keyword in ["struct ", "enum ", "trait "] {
+                let name = declaration.strip_prefix(keyword).and_then(|rest| rest.split(|c: char| !c.is_alphanumeric() && c != '_').next());
+                if let Some(name) = name.filter(|name| !name.is_empty() && !is_pascal_case(name)) {
+                    self.push(Rule::Naming, Finding::new("style.type_name_case", RiskLevel::Low).param("name", name), line.number);
                 }
             }
         }
-        findings
     }
 }
 
+/// `text` without the `.clone()` of each redundant clone found in `bare`, or
+/// None when masking moved byte offsets (non-ASCII lines).
+fn without_redundant_clones(text: &str, bare: &str) -> Option<String> {
+    if text.len() != bare.len() {
+        return None;
+    }
+    let mut clones: Vec<usize> = [TO_STRING_CLONE, TO_OWNED_CLONE]
+        .iter()
+        .flat_map(|&i| bare.match_indices(LINE_TOKENS[i]).map(|(at, token)| at + token.len() - ".clone()".len()))
+        .collect();
+    clones.sort_unstable();
+    let mut fixed = String::with_capacity(text.len());
+    let mut copied = 0;
+    for at in clones {
+        fixed.push_str(&text[copied..at]);
+        copied = at + ".clone()".len();
+    }
+    fixed.push_str(&text[copied..]);
+    Some(fixed)
+}
+
 fn is_snake_case(s: &str) -> bool {
     !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
         && !s.starts_with('_')
@@ -211,13 +260,37 @@ impl Analyzer for StyleAnalyzer {
         "Style & Architecture Assessment"
     }
 
+    fn rules_version(&self) -> u32 {
+        5
+    }
+
+    fn rules(&self) -> Vec<RuleSpec> {
+        vec![
+            RuleSpec::line("STY001", "style.unwrap", Category::Maintainability, &["style.unwrap"]),
+            RuleSpec::line("STY002", "style.unfinished_code", Category::Maintainability, &["style.todo", "style.unimplemented", "style.fixme"]),
+            RuleSpec::line("STY003", "style.redundant_clone", Category::Maintainability, &["style.redundant_clone"]),
+            RuleSpec::line("STY004", "style.architecture_boundaries", Category::Maintainability, &["style.layer_violation"]).disabled_if(self.layers.len() < 2, "fewer than two [style] layers configured"),
+            RuleSpec::line("STY005", "style.naming", Category::Maintainability, &["style.file_name_case", "style.type_name_case"]),
+            RuleSpec::file("STY006", "style.clippy", Category::Maintainability, &["style.clippy"]).needs_checkout().runs_pr_code().disabled_if(!self.clippy, "[style] clippy = false").fails_with("style.clippy_failed"),
+        ]
+    }
+
     async fn analyze(&self, pr: &PullRequest) -> Result<AnalysisResult, AnalysisError> {
+        self.analyze_scanned(pr, LinePass::run_one(self, pr)).await
+    }
+
+    fn line_rules(&self) -> Option<&dyn LineRules> {
+        Some(self)
+    }
+
+    async fn analyze_scanned(&self, pr: &PullRequest, mut lines: LineFindings) -> Result<AnalysisResult, AnalysisError> {
         let mut findings = Vec::new();
-        findings.extend(self.check_unwrap_usage(pr));
-        findings.extend(self.check_todo_macros(pr));
-        findings.extend(self.check_unnecessary_clone(pr));
-        findings.extend(self.check_architecture_boundaries(pr));
-        findings.extend(self.check_naming_conventions(pr));
+        findings.extend(lines.take(Rule::Unwrap as usize));
+        findings.extend(lines.take(Rule::UnfinishedCode as usize));
+        findings.extend(lines.take(Rule::RedundantClone as usize));
+        findings.extend(lines.take(Rule::Architecture as usize));
+        findings.extend(lines.take(Rule::Naming as usize));
+        findings.extend(self.check_clippy(pr).await);
 
         let risk_level = if findings.iter().any(|f| f.severity == RiskLevel::High) {
             RiskLevel::High
@@ -251,12 +324,12 @@ mod tests {
     #[tokio::test]
     async fn test_detects_unwrap_usage() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "src/main.rs",
             vec![
                 "+    let val = some_result.unwrap();".to_string(),
             ],
-        )];
+        )]);
         let analyzer = StyleAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert!(!result.findings.is_empty());
@@ -267,12 +340,12 @@ mod tests {
     #[tokio::test]
     async fn test_detects_todo_macros() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "src/lib.rs",
             vec![
                 "+    todo!(\"implement this\")".to_string(),
             ],
-        )];
+        )]);
         let analyzer = StyleAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert!(!result.findings.is_empty());
@@ -282,12 +355,12 @@ mod tests {
     #[tokio::test]
     async fn test_detects_unimplemented_macro() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "src/lib.rs",
             vec![
                 "+    unimplemented!()".to_string(),
             ],
-        )];
+        )]);
         let analyzer = StyleAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert!(result.findings.iter().any(|f| f.message.contains("unimplemented!()")));
@@ -296,46 +369,154 @@ mod tests {
     #[tokio::test]
     async fn test_ignores_unwrap_in_tests() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "tests/integration.rs",
             vec![
                 "+    let val = some_result.unwrap();".to_string(),
             ],
-        )];
+        )]);
         let analyzer = StyleAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         // Test files should not flag unwrap
         assert!(result.findings.iter().all(|f| !f.message.contains("unwrap()")));
     }
 
+    #[tokio::test]
+    async fn test_custom_test_paths_replace_rust_defaults() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file("spec/models/user_spec.rs", vec!["+    let val = some_result.unwrap();".to_string()]),
+            test_diff_file("tests/integration.rs", vec!["+    let val = some_result.unwrap();".to_string()]),
+        ]);
+        let config = StyleConfig { test_paths: vec!["spec/**".to_string()], ..Default::default() };
+        let result = StyleAnalyzer::with_config(&config).analyze(&pr).await.unwrap();
+        let unwrap_files: Vec<_> = result.findings.iter().filter(|f| f.message.contains("unwrap()")).filter_map(|f| f.file.as_deref()).collect();
+        assert_eq!(unwrap_files, vec!["tests/integration.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_unwrap_in_comments_and_strings_is_ignored() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "src/lib.rs",
+            vec![
+                "+// avoid .unwrap() here".to_string(),
+                "+let hint = \"call .unwrap() only in tests\";".to_string(),
+                "+let v = x.unwrap();".to_string(),
+            ],
+        )]);
+        let result = StyleAnalyzer::new().analyze(&pr).await.unwrap();
+        let lines: Vec<_> = result.findings.iter().filter(|f| f.message.contains("unwrap()")).map(|f| f.line).collect();
+        assert_eq!(lines, vec![Some(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_clippy_disabled_without_checkout() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/lib.rs", vec!["+fn f() {}".to_string()])]);
+        let config = StyleConfig { clippy: true, ..Default::default() };
+        let result = StyleAnalyzer::with_config(&config).analyze(&pr).await.unwrap();
+        assert!(!result.findings.iter().any(|f| f.message.contains("clippy")));
+    }
+
     #[tokio::test]
     async fn test_detects_redundant_clone() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "src/util.rs",
             vec![
                 "+    let s = name.to_string().clone();".to_string(),
             ],
-        )];
+        )]);
         let analyzer = StyleAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert!(result.findings.iter().any(|f| f.message.contains("Redundant clone")));
     }
 
+    #[tokio::test]
+    async fn test_redundant_clone_suggests_the_line_without_it() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file(
+            "src/util.rs",
+            vec![
+                "+    let s = (a.to_owned().clone(), b.to_string().clone(), \".to_string().clone()\");".to_string(),
+            ],
+        )]);
+        let result = StyleAnalyzer::new().analyze(&pr).await.unwrap();
+        let finding = result.findings.iter().find(|f| f.message_id.as_deref() == Some("style.redundant_clone")).unwrap();
+        assert_eq!(finding.suggestion.as_deref(), Some("    let s = (a.to_owned(), b.to_string(), \".to_string().clone()\");"));
+    }
+
     #[tokio::test]
     async fn test_detects_fixme_comment() {
         let mut pr = test_pull_request();
-        pr.files = vec![test_diff_file(
+        pr.set_files(vec![test_diff_file(
             "src/auth.rs",
             vec![
                 "+// FIXME: auth tokens not rotated".to_string(),
             ],
-        )];
+        )]);
         let analyzer = StyleAnalyzer::new();
         let result = analyzer.analyze(&pr).await.unwrap();
         assert!(result.findings.iter().any(|f| f.message.contains("FIXME")));
     }
 
+    #[tokio::test]
+    async fn test_renamed_files_have_only_their_new_name_checked() {
+        let mut pr = test_pull_request();
+        let mut renamed = test_diff_file("src/AuthConfig.rs", vec!["+struct legacy_config;".to_string()]);
+        renamed.set_rename(Some("src/auth_config.rs".to_string()), Some(95));
+        let mut moved = test_diff_file("src/Legacy/OldName.rs", vec![]);
+        moved.set_rename(Some("src/OldName.rs".to_string()), None);
+        pr.set_files(vec![renamed, moved]);
+        let analyzer = StyleAnalyzer::new();
+        let result = analyzer.analyze(&pr).await.unwrap();
+        let naming: Vec<_> = result.findings.iter().filter_map(|f| f.message_id.as_deref().filter(|id| id.ends_with("_name_case")).map(|id| (id, f.file.as_deref()))).collect();
+        assert_eq!(naming, vec![("style.file_name_case", Some("src/AuthConfig.rs"))]);
+    }
+
+    #[tokio::test]
+    async fn test_detects_layer_violations() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file(
+                "src/infra/db.rs",
+                vec![
+                    "+use crate::domain::User;".to_string(),
+                    "+use crate::api::handlers;".to_string(),
+                    "+// see api::handlers".to_string(),
+                ],
+            ),
+            test_diff_file("web/domain/cart.ts", vec!["+import { render } from '../api/views';".to_string()]),
+            test_diff_file("src/api/routes.rs", vec!["+use crate::infra::db;".to_string()]),
+            test_diff_file("tests/infra/db.rs", vec!["+use app::api::handlers;".to_string()]),
+        ]);
+        let config = StyleConfig { layers: vec!["api".to_string(), "domain".to_string(), "infra".to_string()], ..Default::default() };
+        let result = StyleAnalyzer::with_config(&config).analyze(&pr).await.unwrap();
+        let violations: Vec<(&str, Option<usize>, &str)> = result
+            .findings
+            .iter()
+            .filter(|f| f.message_id.as_deref() == Some("style.layer_violation"))
+            .map(|f| (f.file.as_deref().unwrap(), f.line, f.params["to"].as_str()))
+            .collect();
+        assert_eq!(violations, [("src/infra/db.rs", Some(1), "domain"), ("src/infra/db.rs", Some(2), "api"), ("web/domain/cart.ts", Some(1), "api")]);
+        assert_eq!(result.risk_level, RiskLevel::Medium);
+
+        let unconfigured = StyleAnalyzer::new().analyze(&pr).await.unwrap();
+        assert!(!unconfigured.findings.iter().any(|f| f.message_id.as_deref() == Some("style.layer_violation")));
+    }
+
+    #[tokio::test]
+    async fn test_layers_are_globs() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![test_diff_file("src/infra_pg/db.rs", vec!["+use crate::api_v2::handlers;".to_string(), "+use crate::apis::x;".to_string()])]);
+        let config = StyleConfig { layers: vec!["api_*".to_string(), "domain".to_string(), "infra*".to_string()], ..Default::default() };
+        let result = StyleAnalyzer::with_config(&config).analyze(&pr).await.unwrap();
+        let violations: Vec<(Option<usize>, &str)> =
+            result.findings.iter().filter(|f| f.message_id.as_deref() == Some("style.layer_violation")).map(|f| (f.line, f.params["to"].as_str())).collect();
+        assert_eq!(violations, [(Some(1), "api_*")]);
+    }
+
     #[test]
     fn test_is_snake_case() {
         assert!(is_snake_case("hello_world"));
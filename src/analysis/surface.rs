@@ -0,0 +1,356 @@
+//! Surface a change adds: network listeners, HTTP routes, command-line
+//! arguments, environment variables read and new configuration keys.
+//!
+//! Unlike the analyzers this produces no findings and no risk level. It is a
+//! map for security reviewers ("Attack Surface Changes") and for whoever
+//! deploys the change ("New Configuration"), rendered as report sections.
+//! Detection is by pattern over added lines (comments blanked by the lexer),
+//! so it is a hint, not an inventory: routes registered through helpers or
+//! loops are missed.
+
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::is_test_path;
+use super::scan::for_each_line;
+use crate::paths::file_name;
+use crate::pr::types::DiffFile;
+use crate::pr::PullRequest;
+use crate::report::types::{SurfaceChange, SurfaceKind};
+
+/// Compile one of the built-in patterns below. They are literals, each
+/// matched in `test_builtin_patterns`, so an invalid one is a bug caught by
+/// the tests rather than an input error to report.
+#[allow(clippy::expect_used)]
+fn pattern(source: &str) -> Regex {
+    Regex::new(source).expect("built-in surface pattern compiles")
+}
+
+/// Listener calls: `TcpListener::bind(..)`, `net.Listen(..)`, `app.listen(..)`, ...
+static LISTENER: LazyLock<Regex> = LazyLock::new(|| {
+    pattern(r"\b((?:TcpListener|UdpSocket|UnixListener|Server)::bind|axum::serve|net\.Listen(?:Packet)?|http\.ListenAndServe(?:TLS)?|uvicorn\.run|socketserver\.\w+Server|\w+\.listen)\(([^()]*)")
+});
+
+/// `#[get("/path")]` (actix, rocket), `@app.get("/path")` (FastAPI, Flask) and `@app.route("/path")`.
+static ROUTE_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| pattern(r#"^\s*(?:#\[|@\w+\.)(get|post|put|delete|patch|head|options|route|api_route)\(\s*['"]([^'"]*)['"]"#));
+
+/// `app.post("/path", ..)` (Express), `r.GET("/path", ..)` (gin, echo), `.route("/path", get(..))` (axum).
+static ROUTE_CALL: LazyLock<Regex> = LazyLock::new(|| {
+    pattern(r#"\b(?:app|router|server|r|e|g)\.(get|post|put|delete|patch|all|GET|POST|PUT|DELETE|PATCH|Any)\(\s*['"`](/[^'"`]*)['"`]"#)
+});
+static AXUM_ROUTE: LazyLock<Regex> =
+    LazyLock::new(|| pattern(r#"\.route\(\s*"(/[^"]*)"\s*,\s*(?:routing::)?(get|post|put|delete|patch|any)\b"#));
+
+/// `mux.HandleFunc("/path", ..)` (Go), `router.Handle("/path", ..)`.
+static ROUTE_HANDLE: LazyLock<Regex> = LazyLock::new(|| pattern(r#"\.(?:Handle|HandleFunc)\(\s*"([^"]+)""#));
+
+/// clap builder `.long("name")`, argparse `add_argument("--name")`, commander
+/// `.option("--name <value>")`, Go `flag.String("name", ..)`.
+static CLI_LONG: LazyLock<Regex> = LazyLock::new(|| pattern(r#"\.long\(\s*"([^"]+)"\s*\)"#));
+static CLI_FLAG: LazyLock<Regex> = LazyLock::new(|| pattern(r#"\b(?:add_argument|option|requiredOption)\(\s*['"](-[^'"]+)['"]"#));
+static GO_FLAG: LazyLock<Regex> =
+    LazyLock::new(|| pattern(r#"\bflag\.(?:String|Int|Int64|Uint|Uint64|Bool|Float64|Duration)(?:Var)?\((?:&[\w.]+,\s*)?"([^"]+)""#));
+/// clap derive: `#[arg(long)]` / `#[clap(long = "name")]`, named by the field that follows.
+static CLAP_ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| pattern(r#"^\s*#\[(?:arg|clap)\(.*\blong\b(?:\s*=\s*"([^"]+)")?"#));
+static FIELD: LazyLock<Regex> = LazyLock::new(|| pattern(r"^\s*(?:pub(?:\([\w:]+\))?\s+)?(\w+)\s*:"));
+
+/// `env::var("X")`, `os.getenv("X")`, `os.Getenv("X")`, `ENV.fetch("X")`, clap `env = "X"`, ...
+static ENV_CALL: LazyLock<Regex> = LazyLock::new(|| {
+    pattern(r#"(?:\benv::var(?:_os)?|\benv!|\boption_env!|\bos\.getenv|\bos\.environ\.get|\bos\.Getenv|\bos\.LookupEnv|\bENV\.fetch)\(\s*['"]([A-Za-z_][A-Za-z0-9_]*)['"]|\benv\s*=\s*"([A-Za-z_][A-Za-z0-9_]*)""#)
+});
+/// `os.environ["X"]`, `ENV["X"]`, `process.env["X"]` and `process.env.X`.
+static ENV_INDEX: LazyLock<Regex> = LazyLock::new(|| {
+    pattern(r#"\b(?:os\.environ|ENV|process\.env)\[\s*['"]([A-Za-z_][A-Za-z0-9_]*)['"]\s*\]|\bprocess\.env\.([A-Za-z_][A-Za-z0-9_]*)"#)
+});
+
+/// `KEY=value` lines of dotenv and Java properties files.
+static ASSIGNMENT_KEY: LazyLock<Regex> = LazyLock::new(|| pattern(r"^(?:export\s+)?([A-Za-z_][\w.-]*)\s*[=:]"));
+/// `[section]` / `[[array]]` headers of TOML and INI files.
+static SECTION_HEADER: LazyLock<Regex> = LazyLock::new(|| pattern(r"^\[+\s*([^\]]+?)\s*\]+"));
+/// `key = value` in TOML and INI files.
+static TOML_KEY: LazyLock<Regex> = LazyLock::new(|| pattern(r#"^("[^"]+"|[A-Za-z0-9_.-]+)\s*="#));
+/// `key:` in YAML and `"key":` in JSON, with its indentation.
+static NESTED_KEY: LazyLock<Regex> = LazyLock::new(|| pattern(r#"^(\s*)"?([A-Za-z0-9_.-]+)"?\s*:"#));
+
+/// How keys are written in a configuration file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    /// `.env` files; their keys are environment variables
+    Dotenv,
+    Properties,
+    /// TOML, INI, `.cfg` and `.conf`: `[section]` then `key = value`
+    Sectioned,
+    /// YAML and JSON, nested by indentation
+    Nested,
+}
+
+/// Surface added by `pr` outside test code (`test_paths`), first occurrence
+/// of each item, in diff order.
+pub fn attack_surface(pr: &PullRequest, test_paths: &[String]) -> Vec<SurfaceChange> {
+    let mut changes = Vec::new();
+    let mut seen = BTreeSet::new();
+    for file in pr.files().iter().filter(|f| !is_test_path(f.path(), test_paths)) {
+        if let Some(format) = config_format(file.path()) {
+            let kind = if format == ConfigFormat::Dotenv { SurfaceKind::EnvVar } else { SurfaceKind::ConfigKey };
+            for (name, line) in new_config_keys(file, format) {
+                if seen.insert((kind, name.clone())) {
+                    changes.push(SurfaceChange { kind, name, file: file.path().to_string(), line });
+                }
+            }
+            continue;
+        }
+        let mut pending_clap_arg: Option<Option<String>> = None;
+        for_each_line(file, |line| {
+            if !line.added {
+                return;
+            }
+            let code = &*line.masked.code;
+            let mut add = |kind: SurfaceKind, name: String| {
+                if seen.insert((kind, name.clone())) {
+                    changes.push(SurfaceChange { kind, name, file: file.path().to_string(), line: line.number });
+                }
+            };
+
+            if let Some(long) = pending_clap_arg.take() {
+                match FIELD.captures(code) {
+                    Some(field) => add(SurfaceKind::CliArgument, format!("--{}", long.unwrap_or_else(|| field[1].replace('_', "-")))),
+                    // More attributes or doc comments before the field
+                    None if code.trim_start().starts_with("#[") || code.trim().is_empty() => pending_clap_arg = Some(long),
+                    None => {}
+                }
+            }
+            if let Some(attribute) = CLAP_ATTRIBUTE.captures(code) {
+                pending_clap_arg = Some(attribute.get(1).map(|m| m.as_str().to_string()));
+            }
+
+            for call in LISTENER.captures_iter(code) {
+                add(SurfaceKind::Listener, format!("{}({})", &call[1], call[2].trim()));
+            }
+            if let Some(route) = ROUTE_ATTRIBUTE.captures(code) {
+                add(SurfaceKind::Route, route_name(&route[1], &route[2]));
+            }
+            for route in ROUTE_CALL.captures_iter(code) {
+                add(SurfaceKind::Route, route_name(&route[1], &route[2]));
+            }
+            for route in AXUM_ROUTE.captures_iter(code) {
+                add(SurfaceKind::Route, route_name(&route[2], &route[1]));
+            }
+            for route in ROUTE_HANDLE.captures_iter(code) {
+                add(SurfaceKind::Route, route_name("route", &route[1]));
+            }
+            for flag in CLI_LONG.captures_iter(code) {
+                add(SurfaceKind::CliArgument, format!("--{}", &flag[1]));
+            }
+            for flag in CLI_FLAG.captures_iter(code) {
+                add(SurfaceKind::CliArgument, flag[1].to_string());
+            }
+            for flag in GO_FLAG.captures_iter(code) {
+                add(SurfaceKind::CliArgument, format!("-{}", &flag[1]));
+            }
+            for var in ENV_CALL.captures_iter(code).chain(ENV_INDEX.captures_iter(code)) {
+                if let Some(name) = var.get(1).or_else(|| var.get(2)) {
+                    add(SurfaceKind::EnvVar, name.as_str().to_string());
+                }
+            }
+        });
+    }
+    changes
+}
+
+/// Format of a configuration file, or None for other files. Manifests
+/// (`Cargo.toml`, `package.json`, ...) and CI workflows are not
+/// configuration; YAML and JSON count only under a `config`/`settings`
+/// directory or with such a name (`application.yml`, `appsettings.json`).
+fn config_format(path: &str) -> Option<ConfigFormat> {
+    let name = file_name(path).to_ascii_lowercase();
+    if name == ".env" || name.starts_with(".env.") {
+        return Some(ConfigFormat::Dotenv);
+    }
+    let ext = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+    match ext {
+        "properties" => Some(ConfigFormat::Properties),
+        "toml" if !matches!(name.as_str(), "cargo.toml" | "pyproject.toml" | "rust-toolchain.toml") => Some(ConfigFormat::Sectioned),
+        "ini" | "cfg" | "conf" => Some(ConfigFormat::Sectioned),
+        "yaml" | "yml" | "json" => {
+            let normalized = path.replace('\\', "/").to_ascii_lowercase();
+            let in_config_dir = normalized.split('/').any(|segment| matches!(segment, "config" | "configs" | "conf" | "settings"));
+            let config_name = ["config", "settings", "application"].iter().any(|word| name.contains(word));
+            let is_workflow = normalized.starts_with(".github/");
+            ((in_config_dir || config_name) && !is_workflow).then_some(ConfigFormat::Nested)
+        }
+        _ => None,
+    }
+}
+
+/// Keys the diff adds to a configuration file that it does not also remove
+/// (a changed value is not a new key), with their new-file line numbers.
+/// Keys are dotted paths (`database.pool_size`) as far as the section
+/// headers and parents inside the hunk show them.
+fn new_config_keys(file: &DiffFile, format: ConfigFormat) -> Vec<(String, usize)> {
+    let removed: BTreeSet<String> = config_keys(file, format, '-').into_iter().map(|(key, _)| key).collect();
+    config_keys(file, format, '+').into_iter().filter(|(key, _)| !removed.contains(key)).collect()
+}
+
+/// Keys on the lines of one side of the diff (`+` or `-`), reading that
+/// side's version of the file: context lines count, the other side's don't.
+fn config_keys(file: &DiffFile, format: ConfigFormat, side: char) -> Vec<(String, usize)> {
+    let mut keys = Vec::new();
+    for hunk in file.hunks() {
+        let mut section = String::new();
+        let mut parents: Vec<(usize, String)> = Vec::new();
+        let mut line_no = hunk.new_start;
+        for line in &hunk.lines {
+            let sign = line.chars().next().unwrap_or(' ');
+            let number = line_no;
+            if sign != '-' {
+                line_no += 1;
+            }
+            if sign != side && sign != ' ' {
+                continue;
+            }
+            let text = line.get(1..).unwrap_or("");
+            let trimmed = text.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') || trimmed.starts_with("//") {
+                continue;
+            }
+            let key = match format {
+                ConfigFormat::Dotenv | ConfigFormat::Properties => ASSIGNMENT_KEY.captures(trimmed).map(|c| c[1].to_string()),
+                ConfigFormat::Sectioned => {
+                    if let Some(header) = SECTION_HEADER.captures(trimmed) {
+                        section = header[1].to_string();
+                        None
+                    } else {
+                        TOML_KEY.captures(trimmed).map(|c| {
+                            let key = c[1].trim_matches('"');
+                            if section.is_empty() { key.to_string() } else { format!("{}.{}", section, key) }
+                        })
+                    }
+                }
+                ConfigFormat::Nested => NESTED_KEY.captures(text).map(|c| {
+                    let indent = c[1].len();
+                    while parents.last().is_some_and(|(parent_indent, _)| *parent_indent >= indent) {
+                        parents.pop();
+                    }
+                    let path = parents.iter().map(|(_, key)| key.as_str()).chain([&c[2]]).collect::<Vec<_>>().join(".");
+                    parents.push((indent, c[2].to_string()));
+                    path
+                }),
+            };
+            if let (Some(key), true) = (key, sign == side) {
+                keys.push((key, number));
+            }
+        }
+    }
+    keys
+}
+
+/// `GET /users/{id}`; routes registered without a method get `*`.
+fn route_name(method: &str, path: &str) -> String {
+    let method = match method.to_ascii_lowercase().as_str() {
+        "route" | "api_route" | "all" | "any" => "*".to_string(),
+        other => other.to_ascii_uppercase(),
+    };
+    format!("{} {}", method, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::{test_diff_file, test_pull_request};
+
+    fn names(changes: &[SurfaceChange], kind: SurfaceKind) -> Vec<&str> {
+        changes.iter().filter(|c| c.kind == kind).map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_finds_listeners_routes_arguments_and_env_vars() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file(
+                "src/server.rs",
+                vec![
+                    "+    let listener = TcpListener::bind(\"0.0.0.0:9090\").await?;".to_string(),
+                    "+    let app = Router::new().route(\"/admin/users\", get(list_users));".to_string(),
+                    "+    let url = std::env::var(\"ADMIN_DB_URL\")?;".to_string(),
+                    "+    // let key = env::var(\"OLD_KEY\")?;".to_string(),
+                    "+#[get(\"/health\")]".to_string(),
+                    "+    #[arg(long, env = \"ANALYZER_TOKEN\")]".to_string(),
+                    "+    pub api_token: Option<String>,".to_string(),
+                ],
+            ),
+            test_diff_file(
+                "web/app.js",
+                vec!["+app.post('/upload', upload);".to_string(), "+const port = process.env.PORT || 3000;".to_string(), "+app.listen(port);".to_string()],
+            ),
+            test_diff_file("cmd/main.go", vec!["+\tverbose := flag.Bool(\"verbose\", false, \"log more\")".to_string(), "+\tmux.HandleFunc(\"/metrics\", metrics)".to_string()]),
+            test_diff_file("tests/server_test.rs", vec!["+    let listener = TcpListener::bind(\"127.0.0.1:0\").await?;".to_string()]),
+        ]);
+        let changes = attack_surface(&pr, &crate::config::StyleConfig::default().test_paths);
+        assert_eq!(names(&changes, SurfaceKind::Listener), vec!["TcpListener::bind(\"0.0.0.0:9090\")", "app.listen(port)"]);
+        assert_eq!(names(&changes, SurfaceKind::Route), vec!["GET /admin/users", "GET /health", "POST /upload", "* /metrics"]);
+        assert_eq!(names(&changes, SurfaceKind::CliArgument), vec!["--api-token", "-verbose"]);
+        assert_eq!(names(&changes, SurfaceKind::EnvVar), vec!["ADMIN_DB_URL", "ANALYZER_TOKEN", "PORT"]);
+        assert_eq!((changes[0].file.as_str(), changes[0].line), ("src/server.rs", 1));
+    }
+
+    #[test]
+    fn test_lists_new_config_keys_but_not_changed_values() {
+        let mut pr = test_pull_request();
+        pr.set_files(vec![
+            test_diff_file(
+                "config/production.toml",
+                vec![
+                    " [database]".to_string(),
+                    "-pool_size = 10".to_string(),
+                    "+pool_size = 20".to_string(),
+                    "+statement_timeout_ms = 5000".to_string(),
+                    "+[cache]".to_string(),
+                    "+ttl = \"5m\"".to_string(),
+                ],
+            ),
+            test_diff_file(
+                "config/application.yml",
+                vec![" server:".to_string(), "   port: 8080".to_string(), "+  tls:".to_string(), "+    cert_path: /etc/tls/cert.pem".to_string()],
+            ),
+            test_diff_file(".env.example", vec!["+STRIPE_WEBHOOK_SECRET=".to_string(), "+# comment=ignored".to_string()]),
+            test_diff_file("Cargo.toml", vec!["+serde = \"1\"".to_string()]),
+            test_diff_file(".github/workflows/config.yml", vec!["+on: push".to_string()]),
+        ]);
+        let changes = attack_surface(&pr, &[]);
+        assert_eq!(
+            names(&changes, SurfaceKind::ConfigKey),
+            vec!["database.statement_timeout_ms", "cache.ttl", "server.tls", "server.tls.cert_path"]
+        );
+        assert_eq!(names(&changes, SurfaceKind::EnvVar), vec!["STRIPE_WEBHOOK_SECRET"]);
+        assert_eq!(changes[0].line, 3);
+    }
+
+    #[test]
+    fn test_builtin_patterns() {
+        let cases: [(&Regex, &str, &str); 16] = [
+            (&LISTENER, "UdpSocket::bind(addr)", "addr"),
+            (&ROUTE_ATTRIBUTE, "@app.route('/login')", "/login"),
+            (&ROUTE_CALL, "r.DELETE(\"/items/:id\", h)", "/items/:id"),
+            (&AXUM_ROUTE, ".route(\"/users\", routing::post(create))", "post"),
+            (&ROUTE_HANDLE, "router.Handle(\"/debug\", h)", "/debug"),
+            (&CLI_LONG, ".long(\"dry-run\")", "dry-run"),
+            (&CLI_FLAG, "parser.add_argument('--out')", "--out"),
+            (&GO_FLAG, "flag.StringVar(&cfg.addr, \"addr\", \"\", \"\")", "addr"),
+            (&CLAP_ATTRIBUTE, "#[clap(short, long = \"level\")]", "level"),
+            (&FIELD, "pub(crate) max_depth: usize,", "max_depth"),
+            (&ENV_CALL, "os.LookupEnv(\"HOME\")", "HOME"),
+            (&ENV_INDEX, "os.environ['PATH']", "PATH"),
+            (&ASSIGNMENT_KEY, "export db.url=postgres://", "db.url"),
+            (&SECTION_HEADER, "[[bin]]", "bin"),
+            (&TOML_KEY, "\"quoted key\" = 1", "\"quoted key\""),
+            (&NESTED_KEY, "  \"retries\": 3", "retries"),
+        ];
+        for (pattern, line, captured) in cases {
+            let caps = pattern.captures(line).unwrap_or_else(|| panic!("{} should match {:?}", pattern.as_str(), line));
+            assert!(caps.iter().skip(1).flatten().any(|m| m.as_str() == captured), "{:?} should capture {:?}", line, captured);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Syntax trees for changed code (tree-sitter), shared by analyzers that
+//! need more precision than substring matching.
+//!
+//! Supported: Rust, Python, JavaScript, TypeScript/TSX, Go. Everything else
+//! returns `None` from `Syntax::parse`, and callers fall back to their
+//! line-based heuristics.
+
+use tree_sitter::{Language, Node, Parser, Point, Tree};
+
+use crate::pr::types::DiffFile;
+use crate::repo::Checkout;
+
+/// Uppercased fragments that mark a string as SQL.
+const SQL_MARKERS: &[&str] = &["SELECT ", "INSERT INTO", "UPDATE ", "DELETE FROM", " WHERE "];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lang {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl Lang {
+    fn from_path(path: &str) -> Option<Lang> {
+        let ext = path.rsplit_once('.')?.1;
+        match ext {
+            "rs" => Some(Lang::Rust),
+            "py" => Some(Lang::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Lang::JavaScript),
+            "ts" | "mts" | "cts" => Some(Lang::TypeScript),
+            "tsx" => Some(Lang::Tsx),
+            "go" => Some(Lang::Go),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Lang::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Lang::Python => tree_sitter_python::LANGUAGE.into(),
+            Lang::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Lang::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Lang::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Lang::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    /// Node kind that opens a nesting level.
+    fn block_kind(self) -> &'static str {
+        match self {
+            Lang::JavaScript | Lang::TypeScript | Lang::Tsx => "statement_block",
+            Lang::Rust | Lang::Python | Lang::Go => "block",
+        }
+    }
+}
+
+/// A parsed view of one changed file.
+pub struct Syntax {
+    lang: Lang,
+    tree: Tree,
+    text: String,
+    /// New-file line number of each parsed row
+    lines: Vec<usize>,
+    /// True when the whole file was parsed rather than just the diff hunks
+    pub whole_file: bool,
+}
+
+impl Syntax {
+    /// Parse the new side of a changed file: the whole file when a checkout
+    /// has it, otherwise the context and added lines of its hunks.
+    pub fn parse(file: &DiffFile, checkout: Option<&Checkout>) -> Option<Syntax> {
+        let lang = Lang::from_path(file.path())?;
+        let (text, lines, whole_file) = match checkout.and_then(|c| c.read_file(file.path()).ok()) {
+            Some(text) => {
+                let lines = (1..=text.lines().count().max(1)).collect();
+                (text, lines, true)
+            }
+            None => {
+                let mut text = String::new();
+                let mut lines = Vec::new();
+                for hunk in file.hunks() {
+                    let new_side = hunk.lines.iter().filter(|l| !l.starts_with('-'));
+                    for (line_no, line) in (hunk.new_start..).zip(new_side) {
+                        text.push_str(line.get(1..).unwrap_or_default());
+                        text.push('\n');
+                        lines.push(line_no);
+                    }
+                }
+                (text, lines, false)
+            }
+        };
+        let mut parser = Parser::new();
+        parser.set_language(&lang.grammar()).ok()?;
+        let tree = parser.parse(&text, None)?;
+        Some(Syntax { lang, tree, text, lines, whole_file })
+    }
+
+    fn line_of(&self, node: Node) -> usize {
+        self.lines.get(node.start_position().row).copied().unwrap_or(0)
+    }
+
+    fn node_text(&self, node: Node) -> &str {
+        node.utf8_text(self.text.as_bytes()).unwrap_or_default()
+    }
+
+    /// Lines where a string is built by concatenation or formatting and the
+    /// literal parts look like SQL.
+    pub fn dynamic_sql_lines(&self) -> Vec<usize> {
+        let mut lines = Vec::new();
+        self.visit(self.tree.root_node(), &mut |node| {
+            if self.is_dynamic_string(node) {
+                let text = self.node_text(node).to_uppercase();
+                if SQL_MARKERS.iter().any(|m| text.contains(m)) {
+                    lines.push(self.line_of(node));
+                }
+            }
+        });
+        lines.dedup();
+        lines
+    }
+
+    /// Lines calling `eval`/`exec` with anything but a plain string literal.
+    pub fn dynamic_eval_lines(&self) -> Vec<usize> {
+        let (call_kind, args_field) = match self.lang {
+            Lang::Python => ("call", "arguments"),
+            Lang::JavaScript | Lang::TypeScript | Lang::Tsx => ("call_expression", "arguments"),
+            Lang::Rust | Lang::Go => return Vec::new(),
+        };
+        let mut lines = Vec::new();
+        self.visit(self.tree.root_node(), &mut |node| {
+            if node.kind() != call_kind {
+                return;
+            }
+            let Some(function) = node.child_by_field_name("function") else {
+                return;
+            };
+            if function.kind() != "identifier" || !matches!(self.node_text(function), "eval" | "exec") {
+                return;
+            }
+            let first_arg = node.child_by_field_name(args_field).and_then(|args| args.named_child(0));
+            let literal = first_arg.is_some_and(|arg| match arg.kind() {
+                "string" => !has_child_kind(arg, "interpolation"),
+                "template_string" => !has_child_kind(arg, "template_substitution"),
+                _ => false,
+            });
+            if !literal {
+                lines.push(self.line_of(node));
+            }
+        });
+        lines
+    }
+
+    /// Number of enclosing blocks at the first token of a new-file line.
+    pub fn block_depth(&self, line: usize) -> Option<usize> {
+        let row = self.lines.iter().position(|l| *l == line)?;
+        let source_line = self.text.lines().nth(row)?;
+        let column = source_line.len() - source_line.trim_start().len();
+        let point = Point { row, column };
+        let mut node = self.tree.root_node().descendant_for_point_range(point, point)?;
+        let mut depth = 0;
+        while let Some(parent) = node.parent() {
+            if parent.kind() == self.lang.block_kind() {
+                depth += 1;
+            }
+            node = parent;
+        }
+        Some(depth)
+    }
+
+    /// Whether a node builds a string at runtime from literal and dynamic parts.
+    fn is_dynamic_string(&self, node: Node) -> bool {
+        let operator = || node.child_by_field_name("operator").map(|op| self.node_text(op));
+        let has_literal_operand = || {
+            ["left", "right"]
+                .iter()
+                .filter_map(|field| node.child_by_field_name(field))
+                .any(|operand| is_string_literal(operand.kind()))
+        };
+        match (self.lang, node.kind()) {
+            (Lang::Rust, "macro_invocation") => node
+                .child_by_field_name("macro")
+                .is_some_and(|m| matches!(self.node_text(m), "format" | "format_args")),
+            (Lang::Python, "binary_operator") => matches!(operator(), Some("+" | "%")) && has_literal_operand(),
+            (Lang::Python, "string") => has_child_kind(node, "interpolation"),
+            (Lang::Python, "call") => node
+                .child_by_field_name("function")
+                .filter(|f| f.kind() == "attribute")
+                .is_some_and(|f| {
+                    f.child_by_field_name("object").is_some_and(|o| o.kind() == "string")
+                        && f.child_by_field_name("attribute").is_some_and(|a| self.node_text(a) == "format")
+                }),
+            (Lang::JavaScript | Lang::TypeScript | Lang::Tsx, "template_string") => has_child_kind(node, "template_substitution"),
+            (Lang::Go, "call_expression") => node
+                .child_by_field_name("function")
+                .is_some_and(|f| self.node_text(f) == "fmt.Sprintf"),
+            (_, "binary_expression") => operator() == Some("+") && has_literal_operand(),
+            _ => false,
+        }
+    }
+
+    fn visit<'t>(&'t self, node: Node<'t>, f: &mut impl FnMut(Node<'t>)) {
+        f(node);
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.visit(child, f);
+        }
+    }
+}
+
+fn is_string_literal(kind: &str) -> bool {
+    matches!(kind, "string" | "string_literal" | "raw_string_literal" | "template_string" | "interpreted_string_literal")
+}
+
+fn has_child_kind(node: Node, kind: &str) -> bool {
+    let mut cursor = node.walk();
+    let found = node.children(&mut cursor).any(|c| c.kind() == kind);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tests::test_diff_file;
+
+    fn parse(path: &str, lines: &[&str]) -> Syntax {
+        let file = test_diff_file(path, lines.iter().map(|l| format!("+{}", l)).collect());
+        Syntax::parse(&file, None).unwrap()
+    }
+
+    #[test]
+    fn test_unsupported_language_is_not_parsed() {
+        assert!(Syntax::parse(&test_diff_file("query.sql", vec!["+SELECT 1;".to_string()]), None).is_none());
+    }
+
+    #[test]
+    fn test_dynamic_sql_ignores_comments_and_static_strings() {
+        let syntax = parse(
+            "src/db.rs",
+            &[
+                "fn q(id: &str) {",
+                "    // format!(\"SELECT * FROM users WHERE id = {}\", id)",
+                "    let fixed = \"SELECT * FROM users WHERE id = $1\";",
+                "    let q = format!(\"SELECT * FROM users WHERE id = {}\", id);",
+                "}",
+            ],
+        );
+        assert_eq!(syntax.dynamic_sql_lines(), vec![4]);
+    }
+
+    #[test]
+    fn test_dynamic_sql_across_languages() {
+        let python = parse("app/db.py", &["cur.execute(f\"SELECT * FROM t WHERE id = {uid}\")", "cur.execute(\"SELECT * FROM t WHERE id = %s\", (uid,))"]);
+        assert_eq!(python.dynamic_sql_lines(), vec![1]);
+        let js = parse("db.js", &["db.query(`SELECT * FROM t WHERE id = ${id}`);", "db.query('SELECT * FROM t WHERE id = ' + id);"]);
+        assert_eq!(js.dynamic_sql_lines(), vec![1, 2]);
+        let go = parse("db.go", &["package db", "func q(id string) { db.Query(fmt.Sprintf(\"SELECT * FROM t WHERE id = %s\", id)) }"]);
+        assert_eq!(go.dynamic_sql_lines(), vec![2]);
+    }
+
+    #[test]
+    fn test_eval_with_literal_argument_is_ignored() {
+        let python = parse("x.py", &["eval(\"1 + 1\")", "eval(user_input)", "exec(f\"run {cmd}\")"]);
+        assert_eq!(python.dynamic_eval_lines(), vec![2, 3]);
+        let js = parse("x.ts", &["eval('2');", "eval(payload);"]);
+        assert_eq!(js.dynamic_eval_lines(), vec![2]);
+    }
+
+    #[test]
+    fn test_block_depth() {
+        let syntax = parse("src/lib.rs", &["fn f() {", "    if a {", "        if b {", "            go();", "        }", "    }", "}"]);
+        assert_eq!(syntax.block_depth(4), Some(3));
+        assert_eq!(syntax.block_depth(2), Some(1));
+    }
+}
@@ -0,0 +1,28 @@
+//! Stand-in for `syntax` in builds without the `syntax` feature. Nothing is
+//! parsed, so every caller takes its line-based fallback.
+
+use crate::pr::types::DiffFile;
+use crate::repo::Checkout;
+
+/// Never constructed; `parse` always returns `None`.
+pub struct Syntax {
+    pub whole_file: bool,
+}
+
+impl Syntax {
+    pub fn parse(_file: &DiffFile, _checkout: Option<&Checkout>) -> Option<Syntax> {
+        None
+    }
+
+    pub fn dynamic_sql_lines(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    pub fn dynamic_eval_lines(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    pub fn block_depth(&self, _line: usize) -> Option<usize> {
+        None
+    }
+}
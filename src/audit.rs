@@ -0,0 +1,167 @@
+//! Audit trail of write actions: one record per review, comment or check
+//! run the tool posts, appended to `[audit] file` and/or POSTed to
+//! `[audit] url`.
+//!
+//! A record carries a SHA-256 of the request body rather than the body, so
+//! the trail shows exactly what was sent without copying findings into it.
+//! Failed writes are recorded too, with the error.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::config::AuditConfig;
+use crate::report::rfc3339_utc;
+
+/// Limits on delivering a record to `[audit] url`, so an unreachable or
+/// stalled collector cannot hold up the write being audited.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("Failed to write audit log: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to serialize audit record: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    #[error("Failed to send audit record: {0}")]
+    Request(#[from] reqwest::Error),
+}
+
+/// One write made to a repository or review server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// When the write was made (RFC 3339, UTC)
+    pub at: String,
+    /// `[audit] actor`, else the account whose credentials made the write
+    pub actor: String,
+    /// What was written, e.g. `gerrit_review`
+    pub action: String,
+    /// URL the write was sent to
+    pub target: String,
+    /// PR or change number
+    pub pr: u64,
+    /// Hex SHA-256 of the JSON request body
+    pub payload_sha256: String,
+    /// Why the write failed; None if it succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Record {
+    /// A record of `payload` sent to `target` now, by `actor`.
+    pub fn new(action: &str, actor: &str, target: &str, pr: u64, payload: &serde_json::Value, error: Option<String>) -> Self {
+        Self {
+            at: rfc3339_utc(SystemTime::now()),
+            actor: actor.to_string(),
+            action: action.to_string(),
+            target: target.to_string(),
+            pr,
+            payload_sha256: payload_hash(payload),
+            error,
+        }
+    }
+}
+
+/// Hex SHA-256 of the payload as it goes over the wire.
+fn payload_hash(payload: &serde_json::Value) -> String {
+    Sha256::digest(payload.to_string().as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Record a write to every configured destination; does nothing when
+/// `[audit]` is unset. A failing destination does not stop the other; the
+/// first error is returned once both have run.
+pub async fn record(config: &AuditConfig, mut record: Record) -> Result<(), AuditError> {
+    if !config.is_enabled() {
+        return Ok(());
+    }
+    if let Some(actor) = &config.actor {
+        record.actor = actor.clone();
+    }
+    let mut first_error = None;
+    if let Some(path) = &config.file {
+        let appended = serde_json::to_string(&record).map_err(AuditError::from).and_then(|mut line| {
+            line.push('\n');
+            // One write per record keeps concurrent appends from interleaving.
+            OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())?;
+            Ok(())
+        });
+        if let Err(e) = appended {
+            warn!(path = %path.display(), error = %e, "audit log append failed");
+            first_error.get_or_insert(e);
+        }
+    }
+    if let Some(url) = &config.url {
+        if let Err(e) = post(url, config.token(), &record).await {
+            warn!(url = %url, error = %e, "audit record not delivered");
+            first_error.get_or_insert(e.into());
+        }
+    }
+    debug!(action = %record.action, pr = record.pr, "write audited");
+    first_error.map_or(Ok(()), Err)
+}
+
+/// POST `record` to `url`, bounded by the connect and request timeouts.
+async fn post(url: &str, token: Option<String>, record: &Record) -> Result<(), reqwest::Error> {
+    let client = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).timeout(REQUEST_TIMEOUT).build()?;
+    let mut request = client.post(url).header("User-Agent", "pr-analyzer").json(record);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await?.error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_payload_hash() {
+        assert_eq!(payload_hash(&json!({})), "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a");
+        assert_ne!(payload_hash(&json!({ "message": "a" })), payload_hash(&json!({ "message": "b" })));
+    }
+
+    #[tokio::test]
+    async fn test_record_appends_json_lines() {
+        let path = std::env::temp_dir().join("test_audit.jsonl");
+        std::fs::remove_file(&path).ok();
+        let config = AuditConfig { file: Some(path.clone()), actor: Some("release-bot".to_string()), ..AuditConfig::default() };
+        let payload = json!({ "message": "pr-analyzer: overall risk high" });
+        record(&config, Record::new("gerrit_review", "ci", "https://review.example.com/a/changes/p~7/revisions/abc/review", 7, &payload, None)).await.unwrap();
+        record(&config, Record::new("gerrit_review", "ci", "https://review.example.com/a/changes/p~7/revisions/abc/review", 7, &payload, Some("403 Forbidden".to_string()))).await.unwrap();
+
+        let records: Vec<Record> = std::fs::read_to_string(&path).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].actor.as_str(), records[0].pr, records[0].error.as_deref()), ("release-bot", 7, None));
+        assert_eq!(records[0].payload_sha256, payload_hash(&payload));
+        assert_eq!(records[1].error.as_deref(), Some("403 Forbidden"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_record_without_config_does_nothing() {
+        record(&AuditConfig::default(), Record::new("gerrit_review", "ci", "https://x", 1, &json!({}), None)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_returns_delivery_error_after_appending() {
+        let path = std::env::temp_dir().join("test_audit_unreachable.jsonl");
+        std::fs::remove_file(&path).ok();
+        // Port 1 on loopback refuses the connection, so delivery fails fast.
+        let config = AuditConfig { file: Some(path.clone()), url: Some("http://127.0.0.1:1/audit".to_string()), ..AuditConfig::default() };
+        let result = record(&config, Record::new("github_review", "ci", "https://api.github.com/repos/o/r/pulls/3/reviews", 3, &json!({}), None)).await;
+
+        assert!(matches!(result, Err(AuditError::Request(_))), "{:?}", result);
+        let lines = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(lines.lines().count(), 1);
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,169 @@
+//! Baseline of known findings: `--write-baseline` records every finding of a
+//! run, and later runs with `--baseline` leave the recorded ones out, so a
+//! repository with many existing issues can adopt the tool and gate only on
+//! new ones.
+//!
+//! A finding is recorded by fingerprint: its rule code, file, and the
+//! whitespace-normalized text of the line it flags. Line numbers are left
+//! out, so the finding keeps its fingerprint when code above it moves. A
+//! finding without a line uses its message instead of the line text.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::debug;
+
+use crate::pr::types::DiffFile;
+use crate::report::{AnalysisResult, Finding, RiskLevel};
+
+/// Version of the file format, bumped when fingerprints change.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum BaselineError {
+    #[error("Failed to access baseline file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid baseline file: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("Baseline file has format version {found}; this pr-analyzer writes version {FORMAT_VERSION}. Rewrite it with --write-baseline")]
+    Version { found: u32 },
+}
+
+/// On-disk layout. Entries keep the rule, file and message next to the
+/// fingerprint so the file can be reviewed when it is committed.
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    findings: Vec<Entry>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Entry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    fingerprint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rule: Option<String>,
+    message: String,
+}
+
+/// Text of `line` of `path` in the diff, numbered as the analyzers number
+/// lines: the hunk's `new_start` plus the index in the hunk.
+fn line_text<'a>(diff: &'a [DiffFile], path: &str, line: usize) -> Option<&'a str> {
+    let file = diff.iter().find(|f| f.path() == path)?;
+    file.hunks().iter().find_map(|h| h.lines.get(line.checked_sub(h.new_start)?).map(|l| l.get(1..).unwrap_or_default()))
+}
+
+/// Fingerprint of a finding of `analyzer` (see the module docs).
+fn fingerprint(analyzer: &str, finding: &Finding, diff: &[DiffFile]) -> String {
+    let rule = finding.rule_code.as_deref().or(finding.message_id.as_deref()).unwrap_or(analyzer);
+    let file = finding.file.as_deref().unwrap_or_default();
+    let content = match finding.file.as_deref().zip(finding.line).and_then(|(path, line)| line_text(diff, path, line)) {
+        Some(text) => text.split_whitespace().collect::<Vec<_>>().join(" "),
+        None => finding.message.clone(),
+    };
+    let digest = Sha256::digest(format!("{}\0{}\0{}", rule, file, content).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Record every finding of `results` in a new baseline file at `path`.
+pub fn write(path: &Path, results: &[AnalysisResult], diff: &[DiffFile]) -> Result<usize, BaselineError> {
+    let mut findings: Vec<Entry> = results
+        .iter()
+        .flat_map(|r| r.findings.iter().map(move |f| (r.analyzer_name.as_str(), f)))
+        .map(|(analyzer, finding)| Entry {
+            file: finding.file.clone(),
+            fingerprint: fingerprint(analyzer, finding, diff),
+            rule: finding.rule.clone(),
+            message: finding.message.clone(),
+        })
+        .collect();
+    findings.sort();
+    findings.dedup_by(|a, b| a.fingerprint == b.fingerprint);
+    let count = findings.len();
+    let mut json = serde_json::to_string_pretty(&BaselineFile { version: FORMAT_VERSION, findings })?;
+    json.push('\n');
+    std::fs::write(path, json)?;
+    debug!(path = %path.display(), findings = count, "wrote baseline");
+    Ok(count)
+}
+
+/// Drop the findings of `results` that the baseline at `path` records and
+/// return how many were dropped. An analyzer that loses findings has its
+/// risk recomputed from the ones left, so known issues no longer fail gates.
+pub fn apply(path: &Path, results: &mut [AnalysisResult], diff: &[DiffFile]) -> Result<usize, BaselineError> {
+    let file: BaselineFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    if file.version != FORMAT_VERSION {
+        return Err(BaselineError::Version { found: file.version });
+    }
+    let known: BTreeSet<String> = file.findings.into_iter().map(|e| e.fingerprint).collect();
+    let mut suppressed = 0;
+    for result in results {
+        let before = result.findings.len();
+        let analyzer = result.analyzer_name.clone();
+        result.findings.retain(|f| !known.contains(&fingerprint(&analyzer, f, diff)));
+        if result.findings.len() < before {
+            suppressed += before - result.findings.len();
+            result.risk_level = result.findings.iter().map(|f| f.severity).max().unwrap_or(RiskLevel::Low);
+        }
+    }
+    debug!(path = %path.display(), suppressed, "applied baseline");
+    Ok(suppressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pr::diff::parse_diff;
+
+    fn results(line: usize) -> Vec<AnalysisResult> {
+        let mut password = Finding::new("security.hardcoded_password", RiskLevel::High).in_file("src/a.rs").at_line(line);
+        password.rule_code = Some("SEC002".to_string());
+        vec![AnalysisResult {
+            analyzer_name: "Security Risk Assessment".to_string(),
+            risk_level: RiskLevel::High,
+            findings: vec![password, Finding::new("security.new_dependencies", RiskLevel::Medium).param("count", 3)],
+        }]
+    }
+
+    #[test]
+    fn test_baseline_suppresses_known_findings_after_lines_move() {
+        let path = std::env::temp_dir().join(format!("pr-analyzer-baseline-{}.json", std::process::id()));
+        let before = parse_diff("diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,1 +1,2 @@\n fn a() {}\n+let password = \"hunter2\";\n").unwrap();
+        assert_eq!(write(&path, &results(2), &before).unwrap(), 2);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("\"message\": \"Hardcoded password detected\""), "{text}");
+
+        // Two lines added above, and the indentation changed
+        let after = parse_diff("diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,1 +1,4 @@\n fn a() {}\n+fn b() {}\n+fn c() {}\n+    let password =  \"hunter2\";\n").unwrap();
+        let mut moved = results(4);
+        moved[0].findings.push(Finding::new("security.hardcoded_password", RiskLevel::High).in_file("src/a.rs").at_line(2));
+        assert_eq!(apply(&path, &mut moved, &after).unwrap(), 2);
+        assert_eq!(moved[0].findings.len(), 1);
+        assert_eq!(moved[0].findings[0].line, Some(2));
+
+        let mut changed = results(4);
+        changed[0].findings.pop();
+        changed[0].findings[0].rule_code = Some("SEC003".to_string());
+        assert_eq!(apply(&path, &mut changed, &after).unwrap(), 0);
+        assert_eq!(changed[0].risk_level, RiskLevel::High);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_baseline_lowers_risk_of_suppressed_analyzers() {
+        let path = std::env::temp_dir().join(format!("pr-analyzer-baseline-risk-{}.json", std::process::id()));
+        let mut all = results(2);
+        all[0].findings.truncate(1);
+        write(&path, &all, &[]).unwrap();
+        let mut again = results(2);
+        apply(&path, &mut again, &[]).unwrap();
+        assert_eq!(again[0].risk_level, RiskLevel::Medium);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,7 +1,13 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::report::{Output, RiskLevel};
 
 #[derive(Debug, Error)]
 pub enum ConfigError {
@@ -10,60 +16,853 @@ pub enum ConfigError {
 
     #[error("Failed to parse config file: {0}")]
     Parse(#[from] toml::de::Error),
+
+    #[error("Unknown profile '{0}' (built-in: strict, standard, lenient)")]
+    UnknownProfile(String),
+
+    #[error("Invalid extends source '{0}' (expected github:owner/repo[/path.toml][@ref])")]
+    InvalidExtends(String),
+
+    #[error("Failed to fetch shared config: {0}")]
+    ExtendsFetch(#[from] reqwest::Error),
 }
 
 /// Top-level configuration loaded from .pr-analyzer.toml.
 ///
 /// Codex: Implement loading logic.
 /// All fields are optional — the tool should work with zero config.
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Config {
     /// GitHub-specific settings
     #[serde(default)]
     pub github: GitHubConfig,
 
+    /// Gerrit credentials and review voting
+    #[serde(default)]
+    pub gerrit: GerritConfig,
+
     /// Security analyzer settings (read from TOML config, consumed by future configurable patterns)
     #[serde(default)]
     pub security: SecurityConfig,
 
-    /// Style analyzer settings (read from TOML config, consumed by future configurable layers)
+    /// Style analyzer settings (layers, test-path conventions)
     #[serde(default)]
     pub style: StyleConfig,
+
+    /// Process analyzer settings (which files ship or gate the project)
+    #[serde(default)]
+    pub process: ProcessConfig,
+
+    /// Resource budget enforced by the analysis runner
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+
+    /// Globs of files left out of the analysis, e.g. `vendor/**` or
+    /// `*.min.js`; `--ignore` adds more
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// User-defined line rules (`[[rules]]`), run by the rules analyzer
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<UserRule>,
+
+    /// File with more `[[rules]]`, relative to the config file, e.g.
+    /// `rules.toml`; its rules are appended to the config's own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules_file: Option<String>,
+
+    /// Profile applied when `--profile` is not given (defaults to "standard")
+    #[serde(default)]
+    pub profile: Option<String>,
+
+    /// Custom profiles, each overriding a built-in one
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileOverrides>,
+
+    /// Org policy, honored only from the shared (`extends`) config
+    #[serde(default)]
+    pub policy: Policy,
+
+    /// Examples checked by `rules test`; they do not affect analysis
+    #[serde(default, skip_serializing)]
+    pub rule_tests: Vec<RuleTest>,
+
+    /// Report files written on every run, unless `--output` is given
+    #[serde(default, skip_serializing)]
+    pub output: OutputConfig,
+
+    /// Which report sections to show, and in what order
+    #[serde(default, skip_serializing)]
+    pub report: ReportConfig,
+
+    /// Where write actions (reviews, comments, check runs) are recorded
+    #[serde(default, skip_serializing)]
+    pub audit: AuditConfig,
+
+    /// Per-analyzer CI gates, keyed by rule table (e.g. `security = "medium"`);
+    /// they decide the exit status, not what is reported
+    #[serde(default, skip_serializing)]
+    pub ci: BTreeMap<String, Gate>,
+
+    /// Thresholds of the selected profile, filled in by `apply_profile`
+    #[serde(skip_deserializing)]
+    pub thresholds: Profile,
+
+    /// Analyzer or rule picked by `--only-analyzer` / `--only-rule`
+    #[serde(skip)]
+    pub only: Selection,
+}
+
+/// A subset of the checks to run, for debugging one of them. Both unset
+/// runs everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// Analyzer name or rule table, e.g. "security"
+    pub analyzer: Option<String>,
+    /// Rule id, e.g. "security.custom_patterns"
+    pub rule: Option<String>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.analyzer.is_none() && self.rule.is_none()
+    }
 }
 
+/// `[output]`: where the report goes when `--output` is not given. The
+/// terminal summary is printed either way.
 #[derive(Debug, Clone, Default, Deserialize)]
+pub struct OutputConfig {
+    /// `[FORMAT=]PATH` entries, as taken by `--output`
+    #[serde(default)]
+    pub files: Vec<Output>,
+}
+
+/// `[report]`: the shape of the rendered report, for every format. The
+/// analysis, CI gates and `--history` always see the full report.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReportConfig {
+    /// Sections to show, in order: `summary`, analyzers by rule table
+    /// (`security`, `complexity`, ...), `resolved`, `patches`, `policy`,
+    /// `surface`, `reviewers` and `rules`. Empty shows everything except
+    /// the summary.
+    #[serde(default)]
+    pub sections: Vec<String>,
+
+    /// Leave out analyzers with no findings
+    #[serde(default)]
+    pub hide_empty: bool,
+
+    /// Documentation page of a rule, with `{rule_id}` where its id goes.
+    /// Unset links to the generated rule docs; empty leaves findings unlinked.
+    #[serde(default)]
+    pub docs_url: Option<String>,
+}
+
+/// Rule documentation generated by `pr-analyzer rules docs`, as published
+/// with the source.
+/// REST API root of github.com.
+pub const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+
+pub const DEFAULT_DOCS_URL: &str = "https://github.com/kkang132/rust-project/blob/main/docs/rules.md#{rule_id}";
+
+impl ReportConfig {
+    /// The docs link template in effect; None when links are off.
+    pub fn docs_url(&self) -> Option<&str> {
+        match self.docs_url.as_deref() {
+            None => Some(DEFAULT_DOCS_URL),
+            Some("") => None,
+            Some(url) => Some(url),
+        }
+    }
+}
+
+/// `[audit]`: an append-only record of every write the tool makes to a
+/// repository or review server. Unset, nothing is recorded.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditConfig {
+    /// JSON Lines file each record is appended to
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// Endpoint each record is POSTed to as JSON
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Bearer token for `url`. If None, falls back to PR_ANALYZER_AUDIT_TOKEN env var.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Recorded as who acted; defaults to the account whose credentials made the write
+    #[serde(default)]
+    pub actor: Option<String>,
+}
+
+impl AuditConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some() || self.url.is_some()
+    }
+
+    /// Bearer token for `url`: config value, falling back to the PR_ANALYZER_AUDIT_TOKEN env var.
+    pub fn token(&self) -> Option<String> {
+        self.token.clone().or_else(|| std::env::var("PR_ANALYZER_AUDIT_TOKEN").ok())
+    }
+}
+
+/// Lowest analyzer risk level that fails a CI run, or `off` to never fail
+/// on that analyzer (its findings are still reported).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Gate {
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl Gate {
+    pub fn threshold(self) -> Option<RiskLevel> {
+        match self {
+            Gate::Off => None,
+            Gate::Low => Some(RiskLevel::Low),
+            Gate::Medium => Some(RiskLevel::Medium),
+            Gate::High => Some(RiskLevel::High),
+        }
+    }
+}
+
+/// An example for `rules test`: a snippet added to `file`, and whether
+/// `rule` should report it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleTest {
+    /// Shown in the results; defaults to the rule and file
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Rule id, e.g. "security.custom_patterns"
+    pub rule: String,
+    /// Path the snippet is added to; its extension decides comment and string masking
+    pub file: String,
+    /// The added lines
+    pub code: String,
+    /// Whether the rule should report the snippet
+    pub matches: bool,
+    /// For `security.custom_patterns`: the `[security] patterns` entry that
+    /// should (or should not) match, rather than any of them
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct GitHubConfig {
     /// GitHub API token. If None, falls back to GITHUB_TOKEN env var.
+    /// Never serialized, so it cannot leak into the config fingerprint.
+    #[serde(skip_serializing)]
     pub token: Option<String>,
+    /// More tokens for reads, used in turn as each one's rate limit runs out
+    /// (e.g. during `org scan`). If empty, falls back to the comma-separated
+    /// GITHUB_TOKENS env var. Never serialized.
+    #[serde(default, skip_serializing)]
+    pub tokens: Vec<String>,
+    /// REST API root, for GitHub Enterprise Server (`https://<host>/api/v3`).
+    /// If None, falls back to GITHUB_API_URL env var, then api.github.com.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+/// Settings for Gerrit change URLs (`https://<host>/c/<project>/+/<n>`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GerritConfig {
+    /// HTTP username. If None, falls back to GERRIT_USERNAME env var.
+    pub username: Option<String>,
+    /// HTTP password (Settings > HTTP Credentials). If None, falls back to
+    /// GERRIT_HTTP_PASSWORD env var. Never serialized.
+    #[serde(skip_serializing)]
+    pub http_password: Option<String>,
+    /// Label voted on by --review
+    #[serde(default = "default_gerrit_label")]
+    pub label: String,
+    /// Vote cast for each overall risk level; levels without an entry don't vote
+    #[serde(default = "default_gerrit_votes")]
+    pub votes: BTreeMap<RiskLevel, i8>,
+}
+
+impl Default for GerritConfig {
+    fn default() -> Self {
+        Self {
+            username: None,
+            http_password: None,
+            label: default_gerrit_label(),
+            votes: default_gerrit_votes(),
+        }
+    }
+}
+
+fn default_gerrit_label() -> String {
+    "Code-Review".to_string()
+}
+
+fn default_gerrit_votes() -> BTreeMap<RiskLevel, i8> {
+    BTreeMap::from([(RiskLevel::Low, 0), (RiskLevel::Medium, 0), (RiskLevel::High, -1)])
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SecurityConfig {
-    /// Additional regex patterns to flag as security risks
+    /// Additional regex patterns to flag as security risks on added lines
+    /// (e.g., ["TODO.*security", { pattern = "AKIA[0-9A-Z]{16}", severity = "high" }])
     #[serde(default)]
-    pub patterns: Vec<String>,
+    pub patterns: Vec<CustomPattern>,
+
+    /// Third-party GitHub Actions trusted beyond `actions/*` and `github/*`
+    /// (e.g., ["docker/login-action", "myorg/*"])
+    #[serde(default)]
+    pub allowed_actions: Vec<String>,
+
+    /// Globs of authentication/authorization code; any change to a matching
+    /// file is flagged (e.g., ["**/auth/**", "**/middleware/permissions*"])
+    #[serde(default = "default_auth_paths")]
+    pub auth_paths: Vec<String>,
+
+    /// Line prefixes that mark an auth check (decorators, attributes, filters);
+    /// adding or removing one is flagged (e.g., ["@login_required", "#[authorize"])
+    #[serde(default = "default_auth_markers")]
+    pub auth_markers: Vec<String>,
+
+    /// Rescan whole new files from the checkout for secrets and high-entropy
+    /// strings, beyond what the (possibly truncated) diff shows
+    #[serde(default = "default_true")]
+    pub scan_new_files: bool,
+
+    /// Known fake credentials (e.g., ["hunter2", "changeme", "example"]); a
+    /// secret whose value is one of them, ignoring case, is not reported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_values: Vec<String>,
+
+    /// Globs of docs, tests and fixtures whose secrets are demo data; they
+    /// are still reported, but as LOW (e.g., ["tests/**", "docs/**"])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_paths: Vec<String>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            allowed_actions: Vec::new(),
+            auth_paths: default_auth_paths(),
+            auth_markers: default_auth_markers(),
+            scan_new_files: true,
+            allow_values: Vec::new(),
+            allow_paths: Vec::new(),
+        }
+    }
+}
+
+/// One `[security] patterns` entry: a regex, whose matches are MEDIUM, or a
+/// table that also sets the severity and tags for its findings.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "PatternEntry")]
+pub struct CustomPattern {
+    pub pattern: String,
+    pub severity: RiskLevel,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl From<&str> for CustomPattern {
+    fn from(pattern: &str) -> Self {
+        Self { pattern: pattern.to_string(), severity: RiskLevel::Medium, tags: Vec::new() }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PatternEntry {
+    Regex(String),
+    Table {
+        pattern: String,
+        #[serde(default = "default_pattern_severity")]
+        severity: RiskLevel,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+fn default_pattern_severity() -> RiskLevel {
+    RiskLevel::Medium
+}
+
+impl From<PatternEntry> for CustomPattern {
+    fn from(entry: PatternEntry) -> Self {
+        match entry {
+            PatternEntry::Regex(pattern) => Self::from(pattern.as_str()),
+            PatternEntry::Table { pattern, severity, tags } => Self { pattern, severity, tags },
+        }
+    }
+}
+
+/// One `[[rules]]` entry: a regex checked against the code of added lines
+/// (comments blanked, strings kept) in the files its globs select.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UserRule {
+    /// Identifier shown with each finding, e.g. "no-println"
+    pub id: String,
+    pub pattern: String,
+    /// Globs of the files checked; a glob without `/` matches the file
+    /// name at any depth. Empty checks every file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    pub message: String,
+    #[serde(default = "default_pattern_severity")]
+    pub severity: RiskLevel,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Layout of a `rules_file`.
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<UserRule>,
+}
+
+/// Common directory and file names for auth code.
+fn default_auth_paths() -> Vec<String> {
+    [
+        "**/auth/**",
+        "**/authn/**",
+        "**/authz/**",
+        "**/authentication/**",
+        "**/authorization/**",
+        "**/permissions/**",
+        "**/middleware/auth*",
+        "**/middleware/permissions*",
+    ]
+    .iter()
+    .map(|p| p.to_string())
+    .collect()
+}
+
+/// Auth decorators and attributes of Django, Flask, Spring, Jakarta EE,
+/// NestJS, ASP.NET, Rails and common Rust web crates.
+fn default_auth_markers() -> Vec<String> {
+    [
+        "@login_required",
+        "@permission_required",
+        "@user_passes_test",
+        "@requires_auth",
+        "@auth_required",
+        "@jwt_required",
+        "@roles_required",
+        "@PreAuthorize",
+        "@Secured",
+        "@RolesAllowed",
+        "@UseGuards",
+        "[Authorize",
+        "#[authorize",
+        "#[require_auth",
+        "#[has_permissions",
+        "#[has_roles",
+        "before_action :authenticate",
+        "before_action :authorize",
+    ]
+    .iter()
+    .map(|p| p.to_string())
+    .collect()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StyleConfig {
-    /// Directories that define architectural layers (e.g., ["api", "domain", "infra"])
+    /// Directory names or globs that define architectural layers, from the top
+    /// down (e.g., ["api", "domain", "infra*"]); a layer may not import the ones before it
     #[serde(default)]
     pub layers: Vec<String>,
+
+    /// Globs identifying test files (e.g., ["tests/**", "**/*_spec.rb", "spec/**"])
+    #[serde(default = "default_test_paths")]
+    pub test_paths: Vec<String>,
+
+    /// Run `cargo clippy` on changed crates when a checkout is available (--repo / --clone)
+    #[serde(default)]
+    pub clippy: bool,
+}
+
+impl Default for StyleConfig {
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            test_paths: default_test_paths(),
+            clippy: false,
+        }
+    }
+}
+
+/// Test conventions for Rust, Go, Ruby, Python and JavaScript projects.
+fn default_test_paths() -> Vec<String> {
+    [
+        "**/tests/**",
+        "**/*_test.rs",
+        "**/*_test.go",
+        "**/spec/**",
+        "**/*_spec.rb",
+        "**/test_*.py",
+        "**/__tests__/**",
+        "**/*.test.*",
+        "**/*.spec.*",
+    ]
+    .iter()
+    .map(|p| p.to_string())
+    .collect()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProcessConfig {
+    /// Globs of workflows that release, publish or deploy
+    /// (e.g., [".github/workflows/release*.yml", ".gitlab-ci.yml"])
+    #[serde(default = "default_release_workflows")]
+    pub release_workflows: Vec<String>,
+
+    /// Globs of Dockerfiles that build the deployed image, as opposed to
+    /// development or test images (e.g., ["/Dockerfile", "deploy/**/Dockerfile"],
+    /// where the leading `/` keeps the first to the root `Dockerfile`)
+    #[serde(default = "default_deploy_images")]
+    pub deploy_images: Vec<String>,
+
+    /// Conventional-commit types a PR title must start with, as in
+    /// `feat: ...` or `fix(api)!: ...` (empty: titles are not checked)
+    #[serde(default)]
+    pub title_types: Vec<String>,
+
+    /// Regex the PR's source branch must match, e.g. "^feature/JIRA-\\d+"
+    /// (unset: branch names are not checked)
+    #[serde(default)]
+    pub branch_pattern: Option<String>,
+
+    /// Globs of public-facing code; a PR that changes one must also change
+    /// a `changelog_paths` file (empty: not checked)
+    #[serde(default)]
+    pub public_paths: Vec<String>,
+
+    /// Globs of changelog files or fragments (e.g., ["CHANGELOG.md", "changes/*.md"])
+    #[serde(default = "default_changelog_paths")]
+    pub changelog_paths: Vec<String>,
+
+    /// PR labels (Gerrit hashtags) that exempt a PR from the changelog check
+    #[serde(default = "default_changelog_skip_labels")]
+    pub changelog_skip_labels: Vec<String>,
+}
+
+impl Default for ProcessConfig {
+    fn default() -> Self {
+        Self {
+            release_workflows: default_release_workflows(),
+            deploy_images: default_deploy_images(),
+            title_types: Vec::new(),
+            branch_pattern: None,
+            public_paths: Vec::new(),
+            changelog_paths: default_changelog_paths(),
+            changelog_skip_labels: default_changelog_skip_labels(),
+        }
+    }
+}
+
+fn default_release_workflows() -> Vec<String> {
+    [".github/workflows/*release*", ".github/workflows/*publish*", ".github/workflows/*deploy*"].iter().map(|p| p.to_string()).collect()
+}
+
+fn default_deploy_images() -> Vec<String> {
+    ["/Dockerfile"].iter().map(|p| p.to_string()).collect()
+}
+
+fn default_changelog_paths() -> Vec<String> {
+    ["CHANGELOG.md"].iter().map(|p| p.to_string()).collect()
+}
+
+fn default_changelog_skip_labels() -> Vec<String> {
+    ["skip-changelog"].iter().map(|p| p.to_string()).collect()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// Maximum number of analyzers running at once
+    pub max_concurrent_analyzers: usize,
+    /// Files whose diff exceeds this size are skipped and noted in the report
+    pub max_file_size_kb: usize,
+    /// Upper bound on findings kept across all analyzers
+    pub max_total_findings: usize,
+    /// Total diff size above which line-level checks are skipped
+    pub max_diff_bytes: usize,
+    /// Changed files above which line-level checks are skipped
+    pub max_files: usize,
+    /// Commits of history fetched by --clone (blame needs more than one)
+    pub clone_depth: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_analyzers: 8,
+            max_file_size_kb: 1024,
+            max_total_findings: 1000,
+            max_diff_bytes: 16 * 1024 * 1024,
+            max_files: 3000,
+            clone_depth: 50,
+        }
+    }
+}
+
+/// `[policy]` in a shared config: settings a repository's local config
+/// cannot weaken. A `[policy]` table in the local file is ignored.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Policy {
+    /// Thresholds are held, field by field, at least as strict as this
+    /// profile (built-in or from the shared config's `[profiles]`)
+    pub profile: Option<String>,
+    /// Dotted keys (e.g. "security.patterns", "runtime") that keep the shared
+    /// config's value whatever the local file says
+    #[serde(default)]
+    pub locked: Vec<String>,
+    /// Analyzers every run must include (e.g. ["security"]);
+    /// `--only-analyzer` or `--only-rule` leaving one out is an error
+    #[serde(default)]
+    pub required_analyzers: Vec<String>,
+    /// Local settings the policy overrode while loading
+    #[serde(skip)]
+    pub overridden: Vec<String>,
+    /// Thresholds the policy tightened in the selected profile
+    #[serde(skip)]
+    pub tightened: Vec<String>,
+}
+
+impl Policy {
+    /// Everything the policy enforced in this run, for the report.
+    pub fn enforced(&self) -> Vec<String> {
+        self.overridden.iter().chain(&self.tightened).cloned().collect()
+    }
+}
+
+/// Threshold and severity presets bundled under a profile name.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Profile {
+    /// Changed lines above which a PR is a MEDIUM / HIGH risk change
+    pub medium_change_lines: usize,
+    pub high_change_lines: usize,
+    /// Changed files above which a PR is a MEDIUM / HIGH risk change
+    pub medium_change_files: usize,
+    pub high_change_files: usize,
+    /// New dependencies in one manifest that trigger MEDIUM / HIGH
+    pub medium_new_dependencies: usize,
+    pub high_new_dependencies: usize,
+    /// New public items allowed before the API surface is flagged
+    pub max_public_items: usize,
+    /// Indent levels allowed before code counts as deeply nested
+    pub max_nesting_depth: usize,
+    /// Size (lines / KB) above which an added file is flagged
+    pub max_added_file_lines: usize,
+    pub max_added_file_kb: usize,
+    /// Size in KB above which an added data file (CSV, JSON, ...) is flagged
+    pub max_data_file_kb: usize,
+    /// MEDIUM / LOW findings in one analyzer that raise its risk one level
+    pub escalate_medium_findings: usize,
+    pub escalate_low_findings: usize,
+    /// Findings below this severity are left out of the report
+    pub min_severity: RiskLevel,
+}
+
+impl Profile {
+    /// Look up a built-in profile by name.
+    pub fn builtin(name: &str) -> Option<Profile> {
+        match name {
+            "strict" => Some(Profile {
+                medium_change_lines: 100,
+                high_change_lines: 300,
+                medium_change_files: 5,
+                high_change_files: 15,
+                medium_new_dependencies: 2,
+                high_new_dependencies: 4,
+                max_public_items: 5,
+                max_nesting_depth: 3,
+                max_added_file_lines: 500,
+                max_added_file_kb: 100,
+                max_data_file_kb: 20,
+                escalate_medium_findings: 10,
+                escalate_low_findings: 25,
+                min_severity: RiskLevel::Low,
+            }),
+            "standard" => Some(Profile::default()),
+            "lenient" => Some(Profile {
+                medium_change_lines: 400,
+                high_change_lines: 1000,
+                medium_change_files: 20,
+                high_change_files: 40,
+                medium_new_dependencies: 5,
+                high_new_dependencies: 10,
+                max_public_items: 20,
+                max_nesting_depth: 6,
+                max_added_file_lines: 3000,
+                max_added_file_kb: 1000,
+                max_data_file_kb: 200,
+                escalate_medium_findings: 30,
+                escalate_low_findings: 80,
+                min_severity: RiskLevel::Medium,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Profile {
+    /// Tighten every threshold looser than `floor` to the floor's value and
+    /// describe each change (e.g. "max_nesting_depth 6 → 4").
+    fn hold_to(&mut self, floor: &Profile) -> Vec<String> {
+        let mut changes = Vec::new();
+        let mut hold = |name: &str, value: &mut usize, limit: usize| {
+            if *value > limit {
+                changes.push(format!("{} {} → {}", name, value, limit));
+                *value = limit;
+            }
+        };
+        hold("medium_change_lines", &mut self.medium_change_lines, floor.medium_change_lines);
+        hold("high_change_lines", &mut self.high_change_lines, floor.high_change_lines);
+        hold("medium_change_files", &mut self.medium_change_files, floor.medium_change_files);
+        hold("high_change_files", &mut self.high_change_files, floor.high_change_files);
+        hold("medium_new_dependencies", &mut self.medium_new_dependencies, floor.medium_new_dependencies);
+        hold("high_new_dependencies", &mut self.high_new_dependencies, floor.high_new_dependencies);
+        hold("max_public_items", &mut self.max_public_items, floor.max_public_items);
+        hold("max_nesting_depth", &mut self.max_nesting_depth, floor.max_nesting_depth);
+        hold("max_added_file_lines", &mut self.max_added_file_lines, floor.max_added_file_lines);
+        hold("max_added_file_kb", &mut self.max_added_file_kb, floor.max_added_file_kb);
+        hold("max_data_file_kb", &mut self.max_data_file_kb, floor.max_data_file_kb);
+        hold("escalate_medium_findings", &mut self.escalate_medium_findings, floor.escalate_medium_findings);
+        hold("escalate_low_findings", &mut self.escalate_low_findings, floor.escalate_low_findings);
+        if self.min_severity > floor.min_severity {
+            changes.push(format!("min_severity {} → {}", self.min_severity, floor.min_severity));
+            self.min_severity = floor.min_severity;
+        }
+        changes
+    }
+}
+
+impl Default for Profile {
+    /// The "standard" profile: the thresholds the analyzers shipped with.
+    fn default() -> Self {
+        Self {
+            medium_change_lines: 200,
+            high_change_lines: 500,
+            medium_change_files: 10,
+            high_change_files: 20,
+            medium_new_dependencies: 3,
+            high_new_dependencies: 5,
+            max_public_items: 10,
+            max_nesting_depth: 4,
+            max_added_file_lines: 1000,
+            max_added_file_kb: 250,
+            max_data_file_kb: 50,
+            escalate_medium_findings: 15,
+            escalate_low_findings: 40,
+            min_severity: RiskLevel::Low,
+        }
+    }
+}
+
+/// A custom profile from `[profiles.<name>]`: any field left out is taken
+/// from the built-in profile named by `extends` ("standard" if unset).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProfileOverrides {
+    pub extends: Option<String>,
+    pub medium_change_lines: Option<usize>,
+    pub high_change_lines: Option<usize>,
+    pub medium_change_files: Option<usize>,
+    pub high_change_files: Option<usize>,
+    pub medium_new_dependencies: Option<usize>,
+    pub high_new_dependencies: Option<usize>,
+    pub max_public_items: Option<usize>,
+    pub max_nesting_depth: Option<usize>,
+    pub max_added_file_lines: Option<usize>,
+    pub max_added_file_kb: Option<usize>,
+    pub max_data_file_kb: Option<usize>,
+    pub escalate_medium_findings: Option<usize>,
+    pub escalate_low_findings: Option<usize>,
+    pub min_severity: Option<RiskLevel>,
+}
+
+impl ProfileOverrides {
+    fn resolve(&self) -> Result<Profile, ConfigError> {
+        let base_name = self.extends.as_deref().unwrap_or("standard");
+        let base = Profile::builtin(base_name).ok_or_else(|| ConfigError::UnknownProfile(base_name.to_string()))?;
+        Ok(Profile {
+            medium_change_lines: self.medium_change_lines.unwrap_or(base.medium_change_lines),
+            high_change_lines: self.high_change_lines.unwrap_or(base.high_change_lines),
+            medium_change_files: self.medium_change_files.unwrap_or(base.medium_change_files),
+            high_change_files: self.high_change_files.unwrap_or(base.high_change_files),
+            medium_new_dependencies: self.medium_new_dependencies.unwrap_or(base.medium_new_dependencies),
+            high_new_dependencies: self.high_new_dependencies.unwrap_or(base.high_new_dependencies),
+            max_public_items: self.max_public_items.unwrap_or(base.max_public_items),
+            max_nesting_depth: self.max_nesting_depth.unwrap_or(base.max_nesting_depth),
+            max_added_file_lines: self.max_added_file_lines.unwrap_or(base.max_added_file_lines),
+            max_added_file_kb: self.max_added_file_kb.unwrap_or(base.max_added_file_kb),
+            max_data_file_kb: self.max_data_file_kb.unwrap_or(base.max_data_file_kb),
+            escalate_medium_findings: self.escalate_medium_findings.unwrap_or(base.escalate_medium_findings),
+            escalate_low_findings: self.escalate_low_findings.unwrap_or(base.escalate_low_findings),
+            min_severity: self.min_severity.unwrap_or(base.min_severity),
+        })
+    }
 }
 
 impl Config {
+    /// Select a profile by name, or the config file's `profile` when `name`
+    /// is None, and store its thresholds. Custom profiles shadow built-ins.
+    ///
+    /// A `[policy] profile` then tightens any threshold looser than its own.
+    pub fn apply_profile(&mut self, name: Option<&str>) -> Result<(), ConfigError> {
+        let name = name.or(self.profile.as_deref()).unwrap_or("standard").to_string();
+        self.thresholds = self.resolve_profile(&name)?;
+        self.policy.tightened.clear();
+        if let Some(floor_name) = self.policy.profile.clone() {
+            let floor = self.resolve_profile(&floor_name)?;
+            let changes = self.thresholds.hold_to(&floor);
+            if !changes.is_empty() {
+                self.policy.tightened.push(format!("thresholds held to policy profile '{}': {}", floor_name, changes.join(", ")));
+            }
+        }
+        self.profile = Some(name);
+        Ok(())
+    }
+
+    /// Add `--ignore` globs to `ignore`. When the policy locks `ignore`, they
+    /// are dropped and noted as overridden, like a local `ignore` would be.
+    pub fn add_ignores(&mut self, globs: &[String]) {
+        if globs.is_empty() {
+            return;
+        }
+        if self.policy.locked.iter().any(|key| key == "ignore") {
+            warn!(globs = ?globs, "--ignore dropped; ignore is locked by policy");
+            self.policy.overridden.push(format!("ignore: --ignore {} ignored (locked by policy)", globs.join(", ")));
+            return;
+        }
+        self.ignore.extend(globs.iter().cloned());
+    }
+
+    fn resolve_profile(&self, name: &str) -> Result<Profile, ConfigError> {
+        match self.profiles.get(name) {
+            Some(custom) => custom.resolve(),
+            None => Profile::builtin(name).ok_or_else(|| ConfigError::UnknownProfile(name.to_string())),
+        }
+    }
+
     /// Load configuration from .pr-analyzer.toml in the current directory.
     /// Returns default config if the file doesn't exist.
     ///
     /// Codex: Implement file loading.
     /// 1. Check if .pr-analyzer.toml exists in the current directory
-    /// 2. If yes, read and parse it
+    /// 2. If yes, read and parse it (resolving `extends`, see `load_from`)
     /// 3. If no, return Config::default()
     /// 4. Override github.token with GITHUB_TOKEN env var if set
-    pub fn load() -> Result<Config, ConfigError> {
+    pub async fn load() -> Result<Config, ConfigError> {
         let path = Path::new(".pr-analyzer.toml");
         let mut config = if path.exists() {
-            Self::load_from(path)?
+            Self::load_from(path).await?
         } else {
             Config::default()
         };
@@ -79,13 +878,46 @@ impl Config {
 
     /// Load from a specific path (useful for testing).
     ///
-    /// Codex: Implement for testability.
-    pub fn load_from(path: &Path) -> Result<Config, ConfigError> {
+    /// If the file sets `extends = "github:org/repo[/path.toml][@ref]"`, the
+    /// shared base config is fetched (or read from cache) and the local file
+    /// is merged on top of it, subject to the shared config's `[policy]`.
+    pub async fn load_from(path: &Path) -> Result<Config, ConfigError> {
         let contents = fs::read_to_string(path)?;
-        let config = toml::from_str(&contents)?;
+        let local: toml::Value = toml::from_str(&contents)?;
+        let (merged, overridden) = match local.get("extends").and_then(|v| v.as_str()) {
+            Some(spec) => {
+                let source = ExtendsSource::parse(spec)?;
+                let token = local
+                    .get("github")
+                    .and_then(|g| g.get("token"))
+                    .and_then(|t| t.as_str())
+                    .map(str::to_string)
+                    .or_else(|| std::env::var("GITHUB_TOKEN").ok());
+                let api_url = resolve_github_api_url(local.get("github").and_then(|g| g.get("api_url")).and_then(|u| u.as_str()));
+                let base = fetch_shared_config(&source, &api_url, token.as_deref(), &cache_dir()).await?;
+                merge_with_policy(base, local)?
+            }
+            None => (local, Vec::new()),
+        };
+        let mut config: Config = merged.try_into()?;
+        if let Some(file) = &config.rules_file {
+            let rules_path = path.parent().unwrap_or(Path::new("")).join(file);
+            let rules: RulesFile = toml::from_str(&fs::read_to_string(rules_path)?)?;
+            config.rules.extend(rules.rules);
+        }
+        config.policy.overridden = overridden;
+        config.apply_profile(None)?;
         Ok(config)
     }
 
+    /// SHA-256 of the effective configuration (after `extends` and profile
+    /// resolution), recorded in report provenance. The GitHub token is excluded.
+    pub fn fingerprint(&self) -> String {
+        // Serializing plain structs and BTreeMaps cannot fail and is key-order stable.
+        let canonical = serde_json::to_vec(self).unwrap_or_default();
+        Sha256::digest(&canonical).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
     /// Resolve the GitHub token: config file value takes precedence,
     /// falls back to GITHUB_TOKEN env var.
     ///
@@ -96,6 +928,253 @@ impl Config {
             .clone()
             .or_else(|| std::env::var("GITHUB_TOKEN").ok())
     }
+
+    /// Every token reads may use, in order: `github_token()`, then
+    /// `[github] tokens` (or GITHUB_TOKENS), without duplicates.
+    pub fn github_tokens(&self) -> Vec<String> {
+        let extra = if self.github.tokens.is_empty() {
+            std::env::var("GITHUB_TOKENS").map_or(Vec::new(), |list| list.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect())
+        } else {
+            self.github.tokens.clone()
+        };
+        let mut tokens: Vec<String> = Vec::new();
+        for token in self.github_token().into_iter().chain(extra) {
+            if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+        tokens
+    }
+
+    /// REST API root without a trailing slash: `[github] api_url`, falling
+    /// back to GITHUB_API_URL, then api.github.com.
+    pub fn github_api_url(&self) -> String {
+        resolve_github_api_url(self.github.api_url.as_deref())
+    }
+
+    /// Host (with port, if any) of the web UI whose PR URLs this config
+    /// reads: the API host without an `api.` prefix, so github.com for
+    /// api.github.com and the server itself for GitHub Enterprise Server.
+    pub fn github_host(&self) -> String {
+        let api = self.github_api_url();
+        let Ok(url) = reqwest::Url::parse(&api) else {
+            return "github.com".to_string();
+        };
+        let host = url.host_str().unwrap_or("github.com");
+        let host = host.strip_prefix("api.").unwrap_or(host);
+        match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        }
+    }
+
+    /// Gerrit HTTP credentials: config values, falling back to the
+    /// GERRIT_USERNAME and GERRIT_HTTP_PASSWORD env vars. None unless both are set.
+    pub fn gerrit_credentials(&self) -> Option<(String, String)> {
+        let username = self.gerrit.username.clone().or_else(|| std::env::var("GERRIT_USERNAME").ok())?;
+        let password = self.gerrit.http_password.clone().or_else(|| std::env::var("GERRIT_HTTP_PASSWORD").ok())?;
+        Some((username, password))
+    }
+}
+
+/// How long a shared config fetched from a branch or tag is reused.
+/// Configs pinned to a commit SHA are cached indefinitely.
+const EXTENDS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A shared config named by `extends = "github:owner/repo[/path.toml][@ref]"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendsSource {
+    pub owner: String,
+    pub repo: String,
+    /// File within the repository (default `.pr-analyzer.toml`)
+    pub path: String,
+    /// Branch, tag, or commit SHA; the default branch when None
+    pub git_ref: Option<String>,
+}
+
+impl ExtendsSource {
+    pub fn parse(spec: &str) -> Result<ExtendsSource, ConfigError> {
+        let invalid = || ConfigError::InvalidExtends(spec.to_string());
+        let rest = spec.strip_prefix("github:").ok_or_else(invalid)?;
+        let (location, git_ref) = match rest.rsplit_once('@') {
+            Some((location, git_ref)) if !git_ref.is_empty() => (location, Some(git_ref.to_string())),
+            Some(_) => return Err(invalid()),
+            None => (rest, None),
+        };
+        let mut parts = location.splitn(3, '/');
+        let owner = parts.next().filter(|s| !s.is_empty()).ok_or_else(invalid)?;
+        let repo = parts.next().filter(|s| !s.is_empty()).ok_or_else(invalid)?;
+        let path = parts.next().filter(|s| !s.is_empty()).unwrap_or(".pr-analyzer.toml");
+        Ok(ExtendsSource {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            path: path.to_string(),
+            git_ref,
+        })
+    }
+
+    /// Pinned to a full commit SHA, so the content can never change.
+    fn is_pinned(&self) -> bool {
+        self.git_ref.as_deref().is_some_and(|r| r.len() == 40 && r.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+
+    fn cache_file(&self, cache_dir: &Path) -> PathBuf {
+        let name = format!(
+            "{}__{}__{}__{}.toml",
+            self.owner,
+            self.repo,
+            self.path.replace(['/', '\\'], "_"),
+            self.git_ref.as_deref().unwrap_or("HEAD").replace(['/', '\\'], "_")
+        );
+        cache_dir.join(name)
+    }
+}
+
+/// `configured`, else GITHUB_API_URL, else api.github.com; without a
+/// trailing slash so paths can be appended.
+fn resolve_github_api_url(configured: Option<&str>) -> String {
+    let url = configured.map(str::to_string).or_else(|| std::env::var("GITHUB_API_URL").ok()).filter(|u| !u.trim().is_empty());
+    url.as_deref().unwrap_or(DEFAULT_GITHUB_API_URL).trim().trim_end_matches('/').to_string()
+}
+
+/// `$XDG_CACHE_HOME/pr-analyzer/extends`, falling back to `~/.cache`.
+fn cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("pr-analyzer")
+        .join("extends")
+}
+
+/// Fetch the shared config, preferring a fresh cache entry. If the fetch
+/// fails and a stale entry exists, the stale copy is used with a warning.
+async fn fetch_shared_config(source: &ExtendsSource, api_url: &str, token: Option<&str>, cache_dir: &Path) -> Result<toml::Value, ConfigError> {
+    let cache_file = source.cache_file(cache_dir);
+    let cached = fs::read_to_string(&cache_file).ok();
+    let fresh = source.is_pinned()
+        || fs::metadata(&cache_file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < EXTENDS_CACHE_TTL);
+
+    let contents = match cached {
+        Some(contents) if fresh => {
+            debug!(path = %cache_file.display(), "using cached shared config");
+            contents
+        }
+        cached => match download_shared_config(source, api_url, token).await {
+            Ok(contents) => {
+                if let Err(e) = fs::create_dir_all(cache_dir).and_then(|_| fs::write(&cache_file, &contents)) {
+                    warn!(error = %e, "could not cache shared config");
+                }
+                contents
+            }
+            Err(e) => match cached {
+                Some(contents) => {
+                    warn!(error = %e, "shared config fetch failed, using stale cache");
+                    contents
+                }
+                None => return Err(e),
+            },
+        },
+    };
+
+    let mut base: toml::Value = toml::from_str(&contents)?;
+    // Only one level of inheritance: a base config cannot extend another.
+    if let Some(table) = base.as_table_mut() {
+        table.remove("extends");
+    }
+    Ok(base)
+}
+
+async fn download_shared_config(source: &ExtendsSource, api_url: &str, token: Option<&str>) -> Result<String, ConfigError> {
+    let url = format!("{}/repos/{}/{}/contents/{}", api_url, source.owner, source.repo, source.path);
+    debug!(url = %url, git_ref = ?source.git_ref, "fetching shared config");
+    let mut request = reqwest::Client::new()
+        .get(&url)
+        .header("User-Agent", "pr-analyzer")
+        .header("Accept", "application/vnd.github.raw");
+    if let Some(git_ref) = &source.git_ref {
+        request = request.query(&[("ref", git_ref)]);
+    }
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    Ok(request.send().await?.error_for_status()?.text().await?)
+}
+
+/// Merge `local` over `base`: tables merge key by key, any other value
+/// (including arrays) in `local` replaces the base value.
+fn merge_toml(base: toml::Value, local: toml::Value) -> toml::Value {
+    match (base, local) {
+        (toml::Value::Table(mut base), toml::Value::Table(local)) => {
+            for (key, value) in local {
+                let merged = match base.remove(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            toml::Value::Table(base)
+        }
+        (_, local) => local,
+    }
+}
+
+/// `merge_toml`, except that the base's `[policy]` cannot be weakened: the
+/// local `[policy]` is dropped, and locked keys (plus the policy profile's
+/// own `[profiles]` entry) keep the base value. Returns what was overridden.
+fn merge_with_policy(base: toml::Value, mut local: toml::Value) -> Result<(toml::Value, Vec<String>), ConfigError> {
+    let mut overridden = Vec::new();
+    if local.as_table_mut().and_then(|t| t.remove("policy")).is_some() {
+        warn!("[policy] in the local config is ignored; only the shared config can set it");
+        overridden.push("[policy] in the local config ignored".to_string());
+    }
+    let policy: Policy = match base.get("policy") {
+        Some(value) => value.clone().try_into()?,
+        None => Policy::default(),
+    };
+    let mut locked = policy.locked;
+    if let Some(name) = policy.profile {
+        locked.push(format!("profiles.{}", name));
+    }
+
+    let mut merged = merge_toml(base.clone(), local.clone());
+    for key in &locked {
+        let base_value = toml_path(&base, key);
+        let Some(local_value) = toml_path(&local, key) else { continue };
+        if base_value.is_some_and(|b| merge_toml(b.clone(), local_value.clone()) == *b) {
+            continue;
+        }
+        set_toml_path(&mut merged, key, base_value.cloned());
+        overridden.push(format!("{}: local value ignored (locked by policy)", key));
+    }
+    Ok((merged, overridden))
+}
+
+/// Look up a dotted key such as `security.patterns`.
+fn toml_path<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(value, |v, part| v.get(part))
+}
+
+/// Replace (or with None, remove) the value at a dotted key.
+fn set_toml_path(value: &mut toml::Value, key: &str, new: Option<toml::Value>) {
+    let (parent, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (parent.split('.').try_fold(&mut *value, |v, part| v.get_mut(part)), last),
+        None => (Some(value), key),
+    };
+    if let Some(table) = parent.and_then(toml::Value::as_table_mut) {
+        match new {
+            Some(new) => {
+                table.insert(last.to_string(), new);
+            }
+            None => {
+                table.remove(last);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -107,20 +1186,257 @@ mod tests {
         let config = Config::default();
         assert!(config.github.token.is_none());
         assert!(config.security.patterns.is_empty());
+        assert!(config.security.auth_paths.contains(&"**/auth/**".to_string()));
+        assert_eq!(config.process.deploy_images, vec!["/Dockerfile".to_string()]);
+        assert!(config.style.layers.is_empty());
+        assert_eq!(config.runtime.max_concurrent_analyzers, 8);
+        assert!(config.style.test_paths.contains(&"**/tests/**".to_string()));
+    }
+
+    #[test]
+    fn test_builtin_profiles() {
+        let mut config = Config::default();
+        assert_eq!(config.thresholds, Profile::default());
+        config.apply_profile(Some("strict")).unwrap();
+        assert_eq!(config.thresholds.high_change_lines, 300);
+        config.apply_profile(Some("lenient")).unwrap();
+        assert_eq!(config.thresholds.min_severity, RiskLevel::Medium);
+        assert!(matches!(config.apply_profile(Some("nope")), Err(ConfigError::UnknownProfile(_))));
+    }
+
+    #[test]
+    fn test_custom_profile_extends_builtin() {
+        let toml_str = r#"
+profile = "rollout"
+
+[profiles.rollout]
+extends = "lenient"
+max_nesting_depth = 8
+"#;
+        let mut config: Config = toml::from_str(toml_str).unwrap();
+        config.apply_profile(None).unwrap();
+        assert_eq!(config.thresholds.max_nesting_depth, 8);
+        assert_eq!(config.thresholds.high_change_lines, 1000);
+        config.apply_profile(Some("strict")).unwrap();
+        assert_eq!(config.thresholds.max_nesting_depth, 3);
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_rules_not_token() {
+        let base = Config::default();
+        let mut with_token = Config::default();
+        with_token.github.token = Some("ghp_secret".to_string());
+        assert_eq!(base.fingerprint(), with_token.fingerprint());
+        assert_eq!(base.fingerprint().len(), 64);
+
+        let mut strict = Config::default();
+        strict.apply_profile(Some("strict")).unwrap();
+        assert_ne!(base.fingerprint(), strict.fingerprint());
+    }
+
+    #[test]
+    fn test_parse_extends_source() {
+        let source = ExtendsSource::parse("github:acme/pr-analyzer-config").unwrap();
+        assert_eq!(source.path, ".pr-analyzer.toml");
+        assert_eq!(source.git_ref, None);
+        let source = ExtendsSource::parse("github:acme/configs/rust/base.toml@v2").unwrap();
+        assert_eq!((source.owner.as_str(), source.repo.as_str()), ("acme", "configs"));
+        assert_eq!(source.path, "rust/base.toml");
+        assert_eq!(source.git_ref.as_deref(), Some("v2"));
+        assert!(ExtendsSource::parse("acme/configs").is_err());
+        assert!(ExtendsSource::parse("github:acme").is_err());
+        assert!(ExtendsSource::parse("github:acme/configs@").is_err());
+    }
+
+    #[test]
+    fn test_merge_toml_local_overrides_base() {
+        let base: toml::Value = toml::from_str("profile = \"strict\"\n[security]\npatterns = [\"a\"]\nallowed_actions = [\"org/*\"]\n").unwrap();
+        let local: toml::Value = toml::from_str("[security]\npatterns = [\"b\"]\n").unwrap();
+        let config: Config = merge_toml(base, local).try_into().unwrap();
+        assert_eq!(config.profile.as_deref(), Some("strict"));
+        assert_eq!(config.security.patterns, vec![CustomPattern::from("b")]);
+        assert_eq!(config.security.allowed_actions, vec!["org/*".to_string()]);
+    }
+
+    #[test]
+    fn test_policy_cannot_be_weakened_locally() {
+        let base: toml::Value = toml::from_str(
+            r#"
+[policy]
+profile = "org"
+locked = ["security.patterns", "runtime"]
+
+[profiles.org]
+max_nesting_depth = 4
+
+[security]
+patterns = ["AKIA[0-9A-Z]{16}"]
+"#,
+        )
+        .unwrap();
+        let local: toml::Value = toml::from_str(
+            r#"
+profile = "lenient"
+
+[policy]
+locked = []
+
+[profiles.org]
+max_nesting_depth = 10
+
+[security]
+patterns = []
+allowed_actions = ["docker/*"]
+
+[runtime]
+max_file_size_kb = 1
+"#,
+        )
+        .unwrap();
+        let (merged, overridden) = merge_with_policy(base, local).unwrap();
+        let mut config: Config = merged.try_into().unwrap();
+        config.policy.overridden = overridden;
+        config.apply_profile(None).unwrap();
+
+        assert_eq!(config.security.patterns, vec![CustomPattern::from("AKIA[0-9A-Z]{16}")]);
+        assert_eq!(config.security.allowed_actions, vec!["docker/*".to_string()]);
+        assert_eq!(config.runtime.max_file_size_kb, 1024);
+        assert_eq!(config.policy.locked.len(), 2);
+        // lenient, but held to the org profile (standard with depth 4) field by field
+        assert_eq!(config.thresholds.max_nesting_depth, 4);
+        assert_eq!(config.thresholds.high_change_lines, 500);
+        assert_eq!(config.thresholds.min_severity, RiskLevel::Low);
+
+        let enforced = config.policy.enforced();
+        assert_eq!(enforced.len(), 5, "{:?}", enforced);
+        assert!(enforced.iter().any(|e| e.starts_with("security.patterns:")));
+        assert!(enforced.iter().any(|e| e.starts_with("profiles.org:")));
+        assert!(enforced.last().unwrap().contains("max_nesting_depth 6 → 4"));
+
+        // A stricter local choice is left alone
+        config.apply_profile(Some("strict")).unwrap();
+        assert_eq!(config.thresholds.max_nesting_depth, 3);
+        assert!(config.policy.tightened.is_empty());
+    }
+
+    #[test]
+    fn test_cli_ignores_respect_locked_ignore() {
+        let base: toml::Value = toml::from_str("ignore = [\"vendor/**\"]\n[policy]\nlocked = [\"ignore\"]\n").unwrap();
+        let (merged, overridden) = merge_with_policy(base, toml::Value::Table(Default::default())).unwrap();
+        let mut config: Config = merged.try_into().unwrap();
+        config.policy.overridden = overridden;
+
+        config.add_ignores(&["**".to_string()]);
+        assert_eq!(config.ignore, ["vendor/**"]);
+        assert_eq!(config.policy.enforced(), ["ignore: --ignore ** ignored (locked by policy)"]);
+
+        let mut open = Config::default();
+        open.add_ignores(&["**".to_string()]);
+        assert_eq!(open.ignore, ["**"]);
+        assert!(open.policy.enforced().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_shared_config_served_from_cache() {
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+        let source = ExtendsSource::parse(&format!("github:acme/configs@{}", sha)).unwrap();
+        let cache_dir = std::env::temp_dir().join("pr-analyzer-test-extends-cache");
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(source.cache_file(&cache_dir), "extends = \"github:other/repo\"\n[runtime]\nmax_total_findings = 50\n").unwrap();
+
+        let base = fetch_shared_config(&source, DEFAULT_GITHUB_API_URL, None, &cache_dir).await.unwrap();
+        assert!(base.get("extends").is_none());
+        assert_eq!(base["runtime"]["max_total_findings"].as_integer(), Some(50));
+
+        fs::remove_dir_all(&cache_dir).ok();
+    }
+
+    #[test]
+    fn test_parse_custom_test_paths_replace_defaults() {
+        let config: Config = toml::from_str("[style]\ntest_paths = [\"spec/**\"]\n").unwrap();
+        assert_eq!(config.style.test_paths, vec!["spec/**".to_string()]);
         assert!(config.style.layers.is_empty());
     }
 
+    #[test]
+    fn test_github_tokens_follow_primary_token_without_duplicates() {
+        let mut config: Config = toml::from_str("[github]\ntoken = \"a\"\ntokens = [\"b\", \"a\", \"c\"]\n").unwrap();
+        assert_eq!(config.github_tokens(), ["a", "b", "c"]);
+        assert!(!serde_json::to_string(&config).unwrap().contains("\"b\""));
+        config.github.tokens.clear();
+        assert_eq!(config.github_tokens()[0], "a");
+    }
+
+    #[test]
+    fn test_github_host_follows_the_api_url() {
+        let config: Config = toml::from_str("[github]\napi_url = \"https://ghe.example.com/api/v3/\"\n").unwrap();
+        assert_eq!(config.github_api_url(), "https://ghe.example.com/api/v3");
+        assert_eq!(config.github_host(), "ghe.example.com");
+        let config: Config = toml::from_str("[github]\napi_url = \"https://api.github.com\"\n").unwrap();
+        assert_eq!(config.github_host(), "github.com");
+        let config: Config = toml::from_str("[github]\napi_url = \"http://localhost:8080/api/v3\"\n").unwrap();
+        assert_eq!(config.github_host(), "localhost:8080");
+    }
+
+    #[test]
+    fn test_parse_runtime_config_keeps_unset_defaults() {
+        let config: Config = toml::from_str("[runtime]\nmax_file_size_kb = 256\n").unwrap();
+        assert_eq!(config.runtime.max_file_size_kb, 256);
+        assert_eq!(config.runtime.max_total_findings, 1000);
+    }
+
+    #[test]
+    fn test_parse_output_files() {
+        let config: Config = toml::from_str("[output]\nfiles = [\"report.md\", \"codequality=gl.json\"]\n").unwrap();
+        assert_eq!(config.output.files.len(), 2);
+        assert_eq!(config.output.files[1].format, Some(crate::report::Format::CodeQuality));
+        assert!(toml::from_str::<Config>("[output]\nfiles = [\"terminal=out.txt\"]\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_ci_gates() {
+        let config: Config = toml::from_str("[ci]\nsecurity = \"medium\"\nstyle = \"off\"\n").unwrap();
+        assert_eq!(config.ci.get("security"), Some(&Gate::Medium));
+        assert_eq!(config.ci.get("style").and_then(|g| g.threshold()), None);
+        assert!(toml::from_str::<Config>("[ci]\nsecurity = \"critical\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_gerrit_votes() {
+        let config: Config = toml::from_str("[gerrit]\nlabel = \"Verified\"\nvotes = { high = -1, low = 1 }\n").unwrap();
+        assert_eq!(config.gerrit.label, "Verified");
+        assert_eq!(config.gerrit.votes.get(&RiskLevel::Low), Some(&1));
+        assert_eq!(config.gerrit.votes.get(&RiskLevel::Medium), None);
+        assert_eq!(Config::default().gerrit.votes.get(&RiskLevel::High), Some(&-1));
+    }
+
     #[test]
     fn test_parse_config_toml() {
         let toml_str = r#"
 [security]
-patterns = ["TODO.*security"]
+patterns = ["TODO.*security", { pattern = "AKIA[0-9A-Z]{16}", severity = "high", tags = ["aws"] }]
+allowed_actions = ["docker/*"]
 
 [style]
 layers = ["api", "domain", "infra"]
 "#;
         let config: Config = toml::from_str(toml_str).unwrap();
-        assert_eq!(config.security.patterns.len(), 1);
+        assert_eq!(config.security.patterns, vec![CustomPattern::from("TODO.*security"), CustomPattern { pattern: "AKIA[0-9A-Z]{16}".to_string(), severity: RiskLevel::High, tags: vec!["aws".to_string()] }]);
+        assert_eq!(config.security.allowed_actions, vec!["docker/*"]);
         assert_eq!(config.style.layers.len(), 3);
     }
+
+    #[tokio::test]
+    async fn test_rules_file_appends_to_inline_rules() {
+        let dir = std::env::temp_dir().join(format!("pr-analyzer-test-rules-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".pr-analyzer.toml"), "rules_file = \"rules.toml\"\n[[rules]]\nid = \"inline\"\npattern = \"a\"\nmessage = \"m\"\n").unwrap();
+        fs::write(dir.join("rules.toml"), "[[rules]]\nid = \"shared\"\npattern = \"b\"\nfiles = [\"*.rs\"]\nmessage = \"m\"\nseverity = \"high\"\n").unwrap();
+
+        let config = Config::load_from(&dir.join(".pr-analyzer.toml")).await.unwrap();
+        let ids: Vec<(&str, RiskLevel)> = config.rules.iter().map(|r| (r.id.as_str(), r.severity)).collect();
+        assert_eq!(ids, [("inline", RiskLevel::Medium), ("shared", RiskLevel::High)]);
+
+        fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -0,0 +1,244 @@
+//! `doctor`: checks the environment a run depends on (config, GitHub token
+//! and rate limit, Gerrit credentials, git) and prints a fix for each problem.
+
+use pr_analyzer::config::Config;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Below this many remaining core requests a run may stall partway.
+const LOW_RATE_LIMIT: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// Nothing wrong, but worth checking (e.g. only some modes need it)
+    Info,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into(), fix: None }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Run every check. Never fails: problems become `Fail` entries.
+pub async fn run() -> Vec<Check> {
+    let mut checks = Vec::new();
+    let config = match Config::load().await {
+        Ok(config) => {
+            let detail = if std::path::Path::new(".pr-analyzer.toml").exists() {
+                "loaded .pr-analyzer.toml"
+            } else {
+                "no .pr-analyzer.toml in the current directory; using defaults"
+            };
+            checks.push(Check::new("config", Status::Ok, detail));
+            config
+        }
+        Err(e) => {
+            checks.push(Check::new("config", Status::Fail, e.to_string()).fix("fix .pr-analyzer.toml; see the Configuration section of the README"));
+            Config::default()
+        }
+    };
+    checks.extend(check_github(&config.github_api_url(), config.github_token().as_deref()).await);
+    checks.push(check_gerrit(&config));
+    checks.push(check_git());
+    checks
+}
+
+/// Probe `/rate_limit`, which does not count against the limit, and read
+/// token validity, scopes and remaining requests from its response.
+async fn check_github(api_url: &str, token: Option<&str>) -> Vec<Check> {
+    let mut checks = Vec::new();
+    if token.is_none() {
+        checks.push(
+            Check::new("github token", Status::Warn, "not set; GitHub pull requests cannot be fetched")
+                .fix("export GITHUB_TOKEN=<token> or set [github] token in .pr-analyzer.toml"),
+        );
+    }
+    let client = match reqwest::Client::builder().timeout(Duration::from_secs(10)).build() {
+        Ok(client) => client,
+        Err(e) => {
+            checks.push(Check::new("github api", Status::Fail, e.to_string()));
+            return checks;
+        }
+    };
+    let mut request = client.get(format!("{}/rate_limit", api_url)).header("User-Agent", "pr-analyzer");
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            checks.push(
+                Check::new("github api", Status::Fail, format!("cannot reach {}: {}", api_url, e))
+                    .fix("check the network connection, and HTTPS_PROXY if you are behind a proxy"),
+            );
+            return checks;
+        }
+    };
+    checks.push(Check::new("github api", Status::Ok, format!("{} reachable", api_url)));
+
+    let status = response.status();
+    let scopes = response.headers().get("x-oauth-scopes").and_then(|v| v.to_str().ok()).map(str::to_string);
+    if token.is_some() {
+        checks.push(token_check(status.as_u16(), scopes.as_deref()));
+    }
+    if status.is_success() {
+        #[derive(serde::Deserialize)]
+        struct Core {
+            limit: u64,
+            remaining: u64,
+            reset: u64,
+        }
+        #[derive(serde::Deserialize)]
+        struct Resources {
+            core: Core,
+        }
+        #[derive(serde::Deserialize)]
+        struct RateLimit {
+            resources: Resources,
+        }
+        match response.json::<RateLimit>().await {
+            Ok(body) => {
+                let core = body.resources.core;
+                checks.push(rate_limit_check(core.remaining, core.limit, core.reset, unix_now()));
+            }
+            Err(e) => checks.push(Check::new("rate limit", Status::Warn, format!("unreadable response: {}", e))),
+        }
+    }
+    checks
+}
+
+/// Token validity from the probe's status, and whether its scopes can read
+/// pull requests. Fine-grained tokens report no `X-OAuth-Scopes` header.
+fn token_check(status: u16, scopes: Option<&str>) -> Check {
+    if status == 401 {
+        return Check::new("github token", Status::Fail, "rejected by GitHub (401): expired, revoked, or mistyped")
+            .fix("create a new token at https://github.com/settings/tokens and update GITHUB_TOKEN");
+    }
+    if !(200..300).contains(&status) {
+        return Check::new("github token", Status::Warn, format!("could not be verified (HTTP {})", status));
+    }
+    let Some(scopes) = scopes else {
+        return Check::new("github token", Status::Info, "valid (fine-grained; scopes are not reported)")
+            .fix("make sure it grants \"Pull requests: Read\" and \"Contents: Read\" on the repositories you analyze");
+    };
+    let scopes: Vec<&str> = scopes.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+    if scopes.contains(&"repo") {
+        Check::new("github token", Status::Ok, format!("valid, scopes: {}", scopes.join(", ")))
+    } else if scopes.contains(&"public_repo") {
+        Check::new("github token", Status::Warn, "valid, but limited to public repositories (public_repo)")
+            .fix("add the `repo` scope to analyze private repositories")
+    } else {
+        let listed = if scopes.is_empty() { "none".to_string() } else { scopes.join(", ") };
+        Check::new("github token", Status::Warn, format!("valid, scopes: {}; private repositories are not readable", listed))
+            .fix("add the `repo` scope (or `public_repo` for public repositories only)")
+    }
+}
+
+fn rate_limit_check(remaining: u64, limit: u64, reset: u64, now: u64) -> Check {
+    let minutes = reset.saturating_sub(now).div_ceil(60);
+    let detail = format!("{}/{} requests remaining, resets in {} min", remaining, limit, minutes);
+    if remaining == 0 {
+        Check::new("rate limit", Status::Fail, detail).fix(format!("wait {} min, or use a different token", minutes))
+    } else if remaining < LOW_RATE_LIMIT {
+        let fix = if limit <= 60 { "set GITHUB_TOKEN; unauthenticated requests are limited to 60 per hour" } else { "large pull requests may run out before the reset" };
+        Check::new("rate limit", Status::Warn, detail).fix(fix)
+    } else {
+        Check::new("rate limit", Status::Ok, detail)
+    }
+}
+
+fn check_gerrit(config: &Config) -> Check {
+    match config.gerrit_credentials() {
+        Some((username, _)) => Check::new("gerrit credentials", Status::Ok, format!("set for {}", username)),
+        None => Check::new("gerrit credentials", Status::Info, "not set; needed for --review and private Gerrit changes")
+            .fix("set GERRIT_USERNAME and GERRIT_HTTP_PASSWORD (Settings > HTTP Credentials)"),
+    }
+}
+
+/// `--repo` and `--clone` shell out to git.
+fn check_git() -> Check {
+    match std::process::Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            Check::new("git", Status::Ok, String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        Ok(output) => Check::new("git", Status::Warn, format!("`git --version` exited with {}", output.status))
+            .fix("reinstall git; --repo and --clone need it"),
+        Err(e) => Check::new("git", Status::Warn, format!("not runnable: {}", e))
+            .fix("install git and put it on PATH; --repo and --clone need it"),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+pub fn render(checks: &[Check]) -> String {
+    let mut out = String::new();
+    for check in checks {
+        let tag = match check.status {
+            Status::Ok => "ok",
+            Status::Info => "--",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        out.push_str(&format!("[{:<4}] {:<20} {}\n", tag, check.name, check.detail));
+        if let (Some(fix), false) = (&check.fix, check.status == Status::Ok) {
+            out.push_str(&format!("       {:<20} fix: {}\n", "", fix));
+        }
+    }
+    out
+}
+
+pub fn has_failures(checks: &[Check]) -> bool {
+    checks.iter().any(|c| c.status == Status::Fail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_check_classifies_status_and_scopes() {
+        assert_eq!(token_check(401, None).status, Status::Fail);
+        assert_eq!(token_check(200, Some("repo, read:org")).status, Status::Ok);
+        assert_eq!(token_check(200, Some("public_repo")).status, Status::Warn);
+        assert_eq!(token_check(200, Some("")).status, Status::Warn);
+        // Fine-grained tokens send no scope header
+        let fine_grained = token_check(200, None);
+        assert_eq!(fine_grained.status, Status::Info);
+        assert!(fine_grained.fix.is_some());
+    }
+
+    #[test]
+    fn test_rate_limit_thresholds_and_render() {
+        assert_eq!(rate_limit_check(4000, 5000, 1_000, 0).status, Status::Ok);
+        assert_eq!(rate_limit_check(10, 60, 1_000, 0).status, Status::Warn);
+        let exhausted = rate_limit_check(0, 5000, 1_000, 100);
+        assert_eq!(exhausted.status, Status::Fail);
+        assert!(exhausted.detail.contains("resets in 15 min"));
+
+        let checks = vec![Check::new("git", Status::Ok, "git version 2.43.0").fix("unused"), exhausted];
+        let rendered = render(&checks);
+        assert!(rendered.starts_with("[ok  ] git "));
+        assert!(!rendered.contains("unused"));
+        assert!(rendered.contains("[FAIL] rate limit") && rendered.contains("fix: wait 15 min"));
+        assert!(has_failures(&checks));
+    }
+}
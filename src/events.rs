@@ -0,0 +1,38 @@
+//! Progress of a fetch and an analysis, for front ends that embed the
+//! library (a TUI, a server, an editor plugin) and want to show it while
+//! it happens rather than await the final `AnalysisRun`.
+//!
+//! Pass a callback to `ChangeSource::fetch_with_events` and
+//! `run_all_with_events`. It is called on the task that drives the future,
+//! in the order things happen, so it should only record or forward the
+//! event (e.g. into a channel) and return.
+
+use std::time::Duration;
+
+use crate::report::types::Finding;
+
+/// One step of a fetch or an analysis.
+#[derive(Debug, Clone)]
+pub enum AnalysisEvent {
+    /// Fetching the change began; `repo` is None for local input
+    FetchStarted { repo: Option<String> },
+    /// The change was fetched
+    FetchFinished { files: usize, additions: usize, deletions: usize },
+    /// The analyzers that will run, in report order
+    AnalysisStarted { analyzers: Vec<String> },
+    /// The shared line pass over the diff is done
+    LinePassFinished { duration: Duration },
+    /// An analyzer began running
+    AnalyzerStarted { name: String },
+    /// An analyzer returned. Its findings carry their rule and category but
+    /// not yet the run-wide steps: `--only-rule`, volume escalation,
+    /// `min_severity` and the findings budget may still drop or re-rate
+    /// some of them
+    AnalyzerFinished { name: String, duration: Duration, findings: Vec<Finding> },
+}
+
+/// Receiver of `AnalysisEvent`s.
+pub type Events<'a> = &'a (dyn Fn(AnalysisEvent) + Send + Sync);
+
+/// Events that go nowhere, for callers that do not follow progress.
+pub fn ignore(_event: AnalysisEvent) {}
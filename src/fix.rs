@@ -0,0 +1,234 @@
+//! `fix`: apply the replacements findings suggest to the working tree. It
+//! analyzes the uncommitted changes like `lsp` does, shows each suggestion
+//! as the line before and after, and applies it; `--interactive` asks
+//! first, `--dry-run` only shows them.
+//!
+//! A suggestion replaces one added line of the new file. It is applied only
+//! if that line still reads as in the analyzed diff, so edits made since
+//! (or by an earlier fix) are never overwritten.
+
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use pr_analyzer::analysis;
+use pr_analyzer::config::Config;
+use pr_analyzer::pr::diff::parse_diff;
+use pr_analyzer::pr::types::DiffFile;
+use pr_analyzer::pr::PullRequest;
+use pr_analyzer::repo::Checkout;
+use pr_analyzer::report::{AnalysisResult, Catalog};
+
+use crate::lsp::uncommitted_change;
+
+/// What to do with the suggestions found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Apply every suggestion
+    Apply,
+    /// Ask for each suggestion
+    Interactive,
+    /// Show the suggestions, change nothing
+    DryRun,
+}
+
+/// A suggested replacement of one line of the working tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub path: String,
+    /// Line in the file as saved, from 1
+    pub line: usize,
+    /// The line as the analyzed diff added it
+    pub old: String,
+    pub new: String,
+    pub message: String,
+}
+
+/// How many suggestions were applied, declined, and skipped because the
+/// line changed since the analysis.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub applied: usize,
+    pub declined: usize,
+    pub stale: usize,
+}
+
+/// Analyze the uncommitted changes of the current repository and handle
+/// their suggestions according to `mode`.
+pub async fn run(config: Config, catalog: Catalog, mode: Mode) -> Result<Summary, Box<dyn std::error::Error>> {
+    let checkout = Checkout::open(&std::env::current_dir()?)?;
+    let root = checkout.top_level()?;
+    let mut pr = uncommitted_change(parse_diff(&checkout.uncommitted_diff()?)?)?;
+    analysis::ignore_files(&mut pr, &config.ignore);
+    let run = analysis::run_all(&pr, &config, std::future::pending()).await?;
+    let fixes = fixes(&pr, &run.results, &catalog);
+    if fixes.is_empty() {
+        println!("No suggested fixes for the uncommitted changes.");
+        return Ok(Summary::default());
+    }
+    let summary = apply(&fixes, &root, mode, &mut io::stdin().lock(), &mut io::stdout())?;
+    match mode {
+        Mode::DryRun => println!("{} suggested fix(es); nothing was changed (--dry-run).", fixes.len()),
+        _ => println!("Applied {}, declined {}, skipped {} changed since the analysis.", summary.applied, summary.declined, summary.stale),
+    }
+    Ok(summary)
+}
+
+/// The suggestions of `results` on added lines of `pr`, by file and line.
+fn fixes(pr: &PullRequest, results: &[AnalysisResult], catalog: &Catalog) -> Vec<Fix> {
+    let mut fixes = Vec::new();
+    for finding in results.iter().flat_map(|r| &r.findings) {
+        let (Some(new), Some(path), Some(number)) = (&finding.suggestion, &finding.file, finding.line) else {
+            continue;
+        };
+        let Some(file) = pr.files().iter().find(|f| f.path() == path) else {
+            continue;
+        };
+        let (Some(old), Some(line)) = (added_line(file, number), file.file_line(number)) else {
+            continue;
+        };
+        if old != new {
+            fixes.push(Fix { path: path.clone(), line, old: old.to_string(), new: new.clone(), message: catalog.message(finding).into_owned() });
+        }
+    }
+    fixes.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    fixes
+}
+
+/// The text of the added line a finding's line number points at.
+fn added_line(file: &DiffFile, number: usize) -> Option<&str> {
+    file.hunks().iter().find_map(|h| {
+        let index = number.checked_sub(h.new_start).filter(|i| *i < h.lines.len())?;
+        h.lines[index].strip_prefix('+').map(|text| text.trim_end_matches('\r'))
+    })
+}
+
+/// Show each fix and, unless `mode` is a dry run, apply it to the file under
+/// `root`; in interactive mode `input` answers y(es), n(o), a(ll) or q(uit).
+fn apply(fixes: &[Fix], root: &Path, mode: Mode, input: &mut impl BufRead, out: &mut impl Write) -> io::Result<Summary> {
+    let mut summary = Summary::default();
+    let mut ask = mode == Mode::Interactive;
+    for (i, fix) in fixes.iter().enumerate() {
+        writeln!(out, "{}:{}: {}", fix.path, fix.line, fix.message)?;
+        writeln!(out, "- {}", fix.old)?;
+        writeln!(out, "+ {}", fix.new)?;
+        if mode == Mode::DryRun {
+            continue;
+        }
+        if ask {
+            match prompt(input, out)? {
+                Answer::Yes => {}
+                Answer::No => {
+                    summary.declined += 1;
+                    continue;
+                }
+                Answer::All => ask = false,
+                Answer::Quit => {
+                    summary.declined += fixes.len() - i;
+                    break;
+                }
+            }
+        }
+        if replace_line(&root.join(&fix.path), fix)? {
+            summary.applied += 1;
+        } else {
+            writeln!(out, "skipped: the line changed since the analysis")?;
+            summary.stale += 1;
+        }
+    }
+    Ok(summary)
+}
+
+enum Answer {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+/// Ask until the answer is one of y/n/a/q; end of input quits.
+fn prompt(input: &mut impl BufRead, out: &mut impl Write) -> io::Result<Answer> {
+    loop {
+        write!(out, "Apply this fix? [y]es, [n]o, [a]ll, [q]uit: ")?;
+        out.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Ok(Answer::Quit);
+        }
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => return Ok(Answer::Yes),
+            "n" | "no" => return Ok(Answer::No),
+            "a" | "all" => return Ok(Answer::All),
+            "q" | "quit" => return Ok(Answer::Quit),
+            _ => {}
+        }
+    }
+}
+
+/// Replace line `fix.line` of the file at `path` if it still reads
+/// `fix.old`, keeping its line ending. Returns whether it did.
+fn replace_line(path: &Path, fix: &Fix) -> io::Result<bool> {
+    let content = std::fs::read_to_string(path)?;
+    let mut lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let Some(line) = fix.line.checked_sub(1).and_then(|i| lines.get(i)) else {
+        return Ok(false);
+    };
+    let text = line.trim_end_matches(['\r', '\n']);
+    if text != fix.old {
+        return Ok(false);
+    }
+    let replacement = format!("{}{}", fix.new, &line[text.len()..]);
+    lines[fix.line - 1] = &replacement;
+    std::fs::write(path, lines.concat())?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pr_analyzer::report::{Finding, RiskLevel};
+
+    fn fix(line: usize, old: &str, new: &str) -> Fix {
+        Fix { path: "util.rs".to_string(), line, old: old.to_string(), new: new.to_string(), message: "Redundant clone".to_string() }
+    }
+
+    #[test]
+    fn test_suggestions_map_to_file_lines() {
+        let file = DiffFile::builder("src/util.rs").hunk(3, 3, [" fn name() {", "-    old()", "+    s.to_string().clone()"]).build().unwrap();
+        let pr = uncommitted_change(vec![file]).unwrap();
+        let results = vec![AnalysisResult {
+            analyzer_name: "Style & Architecture Assessment".to_string(),
+            risk_level: RiskLevel::Low,
+            findings: vec![
+                Finding::new("style.redundant_clone", RiskLevel::Low).in_file("src/util.rs").at_line(5).suggest("    s.to_string()"),
+                Finding::new("style.unwrap", RiskLevel::Medium).in_file("src/util.rs").at_line(5),
+            ],
+        }];
+        let fixes = fixes(&pr, &results, Catalog::english());
+        assert_eq!(fixes.len(), 1);
+        // Index 2 of the hunk is the second line of the new file's hunk: line 4.
+        assert_eq!((fixes[0].line, fixes[0].old.as_str(), fixes[0].new.as_str()), (4, "    s.to_string().clone()", "    s.to_string()"));
+    }
+
+    #[test]
+    fn test_interactive_fixes_apply_only_accepted_and_unchanged_lines() {
+        let root = std::env::temp_dir().join(format!("pr-analyzer-fix-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("util.rs");
+        std::fs::write(&path, "a.to_string().clone()\r\nb.to_owned().clone()\nc.to_string().clone()\n").unwrap();
+        let fixes = [
+            fix(1, "a.to_string().clone()", "a.to_string()"),
+            fix(2, "b.to_owned().clone()", "b.to_owned()"),
+            fix(3, "edited since", "c.to_string()"),
+        ];
+
+        let mut out = Vec::new();
+        let dry = apply(&fixes, &root, Mode::DryRun, &mut io::empty(), &mut out).unwrap();
+        assert_eq!(dry, Summary::default());
+        assert!(String::from_utf8(out).unwrap().contains("+ a.to_string()\n"));
+
+        let summary = apply(&fixes, &root, Mode::Interactive, &mut "maybe\ny\nn\ny\n".as_bytes(), &mut io::sink()).unwrap();
+        assert_eq!(summary, Summary { applied: 1, declined: 1, stale: 1 });
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a.to_string()\r\nb.to_owned().clone()\nc.to_string().clone()\n");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+//! Run history: one JSON line per analyzed PR, appended with `--history`.
+//!
+//! The file is append-only and human-readable, so it can be committed,
+//! cached between CI runs, or concatenated from several jobs. It is also
+//! each repository's baseline: a PR far larger than the repository's usual
+//! PR is flagged, whatever the absolute thresholds say.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use thiserror::Error;
+use tracing::debug;
+
+use crate::analysis::complexity;
+use crate::report::{Finding, Report, RiskLevel};
+
+/// Earlier PRs of a repository needed before its baseline is trusted.
+const MIN_BASELINE_PRS: usize = 10;
+
+/// How many times the repository's median a PR must reach to be flagged.
+const OUTLIER_FACTOR: f64 = 5.0;
+
+/// A measure of a PR compared with the baseline: message id, parameter
+/// name, and how to read it from a report.
+type Metric = (&'static str, &'static str, fn(&Report) -> usize);
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("Failed to access history file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to serialize history record: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    #[error("Invalid history record on line {line}: {source}")]
+    Parse { line: usize, source: serde_json::Error },
+}
+
+/// One recorded run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// `owner/repo` of the analyzed PR (None for mock runs)
+    pub repo: Option<String>,
+    pub report: Report,
+}
+
+/// Borrowing twin of `Entry`, so appending doesn't clone the report.
+#[derive(Serialize)]
+struct EntryRef<'a> {
+    repo: Option<&'a str>,
+    report: &'a Report,
+}
+
+/// Append a run to the history file, creating it if needed.
+pub fn append(path: &Path, repo: Option<&str>, report: &Report) -> Result<(), HistoryError> {
+    let mut line = serde_json::to_string(&EntryRef { repo, report })?;
+    line.push('\n');
+    // One write per record keeps concurrent appends from interleaving.
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())?;
+    debug!(path = %path.display(), pr = report.pr_number, "appended run to history");
+    Ok(())
+}
+
+/// Read every run in the history file, oldest first. Blank lines are skipped.
+pub fn load(path: &Path) -> Result<Vec<Entry>, HistoryError> {
+    let text = std::fs::read_to_string(path)?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| serde_json::from_str(l).map_err(|source| HistoryError::Parse { line: i + 1, source }))
+        .collect()
+}
+
+/// The most recent recorded run of the same PR, or None when there is none
+/// (including when the history file does not exist yet).
+pub fn latest_run(path: &Path, repo: Option<&str>, pr_number: u64) -> Result<Option<Report>, HistoryError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let latest = load(path)?.into_iter().rev().find(|e| e.repo.as_deref() == repo && e.report.pr_number == pr_number);
+    Ok(latest.map(|e| e.report))
+}
+
+/// Flag the ways `report` is an outlier among the repository's earlier
+/// PRs in the history file: lines changed, files changed and findings,
+/// each against the median of the latest run of every other PR. The notes
+/// go to the complexity section at LOW, so they never change the risk.
+/// Nothing is flagged for local runs, or before the repository has
+/// `MIN_BASELINE_PRS` PRs on record.
+pub fn flag_outliers(path: &Path, repo: Option<&str>, report: &mut Report) -> Result<(), HistoryError> {
+    if repo.is_none() || !path.exists() {
+        return Ok(());
+    }
+    let mut latest = BTreeMap::new();
+    for entry in load(path)?.into_iter().filter(|e| e.repo.as_deref() == repo && e.report.pr_number != report.pr_number) {
+        latest.insert(entry.report.pr_number, entry.report);
+    }
+    let outliers = outliers(&latest.into_values().collect::<Vec<_>>(), report);
+    debug!(outliers = outliers.len(), "compared PR with repository baseline");
+    if let Some(complexity) = report.results.iter_mut().find(|r| r.analyzer_name == complexity::NAME) {
+        complexity.findings.extend(outliers);
+    }
+    Ok(())
+}
+
+/// Outlier notes for `report` against the `baseline` PRs.
+fn outliers(baseline: &[Report], report: &Report) -> Vec<Finding> {
+    if baseline.len() < MIN_BASELINE_PRS {
+        return Vec::new();
+    }
+    let metrics: [Metric; 3] = [
+        ("analysis.outlier_lines", "lines", |r| r.additions + r.deletions),
+        ("analysis.outlier_files", "files", |r| r.files_changed),
+        ("analysis.outlier_findings", "findings", |r| r.results.iter().map(|a| a.findings.len()).sum()),
+    ];
+    metrics
+        .into_iter()
+        .filter_map(|(id, param, metric)| {
+            let median = median(baseline.iter().map(metric).collect());
+            let value = metric(report);
+            let ratio = value as f64 / median.max(1.0);
+            (ratio >= OUTLIER_FACTOR).then(|| {
+                Finding::new(id, RiskLevel::Low)
+                    .param(param, value)
+                    .param("median", median)
+                    .param("ratio", format!("{:.1}", ratio))
+                    .param("prs", baseline.len())
+            })
+        })
+        .collect()
+}
+
+fn median(mut values: Vec<usize>) -> f64 {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) as f64 / 2.0
+    } else {
+        values[mid] as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pr::PullRequest;
+
+    fn report(number: u64) -> Report {
+        let pr = PullRequest::builder("Add cache").number(number).author("alice").totals(1, 10, 2).head_sha("abc123").build().unwrap();
+        crate::report::build(vec![], &pr)
+    }
+
+    #[test]
+    fn test_append_and_load_round_trip() {
+        let path = std::env::temp_dir().join("pr-analyzer-test-history.jsonl");
+        std::fs::remove_file(&path).ok();
+        append(&path, Some("org/repo"), &report(1)).unwrap();
+        append(&path, None, &report(2)).unwrap();
+
+        let entries = load(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].repo.as_deref(), Some("org/repo"));
+        assert_eq!(entries[1].report.pr_number, 2);
+        assert_eq!(entries[0].report.provenance.head_sha.as_deref(), Some("abc123"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_latest_run_matches_repo_and_pr() {
+        let path = std::env::temp_dir().join("pr-analyzer-test-history-latest.jsonl");
+        std::fs::remove_file(&path).ok();
+        assert!(latest_run(&path, Some("org/repo"), 1).unwrap().is_none());
+
+        let mut older = report(1);
+        older.pr_title = "older".to_string();
+        append(&path, Some("org/repo"), &older).unwrap();
+        append(&path, Some("org/repo"), &report(1)).unwrap();
+        append(&path, Some("org/other"), &report(1)).unwrap();
+
+        let latest = latest_run(&path, Some("org/repo"), 1).unwrap().unwrap();
+        assert_eq!(latest.pr_title, "Add cache");
+        assert!(latest_run(&path, Some("org/repo"), 2).unwrap().is_none());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_outliers_against_repo_median() {
+        let baseline: Vec<Report> = (1..=10).map(report).collect();
+        assert!(outliers(&baseline[..9], &report(11)).is_empty());
+        assert!(outliers(&baseline, &report(11)).is_empty());
+
+        let mut large = report(11);
+        large.additions = 70;
+        let flagged = outliers(&baseline, &large);
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].message, "6.0× larger than this repository's median PR: 72 lines changed vs a median of 12 over 10 PRs");
+    }
+
+    #[test]
+    fn test_flag_outliers_uses_latest_run_per_pr() {
+        let path = std::env::temp_dir().join("pr-analyzer-test-history-outliers.jsonl");
+        std::fs::remove_file(&path).ok();
+        for number in 1..=10 {
+            append(&path, Some("org/repo"), &report(number)).unwrap();
+        }
+        let mut large = report(11);
+        large.additions = 100;
+        large.results.push(crate::report::AnalysisResult { analyzer_name: complexity::NAME.to_string(), risk_level: RiskLevel::Low, findings: vec![] });
+        // Earlier runs of the same PR, and other repositories, are not baseline.
+        append(&path, Some("org/repo"), &large).unwrap();
+        append(&path, Some("org/other"), &large).unwrap();
+
+        flag_outliers(&path, Some("org/repo"), &mut large).unwrap();
+        assert_eq!(large.results[0].findings[0].message_id.as_deref(), Some("analysis.outlier_lines"));
+        let mut local = report(11);
+        local.additions = 100;
+        flag_outliers(&path, None, &mut local).unwrap();
+        assert!(local.results.is_empty());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_load_reports_bad_line_number() {
+        let path = std::env::temp_dir().join("pr-analyzer-test-history-bad.jsonl");
+        std::fs::write(&path, "\nnot json\n").unwrap();
+        let err = load(&path).unwrap_err();
+        assert!(matches!(err, HistoryError::Parse { line: 2, .. }));
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,14 @@
+//! Library half of pr-analyzer: everything except the CLI, so benchmarks
+//! (and later other front ends) can drive parsing and analysis directly.
+
+pub mod analysis;
+pub mod audit;
+pub mod baseline;
+pub mod config;
+pub mod events;
+pub mod history;
+pub mod org;
+pub mod paths;
+pub mod pr;
+pub mod repo;
+pub mod report;
@@ -0,0 +1,296 @@
+//! `lsp`: a language server for editors. It analyzes the uncommitted
+//! changes of the working tree it is started in (`git diff HEAD`) and
+//! publishes the findings as diagnostics on the changed files only.
+//!
+//! The diff is re-read when a document is opened or saved and every
+//! `POLL_INTERVAL`; the analyzers run again only when it changed. Files
+//! that no longer have findings get an empty diagnostics list. Checks that
+//! need a checkout (clippy, blame) do not run, as with `--quick`.
+
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use pr_analyzer::analysis;
+use pr_analyzer::config::Config;
+use pr_analyzer::pr::diff::parse_diff;
+use pr_analyzer::pr::types::{BuildError, DiffFile};
+use pr_analyzer::pr::PullRequest;
+use pr_analyzer::repo::Checkout;
+use pr_analyzer::report::{AnalysisResult, Catalog, RiskLevel};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// How often the working tree is checked for changes between editor events.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// JSON-RPC error code for requests the server does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Serve on stdin/stdout until the client sends `exit` or closes stdin.
+pub async fn serve(config: Config, catalog: Catalog) -> Result<(), Box<dyn std::error::Error>> {
+    let checkout = Checkout::open(&std::env::current_dir()?)?;
+    let root = checkout.top_level()?;
+    info!(root = %root.display(), "language server started");
+
+    // Reading stdin blocks, so it gets its own thread.
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        while let Ok(Some(message)) = read_message(&mut stdin) {
+            if tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut server = Server { config, catalog, checkout, root, initialized: false, last_diff: None, published: BTreeSet::new() };
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            message = rx.recv() => {
+                let Some(message) = message else {
+                    debug!("stdin closed");
+                    return Ok(());
+                };
+                if !server.handle(&message).await? {
+                    return Ok(());
+                }
+            }
+            _ = poll.tick() => server.refresh().await?,
+        }
+    }
+}
+
+struct Server {
+    config: Config,
+    catalog: Catalog,
+    checkout: Checkout,
+    root: PathBuf,
+    /// Set by the client's `initialized`; nothing is published before it
+    initialized: bool,
+    /// Diff the current diagnostics were computed from
+    last_diff: Option<String>,
+    /// Paths that currently have diagnostics on the client
+    published: BTreeSet<String>,
+}
+
+impl Server {
+    /// Handle one message; false once the client asked the server to exit.
+    async fn handle(&mut self, message: &Value) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let id = message.get("id");
+        debug!(method, "message from client");
+        match (method, id) {
+            ("initialize", Some(id)) => {
+                let capabilities = json!({ "textDocumentSync": { "openClose": true, "change": 0, "save": true } });
+                let server_info = json!({ "name": "pr-analyzer", "version": env!("CARGO_PKG_VERSION") });
+                send(&json!({ "jsonrpc": "2.0", "id": id, "result": { "capabilities": capabilities, "serverInfo": server_info } }))?;
+            }
+            ("shutdown", Some(id)) => send(&json!({ "jsonrpc": "2.0", "id": id, "result": null }))?,
+            ("exit", None) => return Ok(false),
+            ("initialized", None) => {
+                self.initialized = true;
+                self.refresh().await?;
+            }
+            ("textDocument/didOpen" | "textDocument/didSave", None) => self.refresh().await?,
+            // Responses from the client and notifications we do not use.
+            (_, None) => {}
+            (_, Some(id)) => {
+                let error = json!({ "code": METHOD_NOT_FOUND, "message": format!("unsupported method '{}'", method) });
+                send(&json!({ "jsonrpc": "2.0", "id": id, "error": error }))?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Re-read the diff and, if it changed, analyze it and publish.
+    /// Analysis failures are logged; only a broken stdout ends the server.
+    async fn refresh(&mut self) -> io::Result<()> {
+        if !self.initialized {
+            return Ok(());
+        }
+        let diff = match self.checkout.uncommitted_diff() {
+            Ok(diff) => diff,
+            Err(e) => {
+                warn!(error = %e, "could not read the working tree diff");
+                return Ok(());
+            }
+        };
+        if self.last_diff.as_ref() == Some(&diff) {
+            return Ok(());
+        }
+        let files = match parse_diff(&diff) {
+            Ok(files) => files,
+            Err(e) => {
+                warn!(error = %e, "could not parse the working tree diff");
+                return Ok(());
+            }
+        };
+        let mut pr = match uncommitted_change(files) {
+            Ok(pr) => pr,
+            Err(e) => {
+                warn!(error = %e, "could not read the working tree diff as a change");
+                return Ok(());
+            }
+        };
+        analysis::ignore_files(&mut pr, &self.config.ignore);
+        let run = match analysis::run_all(&pr, &self.config, std::future::pending()).await {
+            Ok(run) => run,
+            Err(e) => {
+                warn!(error = %e, "analysis failed");
+                return Ok(());
+            }
+        };
+        let by_file = diagnostics(&run.results, pr.files(), &self.catalog);
+        let stale: Vec<String> = self.published.iter().filter(|path| !by_file.iter().any(|(p, _)| p == *path)).cloned().collect();
+        for path in stale {
+            send(&publish(&self.root, &path, Vec::new()))?;
+        }
+        for (path, diagnostics) in &by_file {
+            send(&publish(&self.root, path, diagnostics.clone()))?;
+        }
+        info!(files = by_file.len(), "diagnostics published");
+        self.published = by_file.into_iter().map(|(path, _)| path).collect();
+        self.last_diff = Some(diff);
+        Ok(())
+    }
+}
+
+/// The working tree changes as a change to analyze.
+pub(crate) fn uncommitted_change(files: Vec<DiffFile>) -> Result<PullRequest, BuildError> {
+    PullRequest::builder("uncommitted changes").files(files).build()
+}
+
+/// LSP diagnostics per changed file, in diff order, for the findings that
+/// name a file of `diff`. Findings about the change as a whole have no
+/// place in an editor and are left out.
+fn diagnostics(results: &[AnalysisResult], diff: &[DiffFile], catalog: &Catalog) -> Vec<(String, Vec<Value>)> {
+    let mut by_file = Vec::new();
+    for file in diff.iter().filter(|f| !f.is_deleted()) {
+        let mut diagnostics = Vec::new();
+        for result in results {
+            for finding in result.findings.iter().filter(|f| f.file.as_deref() == Some(file.path())) {
+                let line = finding.line.and_then(|n| file.file_line(n)).map_or(0, |l| l - 1);
+                let severity = match finding.severity {
+                    RiskLevel::High => 1,
+                    RiskLevel::Medium => 2,
+                    RiskLevel::Low => 3,
+                };
+                diagnostics.push(json!({
+                    "range": { "start": { "line": line, "character": 0 }, "end": { "line": line + 1, "character": 0 } },
+                    "severity": severity,
+                    "code": finding.message_id,
+                    "source": "pr-analyzer",
+                    "message": format!("{} ({})", catalog.message(finding), result.analyzer_name),
+                }));
+            }
+        }
+        if !diagnostics.is_empty() {
+            by_file.push((file.path().to_string(), diagnostics));
+        }
+    }
+    by_file
+}
+
+fn publish(root: &Path, path: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": file_uri(&root.join(path)), "diagnostics": diagnostics },
+    })
+}
+
+/// `file://` URI of an absolute path, percent-encoding everything but
+/// unreserved characters and `/`.
+fn file_uri(path: &Path) -> String {
+    let mut uri = "file://".to_string();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Read one `Content-Length` framed message; None at end of input.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "message without Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn frame(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut out = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    out.extend_from_slice(body.as_bytes());
+    out
+}
+
+fn send(message: &Value) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&frame(message))?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pr_analyzer::report::Finding;
+
+    #[test]
+    fn test_messages_round_trip() {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "rootUri": "file:///tmp/é" } });
+        let mut framed = frame(&message);
+        framed.extend(frame(&json!({ "jsonrpc": "2.0", "method": "exit" })));
+        let mut reader = io::Cursor::new(framed);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap().unwrap()["method"], "exit");
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_findings_become_diagnostics_on_file_lines() {
+        let file = DiffFile::builder("src/db.rs").hunk(10, 10, [" fn query() {", "-    old()", "+    run(q)"]).build().unwrap();
+        let results = vec![AnalysisResult {
+            analyzer_name: "Security Risk Assessment".to_string(),
+            risk_level: RiskLevel::High,
+            findings: vec![
+                Finding::new("security.sql_construction", RiskLevel::High).in_file("src/db.rs").at_line(12),
+                Finding::new("security.auth_surfaces", RiskLevel::Medium).param("count", 1).param("files", "src/auth.rs"),
+            ],
+        }];
+        let by_file = diagnostics(&results, &[file], Catalog::english());
+        assert_eq!(by_file.len(), 1);
+        let (path, diagnostics) = &by_file[0];
+        assert_eq!(path, "src/db.rs");
+        assert_eq!(diagnostics.len(), 1);
+        // Index 2 of the hunk is the second line of the new file's hunk: line 11, 0-based 10.
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 10);
+        assert_eq!(diagnostics[0]["severity"], 1);
+        assert_eq!(diagnostics[0]["code"], "security.sql_construction");
+
+        let note = publish(Path::new("/work/my repo"), path, Vec::new());
+        assert_eq!(note["params"]["uri"], "file:///work/my%20repo/src/db.rs");
+    }
+}
@@ -1,75 +1,632 @@
-mod analysis;
-mod config;
-mod pr;
-mod report;
+mod doctor;
+mod fix;
+mod lsp;
+mod profile;
+mod rule_tests;
+mod telemetry;
 
-use clap::Parser;
-use std::path::PathBuf;
-use tracing::{debug, info, info_span};
-use tracing_subscriber::EnvFilter;
+use pr_analyzer::{analysis, baseline, config, history, org, pr, repo, report};
 
-/// PR Analyzer — CLI tool that takes a GitHub Pull Request URL and returns
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::field::Empty;
+use tracing::{debug, info, info_span, warn, Instrument, Span};
+
+/// PR Analyzer — CLI tool that takes a GitHub Pull Request or Gerrit change URL and returns
 /// a structured risk assessment across security, complexity, and style dimensions.
 #[derive(Parser, Debug)]
-#[command(name = "pr-analyzer", version, about)]
+#[command(name = "pr-analyzer", version, about, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// GitHub Pull Request URL (e.g., https://github.com/org/repo/pull/42)
+    /// or Gerrit change URL (e.g., https://review.example.com/c/project/+/12345)
+    /// or path to an mbox / directory of `git format-patch` files
     ///
-    /// Not required when --mock is used.
+    /// Not required with --mock, --local or --diff-file.
     pr_url: Option<String>,
 
-    /// Optional output file path for markdown report
+    /// Analyze the repository in the current directory instead of a PR: its uncommitted changes
+    /// (staged and unstaged), or with a value the commits in that range (e.g. main..HEAD; a
+    /// single revision means REV..HEAD). Needs no token or remote; add --repo . for whole-file checks
+    #[arg(long, value_name = "RANGE", num_args = 0..=1, default_missing_value = "", conflicts_with_all = ["pr_url", "mock", "clone", "review", "diff_file"])]
+    local: Option<String>,
+
+    /// With --local, only the staged changes
+    #[arg(long, requires = "local")]
+    staged: bool,
+
+    /// Analyze a unified diff from this file, or from stdin for `-` (e.g. `git diff | pr-analyzer --diff-file -`)
+    #[arg(long, conflicts_with_all = ["pr_url", "mock", "clone", "review"])]
+    diff_file: Option<PathBuf>,
+
+    /// Leave files matching this glob out of the analysis, repeatable (e.g. --ignore 'vendor/**'
+    /// --ignore '*.min.js'); adds to `ignore` in the config unless a policy locks it. A glob
+    /// without `/` matches the file name in any directory
+    #[arg(long, value_name = "GLOB")]
+    ignore: Vec<String>,
+
+    /// Report file as [FORMAT=]PATH, repeatable (e.g. -o report.md -o codequality=gl.json). The format
+    /// is the prefix, else --format, else the extension (md, json, html, xml, rdjson, diff), else
+    /// markdown. Replaces [output] files; the terminal summary is still printed
     #[arg(short, long)]
-    output: Option<PathBuf>,
+    output: Vec<report::Output>,
+
+    /// Report format: terminal, markdown, json (includes provenance metadata), html (risk heatmap),
+    /// annotated-diff (the diff with findings under the lines they flag), teamcity (service
+    /// messages), codequality (GitLab Code Quality JSON), checkstyle (XML), rdjson (reviewdog), or
+    /// github (GitHub Actions annotations)
+    #[arg(long, value_enum)]
+    format: Option<report::Format>,
+
+    /// Order of analyzers and findings: severity, file (default), or analyzer
+    #[arg(long, value_enum)]
+    sort: Option<report::SortOrder>,
+
+    /// Sections of the terminal and markdown reports: analyzer (default) or
+    /// category (injection, secrets, supply chain, ...), whichever analyzer found them
+    #[arg(long, value_enum)]
+    group_by: Option<report::GroupBy>,
+
+    /// Message catalog (TOML) for finding messages and report headings, e.g. a
+    /// translation; entries it leaves out stay in English
+    #[arg(long)]
+    messages: Option<PathBuf>,
+
+    /// Exit with status 1 when an analyzer's risk reaches this level; `[ci]` entries in the config
+    /// (e.g. security = "medium", style = "off") replace it per analyzer
+    #[arg(long, value_enum)]
+    fail_on: Option<config::Gate>,
+
+    /// Write a detached signature next to each report file
+    #[arg(long, value_enum)]
+    sign: Option<report::Signer>,
+
+    /// Secret key for --sign (required for minisign, optional for cosign)
+    #[arg(long, requires = "sign")]
+    sign_key: Option<PathBuf>,
+
+    /// Existing checkout of the PR head; enables whole-file checks and git-blame reviewer suggestions
+    #[arg(long, conflicts_with = "clone")]
+    repo: Option<PathBuf>,
+
+    /// Shallow-clone the PR head into a temporary directory (same checks as --repo)
+    #[arg(long, conflicts_with = "mock")]
+    clone: bool,
 
     /// Use a built-in mock PR for demo purposes (no GitHub token needed)
     #[arg(long)]
     r#mock: bool,
+
+    /// Threshold preset: strict, standard, lenient, or a [profiles.<name>] from config
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Post the result as a review: with a label vote on Gerrit, with a comment per finding on the
+    /// diff on GitHub (skipped for PRs from forks)
+    #[arg(long, conflicts_with = "mock")]
+    review: bool,
+
+    /// Post the markdown report as a comment on the PR, editing the earlier one on later runs
+    /// (GitHub PRs only; skipped for PRs from forks)
+    #[arg(long, conflicts_with = "mock")]
+    comment: bool,
+
+    /// Print wall time and memory per phase (and per analyzer) to stderr
+    #[arg(long)]
+    profile_run: bool,
+
+    /// Export tracing spans (fetch, line pass, each analyzer, output) with the PR's metadata to
+    /// this OpenTelemetry collector over OTLP/HTTP, e.g. http://localhost:4318. Also enabled by
+    /// OTEL_EXPORTER_OTLP_ENDPOINT; needs a build with the `otel` feature
+    #[arg(long)]
+    otel_endpoint: Option<String>,
+
+    /// Append this run's report to a history file (JSON Lines), for `site build`;
+    /// findings are marked NEW or PERSISTING against the PR's previous run there
+    #[arg(long)]
+    history: Option<PathBuf>,
+
+    /// Known findings (JSON, written by --write-baseline): findings recorded there are left
+    /// out of the report and the gates, so only new ones are reported
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Record this run's findings in the --baseline file, replacing it, instead of leaving
+    /// them out; the report still shows them all
+    #[arg(long, requires = "baseline")]
+    write_baseline: bool,
+
+    /// Markdown report of an earlier run of this PR, e.g. the body of the comment
+    /// it was posted as; findings are marked against the report embedded in it,
+    /// as with --history but without keeping a history file
+    #[arg(long)]
+    previous: Option<PathBuf>,
+
+    /// Run only this analyzer (e.g. security, style, api_contract) and print
+    /// why each line did or did not match to stderr
+    #[arg(long, conflicts_with = "history")]
+    only_analyzer: Option<String>,
+
+    /// Run only this rule (e.g. security.custom_patterns; see the report's
+    /// rules appendix) and print why each line did or did not match to stderr
+    #[arg(long, conflicts_with = "history")]
+    only_rule: Option<String>,
+
+    /// Fast diff-only run for pre-commit hooks and editors: checks that need a checkout are
+    /// skipped and analyzers still running after the time budget are left out; the report
+    /// is marked as a partial analysis
+    #[arg(long, conflicts_with_all = ["repo", "clone"])]
+    quick: bool,
+
+    /// Print to stderr, for each finding, the rule that reported it, what
+    /// matched on its line, and why it has its severity
+    #[arg(long)]
+    explain_matches: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check config, GitHub token and rate limit, Gerrit credentials, and git; print fixes
+    Doctor,
+    /// Static dashboard of past runs
+    Site {
+        #[command(subcommand)]
+        action: SiteCommand,
+    },
+    /// Check rules against examples, or document them
+    Rules {
+        #[command(subcommand)]
+        action: RulesCommand,
+    },
+    /// Language server on stdin/stdout: findings on the uncommitted changes of the
+    /// working tree as editor diagnostics
+    Lsp,
+    /// Apply the fixes findings suggest to the uncommitted changes of the working tree
+    Fix {
+        /// Ask before applying each fix
+        #[arg(long)]
+        interactive: bool,
+        /// Show the fixes without changing any file
+        #[arg(long, conflicts_with = "interactive")]
+        dry_run: bool,
+    },
+    /// Risk across a GitHub organization
+    Org {
+        #[command(subcommand)]
+        action: OrgCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum OrgCommand {
+    /// Analyze the PRs updated recently in every repository of an organization and print
+    /// the repositories ranked by risk (markdown), or a leaderboard. Stops early, with a
+    /// partial summary, at the GitHub rate limit
+    Scan {
+        /// Organization login, e.g. `acme`
+        org: String,
+        /// Period to cover: PRs updated within it, e.g. 7d, 12h or 2w
+        #[arg(long, default_value = "7d", value_parser = org::parse_period)]
+        since: Duration,
+        /// PRs fetched and analyzed at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Print repositories (default) or PRs ranked by risk score instead: each finding
+        /// scores 10 if HIGH, 3 if MEDIUM, 1 if LOW
+        #[arg(long, value_enum, value_name = "BY", num_args = 0..=1, default_missing_value = "repos")]
+        leaderboard: Option<org::LeaderboardBy>,
+        /// Leaderboard format
+        #[arg(long, value_enum, default_value_t, requires = "leaderboard")]
+        format: org::LeaderboardFormat,
+        /// JSON file of the last scan's scores: the leaderboard shows the change since
+        /// then, and the file is rewritten with this scan's (unless it was cut short)
+        #[arg(long, requires = "leaderboard")]
+        scores: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RulesCommand {
+    /// Run the `[[rule_tests]]` examples from .pr-analyzer.toml and --dir with the
+    /// current config; exits 1 if a rule does not match as expected
+    Test {
+        /// Directory of `*.toml` files with more `[[rule_tests]]` examples
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Print a markdown page documenting every rule with its default settings, in the
+    /// language of --messages; findings link to it through `[report] docs_url`
+    Docs,
+}
+
+#[derive(Subcommand, Debug)]
+enum SiteCommand {
+    /// Generate an HTML site (index, trends, per-PR pages) from a history file
+    Build {
+        /// History file written by --history
+        #[arg(long)]
+        from: PathBuf,
+        /// Output directory, e.g. for GitHub Pages
+        #[arg(long)]
+        out: PathBuf,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_target(true)
-        .with_writer(std::io::stderr)
-        .init();
-
     let cli = Cli::parse();
+    let telemetry = telemetry::init(cli.otel_endpoint.as_deref())?;
+    if let Some(Command::Doctor) = &cli.command {
+        let checks = doctor::run().await;
+        print!("{}", doctor::render(&checks));
+        if doctor::has_failures(&checks) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Site { action: SiteCommand::Build { from, out } }) = &cli.command {
+        let entries = history::load(from)?;
+        let index = report::build_site(&entries, out)?;
+        println!("Site written to {}", index.display());
+        return Ok(());
+    }
+    if let Some(Command::Org { action: OrgCommand::Scan { org, since, concurrency, leaderboard, format, scores } }) = &cli.command {
+        let mut config = config::Config::load().await?;
+        config.apply_profile(cli.profile.as_deref())?;
+        let previous = scores.as_deref().map(org::Scores::load).transpose()?.flatten();
+        let summary = org::scan(org, *since, *concurrency, &config).await?;
+        let Some(by) = leaderboard else {
+            print!("{}", org::render(&summary));
+            return Ok(());
+        };
+        print!("{}", org::render_leaderboard(&org::leaderboard(&summary, *by, previous.as_ref()), *format));
+        match scores {
+            Some(_) if summary.rate_limited.is_some() => warn!("scan cut short by the rate limit; scores file left unchanged"),
+            Some(path) => org::Scores::of(&summary).save(path)?,
+            None => {}
+        }
+        return Ok(());
+    }
+    if let Some(Command::Rules { action: RulesCommand::Test { dir } }) = &cli.command {
+        let mut config = config::Config::load().await?;
+        config.apply_profile(cli.profile.as_deref())?;
+        let tests = rule_tests::collect(&config, dir.as_deref())?;
+        let results = rule_tests::run(&config, tests).await;
+        print!("{}", rule_tests::render(&results));
+        if rule_tests::has_failures(&results) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let catalog = match cli.messages.as_deref() {
+        Some(path) => report::Catalog::load(path)?,
+        None => report::Catalog::english().clone(),
+    };
+    if let Some(Command::Rules { action: RulesCommand::Docs }) = &cli.command {
+        print!("{}", analysis::docs::render(&config::Config::default(), &catalog));
+        return Ok(());
+    }
+    if let Some(Command::Lsp) = &cli.command {
+        let mut config = config::Config::load().await?;
+        config.apply_profile(cli.profile.as_deref())?;
+        return lsp::serve(config, catalog).await;
+    }
+    if let Some(Command::Fix { interactive, dry_run }) = &cli.command {
+        let mut config = config::Config::load().await?;
+        config.apply_profile(cli.profile.as_deref())?;
+        let mode = match (interactive, dry_run) {
+            (_, true) => fix::Mode::DryRun,
+            (true, _) => fix::Mode::Interactive,
+            _ => fix::Mode::Apply,
+        };
+        fix::run(config, catalog, mode).await?;
+        return Ok(());
+    }
+
+    let span = info_span!(
+        "run",
+        pr.url = cli.pr_url.as_deref().unwrap_or(if cli.r#mock { "mock" } else { "local" }),
+        pr.number = Empty,
+        pr.title = Empty,
+        pr.author = Empty,
+        pr.head_sha = Empty,
+        pr.files = Empty,
+        pr.additions = Empty,
+        pr.deletions = Empty,
+        overall_risk = Empty,
+    );
+    let status = analyze(&cli, &catalog).instrument(span).await;
+    telemetry.shutdown();
+    match status? {
+        0 => Ok(()),
+        code => std::process::exit(code),
+    }
+}
 
-    let (pull_request, config) = if cli.r#mock {
+/// One analysis run, under the root `run` span. Returns the exit status.
+async fn analyze(cli: &Cli, catalog: &report::Catalog) -> Result<i32, Box<dyn std::error::Error>> {
+    let only = config::Selection { analyzer: cli.only_analyzer.clone(), rule: cli.only_rule.clone() };
+    // Rule ids do not depend on the config file, so a typo fails before anything is fetched.
+    analysis::check_selection(&config::Config { only: only.clone(), ..config::Config::default() })?;
+    let interrupt = install_interrupt_handler();
+    let mut run_profile = profile::RunProfile::start();
+
+    let (mut pull_request, mut config, source) = if cli.r#mock {
         info!("using mock PR data for demo");
-        (build_mock_pr()?, config::Config::default())
+        let mut config = config::Config::default();
+        config.apply_profile(cli.profile.as_deref())?;
+        (build_mock_pr()?, config, None)
     } else {
-        let pr_url = cli.pr_url.as_deref().ok_or(
-            "PR URL is required unless --mock is used. Usage: pr-analyzer <URL> or pr-analyzer --mock",
-        )?;
+        let local = local_change(cli)?;
+        let pr_url = match (&local, cli.pr_url.as_deref()) {
+            (Some(_), _) => "local",
+            (None, Some(pr_url)) => pr_url,
+            (None, None) => {
+                return Err("PR URL is required unless --mock, --local or --diff-file is used. Usage: pr-analyzer <URL> or pr-analyzer --mock".into())
+            }
+        };
 
         let _main_span = info_span!("pr_analyze", pr_url = %pr_url).entered();
 
-        info!("parsing PR URL");
-        let parsed_url = pr::parse_pr_url(pr_url)?;
-        debug!(owner = %parsed_url.owner, repo = %parsed_url.repo, pr = parsed_url.pr_number, "parsed PR URL");
-
+        // Loaded first: it names the GitHub host PR URLs are parsed against
         info!("loading configuration");
-        let config = config::Config::load()?;
+        let mut config = config::Config::load().await?;
+        config.apply_profile(cli.profile.as_deref())?;
+        debug!(profile = config.profile.as_deref().unwrap_or("standard"), "selected profile");
+
+        info!("parsing PR URL");
+        let source = match local {
+            Some(change) => pr::ChangeSource::Local(change),
+            None => pr::ChangeSource::parse(pr_url, &config.github_host())?,
+        };
+        debug!(?source, "parsed PR URL");
+        if cli.review && !matches!(source, pr::ChangeSource::Gerrit(_) | pr::ChangeSource::GitHub(_)) {
+            return Err("--review is only supported for GitHub pull requests and Gerrit changes".into());
+        }
+        if cli.comment && !matches!(source, pr::ChangeSource::GitHub(_)) {
+            return Err("--comment is only supported for GitHub pull requests".into());
+        }
 
-        info!("fetching pull request from GitHub");
-        let fetched = pr::fetch_pull_request(&parsed_url, &config).await?;
-        info!(files = fetched.files_changed, additions = fetched.additions, deletions = fetched.deletions, "fetched PR metadata");
-        (fetched, config)
+        info!("fetching pull request");
+        let fetched = tokio::select! {
+            fetched = source.fetch(&config) => fetched?,
+            _ = interrupted(interrupt.clone()) => {
+                warn!("interrupted while fetching; no report written");
+                eprintln!("Interrupted before analysis started; no report written.");
+                return Ok(INTERRUPTED_EXIT_CODE);
+            }
+        };
+        info!(files = fetched.files_changed(), additions = fetched.additions(), deletions = fetched.deletions(), "fetched PR metadata");
+        let mut fetched = fetched;
+        if cli.clone {
+            let pr::ChangeSource::GitHub(parsed_url) = &source else {
+                return Err("--clone is only supported for GitHub pull requests; use --repo with a local checkout".into());
+            };
+            let (host, token) = (config.github_host(), config.github_token());
+            let checkout = tokio::select! {
+                checkout = repo::Checkout::clone_pull_request(parsed_url, &host, token.as_deref(), config.runtime.clone_depth) => checkout?,
+                _ = interrupted(interrupt.clone()) => {
+                    warn!("interrupted while cloning; no report written");
+                    eprintln!("Interrupted before analysis started; no report written.");
+                    return Ok(INTERRUPTED_EXIT_CODE);
+                }
+            };
+            fetched.set_checkout(checkout);
+        }
+        (fetched, config, Some(source))
     };
+    config.add_ignores(&cli.ignore);
+    analysis::ignore_files(&mut pull_request, &config.ignore);
+    let span = Span::current();
+    span.record("pr.number", pull_request.number());
+    span.record("pr.title", pull_request.title());
+    span.record("pr.author", pull_request.author());
+    if let Some(sha) = pull_request.head_sha() {
+        span.record("pr.head_sha", sha);
+    }
+    span.record("pr.files", pull_request.files_changed());
+    span.record("pr.additions", pull_request.additions());
+    span.record("pr.deletions", pull_request.deletions());
+    if pull_request.is_fork() {
+        info!("PR comes from a fork; checks that run its code are skipped");
+    }
+    if let Some(root) = cli.repo.as_deref() {
+        let checkout = repo::Checkout::open(root)?;
+        let local_head = checkout.head_sha()?;
+        if pull_request.head_sha().is_some_and(|sha| sha != local_head) {
+            warn!(local_head = %local_head, pr_head = ?pull_request.head_sha(), "--repo is not at the PR head; whole-file checks may be stale");
+        }
+        pull_request.set_checkout(checkout);
+    }
+
+    config.only = only;
+    analysis::check_selection(&config)?;
+    analysis::gate::check(&config)?;
+    let layout = analysis::report_layout(&config)?;
+    run_profile.mark("fetch");
 
     info!("running analysis");
-    let results = analysis::run_all(&pull_request, &config).await?;
-    info!(analyzers = results.len(), "analysis complete");
+    // The deadline is fixed here, not at the first poll, so preprocessing
+    // and the line pass spend the budget too.
+    let deadline = tokio::time::Instant::now() + QUICK_BUDGET;
+    let budget = async {
+        match cli.quick {
+            true => tokio::time::sleep_until(deadline).await,
+            false => std::future::pending().await,
+        }
+    };
+    let cancel = async {
+        tokio::select! {
+            _ = interrupted(interrupt.clone()) => {}
+            _ = budget => {}
+        }
+    };
+    let run = analysis::run_all(&pull_request, &config, cancel).await?;
+    let over_budget = run.interrupted && !*interrupt.borrow();
+    if over_budget {
+        warn!(budget_ms = QUICK_BUDGET.as_millis() as u64, "--quick time budget spent; unfinished analyzers are left out");
+    }
+    run_profile.mark("analysis");
+    for (analyzer, elapsed) in &run.timings {
+        run_profile.nested(analyzer, *elapsed);
+    }
+    info!(analyzers = run.results.len(), interrupted = run.interrupted, "analysis complete");
+    if !config.only.is_empty() {
+        eprint!("{}", analysis::explain::explain(&pull_request, &config, &run)?);
+    }
+    if cli.explain_matches {
+        eprint!("{}", analysis::explain::explain_findings(&pull_request, &config, &run)?);
+    }
+
+    let mut results = run.results;
+    let baselined = match (cli.baseline.as_deref(), cli.write_baseline) {
+        (Some(path), true) => {
+            let count = baseline::write(path, &results, pull_request.files())?;
+            info!(path = %path.display(), findings = count, "baseline written");
+            0
+        }
+        (Some(path), false) => baseline::apply(path, &mut results, pull_request.files())?,
+        (None, _) => 0,
+    };
 
     info!("generating report");
-    let built_report = report::build(results, &pull_request);
-    report::output(&built_report, cli.output.as_deref())?;
+    let mut built_report = report::build(results, &pull_request);
+    built_report.baselined = baselined;
+    built_report.interrupted = run.interrupted && !over_budget;
+    built_report.quick = cli.quick;
+    built_report.rules = run.rules;
+    built_report.warnings = run.warnings;
+    built_report.provenance.config_hash = Some(config.fingerprint());
+    built_report.provenance.rule_versions = analysis::rule_versions(&config);
+    built_report.provenance.policy = config.policy.enforced();
+    built_report.attack_surface = analysis::surface::attack_surface(&pull_request, &config.style.test_paths);
+    built_report.split = analysis::split::suggest(&pull_request, &built_report.results, &config.style.test_paths);
+    built_report.review_effort = Some(analysis::effort::estimate(&pull_request, &built_report.results, &config.style.test_paths));
+    let repo_name = source.as_ref().and_then(pr::ChangeSource::repo_name);
+    if let Some(path) = cli.history.as_deref() {
+        history::flag_outliers(path, repo_name.as_deref(), &mut built_report)?;
+    }
+    report::sort(&mut built_report, cli.sort.unwrap_or_default());
+    if let Some(template) = config.report.docs_url() {
+        report::link_docs(&mut built_report, template);
+    }
+    if let Some(checkout) = &pull_request.checkout() {
+        info!("suggesting reviewers from git blame");
+        built_report.reviewers = checkout.suggest_reviewers(&built_report.results);
+    }
+    let previous = match (cli.previous.as_deref(), cli.history.as_deref()) {
+        (Some(path), _) => {
+            let previous = report::extract_embedded(&std::fs::read_to_string(path)?)?;
+            if previous.is_none() {
+                warn!(path = %path.display(), "--previous has no embedded report; nothing to compare against");
+            }
+            previous
+        }
+        (None, Some(path)) => history::latest_run(path, repo_name.as_deref(), built_report.pr_number)?,
+        (None, None) => None,
+    };
+    if let Some(previous) = &previous {
+        report::compare_with_previous(&mut built_report, previous);
+    }
+    let outputs = if cli.output.is_empty() { &config.output.files } else { &cli.output };
+    let mut sinks = report::sinks(outputs, cli.format, cli.group_by.unwrap_or_default());
+    if let (true, Some(pr::ChangeSource::GitHub(url))) = (cli.comment, &source) {
+        if pull_request.is_fork() {
+            warn!("PR is from a fork; not posting a comment");
+        } else {
+            let client = pr::GitHubClient::for_writes(&config)?;
+            sinks.push(Box::new(report::CommentSink::new(url.clone(), client, config.audit.clone(), cli.group_by.unwrap_or_default())));
+        }
+    }
+    report::emit(&sinks, &layout.apply(&built_report), pull_request.files(), catalog).await?;
+    if let Some(path) = cli.history.as_deref() {
+        history::append(path, repo_name.as_deref(), &built_report)?;
+    }
+    match (cli.review, &source) {
+        (true, Some(pr::ChangeSource::Gerrit(change))) => {
+            info!("posting Gerrit review");
+            pr::gerrit::post_review(change, &built_report, pull_request.files(), &config, catalog).await?;
+        }
+        (true, Some(pr::ChangeSource::GitHub(_))) if pull_request.is_fork() => warn!("PR is from a fork; not posting a review"),
+        (true, Some(pr::ChangeSource::GitHub(url))) => {
+            info!("posting GitHub review");
+            pr::review::post_review(url, &built_report, pull_request.files(), &config).await?;
+        }
+        _ => {}
+    }
+    if let Some(signer) = cli.sign {
+        let files: Vec<&Path> = sinks.iter().filter_map(|s| s.path()).collect();
+        if files.is_empty() {
+            return Err("--sign needs a report file: pass --output or set [output] files".into());
+        }
+        for path in files {
+            let signature = report::sign(path, signer, cli.sign_key.as_deref())?;
+            info!(signature = %signature.display(), "report signed");
+        }
+    }
+    run_profile.mark("report");
+    if cli.profile_run {
+        eprint!("{}", run_profile.render());
+    }
+    Span::current().record("overall_risk", built_report.overall_risk.to_string());
     info!(overall_risk = %built_report.overall_risk, "done");
 
-    Ok(())
+    if built_report.interrupted {
+        return Ok(INTERRUPTED_EXIT_CODE);
+    }
+    let verdicts = analysis::gate::evaluate(&built_report, &config, cli.fail_on)?;
+    eprint!("{}", analysis::gate::render(&verdicts));
+    Ok(if verdicts.iter().any(analysis::gate::Verdict::fails) { 1 } else { 0 })
+}
+
+/// The local change `--local`, `--staged` or `--diff-file` asks for, if any.
+fn local_change(cli: &Cli) -> Result<Option<pr::local::LocalChange>, Box<dyn std::error::Error>> {
+    use pr::local::LocalChange;
+    Ok(match (&cli.diff_file, cli.local.as_deref()) {
+        (Some(path), _) => Some(LocalChange::DiffFile(path.clone())),
+        (None, Some("")) if cli.staged => Some(LocalChange::Staged),
+        (None, Some("")) => Some(LocalChange::Uncommitted),
+        (None, Some(_)) if cli.staged => return Err("--staged cannot be combined with a commit range".into()),
+        (None, Some(range)) => Some(LocalChange::Range(range.to_string())),
+        (None, None) => None,
+    })
+}
+
+/// Time `--quick` gives the whole analysis, preprocessing and line pass
+/// included.
+const QUICK_BUDGET: Duration = Duration::from_millis(500);
+
+/// Conventional exit status for a process stopped by SIGINT (128 + 2).
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Listen for Ctrl-C in the background. The returned receiver flips to
+/// `true` once; every cancellable stage waits on a clone of it.
+fn install_interrupt_handler() -> watch::Receiver<bool> {
+    let (tx, rx) = watch::channel(false);
+    tokio::spawn(async move {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => {
+                warn!("received Ctrl-C, cancelling");
+                tx.send_replace(true);
+                // Keep the sender alive so receivers never see a closed channel.
+                std::future::pending::<()>().await;
+            }
+            Err(e) => warn!(error = %e, "could not install Ctrl-C handler"),
+        }
+    });
+    rx
+}
+
+/// Resolves when Ctrl-C has been received. Never resolves if the handler
+/// could not be installed, so the run simply cannot be interrupted.
+async fn interrupted(mut rx: watch::Receiver<bool>) {
+    if rx.wait_for(|fired| *fired).await.is_err() {
+        std::future::pending::<()>().await;
+    }
 }
 
 /// Build a mock PullRequest from the embedded sample diff fixture.
@@ -77,16 +634,5 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 fn build_mock_pr() -> Result<pr::PullRequest, Box<dyn std::error::Error>> {
     let diff_text = include_str!("../tests/fixtures/sample_diff.patch");
     let files = pr::diff::parse_diff(diff_text)?;
-    let additions: usize = files.iter().map(|f| f.additions).sum();
-    let deletions: usize = files.iter().map(|f| f.deletions).sum();
-
-    Ok(pr::PullRequest {
-        number: 42,
-        title: "Add OAuth2 login flow".to_string(),
-        author: "alice".to_string(),
-        files_changed: files.len(),
-        additions,
-        deletions,
-        files,
-    })
+    Ok(pr::PullRequest::builder("Add OAuth2 login flow").number(42).author("alice").files(files).build()?)
 }
@@ -0,0 +1,626 @@
+//! `org scan`: the recent pull requests of a whole GitHub organization.
+//!
+//! Lists the organization's repositories (archived ones are skipped) and,
+//! in each, the PRs updated within the period; analyzes every PR as a
+//! single-PR run would, a few at a time; and ranks the repositories by the
+//! risk of their PRs. All requests share one client, so with several
+//! `[github] tokens` the scan moves on to the next token as each runs out.
+//! Hitting the rate limit of the last one stops the scan, and the summary
+//! covers what was analyzed until then.
+//!
+//! `--leaderboard` ranks repositories or PRs by risk score instead, with
+//! the change in score since the scan saved with `--scores`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use colored::Colorize;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, instrument, warn};
+
+use crate::analysis;
+use crate::config::Config;
+use crate::pr::{fetch_pull_request_with, GitHubClient, GitHubOperation, PrError, PrUrl};
+use crate::report::{self, rfc3339_utc, RiskLevel};
+
+/// Repositories or PRs per page of a GitHub list endpoint (its maximum).
+const PAGE_SIZE: usize = 100;
+
+/// Weight of a finding in a risk score, by severity: one HIGH finding
+/// outweighs three MEDIUM ones.
+const fn score_weight(severity: RiskLevel) -> u32 {
+    match severity {
+        RiskLevel::High => 10,
+        RiskLevel::Medium => 3,
+        RiskLevel::Low => 1,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ScoresError {
+    #[error("Failed to access scores file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid scores file: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// One analyzed PR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrSummary {
+    pub number: u64,
+    pub title: String,
+    pub risk: RiskLevel,
+    pub findings: usize,
+    /// Sum of `score_weight` over its findings
+    pub score: u32,
+}
+
+/// The recent PRs of one repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoSummary {
+    /// `owner/repo`
+    pub repo: String,
+    pub prs: Vec<PrSummary>,
+    /// PRs that could not be fetched or analyzed
+    pub failed: usize,
+}
+
+impl RepoSummary {
+    /// Highest risk of its PRs; None when none could be analyzed.
+    pub fn risk(&self) -> Option<RiskLevel> {
+        self.prs.iter().map(|p| p.risk).max()
+    }
+
+    fn count(&self, risk: RiskLevel) -> usize {
+        self.prs.iter().filter(|p| p.risk == risk).count()
+    }
+
+    fn findings(&self) -> usize {
+        self.prs.iter().map(|p| p.findings).sum()
+    }
+
+    /// Sum of its PRs' scores.
+    pub fn score(&self) -> u32 {
+        self.prs.iter().map(|p| p.score).sum()
+    }
+
+    /// The PR with the highest risk, then the most findings.
+    fn riskiest(&self) -> Option<&PrSummary> {
+        self.prs.iter().max_by(|a, b| a.risk.cmp(&b.risk).then(a.findings.cmp(&b.findings)).then(b.number.cmp(&a.number)))
+    }
+}
+
+/// Result of a scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrgSummary {
+    pub org: String,
+    /// Start of the period (RFC 3339, UTC)
+    pub since: String,
+    /// Repositories listed, with or without recent PRs
+    pub repositories: usize,
+    /// Repositories with PRs in the period, riskiest first
+    pub repos: Vec<RepoSummary>,
+    /// Minutes until the rate limit resets, when it stopped the scan early
+    pub rate_limited: Option<u64>,
+}
+
+/// How one PR of the scan went.
+enum Outcome {
+    Analyzed(PrSummary),
+    Failed,
+    RateLimited(u64),
+}
+
+/// A period such as `7d`, `12h`, `2w` or `30m`.
+pub fn parse_period(text: &str) -> Result<Duration, String> {
+    let (number, unit) = text.split_at(text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len()));
+    let count: u64 = number.parse().map_err(|_| format!("'{}' is not a period like 7d, 12h or 2w", text))?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("'{}' has no unit: use m, h, d or w, e.g. 7d", text)),
+    };
+    let secs = count.checked_mul(unit_secs).ok_or_else(|| format!("'{}' is too long a period", text))?;
+    Ok(Duration::from_secs(secs))
+}
+
+/// Analyze the PRs of `org` updated within `period`, at most `concurrency`
+/// at a time. Failing to list the organization's repositories is an
+/// error; a repository or PR that fails is logged and counted, and the
+/// scan goes on.
+#[instrument(skip(config))]
+pub async fn scan(org: &str, period: Duration, concurrency: usize, config: &Config) -> Result<OrgSummary, PrError> {
+    let client = GitHubClient::for_reads(config)?;
+    let since = rfc3339_utc(SystemTime::now().checked_sub(period).unwrap_or(SystemTime::UNIX_EPOCH));
+    let names = match list_repositories(&client, org).await {
+        Err(PrError::NotFound(_)) => return Err(PrError::OrgNotFound(org.to_string())),
+        listed => listed?,
+    };
+    info!(repositories = names.len(), since = %since, "listed organization repositories");
+
+    let mut rate_limited = None;
+    let mut targets = Vec::new();
+    for name in &names {
+        match recent_pulls(&client, org, name, &since).await {
+            Ok(numbers) => targets.extend(numbers.into_iter().map(|pr_number| PrUrl { owner: org.to_string(), repo: name.clone(), pr_number })),
+            Err(PrError::RateLimited { reset_in_minutes }) => {
+                rate_limited = Some(reset_in_minutes);
+                break;
+            }
+            Err(e) => warn!(repo = %name, error = %e, "could not list pull requests; repository skipped"),
+        }
+    }
+    info!(pull_requests = targets.len(), "analyzing recent pull requests");
+
+    let stopped = AtomicBool::new(rate_limited.is_some());
+    let outcomes: Vec<(String, Outcome)> = stream::iter(targets)
+        .map(|url| {
+            let (stopped, client) = (&stopped, &client);
+            async move {
+                if stopped.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let outcome = analyze(client, &url, config).await;
+                if matches!(outcome, Outcome::RateLimited(_)) {
+                    stopped.store(true, Ordering::Relaxed);
+                }
+                Some((url.repo, outcome))
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(std::future::ready)
+        .collect()
+        .await;
+
+    let mut by_repo: BTreeMap<String, RepoSummary> = BTreeMap::new();
+    for (repo, outcome) in outcomes {
+        let summary = by_repo.entry(repo.clone()).or_insert_with(|| RepoSummary { repo: format!("{}/{}", org, repo), ..RepoSummary::default() });
+        match outcome {
+            Outcome::Analyzed(pr) => summary.prs.push(pr),
+            Outcome::Failed => summary.failed += 1,
+            Outcome::RateLimited(minutes) => {
+                summary.failed += 1;
+                rate_limited.get_or_insert(minutes);
+            }
+        }
+    }
+    let mut repos: Vec<RepoSummary> = by_repo.into_values().collect();
+    rank(&mut repos);
+    Ok(OrgSummary { org: org.to_string(), since, repositories: names.len(), repos, rate_limited })
+}
+
+/// Riskiest repository first: by highest PR risk, then number of HIGH PRs,
+/// then findings, then name.
+fn rank(repos: &mut [RepoSummary]) {
+    for repo in repos.iter_mut() {
+        repo.prs.sort_by(|a, b| b.risk.cmp(&a.risk).then(a.number.cmp(&b.number)));
+    }
+    repos.sort_by(|a, b| {
+        b.risk()
+            .cmp(&a.risk())
+            .then(b.count(RiskLevel::High).cmp(&a.count(RiskLevel::High)))
+            .then(b.findings().cmp(&a.findings()))
+            .then(a.repo.cmp(&b.repo))
+    });
+}
+
+/// Fetch and analyze one PR.
+async fn analyze(client: &GitHubClient, url: &PrUrl, config: &Config) -> Outcome {
+    let mut pr = match fetch_pull_request_with(client, url).await {
+        Ok(pr) => pr,
+        Err(PrError::RateLimited { reset_in_minutes }) => return Outcome::RateLimited(reset_in_minutes),
+        Err(e) => {
+            warn!(repo = %url.repo, pr = url.pr_number, error = %e, "could not fetch pull request");
+            return Outcome::Failed;
+        }
+    };
+    analysis::ignore_files(&mut pr, &config.ignore);
+    match analysis::run_all(&pr, config, std::future::pending()).await {
+        Ok(run) => {
+            let report = report::build(run.results, &pr);
+            let findings = report.results.iter().map(|r| r.findings.len()).sum();
+            let score = report.results.iter().flat_map(|r| &r.findings).map(|f| score_weight(f.severity)).sum();
+            debug!(repo = %url.repo, pr = pr.number(), risk = %report.overall_risk, score, "analyzed");
+            Outcome::Analyzed(PrSummary { number: pr.number(), title: pr.title().to_string(), risk: report.overall_risk, findings, score })
+        }
+        Err(e) => {
+            warn!(repo = %url.repo, pr = url.pr_number, error = %e, "could not analyze pull request");
+            Outcome::Failed
+        }
+    }
+}
+
+/// Names of the organization's repositories that are not archived.
+async fn list_repositories(client: &GitHubClient, org: &str) -> Result<Vec<String>, PrError> {
+    #[derive(Deserialize)]
+    struct Repository {
+        name: String,
+        #[serde(default)]
+        archived: bool,
+    }
+
+    let context = PrUrl { owner: org.to_string(), repo: String::new(), pr_number: 0 };
+    let mut names = Vec::new();
+    for page in 1.. {
+        let url = client.api(&format!("/orgs/{}/repos?type=all&per_page={}&page={}", org, PAGE_SIZE, page));
+        let repositories: Vec<Repository> = client.get(&url, None, GitHubOperation::ListRepositories, &context).await?.json().await?;
+        let last = repositories.len() < PAGE_SIZE;
+        names.extend(repositories.into_iter().filter(|r| !r.archived).map(|r| r.name));
+        if last {
+            break;
+        }
+    }
+    Ok(names)
+}
+
+/// Numbers of the repository's PRs, open or closed, updated at or after
+/// `since` (RFC 3339). Pages are read newest first until one reaches past it.
+async fn recent_pulls(client: &GitHubClient, owner: &str, repo: &str, since: &str) -> Result<Vec<u64>, PrError> {
+    #[derive(Deserialize)]
+    struct Pull {
+        number: u64,
+        updated_at: String,
+    }
+
+    let context = PrUrl { owner: owner.to_string(), repo: repo.to_string(), pr_number: 0 };
+    let mut numbers = Vec::new();
+    for page in 1.. {
+        let url = client.api(&format!("/repos/{}/{}/pulls?state=all&sort=updated&direction=desc&per_page={}&page={}", owner, repo, PAGE_SIZE, page));
+        let pulls: Vec<Pull> = client.get(&url, None, GitHubOperation::ListRepositories, &context).await?.json().await?;
+        let last = pulls.len() < PAGE_SIZE;
+        // Both sides are `YYYY-MM-DDTHH:MM:SSZ`, so text order is time order.
+        let recent = pulls.iter().take_while(|p| p.updated_at.as_str() >= since).count();
+        numbers.extend(pulls[..recent].iter().map(|p| p.number));
+        if last || recent < pulls.len() {
+            break;
+        }
+    }
+    Ok(numbers)
+}
+
+/// The summary as markdown: one row per repository with PRs in the period.
+pub fn render(summary: &OrgSummary) -> String {
+    let mut out = format!("# Organization risk: {}\n\nPull requests updated since {}.\n\n", summary.org, summary.since);
+    if let Some(minutes) = summary.rate_limited {
+        let _ = writeln!(out, "> **PARTIAL: the GitHub rate limit stopped the scan; it resets in {} min.**\n", minutes);
+    }
+    if summary.repos.is_empty() {
+        let _ = writeln!(out, "No pull requests in {} repositories.", summary.repositories);
+        return out;
+    }
+    out.push_str("| Repository | Risk | PRs | High | Medium | Low | Findings | Riskiest PR |\n|---|---|---|---|---|---|---|---|\n");
+    for repo in &summary.repos {
+        let risk = repo.risk().map_or("-".to_string(), |r| r.to_string());
+        let riskiest = repo.riskiest().map_or(String::new(), |pr| format!("#{} {}", pr.number, pr.title.replace('|', "\\|")));
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} | {} |",
+            repo.repo,
+            risk,
+            repo.prs.len(),
+            repo.count(RiskLevel::High),
+            repo.count(RiskLevel::Medium),
+            repo.count(RiskLevel::Low),
+            repo.findings(),
+            riskiest
+        );
+    }
+    let quiet = summary.repositories.saturating_sub(summary.repos.len());
+    let failed: usize = summary.repos.iter().map(|r| r.failed).sum();
+    let _ = write!(out, "\n{} repositories without pull requests in the period", quiet);
+    if failed > 0 {
+        let _ = write!(out, "; {} pull requests could not be analyzed (see the log)", failed);
+    }
+    out.push_str(".\n");
+    out
+}
+
+/// What a leaderboard ranks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LeaderboardBy {
+    /// Repositories, by the summed score of their PRs
+    #[default]
+    Repos,
+    /// Pull requests across all repositories
+    Prs,
+}
+
+/// Output format of a leaderboard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LeaderboardFormat {
+    #[default]
+    Terminal,
+    Markdown,
+    Csv,
+}
+
+/// Scores of one scan, kept with `--scores` so the next leaderboard can
+/// show how they moved.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scores {
+    /// When the scan ran (RFC 3339, UTC)
+    pub scanned_at: String,
+    /// Score per repository (`owner/repo`) and per PR (`owner/repo#N`)
+    pub scores: BTreeMap<String, u32>,
+}
+
+impl Scores {
+    /// The scores of every repository and PR of `summary`.
+    pub fn of(summary: &OrgSummary) -> Self {
+        let mut scores = BTreeMap::new();
+        for repo in &summary.repos {
+            scores.insert(repo.repo.clone(), repo.score());
+            scores.extend(repo.prs.iter().map(|pr| (format!("{}#{}", repo.repo, pr.number), pr.score)));
+        }
+        Self { scanned_at: rfc3339_utc(SystemTime::now()), scores }
+    }
+
+    /// The scores saved at `path`, or None when there is no file yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, ScoresError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ScoresError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        debug!(path = %path.display(), entries = self.scores.len(), "saved scan scores");
+        Ok(())
+    }
+}
+
+/// One row of a leaderboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub rank: usize,
+    /// `owner/repo`, or `owner/repo#N` for a PR
+    pub name: String,
+    /// PR title (None for a repository)
+    pub title: Option<String>,
+    /// None for a repository none of whose PRs could be analyzed
+    pub risk: Option<RiskLevel>,
+    pub score: u32,
+    /// Score change since the previous scan; None when it was not in it
+    pub delta: Option<i64>,
+    pub findings: usize,
+}
+
+/// Repositories or PRs ranked by risk score.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leaderboard {
+    pub org: String,
+    pub by: LeaderboardBy,
+    /// Start of the scanned period (RFC 3339, UTC)
+    pub since: String,
+    /// When the scan the deltas compare with ran; None without one
+    pub previous_scan: Option<String>,
+    /// Highest score first
+    pub standings: Vec<Standing>,
+    pub rate_limited: Option<u64>,
+}
+
+/// Rank the repositories or PRs of `summary` by score, then risk, then
+/// name, with deltas against `previous`.
+pub fn leaderboard(summary: &OrgSummary, by: LeaderboardBy, previous: Option<&Scores>) -> Leaderboard {
+    let mut standings: Vec<Standing> = match by {
+        LeaderboardBy::Repos => summary
+            .repos
+            .iter()
+            .map(|repo| Standing { rank: 0, name: repo.repo.clone(), title: None, risk: repo.risk(), score: repo.score(), delta: None, findings: repo.findings() })
+            .collect(),
+        LeaderboardBy::Prs => summary
+            .repos
+            .iter()
+            .flat_map(|repo| {
+                repo.prs.iter().map(move |pr| Standing { rank: 0, name: format!("{}#{}", repo.repo, pr.number), title: Some(pr.title.clone()), risk: Some(pr.risk), score: pr.score, delta: None, findings: pr.findings })
+            })
+            .collect(),
+    };
+    standings.sort_by(|a, b| b.score.cmp(&a.score).then(b.risk.cmp(&a.risk)).then(a.name.cmp(&b.name)));
+    for (i, standing) in standings.iter_mut().enumerate() {
+        standing.rank = i + 1;
+        standing.delta = previous.and_then(|p| p.scores.get(&standing.name)).map(|&before| i64::from(standing.score) - i64::from(before));
+    }
+    Leaderboard {
+        org: summary.org.clone(),
+        by,
+        since: summary.since.clone(),
+        previous_scan: previous.map(|p| p.scanned_at.clone()),
+        standings,
+        rate_limited: summary.rate_limited,
+    }
+}
+
+/// The leaderboard in `format`.
+pub fn render_leaderboard(board: &Leaderboard, format: LeaderboardFormat) -> String {
+    match format {
+        LeaderboardFormat::Terminal => leaderboard_terminal(board),
+        LeaderboardFormat::Markdown => leaderboard_markdown(board),
+        LeaderboardFormat::Csv => leaderboard_csv(board),
+    }
+}
+
+/// A standing's delta as shown to people: `+4`, `-2`, `0`, `new` when the
+/// previous scan did not have it, or `-` without a previous scan.
+fn delta_text(board: &Leaderboard, standing: &Standing) -> String {
+    match (&board.previous_scan, standing.delta) {
+        (None, _) => "-".to_string(),
+        (Some(_), None) => "new".to_string(),
+        (Some(_), Some(delta)) if delta > 0 => format!("+{}", delta),
+        (Some(_), Some(delta)) => delta.to_string(),
+    }
+}
+
+/// Period and comparison, as one sentence.
+fn leaderboard_intro(board: &Leaderboard) -> String {
+    let compared = board.previous_scan.as_ref().map_or("no previous scan to compare with".to_string(), |at| format!("changes since the scan of {}", at));
+    format!("Pull requests updated since {}; {}.", board.since, compared)
+}
+
+fn leaderboard_terminal(board: &Leaderboard) -> String {
+    let mut out = format!("{}\n{}\n", format!("Risk leaderboard: {}", board.org).bold(), leaderboard_intro(board));
+    if let Some(minutes) = board.rate_limited {
+        let _ = writeln!(out, "{}", format!("PARTIAL: the GitHub rate limit stopped the scan; it resets in {} min.", minutes).yellow().bold());
+    }
+    out.push('\n');
+    if board.standings.is_empty() {
+        out.push_str("No pull requests in the period.\n");
+        return out;
+    }
+    let _ = writeln!(out, "{:>4}  {:<6}  {:>5}  {:>5}  {:>8}  NAME", "#", "RISK", "SCORE", "DELTA", "FINDINGS");
+    for standing in &board.standings {
+        let risk = match standing.risk {
+            Some(RiskLevel::High) => format!("{:<6}", "HIGH").red().bold(),
+            Some(RiskLevel::Medium) => format!("{:<6}", "MEDIUM").yellow().bold(),
+            Some(RiskLevel::Low) => format!("{:<6}", "LOW").green().bold(),
+            None => format!("{:<6}", "-").normal(),
+        };
+        let title = standing.title.as_ref().map_or(String::new(), |t| format!(" {}", t));
+        let _ = writeln!(out, "{:>4}  {}  {:>5}  {:>5}  {:>8}  {}{}", standing.rank, risk, standing.score, delta_text(board, standing), standing.findings, standing.name, title);
+    }
+    out
+}
+
+fn leaderboard_markdown(board: &Leaderboard) -> String {
+    let mut out = format!("# Risk leaderboard: {}\n\n{}\n\n", board.org, leaderboard_intro(board));
+    if let Some(minutes) = board.rate_limited {
+        let _ = writeln!(out, "> **PARTIAL: the GitHub rate limit stopped the scan; it resets in {} min.**\n", minutes);
+    }
+    if board.standings.is_empty() {
+        out.push_str("No pull requests in the period.\n");
+        return out;
+    }
+    let column = match board.by {
+        LeaderboardBy::Repos => "Repository",
+        LeaderboardBy::Prs => "Pull request",
+    };
+    let _ = writeln!(out, "| # | {} | Risk | Score | Change | Findings |\n|---|---|---|---|---|---|", column);
+    for standing in &board.standings {
+        let name = match &standing.title {
+            Some(title) => format!("{} {}", standing.name, title.replace('|', "\\|")),
+            None => standing.name.clone(),
+        };
+        let risk = standing.risk.map_or("-".to_string(), |r| r.to_string());
+        let _ = writeln!(out, "| {} | {} | {} | {} | {} | {} |", standing.rank, name, risk, standing.score, delta_text(board, standing), standing.findings);
+    }
+    out
+}
+
+/// CSV with a header row, for spreadsheets; `delta` is empty when there is
+/// nothing to compare with.
+fn leaderboard_csv(board: &Leaderboard) -> String {
+    let mut out = String::from("rank,name,title,risk,score,delta,findings\n");
+    for s in &board.standings {
+        let risk = s.risk.map_or(String::new(), |r| r.to_string());
+        let delta = s.delta.map_or(String::new(), |d| d.to_string());
+        let title = csv_field(s.title.as_deref().unwrap_or(""));
+        let _ = writeln!(out, "{},{},{},{},{},{},{}", s.rank, csv_field(&s.name), title, risk, s.score, delta, s.findings);
+    }
+    out
+}
+
+/// Quote a CSV field when it holds a comma, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pr(number: u64, risk: RiskLevel, findings: usize) -> PrSummary {
+        PrSummary { number, title: format!("PR {}", number), risk, findings, score: findings as u32 * score_weight(risk) }
+    }
+
+    fn repo(name: &str, prs: Vec<PrSummary>) -> RepoSummary {
+        RepoSummary { repo: format!("acme/{}", name), prs, failed: 0 }
+    }
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!(parse_period("7d"), Ok(Duration::from_secs(7 * 86_400)));
+        assert_eq!(parse_period("12h"), Ok(Duration::from_secs(12 * 3_600)));
+        assert_eq!(parse_period("2w"), Ok(Duration::from_secs(14 * 86_400)));
+        assert!(parse_period("7").is_err());
+        assert!(parse_period("d").is_err());
+        assert!(parse_period("7y").is_err());
+        assert_eq!(parse_period("99999999999999999w"), Err("'99999999999999999w' is too long a period".to_string()));
+    }
+
+    #[test]
+    fn test_rank_puts_riskiest_repository_first() {
+        let mut repos = vec![
+            repo("docs", vec![pr(1, RiskLevel::Low, 0)]),
+            repo("api", vec![pr(4, RiskLevel::High, 2), pr(5, RiskLevel::Low, 1)]),
+            repo("web", vec![pr(2, RiskLevel::High, 9), pr(3, RiskLevel::High, 1)]),
+            RepoSummary { repo: "acme/broken".to_string(), prs: Vec::new(), failed: 2 },
+        ];
+        rank(&mut repos);
+        let order: Vec<&str> = repos.iter().map(|r| r.repo.as_str()).collect();
+        assert_eq!(order, ["acme/web", "acme/api", "acme/docs", "acme/broken"]);
+        assert_eq!(repos[0].riskiest().map(|p| p.number), Some(2));
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let summary = OrgSummary {
+            org: "acme".to_string(),
+            since: "2026-10-09T00:00:00Z".to_string(),
+            repositories: 5,
+            repos: vec![repo("web", vec![pr(2, RiskLevel::High, 9), pr(3, RiskLevel::Low, 1)])],
+            rate_limited: Some(12),
+        };
+        let md = render(&summary);
+        assert!(md.starts_with("# Organization risk: acme\n\nPull requests updated since 2026-10-09T00:00:00Z.\n\n> **PARTIAL:"), "{md}");
+        assert!(md.contains("| acme/web | HIGH | 2 | 1 | 0 | 1 | 10 | #2 PR 2 |\n"), "{md}");
+        assert!(md.ends_with("\n4 repositories without pull requests in the period.\n"), "{md}");
+    }
+
+    #[test]
+    fn test_leaderboard_ranks_by_score_with_deltas() {
+        let summary = OrgSummary {
+            org: "acme".to_string(),
+            since: "2026-10-09T00:00:00Z".to_string(),
+            repositories: 3,
+            repos: vec![
+                repo("web", vec![pr(2, RiskLevel::Medium, 4), pr(3, RiskLevel::Low, 1)]),
+                repo("api", vec![pr(7, RiskLevel::High, 1)]),
+                RepoSummary { repo: "acme/broken".to_string(), prs: Vec::new(), failed: 1 },
+            ],
+            rate_limited: None,
+        };
+        let previous = Scores { scanned_at: "2026-10-09T00:00:00Z".to_string(), scores: BTreeMap::from([("acme/web".to_string(), 20), ("acme/web#2".to_string(), 12)]) };
+
+        let repos = leaderboard(&summary, LeaderboardBy::Repos, Some(&previous));
+        let rows: Vec<(usize, &str, u32, Option<i64>)> = repos.standings.iter().map(|s| (s.rank, s.name.as_str(), s.score, s.delta)).collect();
+        assert_eq!(rows, [(1, "acme/web", 13, Some(-7)), (2, "acme/api", 10, None), (3, "acme/broken", 0, None)]);
+        let md = render_leaderboard(&repos, LeaderboardFormat::Markdown);
+        assert!(md.contains("changes since the scan of 2026-10-09T00:00:00Z."), "{md}");
+        assert!(md.contains("| 1 | acme/web | MEDIUM | 13 | -7 | 5 |\n| 2 | acme/api | HIGH | 10 | new | 1 |\n"), "{md}");
+
+        let prs = leaderboard(&summary, LeaderboardBy::Prs, None);
+        assert_eq!(prs.standings.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["acme/web#2", "acme/api#7", "acme/web#3"]);
+        let csv = render_leaderboard(&prs, LeaderboardFormat::Csv);
+        assert!(csv.starts_with("rank,name,title,risk,score,delta,findings\n1,acme/web#2,PR 2,MEDIUM,12,,4\n"), "{csv}");
+        assert_eq!(Scores::of(&summary).scores["acme/web#2"], 12);
+    }
+
+    #[test]
+    fn test_csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("Fix login"), "Fix login");
+        assert_eq!(csv_field("Fix \"a, b\""), "\"Fix \"\"a, b\"\"\"");
+    }
+}
@@ -0,0 +1,112 @@
+//! Path matching for every setting that selects files by glob: `ignore`,
+//! `[[rules]] files`, `[style] test_paths` and `layers` (globs for one
+//! directory name), `[security] auth_paths` and `allow_paths`, and the
+//! `[process]` path lists. They all follow the same rules, close to
+//! `.gitignore`:
+//!
+//! - `**` spans any number of directories; `*` and `?` stay within one
+//! - a glob without `/` matches the file name at any depth (`*.min.js`)
+//! - a leading `/` anchors a glob at the repository root (`/Dockerfile`)
+//! - `/` and `\` both separate directories, in globs and in paths
+//! - case matters, as it does to git: `Dockerfile` is not `dockerfile`
+//!
+//! Paths come from the change under review, so matching must stay cheap
+//! whatever they hold: it takes at most pattern × path steps, however many
+//! stars the glob has.
+
+/// Final component of a path. Accepts `/` and `\` separators so analyzers
+/// work on any `PullRequest`, not only ones built by `parse_diff`.
+pub fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Whether `path` matches the glob `pattern`.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    if !pattern.contains(['/', '\\']) {
+        return match_segment(pattern.as_bytes(), file_name(path).as_bytes());
+    }
+    match_segments(&segments(pattern), &segments(path))
+}
+
+/// Whether `path` matches any of `patterns`.
+pub fn matches_any(patterns: &[String], path: &str) -> bool {
+    patterns.iter().any(|pattern| matches(pattern, path))
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split(['/', '\\']).filter(|s| !s.is_empty()).collect()
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    wildcard(pattern, path, |segment| *segment == "**", |segment, name| match_segment(segment.as_bytes(), name.as_bytes()))
+}
+
+fn match_segment(pattern: &[u8], name: &[u8]) -> bool {
+    wildcard(pattern, name, |c| *c == b'*', |c, n| *c == b'?' || c == n)
+}
+
+/// Match `text` against `pattern`, where a star item matches any run of
+/// items and every other item matches one (`one`). On a mismatch only the
+/// most recent star takes one more item: a match found through an earlier
+/// star can always be found through the later one, so no other backtracking
+/// is needed.
+fn wildcard<P, T>(pattern: &[P], text: &[T], is_star: impl Fn(&P) -> bool, one: impl Fn(&P, &T) -> bool) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && is_star(&pattern[p]) {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && one(&pattern[p], &text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            star = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(is_star)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_globs_with_directories() {
+        assert!(matches("tests/**", "tests/unit/a.rs"));
+        assert!(!matches("tests/**", "src/tests/a.rs"));
+        assert!(matches("**/*_spec.rb", "spec/models/user_spec.rb"));
+        assert!(matches("**/*_spec.rb", "user_spec.rb"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/pr/diff.rs"));
+        assert!(matches("**/*.test.*", "web/app.test.tsx"));
+        assert!(matches("src\\**", "src/pr/diff.rs"));
+    }
+
+    #[test]
+    fn test_globs_without_directories_match_the_file_name() {
+        assert!(matches("*.min.js", "web/vendor/app.min.js"));
+        assert!(matches("file?.txt", "docs/file1.txt"));
+        assert!(matches("CHANGELOG.md", "CHANGELOG.md"));
+        assert!(!matches("Dockerfile", "dockerfile"));
+        assert!(matches("/Dockerfile", "Dockerfile"));
+        assert!(!matches("/Dockerfile", "dev/Dockerfile"));
+        assert!(matches_any(&["*.lock".to_string(), "vendor/**".to_string()], "vendor\\a\\b.c"));
+    }
+
+    #[test]
+    fn test_many_stars_match_in_polynomial_time() {
+        let name = "a".repeat(5000);
+        assert!(!matches("*a*a*a*a*a*a*a*a*a*a*a*a*b", &name));
+        assert!(matches("*a*a*a*a*a*a*a*a*a*a*a*a*", &name));
+        let deep = vec!["a"; 2000].join("/");
+        assert!(!matches("**/**/**/**/**/**/**/**/**/**/b", &deep));
+        assert!(matches("**/a/**/a/**/a/**/a/**/a", &deep));
+        assert!(matches("a*b?d*", "axxbcdyy"));
+        assert!(!matches("a*b?d", "abd"));
+    }
+}
@@ -0,0 +1,122 @@
+//! `--comment`: the markdown report as a comment on the PR's conversation.
+//!
+//! The comment starts with a hidden marker. A later run finds the comment
+//! by it and edits it, so a PR carries one up-to-date report however often
+//! the job runs. Every create or edit is recorded in the `[audit]` log.
+
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, instrument};
+
+use super::{GitHubClient, GitHubOperation, PrError, PrUrl};
+use crate::audit;
+use crate::config::AuditConfig;
+
+/// First line of every comment this tool posts.
+pub const MARKER: &str = "<!-- pr-analyzer:comment -->";
+
+/// Longest comment body GitHub accepts, in characters.
+const MAX_BODY_CHARS: usize = 65_536;
+
+/// Comments listed per page (the API's maximum).
+const PAGE_SIZE: usize = 100;
+
+/// Post `markdown` as the tool's comment on the PR, or edit the comment an
+/// earlier run posted.
+#[instrument(skip(client, markdown, audit_config), fields(owner = %url.owner, repo = %url.repo, pr = url.pr_number))]
+pub async fn upsert(client: &GitHubClient, url: &PrUrl, markdown: &str, audit_config: &AuditConfig) -> Result<(), PrError> {
+    #[derive(Deserialize)]
+    struct User {
+        login: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Posted {
+        user: User,
+    }
+
+    let payload = json!({ "body": body(markdown) });
+    let api = client.api(&format!("/repos/{}/{}/issues", url.owner, url.repo));
+    let (action, target, response) = match find_comment(client, url).await? {
+        Some(id) => {
+            let target = format!("{}/comments/{}", api, id);
+            debug!(comment = id, "updating existing comment");
+            let response = client.patch_json(&target, &payload, GitHubOperation::PostComment, url).await;
+            ("github_comment_update", target, response)
+        }
+        None => {
+            let target = format!("{}/{}/comments", api, url.pr_number);
+            debug!("creating comment");
+            let response = client.post_json(&target, &payload, GitHubOperation::PostComment, url).await;
+            ("github_comment_create", target, response)
+        }
+    };
+    let posted = match response {
+        Ok(response) => response.json::<Posted>().await.map_err(PrError::from),
+        Err(e) => Err(e),
+    };
+    let actor = posted.as_ref().map_or("unknown", |p| p.user.login.as_str());
+    let error = posted.as_ref().err().map(ToString::to_string);
+    let audited = audit::record(audit_config, audit::Record::new(action, actor, &target, url.pr_number, &payload, error)).await;
+    posted?;
+    audited?;
+    Ok(())
+}
+
+/// Id of the first comment on the PR that starts with `MARKER`.
+async fn find_comment(client: &GitHubClient, url: &PrUrl) -> Result<Option<u64>, PrError> {
+    #[derive(Deserialize)]
+    struct Comment {
+        id: u64,
+        #[serde(default)]
+        body: String,
+    }
+
+    for page in 1.. {
+        let list = client.api(&format!("/repos/{}/{}/issues/{}/comments?per_page={}&page={}", url.owner, url.repo, url.pr_number, PAGE_SIZE, page));
+        let comments: Vec<Comment> = client.get(&list, None, GitHubOperation::ReadPullRequest, url).await?.json().await?;
+        if let Some(comment) = comments.iter().find(|c| c.body.starts_with(MARKER)) {
+            return Ok(Some(comment.id));
+        }
+        if comments.len() < PAGE_SIZE {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+/// The comment text: the marker, then the report. A report over GitHub's
+/// limit is cut at a line end with a note, which also drops its embedded
+/// copy (so `--previous` finds nothing to compare against).
+fn body(markdown: &str) -> String {
+    let mut body = format!("{}\n{}", MARKER, markdown);
+    if body.chars().count() <= MAX_BODY_CHARS {
+        return body;
+    }
+    const NOTE: &str = "\n\n_Report truncated: it is longer than a GitHub comment can be. The full report is in the job output._\n";
+    let keep = MAX_BODY_CHARS - NOTE.chars().count();
+    let cut = body.char_indices().nth(keep).map_or(body.len(), |(i, _)| i);
+    let cut = body[..cut].rfind('\n').unwrap_or(cut);
+    body.truncate(cut);
+    body.push_str(NOTE);
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_starts_with_marker() {
+        assert_eq!(body("# PR #42\n"), "<!-- pr-analyzer:comment -->\n# PR #42\n");
+    }
+
+    #[test]
+    fn test_body_truncated_to_comment_limit() {
+        let markdown = "- **[LOW]** é finding\n".repeat(5_000);
+        let body = body(&markdown);
+        assert!(body.chars().count() <= MAX_BODY_CHARS);
+        assert!(body.starts_with(MARKER));
+        assert!(body.contains("finding\n\n_Report truncated"), "{}", &body[body.len() - 200..]);
+    }
+}
@@ -1,6 +1,10 @@
 use super::types::{DiffFile, Hunk};
 use super::PrError;
 
+/// Largest line number or line count a hunk header may announce. Real files
+/// are far below it; it keeps `new_start + index` from overflowing.
+pub const MAX_LINE_NUMBER: usize = u32::MAX as usize;
+
 /// Parse a unified diff string into a vector of DiffFile structs.
 ///
 /// Codex: Implement unified diff parsing.
@@ -11,6 +15,8 @@ use super::PrError;
 ///
 /// New files have: `--- /dev/null`
 /// Deleted files have: `+++ /dev/null`
+/// Renamed files have `rename from {path}` and `rename to {path}`, usually
+/// after `similarity index {n}%`; a pure rename has no hunks.
 ///
 /// Hunks start with: @@ -{old_start},{old_count} +{new_start},{new_count} @@
 ///
@@ -18,6 +24,14 @@ use super::PrError;
 ///   '+' for additions
 ///   '-' for deletions
 ///   ' ' for context (unchanged)
+///
+/// Patches produced on Windows are accepted: CRLF line endings are stripped
+/// and `\` path separators are normalized to `/`.
+///
+/// The input may be untrusted (stdin, webhooks): no input makes the parser
+/// panic, and it allocates only in proportion to the input. Hunk ranges
+/// beyond MAX_LINE_NUMBER are rejected, so line arithmetic downstream cannot
+/// overflow. Use `parse_diff_bytes` for input that may not be UTF-8.
 pub fn parse_diff(_raw_diff: &str) -> Result<Vec<DiffFile>, PrError> {
     let raw_diff = _raw_diff;
     if raw_diff.trim().is_empty() {
@@ -30,7 +44,7 @@ pub fn parse_diff(_raw_diff: &str) -> Result<Vec<DiffFile>, PrError> {
 
     let finish_hunk = |file: &mut Option<DiffFile>, hunk: &mut Option<Hunk>| {
         if let (Some(file), Some(hunk)) = (file.as_mut(), hunk.take()) {
-            file.hunks.push(hunk);
+            file.hunks_mut().push(hunk);
         }
     };
 
@@ -43,6 +57,7 @@ pub fn parse_diff(_raw_diff: &str) -> Result<Vec<DiffFile>, PrError> {
         };
 
     for line in raw_diff.lines() {
+        let line = line.trim_end_matches('\r');
         if let Some(rest) = line.strip_prefix("diff --git ") {
             finish_file(&mut files, &mut current_file, &mut current_hunk);
             let mut parts = rest.split_whitespace();
@@ -52,19 +67,14 @@ pub fn parse_diff(_raw_diff: &str) -> Result<Vec<DiffFile>, PrError> {
             let b_path = parts
                 .next()
                 .ok_or_else(|| PrError::DiffParse("Missing b/ path in diff header".to_string()))?;
+            let a_path = normalize_path(a_path);
+            let b_path = normalize_path(b_path);
             let path = b_path
                 .strip_prefix("b/")
                 .or_else(|| a_path.strip_prefix("a/"))
-                .unwrap_or(b_path)
+                .unwrap_or(&b_path)
                 .to_string();
-            current_file = Some(DiffFile {
-                path,
-                is_new: false,
-                is_deleted: false,
-                additions: 0,
-                deletions: 0,
-                hunks: Vec::new(),
-            });
+            current_file = Some(DiffFile::empty(path));
             continue;
         }
 
@@ -81,26 +91,45 @@ pub fn parse_diff(_raw_diff: &str) -> Result<Vec<DiffFile>, PrError> {
             continue;
         }
 
-        if line.starts_with("--- ") || line.starts_with("+++ ") {
+        // File headers come before the first hunk; inside a hunk, `--- x` is
+        // a removed line reading `-- x`
+        if current_hunk.is_none() {
             if let Some(file) = current_file.as_mut() {
-                let path = line[4..].trim();
-                if line.starts_with("--- ") && path == "/dev/null" {
-                    file.is_new = true;
+                if let Some(from) = line.strip_prefix("rename from ").map(str::trim).filter(|p| !p.is_empty()) {
+                    file.set_rename(Some(normalize_path(from)), file.similarity());
+                    continue;
                 }
-                if line.starts_with("+++ ") && path == "/dev/null" {
-                    file.is_deleted = true;
+                if let Some(to) = line.strip_prefix("rename to ").map(str::trim).filter(|p| !p.is_empty()) {
+                    file.set_path(normalize_path(to));
+                    continue;
+                }
+                if let Some(index) = line.strip_prefix("similarity index ") {
+                    let previous = file.previous_path().map(str::to_string);
+                    file.set_rename(previous, index.trim().trim_end_matches('%').parse().ok().filter(|s| *s <= 100));
+                    continue;
                 }
             }
-            continue;
+            if let Some(path) = line.strip_prefix("--- ") {
+                if let Some(file) = current_file.as_mut() {
+                    file.set_new(file.is_new() || path.trim() == "/dev/null");
+                }
+                continue;
+            }
+            if let Some(path) = line.strip_prefix("+++ ") {
+                if let Some(file) = current_file.as_mut() {
+                    file.set_deleted(file.is_deleted() || path.trim() == "/dev/null");
+                }
+                continue;
+            }
         }
 
         if let (Some(file), Some(hunk)) = (current_file.as_mut(), current_hunk.as_mut()) {
             if line.starts_with('+') || line.starts_with('-') || line.starts_with(' ') {
                 hunk.lines.push(line.to_string());
-                if line.starts_with('+') && !line.starts_with("+++") {
-                    file.additions += 1;
-                } else if line.starts_with('-') && !line.starts_with("---") {
-                    file.deletions += 1;
+                if line.starts_with('+') {
+                    file.set_counts(file.additions() + 1, file.deletions());
+                } else if line.starts_with('-') {
+                    file.set_counts(file.additions(), file.deletions() + 1);
                 }
             }
         }
@@ -110,6 +139,26 @@ pub fn parse_diff(_raw_diff: &str) -> Result<Vec<DiffFile>, PrError> {
     Ok(files)
 }
 
+/// Parse a diff that may not be valid UTF-8: invalid sequences become
+/// U+FFFD, as GitHub shows them, instead of failing the whole diff.
+pub fn parse_diff_bytes(raw_diff: &[u8]) -> Result<Vec<DiffFile>, PrError> {
+    parse_diff(&String::from_utf8_lossy(raw_diff))
+}
+
+/// Drop the `-- ` signature that `git format-patch` appends after the last
+/// hunk; otherwise its first line would be read as a removed line.
+pub fn strip_signature(patch: &str) -> &str {
+    match patch.rfind("\n-- \n") {
+        Some(pos) => &patch[..pos + 1],
+        None => patch,
+    }
+}
+
+/// Convert Windows `\` separators to `/` so path checks see one form.
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
 fn parse_hunk_header(line: &str) -> Result<(usize, usize, usize, usize), PrError> {
     let header = line
         .trim()
@@ -145,6 +194,9 @@ fn parse_range(part: &str, prefix: char) -> Result<(usize, usize), PrError> {
     let count = count_str.parse::<usize>().map_err(|_| {
         PrError::DiffParse(format!("Invalid range count in {}", part))
     })?;
+    if start > MAX_LINE_NUMBER || count > MAX_LINE_NUMBER {
+        return Err(PrError::DiffParse(format!("Range out of bounds in {}", part)));
+    }
     Ok((start, count))
 }
 
@@ -169,9 +221,9 @@ index abc1234..def5678 100644
     fn test_parse_single_file_diff() {
         let files = parse_diff(SAMPLE_DIFF).unwrap();
         assert_eq!(files.len(), 1);
-        assert_eq!(files[0].path, "src/main.rs");
-        assert_eq!(files[0].additions, 2);
-        assert_eq!(files[0].deletions, 1);
+        assert_eq!(files[0].path(), "src/main.rs");
+        assert_eq!(files[0].additions(), 2);
+        assert_eq!(files[0].deletions(), 1);
     }
 
     #[test]
@@ -187,8 +239,8 @@ index 0000000..e69de29
 "#;
         let files = parse_diff(diff).unwrap();
         assert_eq!(files.len(), 1);
-        assert!(files[0].is_new);
-        assert!(!files[0].is_deleted);
+        assert!(files[0].is_new());
+        assert!(!files[0].is_deleted());
     }
 
     #[test]
@@ -204,8 +256,49 @@ index e69de29..0000000
 "#;
         let files = parse_diff(diff).unwrap();
         assert_eq!(files.len(), 1);
-        assert!(!files[0].is_new);
-        assert!(files[0].is_deleted);
+        assert!(!files[0].is_new());
+        assert!(files[0].is_deleted());
+    }
+
+    #[test]
+    fn test_parse_renamed_file_diff() {
+        let diff = r#"diff --git a/src/old_name.rs b/src/new_name.rs
+similarity index 94%
+rename from src/old_name.rs
+rename to src/new_name.rs
+index abc1234..def5678 100644
+--- a/src/old_name.rs
++++ b/src/new_name.rs
+@@ -1,2 +1,2 @@
+ fn main() {
+-    old();
++    new();
+diff --git a/docs/a.md b/docs/b.md
+similarity index 100%
+rename from docs/a.md
+rename to docs/b.md
+"#;
+        let files = parse_diff(diff).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path(), "src/new_name.rs");
+        assert_eq!(files[0].previous_path(), Some("src/old_name.rs"));
+        assert_eq!(files[0].similarity(), Some(94));
+        assert!(!files[0].is_new() && !files[0].is_deleted());
+        assert_eq!((files[0].additions(), files[0].deletions()), (1, 1));
+        assert_eq!(files[1].previous_path(), Some("docs/a.md"));
+        assert_eq!(files[1].similarity(), Some(100));
+        assert!(files[1].hunks().is_empty());
+    }
+
+    #[test]
+    fn test_parse_windows_diff_with_crlf_and_backslashes() {
+        let diff = "diff --git a\\src\\app\\main.rs b\\src\\app\\main.rs\r\n--- a\\src\\app\\main.rs\r\n+++ b\\src\\app\\main.rs\r\n@@ -1,2 +1,2 @@\r\n fn main() {\r\n-    old();\r\n+    new();\r\n";
+        let files = parse_diff(diff).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path(), "src/app/main.rs");
+        assert_eq!(files[0].additions(), 1);
+        assert_eq!(files[0].deletions(), 1);
+        assert_eq!(files[0].hunks()[0].lines[2], "+    new();");
     }
 
     #[test]
@@ -213,4 +306,71 @@ index e69de29..0000000
         let files = parse_diff("").unwrap();
         assert!(files.is_empty());
     }
+
+    #[test]
+    fn test_format_patch_signature_is_not_a_deletion() {
+        let patch = "Subject: x\n---\ndiff --git a/f b/f\n@@ -1 +1 @@\n-a\n+b\n-- \n2.43.0\n";
+        assert!(strip_signature(patch).ends_with("+b\n"));
+        let files = parse_diff(strip_signature(patch)).unwrap();
+        assert_eq!((files[0].additions(), files[0].deletions()), (1, 1));
+    }
+
+    #[test]
+    fn test_header_like_lines_inside_a_hunk_are_changes() {
+        let diff = "diff --git a/q.sql b/q.sql\n--- a/q.sql\n+++ b/q.sql\n@@ -1,2 +1,2 @@\n--- /dev/null\n+++ /dev/null\n select 1;\n";
+        let files = parse_diff(diff).unwrap();
+        assert!(!files[0].is_new() && !files[0].is_deleted());
+        assert_eq!((files[0].additions(), files[0].deletions()), (1, 1));
+        assert_eq!(files[0].hunks()[0].lines.len(), 3);
+    }
+
+    #[test]
+    fn test_out_of_bounds_ranges_and_invalid_utf8_are_handled() {
+        let huge = format!("diff --git a/f b/f\n@@ -1,1 +{},1 @@\n+x\n", usize::MAX);
+        assert!(matches!(parse_diff(&huge), Err(PrError::DiffParse(_))));
+        let over = format!("diff --git a/f b/f\n@@ -1,{} +1,1 @@\n+x\n", MAX_LINE_NUMBER + 1);
+        assert!(matches!(parse_diff(&over), Err(PrError::DiffParse(_))));
+
+        let files = parse_diff_bytes(b"diff --git a/f b/f\n@@ -1,1 +1,1 @@\n-caf\xe9\n+caf\xc3\xa9\n").unwrap();
+        assert_eq!(files[0].hunks()[0].lines, vec!["-caf\u{fffd}", "+café"]);
+    }
+
+    /// Mutations of real diffs, the way the fuzz target (fuzz/) explores
+    /// them, so every `cargo test` run covers some of that ground.
+    #[test]
+    fn test_mutated_diffs_never_panic() {
+        const BYTES: &[u8] = b"@+- \n\r,0917\\/\xff\xc3diff --git ";
+        let seeds = [SAMPLE_DIFF.as_bytes(), include_bytes!("../../tests/fixtures/sample_diff.patch")];
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound.max(1) as u64) as usize
+        };
+        for round in 0..2000 {
+            let mut input = seeds[round % seeds.len()].to_vec();
+            for _ in 0..1 + next(8) {
+                let at = next(input.len());
+                match next(4) {
+                    0 => input[at] = BYTES[next(BYTES.len())],
+                    1 => input.insert(at, BYTES[next(BYTES.len())]),
+                    2 => drop(input.remove(at)),
+                    _ => input.truncate(at),
+                }
+                if input.is_empty() {
+                    break;
+                }
+            }
+            let Ok(files) = parse_diff_bytes(&input) else {
+                continue;
+            };
+            for file in files {
+                let lines = file.hunks().iter().flat_map(|h| &h.lines);
+                assert_eq!(lines.clone().filter(|l| l.starts_with('+')).count(), file.additions());
+                assert_eq!(lines.filter(|l| l.starts_with('-')).count(), file.deletions());
+                assert!(file.hunks().iter().all(|h| h.new_start <= MAX_LINE_NUMBER && h.old_count <= MAX_LINE_NUMBER));
+            }
+        }
+    }
 }
@@ -0,0 +1,270 @@
+//! Gerrit changes: `https://<host>[/<prefix>]/c/<project>/+/<number>`.
+//!
+//! The current patch set is fetched through the Gerrit REST API and parsed
+//! like a GitHub diff. `--review` posts the report back as a review with a
+//! label vote and inline comments.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, instrument};
+
+use super::types::DiffFile;
+use super::{diff, PrError, PullRequest};
+use crate::audit;
+use crate::config::Config;
+use crate::report::{Catalog, Report};
+
+/// A Gerrit change URL split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeUrl {
+    /// Scheme, host and any path prefix before `/c/`, e.g. `https://review.example.com`
+    pub base: String,
+    /// Project name, may contain `/` (e.g. `chromium/src`)
+    pub project: String,
+    pub number: u64,
+}
+
+impl ChangeUrl {
+    /// `<project>~<number>`, the change id form accepted by every REST endpoint.
+    fn id(&self) -> String {
+        format!("{}~{}", encode_component(&self.project), self.number)
+    }
+
+    /// REST endpoint URL; authenticated requests go through the `/a/` prefix.
+    fn endpoint(&self, authenticated: bool, path: &str) -> String {
+        let auth = if authenticated { "/a" } else { "" };
+        format!("{}{}/changes/{}{}", self.base, auth, self.id(), path)
+    }
+}
+
+/// Parse a Gerrit change URL. A trailing patch set number is ignored; the
+/// current patch set is always analyzed.
+pub fn parse_change_url(url: &str) -> Result<ChangeUrl, PrError> {
+    let invalid = || PrError::InvalidUrl(url.to_string());
+    let parsed = reqwest::Url::parse(url).map_err(|_| invalid())?;
+    let host = parsed.host_str().ok_or_else(invalid)?;
+    let path = parsed.path();
+    let (prefix, rest) = path.split_once("/c/").ok_or_else(invalid)?;
+    let (project, change) = rest.split_once("/+/").ok_or_else(invalid)?;
+    let number = change.split('/').next().and_then(|n| n.parse::<u64>().ok()).ok_or_else(invalid)?;
+    if project.is_empty() {
+        return Err(invalid());
+    }
+    let port = parsed.port().map(|p| format!(":{}", p)).unwrap_or_default();
+    Ok(ChangeUrl {
+        base: format!("{}://{}{}{}", parsed.scheme(), host, port, prefix.trim_end_matches('/')),
+        project: project.to_string(),
+        number,
+    })
+}
+
+/// Fetch the current patch set of a change as a PullRequest.
+#[instrument(skip(config), fields(project = %change.project, change = change.number))]
+pub async fn fetch_change(change: &ChangeUrl, config: &Config) -> Result<PullRequest, PrError> {
+    #[derive(Deserialize)]
+    struct Account {
+        username: Option<String>,
+        name: Option<String>,
+        email: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct ChangeInfo {
+        #[serde(rename = "_number")]
+        number: u64,
+        subject: String,
+        owner: Account,
+        current_revision: Option<String>,
+        insertions: usize,
+        deletions: usize,
+        #[serde(default)]
+        hashtags: Vec<String>,
+    }
+
+    let credentials = config.gerrit_credentials();
+    let client = reqwest::Client::new();
+    let get = |path: &str| {
+        let request = client.get(change.endpoint(credentials.is_some(), path)).header("User-Agent", "pr-analyzer");
+        match &credentials {
+            Some((user, password)) => request.basic_auth(user, Some(password)),
+            None => request,
+        }
+    };
+
+    debug!("fetching change metadata from Gerrit");
+    let body = get("?o=CURRENT_REVISION&o=DETAILED_ACCOUNTS")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(PrError::GerritRequest)?
+        .text()
+        .await
+        .map_err(PrError::GerritRequest)?;
+    let info: ChangeInfo = serde_json::from_str(strip_xssi_prefix(&body)).map_err(|e| PrError::GerritResponse(e.to_string()))?;
+
+    debug!("fetching current patch set from Gerrit");
+    let patch = get("/revisions/current/patch?raw")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(PrError::GerritRequest)?
+        .text()
+        .await
+        .map_err(PrError::GerritRequest)?;
+    let files = diff::parse_diff(diff::strip_signature(&patch))?;
+    debug!(parsed_files = files.len(), "parsed patch set");
+
+    let author = info.owner.username.or(info.owner.name).or(info.owner.email).unwrap_or_else(|| "unknown".to_string());
+    let pr = PullRequest::builder(info.subject)
+        .number(info.number)
+        .author(author)
+        .totals(files.len(), info.insertions, info.deletions)
+        .labels(info.hashtags)
+        .files(files);
+    Ok(match info.current_revision {
+        Some(sha) => pr.head_sha(sha),
+        None => pr,
+    }
+    .build()?)
+}
+
+/// Post the report as a review on the analyzed patch set: a summary
+/// message, a vote on the configured label, and one comment per located
+/// finding, in `catalog`'s language. The attempt is recorded in the
+/// `[audit]` log, failed or not.
+#[instrument(skip(report, diff, config, catalog), fields(project = %change.project, change = change.number))]
+pub async fn post_review(change: &ChangeUrl, report: &Report, diff: &[DiffFile], config: &Config, catalog: &Catalog) -> Result<(), PrError> {
+    let (user, password) = config.gerrit_credentials().ok_or(PrError::MissingGerritCredentials)?;
+    let revision = report.provenance.head_sha.as_deref().unwrap_or("current");
+    let target = change.endpoint(true, &format!("/revisions/{}/review", revision));
+    let body = review_input(report, diff, config, catalog);
+    let posted = reqwest::Client::new()
+        .post(&target)
+        .header("User-Agent", "pr-analyzer")
+        .basic_auth(&user, Some(password))
+        .json(&body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    let error = posted.as_ref().err().map(ToString::to_string);
+    let audited = audit::record(&config.audit, audit::Record::new("gerrit_review", &user, &target, change.number, &body, error)).await;
+    posted.map_err(PrError::GerritRequest)?;
+    audited?;
+    debug!("review posted");
+    Ok(())
+}
+
+/// Body of `POST .../review` (Gerrit `ReviewInput`). Gerrit places a comment
+/// by its line in the patch set's file, so finding lines are mapped through
+/// `diff`; a line past the diff is kept as is.
+fn review_input(report: &Report, diff: &[DiffFile], config: &Config, catalog: &Catalog) -> serde_json::Value {
+    let mut message = format!("pr-analyzer: overall risk {}\n", report.overall_risk);
+    let mut comments: BTreeMap<&str, Vec<serde_json::Value>> = BTreeMap::new();
+    for result in &report.results {
+        message.push_str(&format!("\n{}: {} ({} findings)", result.analyzer_name, result.risk_level, result.findings.len()));
+        for finding in &result.findings {
+            let text = format!("[{}] {}", finding.severity, catalog.message(finding));
+            match &finding.file {
+                Some(file) => {
+                    let mut comment = json!({ "message": text, "unresolved": finding.severity > crate::report::RiskLevel::Low });
+                    if let Some(line) = finding.line {
+                        let line = diff.iter().find(|f| f.path() == file).and_then(|f| f.file_line(line)).unwrap_or(line);
+                        comment["line"] = json!(line);
+                    }
+                    comments.entry(file.as_str()).or_default().push(comment);
+                }
+                None => message.push_str(&format!("\n  - {}", text)),
+            }
+        }
+    }
+    let mut review = json!({ "message": message, "comments": comments });
+    if let Some(vote) = config.gerrit.votes.get(&report.overall_risk) {
+        review["labels"] = json!({ config.gerrit.label.as_str(): vote });
+    }
+    review
+}
+
+/// Gerrit prefixes JSON responses with `)]}'` to defeat XSSI.
+fn strip_xssi_prefix(body: &str) -> &str {
+    body.strip_prefix(")]}'").unwrap_or(body).trim_start()
+}
+
+/// Percent-encode a path component (project names contain `/`).
+fn encode_component(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::types::{AnalysisResult, Finding};
+    use crate::report::RiskLevel;
+
+    #[test]
+    fn test_parse_change_url() {
+        let change = parse_change_url("https://review.example.com/c/chromium/src/+/12345").unwrap();
+        assert_eq!(change.base, "https://review.example.com");
+        assert_eq!(change.project, "chromium/src");
+        assert_eq!(change.number, 12345);
+        assert_eq!(change.endpoint(true, "/detail"), "https://review.example.com/a/changes/chromium%2Fsrc~12345/detail");
+
+        let with_prefix = parse_change_url("https://host:8443/gerrit/c/proj/+/7/3").unwrap();
+        assert_eq!((with_prefix.base.as_str(), with_prefix.number), ("https://host:8443/gerrit", 7));
+    }
+
+    #[test]
+    fn test_parse_change_url_rejects_other_urls() {
+        assert!(parse_change_url("https://github.com/org/repo/pull/42").is_err());
+        assert!(parse_change_url("https://review.example.com/c/+/12").is_err());
+        assert!(parse_change_url("https://review.example.com/c/proj/+/abc").is_err());
+    }
+
+    #[test]
+    fn test_strip_xssi_prefix() {
+        assert_eq!(strip_xssi_prefix(")]}'\n{\"a\":1}"), "{\"a\":1}");
+        assert_eq!(strip_xssi_prefix("{}"), "{}");
+    }
+
+    #[test]
+    fn test_review_input_votes_and_comments() {
+        let pr = PullRequest::builder("t").number(5).author("a").totals(1, 1, 0).head_sha("deadbeef").build().unwrap();
+        let report = crate::report::build(
+            vec![AnalysisResult {
+                analyzer_name: "Security Risk Assessment".to_string(),
+                risk_level: RiskLevel::High,
+                findings: vec![
+                    Finding::new("security.hardcoded_password", RiskLevel::High).in_file("src/a.rs").at_line(3),
+                    Finding::text("3 new dependencies", RiskLevel::Medium),
+                    Finding::new("security.hardcoded_password", RiskLevel::High).in_file("src/b.rs").at_line(40),
+                ],
+            }],
+            &pr,
+        );
+        // Hunk line 3 follows a removed line, so it is line 2 of the saved file.
+        let diff = [DiffFile::builder("src/a.rs").hunk(1, 1, [" a", "-b", "+password = \"x\""]).build().unwrap()];
+        let review = review_input(&report, &diff, &Config::default(), Catalog::english());
+        assert_eq!(review["labels"]["Code-Review"], json!(-1));
+        assert_eq!(review["comments"]["src/a.rs"][0]["line"], json!(2));
+        assert_eq!(review["comments"]["src/b.rs"][0]["line"], json!(40));
+        assert_eq!(review["comments"]["src/a.rs"][0]["message"], json!("[HIGH] Hardcoded password detected"));
+        assert!(review["message"].as_str().unwrap().contains("[MEDIUM] 3 new dependencies"));
+    }
+
+    #[test]
+    fn test_review_input_uses_catalog() {
+        let pr = PullRequest::builder("t").number(5).author("a").build().unwrap();
+        let mut finding = Finding::new("security.hardcoded_password", RiskLevel::High).in_file("src/a.rs").at_line(1);
+        // A message stored by an older version; the catalog's text wins.
+        finding.message = "old text".to_string();
+        let report = crate::report::build(vec![AnalysisResult { analyzer_name: "Security Risk Assessment".to_string(), risk_level: RiskLevel::High, findings: vec![finding] }], &pr);
+        let review = review_input(&report, &[], &Config::default(), Catalog::english());
+        assert_eq!(review["comments"]["src/a.rs"][0]["message"], json!("[HIGH] Hardcoded password detected"));
+    }
+}
@@ -0,0 +1,134 @@
+//! Local input, for checks before anything is pushed: the working tree,
+//! the index, or a commit range of the repository in the current
+//! directory, or a unified diff from a file or stdin.
+//!
+//! No token or remote is needed. The change has no number and no
+//! checkout; pass `--repo .` for whole-file checks.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, instrument};
+
+use super::{diff, PrError, PullRequest};
+use crate::repo::{Checkout, RepoError};
+
+/// What `--local` / `--staged` / `--diff-file` asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalChange {
+    /// Staged and unstaged changes to tracked files
+    Uncommitted,
+    /// Staged changes only
+    Staged,
+    /// Commits in `base..head` or `base...head`; a single revision means `<rev>..HEAD`
+    Range(String),
+    /// A unified diff in a file, or on stdin for `-`
+    DiffFile(PathBuf),
+}
+
+impl LocalChange {
+    /// Title of the report, e.g. "uncommitted changes" or "main..HEAD".
+    fn title(&self) -> String {
+        match self {
+            LocalChange::Uncommitted => "uncommitted changes".to_string(),
+            LocalChange::Staged => "staged changes".to_string(),
+            LocalChange::Range(range) => normalize_range(range),
+            LocalChange::DiffFile(path) if path == Path::new("-") => "diff from stdin".to_string(),
+            LocalChange::DiffFile(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// A range as given, or `<rev>..HEAD` for a single revision.
+fn normalize_range(range: &str) -> String {
+    if range.contains("..") {
+        range.to_string()
+    } else {
+        format!("{}..HEAD", range)
+    }
+}
+
+/// The head end of a range; an empty end means `HEAD`, as in git.
+fn range_head(range: &str) -> &str {
+    let head = range.rsplit_once("..").map_or(range, |(_, head)| head.trim_start_matches('.'));
+    if head.is_empty() {
+        "HEAD"
+    } else {
+        head
+    }
+}
+
+/// Read the local change as a PullRequest.
+#[instrument]
+pub fn load(change: &LocalChange) -> Result<PullRequest, PrError> {
+    let current = || Checkout::open(&std::env::current_dir().map_err(RepoError::from)?);
+    let (text, head_sha, author) = match change {
+        LocalChange::DiffFile(path) => (read_diff(path)?, None, String::new()),
+        LocalChange::Uncommitted => (current()?.uncommitted_diff()?, None, String::new()),
+        LocalChange::Staged => (current()?.staged_diff()?, None, String::new()),
+        LocalChange::Range(range) => {
+            // Anything git would take as an option is not a range.
+            if range.starts_with('-') {
+                return Err(PrError::InvalidRange(range.clone()));
+            }
+            let checkout = current()?;
+            let range = normalize_range(range);
+            let head = range_head(&range);
+            (checkout.range_diff(&range)?, Some(checkout.resolve(head)?), checkout.commit_author(head)?)
+        }
+    };
+    let files = diff::parse_diff(&text)?;
+    debug!(parsed_files = files.len(), "parsed local diff");
+    let pr = PullRequest::builder(change.title()).author(author).files(files);
+    Ok(match head_sha {
+        Some(sha) => pr.head_sha(sha),
+        None => pr,
+    }
+    .build()?)
+}
+
+/// The contents of a diff file, or of stdin for `-`.
+/// Bytes that are not UTF-8 (a Latin-1 source file, a binary patch) are
+/// replaced rather than failing the read.
+fn read_diff(path: &Path) -> Result<String, PrError> {
+    let bytes = if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes).map_err(PrError::DiffRead)?;
+        bytes
+    } else {
+        std::fs::read(path).map_err(PrError::DiffRead)?
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(normalize_range("HEAD~3"), "HEAD~3..HEAD");
+        assert_eq!(normalize_range("main...feature"), "main...feature");
+        assert_eq!(range_head("main..feature"), "feature");
+        assert_eq!(range_head("main...feature"), "feature");
+        assert_eq!(range_head("main.."), "HEAD");
+    }
+
+    #[test]
+    fn test_load_diff_file() {
+        let path = std::env::temp_dir().join("test_local_change.diff");
+        std::fs::write(&path, include_str!("../../tests/fixtures/sample_diff.patch")).unwrap();
+        let pr = load(&LocalChange::DiffFile(path.clone())).unwrap();
+        assert_eq!(pr.title(), path.display().to_string());
+        assert!(!pr.files().is_empty());
+        assert_eq!(pr.additions(), pr.files().iter().map(|f| f.additions()).sum::<usize>());
+        std::fs::remove_file(&path).ok();
+
+        assert!(matches!(load(&LocalChange::DiffFile(PathBuf::from("no-such.diff"))), Err(PrError::DiffRead(_))));
+    }
+
+    #[test]
+    fn test_range_cannot_be_an_option() {
+        assert!(matches!(load(&LocalChange::Range("--output=x".to_string())), Err(PrError::InvalidRange(_))));
+    }
+}
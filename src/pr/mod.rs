@@ -1,10 +1,18 @@
+pub mod comment;
 pub mod diff;
+pub mod gerrit;
+pub mod local;
+pub mod review;
+pub mod series;
+pub mod tokens;
 pub mod types;
 
 pub use types::{PrUrl, PullRequest};
 
 use thiserror::Error;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
+
+use crate::events::{AnalysisEvent, Events};
 
 #[derive(Debug, Error)]
 pub enum PrError {
@@ -19,18 +27,132 @@ pub enum PrError {
 
     #[error("GitHub token not found in environment")]
     MissingToken,
+
+    #[error("Pull request {0} not found: check the URL, and that the token can read the repository")]
+    NotFound(String),
+
+    #[error("Organization {0} not found: check the name, and that the token can read it")]
+    OrgNotFound(String),
+
+    #[error("GitHub rejected the token (401 Unauthorized): it may be expired or revoked; run `pr-analyzer doctor` to check it")]
+    Unauthorized,
+
+    #[error("GitHub denied access (403 Forbidden) to {operation}: {message}; {advice}")]
+    Forbidden { operation: &'static str, message: String, advice: String },
+
+    #[error("GitHub rate limit exceeded; resets in {reset_in_minutes} min. Wait, or add tokens to [github] tokens (GITHUB_TOKENS)")]
+    RateLimited { reset_in_minutes: u64 },
+
+    #[error("GitHub will not render this diff: {0}. Analyze it locally instead: `git format-patch <base>..<head> -o outgoing/ && pr-analyzer outgoing/`")]
+    DiffTooLarge(String),
+
+    #[error("GitHub API returned {status}: {message}")]
+    GitHubResponse { status: u16, message: String },
+
+    #[error("Gerrit API request failed: {0}")]
+    GerritRequest(reqwest::Error),
+
+    #[error("Unexpected Gerrit response: {0}")]
+    GerritResponse(String),
+
+    #[error("Failed to read patch series: {0}")]
+    SeriesRead(std::io::Error),
+
+    #[error("Gerrit credentials not found (set GERRIT_USERNAME and GERRIT_HTTP_PASSWORD)")]
+    MissingGerritCredentials,
+
+    #[error("Failed to read local changes: {0}")]
+    Local(#[from] crate::repo::RepoError),
+
+    #[error("Invalid commit range: {0}")]
+    InvalidRange(String),
+
+    #[error("Failed to read diff: {0}")]
+    DiffRead(std::io::Error),
+
+    #[error("Write was made but not audited: {0}")]
+    Audit(#[from] crate::audit::AuditError),
+
+    #[error("Inconsistent change data: {0}")]
+    Build(#[from] types::BuildError),
+}
+
+/// Where the change under analysis lives.
+#[derive(Debug, Clone)]
+pub enum ChangeSource {
+    GitHub(PrUrl),
+    Gerrit(gerrit::ChangeUrl),
+    /// Local mbox file or directory of `git format-patch` files
+    Series(std::path::PathBuf),
+    /// Changes in the current repository or a diff file (`--local`, `--diff-file`)
+    Local(local::LocalChange),
+}
+
+impl ChangeSource {
+    /// Recognize a GitHub PR URL on `github_host` (see `Config::github_host`),
+    /// a Gerrit change URL, or an existing local path to a patch series.
+    pub fn parse(url: &str, github_host: &str) -> Result<Self, PrError> {
+        parse_pr_url(url, github_host)
+            .map(ChangeSource::GitHub)
+            .or_else(|_| gerrit::parse_change_url(url).map(ChangeSource::Gerrit))
+            .or_else(|e| {
+                let path = std::path::Path::new(url);
+                if path.exists() {
+                    Ok(ChangeSource::Series(path.to_path_buf()))
+                } else {
+                    Err(e)
+                }
+            })
+    }
+
+    /// `owner/repo` for GitHub, `<host>/<project>` for Gerrit, None for local input.
+    pub fn repo_name(&self) -> Option<String> {
+        match self {
+            ChangeSource::GitHub(url) => Some(format!("{}/{}", url.owner, url.repo)),
+            ChangeSource::Gerrit(change) => {
+                let host = change.base.split_once("://").map_or(change.base.as_str(), |(_, rest)| rest);
+                Some(format!("{}/{}", host, change.project))
+            }
+            ChangeSource::Series(_) | ChangeSource::Local(_) => None,
+        }
+    }
+
+    /// Fetch metadata and diff from the matching API.
+    pub async fn fetch(&self, config: &crate::config::Config) -> Result<PullRequest, PrError> {
+        match self {
+            ChangeSource::GitHub(url) => fetch_pull_request(url, config).await,
+            ChangeSource::Gerrit(change) => gerrit::fetch_change(change, config).await,
+            ChangeSource::Series(path) => series::load(path),
+            ChangeSource::Local(change) => local::load(change),
+        }
+    }
+
+    /// `fetch`, reporting its start and end to `events`.
+    pub async fn fetch_with_events(&self, config: &crate::config::Config, events: Events<'_>) -> Result<PullRequest, PrError> {
+        events(AnalysisEvent::FetchStarted { repo: self.repo_name() });
+        let pr = self.fetch(config).await?;
+        events(AnalysisEvent::FetchFinished { files: pr.files_changed(), additions: pr.additions(), deletions: pr.deletions() });
+        Ok(pr)
+    }
 }
 
 /// Parse a GitHub PR URL into its component parts.
 ///
 /// Codex: Implement URL parsing.
-/// Expected format: https://github.com/{owner}/{repo}/pull/{number}
+/// Expected format: https://{host}/{owner}/{repo}/pull/{number}, where
+/// `host` is github.com or a GitHub Enterprise Server host (with its port,
+/// if any).
 /// Return PrError::InvalidUrl for malformed URLs.
-pub fn parse_pr_url(_url: &str) -> Result<PrUrl, PrError> {
+pub fn parse_pr_url(_url: &str, host: &str) -> Result<PrUrl, PrError> {
     let parsed = reqwest::Url::parse(_url)
         .map_err(|_| PrError::InvalidUrl(_url.to_string()))?;
 
-    if parsed.host_str() != Some("github.com") {
+    let url_host = match (parsed.host_str(), parsed.port()) {
+        (Some(name), Some(port)) => format!("{}:{}", name, port),
+        (Some(name), None) => name.to_string(),
+        (None, _) => return Err(PrError::InvalidUrl(_url.to_string())),
+    };
+    if !url_host.eq_ignore_ascii_case(host) {
         return Err(PrError::InvalidUrl(_url.to_string()));
     }
 
@@ -68,64 +190,348 @@ pub async fn fetch_pull_request(
     _pr_url: &PrUrl,
     _config: &crate::config::Config,
 ) -> Result<PullRequest, PrError> {
-    let token = _config.github_token().ok_or(PrError::MissingToken)?;
-    let client = reqwest::Client::new();
-    let base_url = format!(
-        "https://api.github.com/repos/{}/{}/pulls/{}",
-        _pr_url.owner, _pr_url.repo, _pr_url.pr_number
-    );
+    fetch_pull_request_with(&GitHubClient::for_reads(_config)?, _pr_url).await
+}
+
+/// `fetch_pull_request` with an existing client, so a scan of many PRs
+/// shares one token pool.
+pub async fn fetch_pull_request_with(client: &GitHubClient, pr_url: &PrUrl) -> Result<PullRequest, PrError> {
+    let base_url = client.api(&format!(
+        "/repos/{}/{}/pulls/{}",
+        pr_url.owner, pr_url.repo, pr_url.pr_number
+    ));
 
     #[derive(serde::Deserialize)]
     struct User {
         login: String,
     }
 
+    #[derive(serde::Deserialize)]
+    struct Repo {
+        full_name: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Head {
+        sha: String,
+        r#ref: String,
+        /// None when the fork has been deleted
+        repo: Option<Repo>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Base {
+        repo: Repo,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Label {
+        name: String,
+    }
+
     #[derive(serde::Deserialize)]
     struct PullResponse {
         number: u64,
         title: String,
         user: User,
+        head: Head,
+        base: Base,
+        #[serde(default)]
+        labels: Vec<Label>,
         changed_files: usize,
         additions: usize,
         deletions: usize,
     }
 
     debug!("fetching PR metadata from GitHub API");
-    let response = client
-        .get(&base_url)
-        .header("User-Agent", "pr-analyzer")
-        .bearer_auth(&token)
-        .send()
-        .await?
-        .error_for_status()?;
+    let response = client.get(&base_url, None, GitHubOperation::ReadPullRequest, pr_url).await?;
 
     let metadata = response.json::<PullResponse>().await?;
     debug!(title = %metadata.title, changed_files = metadata.changed_files, "received PR metadata");
 
     debug!("fetching PR diff from GitHub API");
-    let diff_text = client
-        .get(&base_url)
-        .header("User-Agent", "pr-analyzer")
-        .bearer_auth(&token)
-        .header("Accept", "application/vnd.github.diff")
-        .send()
-        .await?
-        .error_for_status()?
-        .text()
-        .await?;
+    let response = client.get(&base_url, Some("application/vnd.github.diff"), GitHubOperation::ReadPullRequest, pr_url).await?;
+    let diff_text = response.text().await?;
     debug!(diff_bytes = diff_text.len(), "received PR diff");
 
     let files = diff::parse_diff(&diff_text)?;
     debug!(parsed_files = files.len(), "parsed diff");
 
-    Ok(PullRequest {
-        number: metadata.number,
-        title: metadata.title,
-        author: metadata.user.login,
-        files_changed: metadata.changed_files,
-        additions: metadata.additions,
-        deletions: metadata.deletions,
-        files,
+    let fork = metadata.head.repo.as_ref().is_none_or(|repo| !repo.full_name.eq_ignore_ascii_case(&metadata.base.repo.full_name));
+    if fork {
+        debug!(base = %metadata.base.repo.full_name, "PR comes from a fork");
+    }
+    let pr = PullRequest::builder(metadata.title)
+        .number(metadata.number)
+        .author(metadata.user.login)
+        .totals(metadata.changed_files, metadata.additions, metadata.deletions)
+        .head_sha(metadata.head.sha)
+        .branch(metadata.head.r#ref)
+        .labels(metadata.labels.into_iter().map(|l| l.name))
+        .files(files);
+    Ok(if fork { pr.fork() } else { pr }.build()?)
+}
+
+/// The PR's current head commit, to tell whether a report about an earlier
+/// head is stale.
+pub async fn current_head_sha(client: &GitHubClient, pr_url: &PrUrl) -> Result<String, PrError> {
+    #[derive(serde::Deserialize)]
+    struct Head {
+        sha: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct PullResponse {
+        head: Head,
+    }
+
+    let url = client.api(&format!("/repos/{}/{}/pulls/{}", pr_url.owner, pr_url.repo, pr_url.pr_number));
+    let pull: PullResponse = client.get(&url, None, GitHubOperation::ReadPullRequest, pr_url).await?.json().await?;
+    Ok(pull.head.sha)
+}
+
+/// A GitHub API call, named so a 403 can say which token permission it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitHubOperation {
+    /// PR metadata and diff
+    ReadPullRequest,
+    /// The repositories of an organization, and the PRs of each (`org scan`)
+    ListRepositories,
+    /// A comment on the PR conversation
+    PostComment,
+    /// A review with inline comments (`--review`)
+    CreateReview,
+}
+
+impl GitHubOperation {
+    fn describe(self) -> &'static str {
+        match self {
+            GitHubOperation::ReadPullRequest => "read the pull request",
+            GitHubOperation::ListRepositories => "list the organization's repositories and pull requests",
+            GitHubOperation::PostComment => "post a PR comment",
+            GitHubOperation::CreateReview => "create a pull request review",
+        }
+    }
+
+    /// Fine-grained token or GitHub App permission the operation needs.
+    fn permission(self) -> &'static str {
+        match self {
+            GitHubOperation::ReadPullRequest => "pull_requests=read",
+            GitHubOperation::ListRepositories => "metadata=read",
+            GitHubOperation::PostComment => "issues=write",
+            GitHubOperation::CreateReview => "pull_requests=write",
+        }
+    }
+}
+
+/// Authenticated GitHub REST client. Every response goes through
+/// `check_github_status`, so a failure on any operation, read or write,
+/// is explained the same way. With several tokens, a request that finds
+/// its token's rate limit exhausted is retried with the next one.
+pub struct GitHubClient {
+    http: reqwest::Client,
+    tokens: tokens::TokenPool,
+    /// REST API root, without a trailing slash
+    api_url: String,
+}
+
+impl GitHubClient {
+    pub fn new(token: String) -> Self {
+        Self::with_tokens(vec![token])
+    }
+
+    /// A client that rotates among `tokens`, which must not be empty.
+    pub fn with_tokens(tokens: Vec<String>) -> Self {
+        Self { http: reqwest::Client::new(), tokens: tokens::TokenPool::new(tokens), api_url: crate::config::DEFAULT_GITHUB_API_URL.to_string() }
+    }
+
+    /// Send requests to the API at `api_url` (e.g. GitHub Enterprise
+    /// Server's `https://<host>/api/v3`) instead of api.github.com.
+    pub fn at(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// A client with every token of `config` (see `Config::github_tokens`),
+    /// for reads. Writes use `Config::github_token` alone, so they always
+    /// come from the same account.
+    pub fn for_reads(config: &crate::config::Config) -> Result<Self, PrError> {
+        let tokens = config.github_tokens();
+        if tokens.is_empty() {
+            return Err(PrError::MissingToken);
+        }
+        Ok(Self::with_tokens(tokens).at(&config.github_api_url()))
+    }
+
+    /// A client with `config`'s primary token only, for writes.
+    pub fn for_writes(config: &crate::config::Config) -> Result<Self, PrError> {
+        let token = config.github_token().ok_or(PrError::MissingToken)?;
+        Ok(Self::new(token).at(&config.github_api_url()))
+    }
+
+    /// Full URL of the API endpoint at `path` (starting with `/`).
+    pub fn api(&self, path: &str) -> String {
+        format!("{}{}", self.api_url, path)
+    }
+
+    /// Send the request `build` makes with a token from the pool, recording
+    /// the token's quota from the response.
+    async fn send(&self, build: impl Fn() -> reqwest::RequestBuilder, operation: GitHubOperation, pr_url: &PrUrl) -> Result<reqwest::Response, PrError> {
+        let mut attempts = 1;
+        loop {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            let (index, token) = self.tokens.pick(now);
+            let response = build().header("User-Agent", "pr-analyzer").bearer_auth(token).send().await?;
+            let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<u64>().ok());
+            let remaining = header("x-ratelimit-remaining");
+            self.tokens.record(index, remaining, header("x-ratelimit-reset"));
+            let exhausted = remaining == Some(0) && matches!(response.status().as_u16(), 403 | 429);
+            if exhausted && attempts < self.tokens.len() && self.tokens.has_other(index, now) {
+                warn!(token = index + 1, tokens = self.tokens.len(), "GitHub rate limit exhausted; switching to the next token");
+                attempts += 1;
+                continue;
+            }
+            return check_github_status(response, operation, pr_url).await;
+        }
+    }
+
+    /// GET `url`, optionally with an `Accept` media type.
+    pub async fn get(&self, url: &str, accept: Option<&str>, operation: GitHubOperation, pr_url: &PrUrl) -> Result<reqwest::Response, PrError> {
+        let build = || {
+            let request = self.http.get(url);
+            match accept {
+                Some(accept) => request.header("Accept", accept),
+                None => request,
+            }
+        };
+        self.send(build, operation, pr_url).await
+    }
+
+    /// POST a JSON body to `url`.
+    pub async fn post_json(&self, url: &str, body: &serde_json::Value, operation: GitHubOperation, pr_url: &PrUrl) -> Result<reqwest::Response, PrError> {
+        self.send(|| self.http.post(url).json(body), operation, pr_url).await
+    }
+
+    /// PATCH `url` with a JSON body.
+    pub async fn patch_json(&self, url: &str, body: &serde_json::Value, operation: GitHubOperation, pr_url: &PrUrl) -> Result<reqwest::Response, PrError> {
+        self.send(|| self.http.patch(url).json(body), operation, pr_url).await
+    }
+}
+
+/// Pass a successful response through; turn a failed one into the most
+/// specific `PrError` its status, rate-limit and scope headers and body allow.
+async fn check_github_status(response: reqwest::Response, operation: GitHubOperation, pr_url: &PrUrl) -> Result<reqwest::Response, PrError> {
+    let Err(status_error) = response.error_for_status_ref() else {
+        return Ok(response);
+    };
+    let status = response.status().as_u16();
+    let text = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let header = |name: &str| text(name).and_then(|v| v.parse::<u64>().ok());
+    let rate_limit = RateLimitHeaders {
+        remaining: header("x-ratelimit-remaining"),
+        reset: header("x-ratelimit-reset"),
+        retry_after: header("retry-after"),
+    };
+    let scopes = ScopeHeaders {
+        granted: text("x-oauth-scopes"),
+        accepted: text("x-accepted-oauth-scopes"),
+        accepted_permissions: text("x-accepted-github-permissions"),
+    };
+    let body = response.text().await.unwrap_or_default();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    Err(github_error(status, &rate_limit, &scopes, &body, operation, pr_url, now).unwrap_or(PrError::ApiRequest(status_error)))
+}
+
+struct RateLimitHeaders {
+    remaining: Option<u64>,
+    /// Unix time the window resets
+    reset: Option<u64>,
+    /// Seconds to wait, sent with secondary rate limits
+    retry_after: Option<u64>,
+}
+
+/// What the token has and what the endpoint accepts, as GitHub reports them.
+struct ScopeHeaders {
+    /// `X-OAuth-Scopes`: the token's scopes; sent for classic tokens only
+    granted: Option<String>,
+    /// `X-Accepted-OAuth-Scopes`: classic scopes the endpoint accepts
+    accepted: Option<String>,
+    /// `X-Accepted-GitHub-Permissions`: fine-grained permissions it needs,
+    /// e.g. `issues=write; pull_requests=write` (alternatives split by `;`)
+    accepted_permissions: Option<String>,
+}
+
+/// Classic token scopes, either of which allows every operation the
+/// analyzer performs; named when GitHub does not send `X-Accepted-OAuth-Scopes`.
+const CLASSIC_SCOPES: &[&str] = &["repo", "public_repo"];
+
+/// Split a comma-separated scope header.
+fn scope_list(header: Option<&str>) -> Vec<&str> {
+    header.map_or(Vec::new(), |h| h.split(',').map(str::trim).filter(|s| !s.is_empty()).collect())
+}
+
+/// Which scopes or permissions the token is missing for `operation`.
+fn missing_permissions(operation: GitHubOperation, scopes: &ScopeHeaders, message: &str) -> String {
+    if message.contains("SAML") || message.contains("SSO") {
+        return "authorize the token for this organization's SAML SSO (token settings > Configure SSO)".to_string();
+    }
+    let Some(granted) = scopes.granted.as_deref() else {
+        let needed: Vec<&str> = match scopes.accepted_permissions.as_deref() {
+            Some(header) => header.split(';').map(str::trim).filter(|p| !p.is_empty()).collect(),
+            None => vec![operation.permission()],
+        };
+        return format!("a fine-grained token or GitHub App needs the `{}` permission on this repository", needed.join("` or `"));
+    };
+    let granted = scope_list(Some(granted));
+    let accepted = scope_list(scopes.accepted.as_deref());
+    let needed: Vec<&str> = if accepted.is_empty() { CLASSIC_SCOPES.to_vec() } else { accepted };
+    let has = if granted.is_empty() { "no scopes".to_string() } else { format!("scopes {}", granted.join(", ")) };
+    if needed.iter().any(|s| granted.contains(s)) {
+        format!("the token has {}, which should suffice; check that its owner can access the repository", has)
+    } else {
+        format!("the token has {}; add `{}`", has, needed.join("` or `"))
+    }
+}
+
+/// Classify a failed GitHub response. None when there is nothing more
+/// useful to say than the status line.
+fn github_error(status: u16, rate_limit: &RateLimitHeaders, scopes: &ScopeHeaders, body: &str, operation: GitHubOperation, pr_url: &PrUrl, now: u64) -> Option<PrError> {
+    let message = github_message(body);
+    let rate_limited = rate_limit.remaining == Some(0)
+        || rate_limit.retry_after.is_some()
+        || message.as_deref().is_some_and(|m| m.to_ascii_lowercase().contains("rate limit"));
+    match status {
+        401 => Some(PrError::Unauthorized),
+        403 | 429 if rate_limited => {
+            let wait = rate_limit.retry_after.or_else(|| rate_limit.reset.map(|reset| reset.saturating_sub(now))).unwrap_or(0);
+            Some(PrError::RateLimited { reset_in_minutes: wait.div_ceil(60) })
+        }
+        403 => {
+            let message = message.unwrap_or_else(|| "no reason given".to_string());
+            let advice = missing_permissions(operation, scopes, &message);
+            Some(PrError::Forbidden { operation: operation.describe(), message, advice })
+        }
+        404 => Some(PrError::NotFound(format!("{}/{}#{}", pr_url.owner, pr_url.repo, pr_url.pr_number))),
+        406 | 422 if body.contains("too_large") || body.contains("diff exceeded") => {
+            Some(PrError::DiffTooLarge(message.unwrap_or_else(|| "the diff is too large".to_string())))
+        }
+        _ => message.map(|message| PrError::GitHubResponse { status, message }),
+    }
+}
+
+/// The `message` of a GitHub error body, plus the first `errors[].message`
+/// when present (that is where 422s explain themselves).
+fn github_message(body: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(body).ok()?;
+    let message = json.get("message")?.as_str()?.to_string();
+    let detail = json
+        .get("errors")
+        .and_then(|e| e.get(0))
+        .and_then(|e| e.get("message").or_else(|| e.get("code")))
+        .and_then(|m| m.as_str());
+    Some(match detail {
+        Some(detail) => format!("{} ({})", message, detail),
+        None => message,
     })
 }
 
@@ -135,16 +541,102 @@ mod tests {
 
     #[test]
     fn test_parse_valid_pr_url() {
-        let url = parse_pr_url("https://github.com/org/repo/pull/42").unwrap();
+        let url = parse_pr_url("https://github.com/org/repo/pull/42", "github.com").unwrap();
         assert_eq!(url.owner, "org");
         assert_eq!(url.repo, "repo");
         assert_eq!(url.pr_number, 42);
     }
 
+    #[test]
+    fn test_parse_enterprise_pr_url_on_the_configured_host() {
+        let url = parse_pr_url("https://ghe.example.com/org/repo/pull/7", "ghe.example.com").unwrap();
+        assert_eq!((url.owner.as_str(), url.pr_number), ("org", 7));
+        assert!(parse_pr_url("https://ghe.example.com:8443/org/repo/pull/7", "ghe.example.com:8443").is_ok());
+        assert!(parse_pr_url("https://ghe.example.com/org/repo/pull/7", "github.com").is_err());
+        assert!(parse_pr_url("https://github.com/org/repo/pull/7", "ghe.example.com").is_err());
+
+        let client = GitHubClient::new("t".to_string()).at("https://ghe.example.com/api/v3/");
+        assert_eq!(client.api("/repos/org/repo/pulls/7"), "https://ghe.example.com/api/v3/repos/org/repo/pulls/7");
+    }
+
+    #[test]
+    fn test_change_source_recognizes_github_and_gerrit() {
+        assert!(matches!(ChangeSource::parse("https://github.com/org/repo/pull/42", "github.com"), Ok(ChangeSource::GitHub(_))));
+        let gerrit = ChangeSource::parse("https://review.example.com/c/infra/tools/+/99", "github.com").unwrap();
+        assert!(matches!(gerrit, ChangeSource::Gerrit(_)));
+        assert_eq!(gerrit.repo_name().as_deref(), Some("review.example.com/infra/tools"));
+        assert!(ChangeSource::parse("https://example.com/org/repo", "github.com").is_err());
+        assert!(matches!(ChangeSource::parse("tests/fixtures/sample_diff.patch", "github.com"), Ok(ChangeSource::Series(_))));
+    }
+
+    #[test]
+    fn test_github_errors_map_to_specific_variants() {
+        let url = parse_pr_url("https://github.com/org/repo/pull/42", "github.com").unwrap();
+        let no_limit = RateLimitHeaders { remaining: None, reset: None, retry_after: None };
+        let no_scopes = ScopeHeaders { granted: None, accepted: None, accepted_permissions: None };
+        let map = |status, headers: &RateLimitHeaders, body| github_error(status, headers, &no_scopes, body, GitHubOperation::ReadPullRequest, &url, 1_000);
+
+        assert!(matches!(map(401, &no_limit, r#"{"message":"Bad credentials"}"#), Some(PrError::Unauthorized)));
+        let not_found = map(404, &no_limit, r#"{"message":"Not Found"}"#).unwrap();
+        assert!(not_found.to_string().contains("org/repo#42"));
+        let saml = map(403, &no_limit, r#"{"message":"Resource protected by organization SAML enforcement"}"#).unwrap();
+        assert!(matches!(&saml, PrError::Forbidden { message, advice, .. } if message.contains("SAML") && advice.contains("SSO")));
+
+        let exhausted = RateLimitHeaders { remaining: Some(0), reset: Some(1_000 + 125), retry_after: None };
+        assert!(matches!(map(403, &exhausted, "{}"), Some(PrError::RateLimited { reset_in_minutes: 3 })));
+        let secondary = RateLimitHeaders { remaining: None, reset: None, retry_after: Some(60) };
+        assert!(matches!(map(429, &secondary, ""), Some(PrError::RateLimited { reset_in_minutes: 1 })));
+
+        let too_large = r#"{"message":"Sorry, the diff exceeded the maximum number of lines (20000)","errors":[{"resource":"PullRequest","field":"diff","code":"too_large"}]}"#;
+        assert!(matches!(map(406, &no_limit, too_large), Some(PrError::DiffTooLarge(m)) if m.contains("20000")));
+
+        let unprocessable = r#"{"message":"Validation Failed","errors":[{"message":"No commit found for SHA: abc"}]}"#;
+        let err = map(422, &no_limit, unprocessable).unwrap();
+        assert_eq!(err.to_string(), "GitHub API returned 422: Validation Failed (No commit found for SHA: abc)");
+        assert!(map(500, &no_limit, "<html>").is_none());
+    }
+
+    #[test]
+    fn test_forbidden_names_missing_scopes_for_the_operation() {
+        let url = parse_pr_url("https://github.com/org/repo/pull/42", "github.com").unwrap();
+        let no_limit = RateLimitHeaders { remaining: None, reset: None, retry_after: None };
+        let forbidden = |operation, granted: Option<&str>, accepted: Option<&str>, permissions: Option<&str>| {
+            let scopes = ScopeHeaders { granted: granted.map(str::to_string), accepted: accepted.map(str::to_string), accepted_permissions: permissions.map(str::to_string) };
+            github_error(403, &no_limit, &scopes, r#"{"message":"Resource not accessible by integration"}"#, operation, &url, 0).unwrap().to_string()
+        };
+
+        assert_eq!(
+            forbidden(GitHubOperation::PostComment, Some("read:org, gist"), Some("repo, public_repo"), None),
+            "GitHub denied access (403 Forbidden) to post a PR comment: Resource not accessible by integration; the token has scopes read:org, gist; add `repo` or `public_repo`"
+        );
+        assert!(forbidden(GitHubOperation::PostComment, Some(""), None, None).ends_with("the token has no scopes; add `repo` or `public_repo`"));
+        assert!(forbidden(GitHubOperation::PostComment, None, None, Some("issues=write; pull_requests=write")).ends_with("needs the `issues=write` or `pull_requests=write` permission on this repository"));
+        assert!(forbidden(GitHubOperation::ReadPullRequest, None, None, None).contains("`pull_requests=read`"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_with_events_reports_start_and_end() {
+        let seen = std::sync::Mutex::new(Vec::new());
+        let record = |event: AnalysisEvent| seen.lock().unwrap().push(event);
+        let path = std::env::temp_dir().join("test_fetch_with_events.diff");
+        std::fs::write(&path, include_str!("../../tests/fixtures/sample_diff.patch")).unwrap();
+        let config = crate::config::Config::default();
+        let pr = ChangeSource::Local(local::LocalChange::DiffFile(path.clone())).fetch_with_events(&config, &record).await.unwrap();
+        std::fs::remove_file(&path).ok();
+        let missing = ChangeSource::Local(local::LocalChange::DiffFile("no-such.diff".into()));
+        assert!(missing.fetch_with_events(&config, &record).await.is_err());
+
+        let seen = seen.into_inner().unwrap();
+        assert!(matches!(seen[0], AnalysisEvent::FetchStarted { repo: None }));
+        assert!(matches!(seen[1], AnalysisEvent::FetchFinished { files, additions, deletions }
+            if (files, additions, deletions) == (pr.files_changed(), pr.additions(), pr.deletions())));
+        assert!(matches!(seen[2..], [AnalysisEvent::FetchStarted { .. }]), "a failed fetch has no FetchFinished");
+    }
+
     #[test]
     fn test_parse_invalid_pr_url() {
-        assert!(parse_pr_url("https://example.com").is_err());
-        assert!(parse_pr_url("not-a-url").is_err());
-        assert!(parse_pr_url("https://github.com/org/repo/pulls/42").is_err());
+        assert!(parse_pr_url("https://example.com", "github.com").is_err());
+        assert!(parse_pr_url("not-a-url", "github.com").is_err());
+        assert!(parse_pr_url("https://github.com/org/repo/pulls/42", "github.com").is_err());
     }
 }
@@ -0,0 +1,151 @@
+//! `--review` on GitHub PRs: the report as a pull request review, with an
+//! inline comment on the diff for every finding that has a file and line.
+//!
+//! GitHub places review comments by diff position: the line's offset below
+//! the file's first `@@` header, each later header counting as a line.
+//! Findings it cannot place (no line, or a line outside the diff) are
+//! listed in the review body instead, since one bad position fails the
+//! whole review.
+
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, instrument};
+
+use super::types::DiffFile;
+use super::{GitHubClient, GitHubOperation, PrError, PrUrl};
+use crate::audit;
+use crate::config::Config;
+use crate::report::{Finding, Report};
+
+/// Post the report as a review on the PR's head commit.
+#[instrument(skip_all, fields(owner = %url.owner, repo = %url.repo, pr = url.pr_number))]
+pub async fn post_review(url: &PrUrl, report: &Report, diff: &[DiffFile], config: &Config) -> Result<(), PrError> {
+    #[derive(Deserialize)]
+    struct User {
+        login: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Posted {
+        user: User,
+    }
+
+    let client = GitHubClient::for_writes(config)?;
+    let target = client.api(&format!("/repos/{}/{}/pulls/{}/reviews", url.owner, url.repo, url.pr_number));
+    let body = review_input(report, diff);
+    let posted = match client.post_json(&target, &body, GitHubOperation::CreateReview, url).await {
+        Ok(response) => response.json::<Posted>().await.map_err(PrError::from),
+        Err(e) => Err(e),
+    };
+    let actor = posted.as_ref().map_or("unknown", |p| p.user.login.as_str());
+    let error = posted.as_ref().err().map(ToString::to_string);
+    let audited = audit::record(&config.audit, audit::Record::new("github_review", actor, &target, url.pr_number, &body, error)).await;
+    posted?;
+    audited?;
+    debug!("review posted");
+    Ok(())
+}
+
+/// Body of `POST .../pulls/{n}/reviews`. The review only comments; it
+/// neither approves nor requests changes.
+fn review_input(report: &Report, diff: &[DiffFile]) -> serde_json::Value {
+    let mut message = format!("pr-analyzer: overall risk {}\n", report.overall_risk);
+    let mut comments = Vec::new();
+    for result in &report.results {
+        message.push_str(&format!("\n{}: {} ({} findings)", result.analyzer_name, result.risk_level, result.findings.len()));
+        for finding in &result.findings {
+            let placed = finding.file.as_deref().zip(finding.line).and_then(|(path, line)| {
+                let file = diff.iter().find(|f| f.path() == path)?;
+                Some((path, position(file, line)?))
+            });
+            match placed {
+                Some((path, position)) => comments.push(json!({ "path": path, "position": position, "body": text(finding) })),
+                None => {
+                    let location = match (&finding.file, finding.line) {
+                        (Some(file), Some(line)) => format!(" (`{}:{}`)", file, line),
+                        (Some(file), None) => format!(" (`{}`)", file),
+                        _ => String::new(),
+                    };
+                    message.push_str(&format!("\n  - {}{}", text(finding), location));
+                }
+            }
+        }
+    }
+    let mut review = json!({ "body": message, "event": "COMMENT", "comments": comments });
+    if let Some(sha) = &report.provenance.head_sha {
+        review["commit_id"] = json!(sha);
+    }
+    review
+}
+
+/// A finding as comment text, with its rule code and a link to the rule's
+/// documentation.
+fn text(finding: &Finding) -> String {
+    let mut text = format!("**[{}]** {}", finding.severity, finding.message);
+    if let Some(code) = &finding.rule_code {
+        text.push_str(&format!(" `{}`", code));
+    }
+    if let Some(url) = &finding.docs_url {
+        text.push_str(&format!(" [docs]({})", url));
+    }
+    text
+}
+
+/// Diff position of a finding's line in `file`. Analyzers number lines as
+/// the hunk's `new_start` plus the index in the hunk, so the index is
+/// recovered from the first hunk it falls in.
+fn position(file: &DiffFile, line: usize) -> Option<usize> {
+    let mut offset = 0;
+    for hunk in file.hunks() {
+        if let Some(index) = line.checked_sub(hunk.new_start).filter(|i| *i < hunk.lines.len()) {
+            return Some(offset + index + 1);
+        }
+        // The hunk's lines, then the next hunk's header.
+        offset += hunk.lines.len() + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pr::diff::parse_diff;
+    use crate::report::{AnalysisResult, RiskLevel};
+
+    const DIFF: &str = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,3 +1,3 @@\n fn a() {\n-    old();\n+    new();\n }\n@@ -20,2 +20,3 @@\n fn b() {\n+    added();\n }\n";
+
+    #[test]
+    fn test_position_counts_later_hunk_headers() {
+        let files = parse_diff(DIFF).unwrap();
+        // `+    new();` is the hunk's third line.
+        assert_eq!(position(&files[0], 3), Some(3));
+        // `+    added();`: four lines and a header above its hunk.
+        assert_eq!(position(&files[0], 21), Some(7));
+        assert_eq!(position(&files[0], 10), None);
+    }
+
+    #[test]
+    fn test_review_input_places_findings() {
+        let diff = parse_diff(DIFF).unwrap();
+        let pr = crate::pr::PullRequest::builder("t").number(5).author("a").totals(1, 2, 1).head_sha("deadbeef").build().unwrap();
+        let report = crate::report::build(
+            vec![AnalysisResult {
+                analyzer_name: "Security Risk Assessment".to_string(),
+                risk_level: RiskLevel::High,
+                findings: vec![
+                    Finding::new("security.hardcoded_password", RiskLevel::High).in_file("src/a.rs").at_line(21),
+                    Finding::new("security.hardcoded_password", RiskLevel::High).in_file("src/a.rs").at_line(90),
+                    Finding::text("3 new dependencies", RiskLevel::Medium),
+                ],
+            }],
+            &pr,
+        );
+        let review = review_input(&report, &diff);
+        assert_eq!(review["event"], "COMMENT");
+        assert_eq!(review["commit_id"], "deadbeef");
+        assert_eq!(review["comments"], json!([{ "path": "src/a.rs", "position": 7, "body": "**[HIGH]** Hardcoded password detected" }]));
+        let body = review["body"].as_str().unwrap();
+        assert!(body.contains("**[HIGH]** Hardcoded password detected (`src/a.rs:90`)"), "{}", body);
+        assert!(body.contains("**[MEDIUM]** 3 new dependencies"), "{}", body);
+    }
+}
@@ -0,0 +1,264 @@
+//! Patch series input for email workflows: an mbox file or a directory of
+//! `git format-patch` output (`*.patch`).
+//!
+//! The series is analyzed as one change. Hunks of the same file from
+//! different patches are concatenated into one `DiffFile`. Each patch is
+//! also kept in `PullRequest.patches` so the report can break down
+//! findings per patch.
+
+use std::path::Path;
+
+use tracing::{debug, instrument};
+
+use super::types::{DiffFile, Patch};
+use super::{diff, PrError, PullRequest};
+
+/// One email of the series, before its diff is parsed.
+#[derive(Debug, Default)]
+struct Message {
+    /// Commit SHA from the `From <sha> <date>` separator, if present
+    commit: Option<String>,
+    subject: String,
+    from: String,
+    body: String,
+}
+
+/// Read an mbox file or a format-patch directory into a PullRequest.
+#[instrument]
+pub fn load(path: &Path) -> Result<PullRequest, PrError> {
+    let messages = if path.is_dir() {
+        let mut files: Vec<_> = std::fs::read_dir(path)
+            .map_err(PrError::SeriesRead)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "patch"))
+            .collect();
+        files.sort();
+        let mut messages = Vec::new();
+        for file in files {
+            messages.extend(split_mbox(&read_lossy(&file)?));
+        }
+        messages
+    } else {
+        split_mbox(&read_lossy(path)?)
+    };
+    debug!(messages = messages.len(), "read patch series");
+    combine(messages, path)
+}
+
+/// Patches mailed from legacy-encoded repositories (Latin-1, Shift-JIS)
+/// are not UTF-8; their invalid bytes are replaced rather than failing the
+/// whole series.
+fn read_lossy(path: &Path) -> Result<String, PrError> {
+    let bytes = std::fs::read(path).map_err(PrError::SeriesRead)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Turn messages into patches (skipping the cover letter and replies
+/// without a diff) and merge them into one PullRequest.
+fn combine(messages: Vec<Message>, path: &Path) -> Result<PullRequest, PrError> {
+    let mut cover = None;
+    let mut head_sha = None;
+    let mut patches = Vec::new();
+    for message in messages {
+        let files = diff::parse_diff(diff::strip_signature(&message.body))?;
+        if files.is_empty() {
+            cover.get_or_insert(message);
+            continue;
+        }
+        head_sha = message.commit.or(head_sha);
+        patches.push(Patch { subject: message.subject, author: message.from, files });
+    }
+    let Some(first) = patches.first() else {
+        return Err(PrError::DiffParse(format!("no patches found in {}", path.display())));
+    };
+
+    let title = match (&cover, patches.len()) {
+        (Some(cover), _) => cover.subject.clone(),
+        (None, 1) => first.subject.clone(),
+        (None, n) => format!("{} (+{} more patches)", first.subject, n - 1),
+    };
+    let author = cover.map_or_else(|| first.author.clone(), |c| c.from);
+
+    let mut files: Vec<DiffFile> = Vec::new();
+    for file in patches.iter().flat_map(|p| &p.files) {
+        match files.iter_mut().find(|f| f.path() == file.path()) {
+            Some(merged) => {
+                merged.set_counts(merged.additions() + file.additions(), merged.deletions() + file.deletions());
+                merged.set_deleted(file.is_deleted());
+                merged.hunks_mut().extend(file.hunks().iter().cloned());
+            }
+            None => files.push(file.clone()),
+        }
+    }
+
+    let mut pr = PullRequest::builder(title).author(author).files(files);
+    if let Some(sha) = head_sha {
+        pr = pr.head_sha(sha);
+    }
+    Ok(patches.into_iter().fold(pr, |pr, patch| pr.patch(patch)).build()?)
+}
+
+/// Split mbox text into messages. A message starts at a `From ` line that
+/// is the first line or follows a blank line.
+fn split_mbox(text: &str) -> Vec<Message> {
+    let mut messages = Vec::new();
+    let mut current: Option<(Message, bool)> = None;
+    let mut previous_blank = true;
+    let mut in_subject = false;
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if previous_blank && line.starts_with("From ") {
+            messages.extend(current.take().map(|(m, _)| m));
+            let commit = line.split_whitespace().nth(1).filter(|s| s.len() == 40 && s.chars().all(|c| c.is_ascii_hexdigit()));
+            current = Some((Message { commit: commit.map(str::to_string), ..Message::default() }, true));
+            previous_blank = false;
+            continue;
+        }
+        previous_blank = line.is_empty();
+        let Some((message, in_headers)) = current.as_mut() else {
+            continue;
+        };
+        if !*in_headers {
+            // mboxrd escapes body lines that start with "From ".
+            let line = if line.starts_with(">From ") { &line[1..] } else { line };
+            message.body.push_str(line);
+            message.body.push('\n');
+        } else if line.is_empty() {
+            *in_headers = false;
+        } else if line.starts_with([' ', '\t']) {
+            // Folded header: continues the previous one (only Subject matters here).
+            if in_subject {
+                message.subject.push_str(line.trim_end());
+            }
+        } else {
+            in_subject = false;
+            if let Some(subject) = header(line, "Subject") {
+                message.subject = strip_patch_prefix(subject).to_string();
+                in_subject = true;
+            } else if let Some(from) = header(line, "From") {
+                message.from = sender_name(from).to_string();
+            }
+        }
+    }
+    messages.extend(current.map(|(m, _)| m));
+    messages
+}
+
+/// Value of `name: value` when the header name matches case-insensitively.
+fn header<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let (key, value) = line.split_once(':')?;
+    key.eq_ignore_ascii_case(name).then(|| value.trim())
+}
+
+/// `[PATCH v2 3/5] net: fix leak` -> `net: fix leak`
+fn strip_patch_prefix(subject: &str) -> &str {
+    let mut subject = subject.trim();
+    while subject.starts_with('[') {
+        match subject.find(']') {
+            Some(end) => subject = subject[end + 1..].trim_start(),
+            None => break,
+        }
+    }
+    subject
+}
+
+/// `Jane Doe <jane@example.com>` -> `Jane Doe`; a bare address is kept.
+fn sender_name(from: &str) -> &str {
+    match from.split_once('<') {
+        Some((name, address)) if name.trim().trim_matches('"').is_empty() => address.trim_end_matches('>').trim(),
+        Some((name, _)) => name.trim().trim_matches('"'),
+        None => from.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERIES: &str = "From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001
+From: Jane Doe <jane@example.com>
+Subject: [PATCH 0/2] Add retry support
+
+Cover letter text.
+
+From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001
+From: Jane Doe <jane@example.com>
+Subject: [PATCH 1/2] net: add retry
+ helper
+
+---
+diff --git a/src/net.rs b/src/net.rs
+--- a/src/net.rs
++++ b/src/net.rs
+@@ -1,2 +1,3 @@
+ fn a() {}
++fn retry() {}
+ fn b() {}
+-- 
+2.43.0
+
+From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001
+From: \"Jane Doe\" <jane@example.com>
+Subject: [PATCH 2/2] net: use retry
+
+---
+diff --git a/src/net.rs b/src/net.rs
+--- a/src/net.rs
++++ b/src/net.rs
+@@ -10,2 +10,2 @@
+-    call();
++    retry();
+diff --git a/src/main.rs b/src/main.rs
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1,2 @@
+ fn main() {}
++// uses retry
+-- 
+2.43.0
+";
+
+    #[test]
+    fn test_split_mbox_headers() {
+        let messages = split_mbox(SERIES);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].subject, "net: add retry helper");
+        assert_eq!(messages[1].from, "Jane Doe");
+        assert_eq!(messages[2].commit.as_deref(), Some("2222222222222222222222222222222222222222"));
+    }
+
+    #[test]
+    fn test_combine_series_into_one_change() {
+        let pr = combine(split_mbox(SERIES), Path::new("series.mbox")).unwrap();
+        assert_eq!(pr.title(), "Add retry support");
+        assert_eq!(pr.author(), "Jane Doe");
+        assert_eq!(pr.patches().len(), 2);
+        assert_eq!(pr.files_changed(), 2);
+        assert_eq!((pr.additions(), pr.deletions()), (3, 1));
+        let net = pr.files().iter().find(|f| f.path() == "src/net.rs").unwrap();
+        assert_eq!(net.hunks().len(), 2);
+        assert_eq!(pr.head_sha(), Some("2222222222222222222222222222222222222222"));
+    }
+
+    #[test]
+    fn test_load_format_patch_directory() {
+        let dir = std::env::temp_dir().join("pr-analyzer-test-series");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let (first, second) = SERIES.split_once("\nFrom 2222").unwrap();
+        let (_, first) = first.split_once("\nFrom 1111").unwrap();
+        std::fs::write(dir.join("0001-net-add-retry.patch"), format!("From 1111{}", first)).unwrap();
+        std::fs::write(dir.join("0002-net-use-retry.patch"), format!("From 2222{}", second)).unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let pr = load(&dir).unwrap();
+        assert_eq!(pr.title(), "net: add retry helper (+1 more patches)");
+        assert_eq!(pr.patches()[1].subject, "net: use retry");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_load_without_patches_is_an_error() {
+        assert!(combine(split_mbox("From x\nSubject: hello\n\nno diff here\n"), Path::new("m")).is_err());
+    }
+}
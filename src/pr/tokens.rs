@@ -0,0 +1,102 @@
+//! A pool of GitHub tokens for reads, so a large `org scan` is not capped by
+//! one token's 5,000 requests an hour.
+//!
+//! Tokens are used in the order configured: each request goes to the first
+//! token with quota left, as the `X-RateLimit-Remaining` and
+//! `X-RateLimit-Reset` headers of its last response report it. A token
+//! whose limit is exhausted is skipped until its window resets.
+
+use std::sync::{Mutex, PoisonError};
+
+/// What the last response made with a token said about its limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Quota {
+    remaining: Option<u64>,
+    /// Unix time the window resets
+    reset: Option<u64>,
+}
+
+impl Quota {
+    /// Unknown quota counts as available: the token has not been used yet.
+    fn available(&self, now: u64) -> bool {
+        self.remaining != Some(0) || self.reset.is_some_and(|reset| reset <= now)
+    }
+}
+
+pub(crate) struct TokenPool {
+    tokens: Vec<String>,
+    quotas: Mutex<Vec<Quota>>,
+}
+
+impl TokenPool {
+    /// A pool of `tokens`, which must not be empty.
+    pub fn new(tokens: Vec<String>) -> Self {
+        assert!(!tokens.is_empty(), "a token pool needs at least one token");
+        let quotas = Mutex::new(vec![Quota::default(); tokens.len()]);
+        Self { tokens, quotas }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Index and value of the token to use: the first with quota left or,
+    /// when all are exhausted, the one whose window resets first.
+    pub fn pick(&self, now: u64) -> (usize, &str) {
+        let quotas = self.quotas.lock().unwrap_or_else(PoisonError::into_inner);
+        let index = quotas
+            .iter()
+            .position(|q| q.available(now))
+            .or_else(|| quotas.iter().enumerate().min_by_key(|(_, q)| q.reset.unwrap_or(u64::MAX)).map(|(i, _)| i))
+            .unwrap_or(0);
+        (index, &self.tokens[index])
+    }
+
+    /// Record the rate-limit headers of a response to a request made with
+    /// token `index`. Responses without them leave the quota as it was.
+    pub fn record(&self, index: usize, remaining: Option<u64>, reset: Option<u64>) {
+        if remaining.is_none() {
+            return;
+        }
+        let mut quotas = self.quotas.lock().unwrap_or_else(PoisonError::into_inner);
+        quotas[index] = Quota { remaining, reset };
+    }
+
+    /// Whether a token other than `index` has quota left.
+    pub fn has_other(&self, index: usize, now: u64) -> bool {
+        let quotas = self.quotas.lock().unwrap_or_else(PoisonError::into_inner);
+        quotas.iter().enumerate().any(|(i, q)| i != index && q.available(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_rotates_past_exhausted_tokens_until_reset() {
+        let pool = TokenPool::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert_eq!(pool.pick(100), (0, "a"));
+        pool.record(0, Some(4999), Some(3_600));
+        assert_eq!(pool.pick(100), (0, "a"));
+
+        pool.record(0, Some(0), Some(3_600));
+        assert!(pool.has_other(0, 100));
+        assert_eq!(pool.pick(100), (1, "b"));
+        pool.record(1, Some(0), Some(1_800));
+        pool.record(2, Some(0), Some(2_400));
+        assert!(!pool.has_other(2, 100));
+        // All exhausted: the one that resets first
+        assert_eq!(pool.pick(100), (1, "b"));
+        // After its window resets, the first token is used again
+        assert_eq!(pool.pick(3_600), (0, "a"));
+    }
+
+    #[test]
+    fn test_pool_ignores_responses_without_rate_limit_headers() {
+        let pool = TokenPool::new(vec!["a".to_string(), "b".to_string()]);
+        pool.record(0, Some(0), Some(3_600));
+        pool.record(0, None, None);
+        assert_eq!(pool.pick(100), (1, "b"));
+    }
+}
@@ -1,41 +1,295 @@
+use thiserror::Error;
+
+use crate::repo::Checkout;
+
 /// Metadata about a pull request fetched from the GitHub API.
 /// Codex: Populate all fields from the GitHub REST API response.
 /// Note: Not Deserialize — PullRequest is constructed manually from
 /// the GitHub API JSON response + parsed diff (DiffFile/Hunk).
+///
+/// Fields are read through accessors and set through `PullRequest::builder`,
+/// which checks them, so the struct can grow without breaking callers.
 #[derive(Debug, Clone)]
 pub struct PullRequest {
     /// PR number (e.g., 42)
-    pub number: u64,
+    number: u64,
     /// PR title
-    pub title: String,
+    title: String,
     /// Author's GitHub login
-    pub author: String,
+    author: String,
     /// Total files changed
-    pub files_changed: usize,
+    files_changed: usize,
     /// Total lines added
-    pub additions: usize,
+    additions: usize,
     /// Total lines deleted
-    pub deletions: usize,
+    deletions: usize,
+    /// Commit SHA of the PR head (None for mock or local diffs)
+    head_sha: Option<String>,
+    /// Name of the PR's source branch (None where the source has none, e.g. Gerrit or patches)
+    branch: Option<String>,
+    /// GitHub labels, or Gerrit hashtags (empty for patches)
+    labels: Vec<String>,
+    /// True when the PR's head branch lives in another repository (a fork,
+    /// or one since deleted). CI jobs run such PRs with read-only tokens and
+    /// without secrets.
+    fork: bool,
+    /// Local checkout of the PR head, when running with --repo or --clone
+    checkout: Option<Checkout>,
     /// Parsed diff files
+    files: Vec<DiffFile>,
+    /// Individual patches when the input is a patch series (empty otherwise)
+    patches: Vec<Patch>,
+}
+
+impl PullRequest {
+    /// Start a change titled `title`, with no files.
+    pub fn builder(title: impl Into<String>) -> PullRequestBuilder {
+        PullRequestBuilder {
+            pr: PullRequest {
+                number: 0,
+                title: title.into(),
+                author: String::new(),
+                files_changed: 0,
+                additions: 0,
+                deletions: 0,
+                head_sha: None,
+                branch: None,
+                labels: Vec::new(),
+                fork: false,
+                checkout: None,
+                files: Vec::new(),
+                patches: Vec::new(),
+            },
+            totals: None,
+        }
+    }
+
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    /// Files changed according to the source, which may list more than
+    /// `files` when it truncates the diff.
+    pub fn files_changed(&self) -> usize {
+        self.files_changed
+    }
+
+    pub fn additions(&self) -> usize {
+        self.additions
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.deletions
+    }
+
+    pub fn head_sha(&self) -> Option<&str> {
+        self.head_sha.as_deref()
+    }
+
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Whether the head branch lives in another repository (see `fork`).
+    pub fn is_fork(&self) -> bool {
+        self.fork
+    }
+
+    pub fn checkout(&self) -> Option<&Checkout> {
+        self.checkout.as_ref()
+    }
+
+    pub fn files(&self) -> &[DiffFile] {
+        &self.files
+    }
+
+    pub fn patches(&self) -> &[Patch] {
+        &self.patches
+    }
+
+    /// Attach a local checkout of the head, e.g. one opened after fetching.
+    pub fn set_checkout(&mut self, checkout: Checkout) {
+        self.checkout = Some(checkout);
+    }
+
+    /// Remove the files `drop` selects, and their lines from the totals.
+    /// Returns the removed files.
+    pub(crate) fn remove_files(&mut self, mut drop: impl FnMut(&DiffFile) -> bool) -> Vec<DiffFile> {
+        let (dropped, kept): (Vec<DiffFile>, Vec<DiffFile>) = std::mem::take(&mut self.files).into_iter().partition(|f| drop(f));
+        self.files = kept;
+        self.files_changed = self.files_changed.saturating_sub(dropped.len());
+        self.additions = self.additions.saturating_sub(dropped.iter().map(|f| f.additions).sum());
+        self.deletions = self.deletions.saturating_sub(dropped.iter().map(|f| f.deletions).sum());
+        dropped
+    }
+
+    /// The files, for rewrites that keep the totals as the source reported
+    /// them (normalization, budgets) and for tests.
+    pub(crate) fn files_mut(&mut self) -> &mut Vec<DiffFile> {
+        &mut self.files
+    }
+
+    pub(crate) fn set_totals(&mut self, files_changed: usize, additions: usize, deletions: usize) {
+        (self.files_changed, self.additions, self.deletions) = (files_changed, additions, deletions);
+    }
+}
+
+/// Setters for tests that edit a change in place.
+#[cfg(test)]
+impl PullRequest {
+    /// Replace the files, leaving the totals alone.
+    pub(crate) fn set_files(&mut self, files: Vec<DiffFile>) {
+        self.files = files;
+    }
+
+    pub(crate) fn patches_mut(&mut self) -> &mut Vec<Patch> {
+        &mut self.patches
+    }
+
+    pub(crate) fn set_title(&mut self, title: impl Into<String>) {
+        self.title = title.into();
+    }
+
+    pub(crate) fn set_labels(&mut self, labels: Vec<String>) {
+        self.labels = labels;
+    }
+
+    pub(crate) fn set_head_sha(&mut self, sha: Option<String>) {
+        self.head_sha = sha;
+    }
+
+    pub(crate) fn set_branch(&mut self, branch: Option<String>) {
+        self.branch = branch;
+    }
+
+    pub(crate) fn set_fork(&mut self, fork: bool) {
+        self.fork = fork;
+    }
+}
+
+/// One patch of a series read from an mbox or `git format-patch` output.
+#[derive(Debug, Clone)]
+pub struct Patch {
+    /// Subject with the `[PATCH n/m]` prefix removed
+    pub subject: String,
+    /// Sender from the `From:` header
+    pub author: String,
+    /// Files this patch changes, with its own hunks
     pub files: Vec<DiffFile>,
 }
 
 /// A single file within the PR diff.
-/// Codex: Populated by the diff parser in diff.rs.
+/// Codex: Populated by the diff parser in diff.rs. Elsewhere, build one with
+/// `DiffFile::builder`.
 #[derive(Debug, Clone)]
 pub struct DiffFile {
     /// File path (e.g., "src/auth/config.rs")
-    pub path: String,
+    path: String,
     /// Whether this is a new file
-    pub is_new: bool,
+    is_new: bool,
     /// Whether this file was deleted
-    pub is_deleted: bool,
+    is_deleted: bool,
     /// Lines added in this file
-    pub additions: usize,
+    additions: usize,
     /// Lines deleted in this file
-    pub deletions: usize,
+    deletions: usize,
+    /// Path before the change, for a file the diff reports as renamed
+    previous_path: Option<String>,
+    /// Similarity to the previous path's content, as a percentage, from
+    /// the `similarity index` header of a rename
+    similarity: Option<u8>,
     /// Hunks (contiguous changed regions)
-    pub hunks: Vec<Hunk>,
+    hunks: Vec<Hunk>,
+}
+
+impl DiffFile {
+    /// Start a changed file at `path`, with no hunks.
+    pub fn builder(path: impl Into<String>) -> DiffFileBuilder {
+        DiffFileBuilder { file: DiffFile::empty(path) }
+    }
+
+    /// A changed file at `path` with no hunks, for the diff parser to fill.
+    pub(crate) fn empty(path: impl Into<String>) -> Self {
+        DiffFile { path: path.into(), is_new: false, is_deleted: false, additions: 0, deletions: 0, previous_path: None, similarity: None, hunks: Vec::new() }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_new(&self) -> bool {
+        self.is_new
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+
+    pub fn additions(&self) -> usize {
+        self.additions
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.deletions
+    }
+
+    pub fn previous_path(&self) -> Option<&str> {
+        self.previous_path.as_deref()
+    }
+
+    pub fn similarity(&self) -> Option<u8> {
+        self.similarity
+    }
+
+    pub fn hunks(&self) -> &[Hunk] {
+        &self.hunks
+    }
+
+    /// The line of the new file a finding's line number points at. Analyzers
+    /// number lines as `new_start` plus the index in the hunk, which counts
+    /// removed lines too; editors and annotations need the line as saved.
+    pub fn file_line(&self, number: usize) -> Option<usize> {
+        self.hunks.iter().find_map(|h| {
+            let index = number.checked_sub(h.new_start).filter(|i| *i < h.lines.len())?;
+            Some(h.new_start + h.lines[..index].iter().filter(|l| !l.starts_with('-')).count())
+        })
+    }
+
+    pub(crate) fn hunks_mut(&mut self) -> &mut Vec<Hunk> {
+        &mut self.hunks
+    }
+
+    pub(crate) fn set_path(&mut self, path: impl Into<String>) {
+        self.path = path.into();
+    }
+
+    pub(crate) fn set_new(&mut self, is_new: bool) {
+        self.is_new = is_new;
+    }
+
+    pub(crate) fn set_deleted(&mut self, is_deleted: bool) {
+        self.is_deleted = is_deleted;
+    }
+
+    pub(crate) fn set_counts(&mut self, additions: usize, deletions: usize) {
+        (self.additions, self.deletions) = (additions, deletions);
+    }
+
+    pub(crate) fn set_rename(&mut self, previous_path: Option<String>, similarity: Option<u8>) {
+        (self.previous_path, self.similarity) = (previous_path, similarity);
+    }
 }
 
 /// A contiguous region of changes within a file.
@@ -55,6 +309,191 @@ pub struct Hunk {
     pub lines: Vec<String>,
 }
 
+/// Why a `PullRequestBuilder` or `DiffFileBuilder` refused its input.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BuildError {
+    #[error("A diff file needs a path")]
+    EmptyPath,
+
+    #[error("{0}: a file cannot be both new and deleted")]
+    NewAndDeleted(String),
+
+    #[error("{path}: rename similarity {similarity}% is over 100%")]
+    Similarity { path: String, similarity: u8 },
+
+    #[error("{path}: hunk line {line:?} does not start with '+', '-', ' ' or '\\'")]
+    HunkLine { path: String, line: String },
+
+    #[error("{0}: a new file cannot remove lines, nor a deleted file add them")]
+    LinesAgainstStatus(String),
+
+    #[error("{0} is listed twice in the diff")]
+    DuplicatePath(String),
+
+    #[error("files_changed is {files_changed}, but the diff lists {listed} files")]
+    TotalsBelowDiff { files_changed: usize, listed: usize },
+
+    #[error("Head SHA {0:?} is not a hexadecimal commit id")]
+    HeadSha(String),
+}
+
+/// Builds a `PullRequest`. Unset totals are summed from the files; set
+/// them (`totals`) when the source reports more than its diff holds, as
+/// GitHub does for diffs it truncates.
+#[derive(Debug)]
+pub struct PullRequestBuilder {
+    pr: PullRequest,
+    totals: Option<(usize, usize, usize)>,
+}
+
+impl PullRequestBuilder {
+    pub fn number(mut self, number: u64) -> Self {
+        self.pr.number = number;
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.pr.author = author.into();
+        self
+    }
+
+    /// Files changed, lines added and lines deleted, as the source reports them.
+    pub fn totals(mut self, files_changed: usize, additions: usize, deletions: usize) -> Self {
+        self.totals = Some((files_changed, additions, deletions));
+        self
+    }
+
+    pub fn head_sha(mut self, sha: impl Into<String>) -> Self {
+        self.pr.head_sha = Some(sha.into());
+        self
+    }
+
+    pub fn branch(mut self, branch: impl Into<String>) -> Self {
+        self.pr.branch = Some(branch.into());
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.pr.labels.push(label.into());
+        self
+    }
+
+    pub fn labels(mut self, labels: impl IntoIterator<Item = String>) -> Self {
+        self.pr.labels.extend(labels);
+        self
+    }
+
+    /// Mark the change as coming from a fork (see `PullRequest::fork`).
+    pub fn fork(mut self) -> Self {
+        self.pr.fork = true;
+        self
+    }
+
+    pub fn checkout(mut self, checkout: Checkout) -> Self {
+        self.pr.checkout = Some(checkout);
+        self
+    }
+
+    pub fn file(mut self, file: DiffFile) -> Self {
+        self.pr.files.push(file);
+        self
+    }
+
+    pub fn files(mut self, files: impl IntoIterator<Item = DiffFile>) -> Self {
+        self.pr.files.extend(files);
+        self
+    }
+
+    pub fn patch(mut self, patch: Patch) -> Self {
+        self.pr.patches.push(patch);
+        self
+    }
+
+    /// The change, if each path is listed once, the head SHA is a
+    /// (possibly abbreviated) commit id and the totals cover the files.
+    pub fn build(self) -> Result<PullRequest, BuildError> {
+        let mut pr = self.pr;
+        let mut seen = std::collections::HashSet::new();
+        if let Some(file) = pr.files.iter().find(|f| !seen.insert(f.path.as_str())) {
+            return Err(BuildError::DuplicatePath(file.path.clone()));
+        }
+        if let Some(sha) = pr.head_sha.as_ref().filter(|sha| !(4..=64).contains(&sha.len()) || !sha.bytes().all(|b| b.is_ascii_hexdigit())) {
+            return Err(BuildError::HeadSha(sha.clone()));
+        }
+        let (files_changed, additions, deletions) =
+            self.totals.unwrap_or_else(|| (pr.files.len(), pr.files.iter().map(|f| f.additions).sum(), pr.files.iter().map(|f| f.deletions).sum()));
+        if files_changed < pr.files.len() {
+            return Err(BuildError::TotalsBelowDiff { files_changed, listed: pr.files.len() });
+        }
+        (pr.files_changed, pr.additions, pr.deletions) = (files_changed, additions, deletions);
+        Ok(pr)
+    }
+}
+
+/// Builds a `DiffFile`; its additions and deletions are counted from the
+/// hunks.
+#[derive(Debug)]
+pub struct DiffFileBuilder {
+    file: DiffFile,
+}
+
+impl DiffFileBuilder {
+    /// Mark the file as added by the change.
+    pub fn new_file(mut self) -> Self {
+        self.file.is_new = true;
+        self
+    }
+
+    /// Mark the file as deleted by the change.
+    pub fn deleted(mut self) -> Self {
+        self.file.is_deleted = true;
+        self
+    }
+
+    /// The file was renamed from `path`, keeping `similarity` percent of its content.
+    pub fn renamed_from(mut self, path: impl Into<String>, similarity: u8) -> Self {
+        self.file.previous_path = Some(path.into());
+        self.file.similarity = Some(similarity);
+        self
+    }
+
+    /// A hunk of raw diff lines (`+added`, `-removed`, ` context`) that
+    /// starts at `old_start` in the old file and `new_start` in the new one.
+    pub fn hunk(mut self, old_start: usize, new_start: usize, lines: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let lines: Vec<String> = lines.into_iter().map(Into::into).collect();
+        let old_count = lines.iter().filter(|l| !l.starts_with(['+', '\\'])).count();
+        let new_count = lines.iter().filter(|l| !l.starts_with(['-', '\\'])).count();
+        self.file.hunks.push(Hunk { old_start, old_count, new_start, new_count, lines });
+        self
+    }
+
+    /// The file, if it has a path, is not both new and deleted, has only
+    /// diff lines in its hunks, and a new file only adds lines (a deleted
+    /// one only removes them).
+    pub fn build(self) -> Result<DiffFile, BuildError> {
+        let mut file = self.file;
+        if file.path.is_empty() {
+            return Err(BuildError::EmptyPath);
+        }
+        if file.is_new && file.is_deleted {
+            return Err(BuildError::NewAndDeleted(file.path));
+        }
+        if let Some(similarity) = file.similarity.filter(|s| *s > 100) {
+            return Err(BuildError::Similarity { path: file.path, similarity });
+        }
+        let lines = || file.hunks.iter().flat_map(|h| &h.lines);
+        if let Some(line) = lines().find(|l| !l.is_empty() && !l.starts_with(['+', '-', ' ', '\\'])) {
+            return Err(BuildError::HunkLine { path: file.path.clone(), line: line.clone() });
+        }
+        file.additions = lines().filter(|l| l.starts_with('+')).count();
+        file.deletions = lines().filter(|l| l.starts_with('-')).count();
+        if (file.is_new && file.deletions > 0) || (file.is_deleted && file.additions > 0) {
+            return Err(BuildError::LinesAgainstStatus(file.path));
+        }
+        Ok(file)
+    }
+}
+
 /// Represents the parsed components of a GitHub PR URL.
 /// Codex: Extracted by parse_pr_url() in pr/mod.rs.
 #[derive(Debug, Clone)]
@@ -68,6 +507,25 @@ pub struct PrUrl {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_builders_count_lines_and_validate() {
+        let file = DiffFile::builder("src/lib.rs").hunk(3, 3, [" fn a() {", "-    old()", "+    new()", "+    more()"]).build().unwrap();
+        assert_eq!((file.additions, file.deletions), (2, 1));
+        assert_eq!((file.hunks[0].old_count, file.hunks[0].new_count), (2, 3));
+        let pr = PullRequest::builder("Add things").number(7).head_sha("0123abcd").file(file.clone()).build().unwrap();
+        assert_eq!((pr.number, pr.files_changed, pr.additions, pr.deletions), (7, 1, 2, 1));
+        let truncated = PullRequest::builder("Big").totals(40, 900, 10).file(file.clone()).build().unwrap();
+        assert_eq!(truncated.files_changed, 40);
+
+        assert_eq!(DiffFile::builder("").build().unwrap_err(), BuildError::EmptyPath);
+        assert_eq!(DiffFile::builder("a").new_file().hunk(0, 1, ["-x"]).build().unwrap_err(), BuildError::LinesAgainstStatus("a".to_string()));
+        assert!(matches!(DiffFile::builder("a").hunk(1, 1, ["x"]).build(), Err(BuildError::HunkLine { .. })));
+        assert!(matches!(DiffFile::builder("a").renamed_from("b", 101).build(), Err(BuildError::Similarity { .. })));
+        assert_eq!(PullRequest::builder("t").file(file.clone()).file(file.clone()).build().unwrap_err(), BuildError::DuplicatePath("src/lib.rs".to_string()));
+        assert_eq!(PullRequest::builder("t").head_sha("main").build().unwrap_err(), BuildError::HeadSha("main".to_string()));
+        assert!(matches!(PullRequest::builder("t").totals(0, 0, 0).file(file).build(), Err(BuildError::TotalsBelowDiff { files_changed: 0, listed: 1 })));
+    }
+
     #[test]
     fn test_pr_url_fields() {
         let url = PrUrl {
@@ -0,0 +1,89 @@
+//! `--profile-run`: wall time and memory per phase of one run, printed to
+//! stderr after the report.
+
+use std::time::{Duration, Instant};
+
+struct Phase {
+    name: String,
+    elapsed: Duration,
+    /// Resident set size when the phase ended, in KB
+    rss_kb: Option<u64>,
+    /// Nested phases (analyzers) are indented and have no memory reading
+    nested: bool,
+}
+
+pub struct RunProfile {
+    start: Instant,
+    last: Instant,
+    phases: Vec<Phase>,
+}
+
+impl RunProfile {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self { start: now, last: now, phases: Vec::new() }
+    }
+
+    /// End the current phase and start the next one.
+    pub fn mark(&mut self, name: &str) {
+        let now = Instant::now();
+        self.phases.push(Phase { name: name.to_string(), elapsed: now - self.last, rss_kb: memory_kb("VmRSS"), nested: false });
+        self.last = now;
+    }
+
+    /// Record a sub-phase measured elsewhere, shown under the last phase.
+    pub fn nested(&mut self, name: &str, elapsed: Duration) {
+        self.phases.push(Phase { name: name.to_string(), elapsed, rss_kb: None, nested: true });
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!("{:<36} {:>10} {:>10}\n", "Phase", "Time", "RSS");
+        for phase in &self.phases {
+            let (name, rss) = if phase.nested { (format!("  {}", phase.name), String::new()) } else { (phase.name.clone(), format_kb(phase.rss_kb)) };
+            let row = format!("{:<36} {:>10} {:>10}", name, format_duration(phase.elapsed), rss);
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+        out.push_str(&format!("{:<36} {:>10} {:>10}\n", "total (peak RSS)", format_duration(self.start.elapsed()), format_kb(memory_kb("VmHWM"))));
+        out
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    format!("{:.1}ms", d.as_secs_f64() * 1000.0)
+}
+
+fn format_kb(kb: Option<u64>) -> String {
+    kb.map_or_else(|| "n/a".to_string(), |kb| format!("{:.1}MB", kb as f64 / 1024.0))
+}
+
+/// A `Vm*` field of `/proc/self/status` in KB; None where procfs is unavailable.
+fn memory_kb(field: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix(field)?.strip_prefix(':'))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_lists_phases_and_nested_timings() {
+        let mut profile = RunProfile::start();
+        profile.mark("fetch");
+        profile.mark("analysis");
+        profile.nested("Security Risk Assessment", Duration::from_millis(12));
+        let rendered = profile.render();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].starts_with("fetch "));
+        assert!(lines[3].starts_with("  Security Risk Assessment") && lines[3].contains("12.0ms"));
+        assert!(lines[4].starts_with("total (peak RSS)"));
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, HashMap};
+
+use tracing::debug;
+
+use super::{Checkout, RepoError};
+use crate::report::types::{AnalysisResult, ReviewerHint};
+
+/// Lines above and below a finding whose authors count toward ownership.
+const BLAME_CONTEXT_LINES: usize = 5;
+
+/// Reviewers suggested per file.
+const MAX_SUGGESTED_REVIEWERS: usize = 2;
+
+/// Author of one line in the current checkout.
+#[derive(Debug, Clone, PartialEq)]
+struct BlameLine {
+    line: usize,
+    author: String,
+}
+
+impl Checkout {
+    /// Suggest reviewers for every file with findings: the most frequent
+    /// authors of the lines around each finding (or of the whole file when
+    /// a finding has no line). Files git cannot blame, such as ones added by
+    /// the PR, are skipped.
+    pub fn suggest_reviewers(&self, results: &[AnalysisResult]) -> Vec<ReviewerHint> {
+        let mut lines_by_file: BTreeMap<&str, Vec<Option<usize>>> = BTreeMap::new();
+        for finding in results.iter().flat_map(|r| &r.findings) {
+            if let Some(file) = &finding.file {
+                lines_by_file.entry(file.as_str()).or_default().push(finding.line);
+            }
+        }
+
+        let mut hints = Vec::new();
+        for (file, lines) in lines_by_file {
+            let blame = match self.blame(file) {
+                Ok(blame) => blame,
+                Err(e) => {
+                    debug!(file, error = %e, "skipping reviewer hint");
+                    continue;
+                }
+            };
+            let reviewers = top_authors(&blame, &lines);
+            if !reviewers.is_empty() {
+                hints.push(ReviewerHint { file: file.to_string(), reviewers });
+            }
+        }
+        hints
+    }
+
+    fn blame(&self, file: &str) -> Result<Vec<BlameLine>, RepoError> {
+        let porcelain = self.git(&["blame", "--porcelain", "--", file])?;
+        Ok(parse_porcelain(&porcelain))
+    }
+}
+
+/// Parse `git blame --porcelain` output. Author details are printed only the
+/// first time a commit appears, so they are remembered per commit.
+fn parse_porcelain(porcelain: &str) -> Vec<BlameLine> {
+    let mut authors: HashMap<&str, String> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(&str, usize)> = None;
+    for line in porcelain.lines() {
+        if line.starts_with('\t') {
+            if let Some((sha, final_line)) = current.take() {
+                let author = authors.get(sha).cloned().unwrap_or_default();
+                lines.push(BlameLine { line: final_line, author });
+            }
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            if let Some((sha, _)) = current {
+                authors.insert(sha, mail.trim_matches(['<', '>']).to_string());
+            }
+        } else {
+            let mut parts = line.split(' ');
+            if let (Some(sha), Some(_), Some(final_line)) = (parts.next(), parts.next(), parts.next()) {
+                if sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                    current = final_line.parse().ok().map(|n| (sha, n));
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Most frequent authors within the context window of each finding line.
+/// Uncommitted lines (`not.committed.yet`) are ignored.
+fn top_authors(blame: &[BlameLine], finding_lines: &[Option<usize>]) -> Vec<String> {
+    let near_finding = |line: usize| {
+        finding_lines.iter().any(|f| match f {
+            Some(f) => line.abs_diff(*f) <= BLAME_CONTEXT_LINES,
+            None => true,
+        })
+    };
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for b in blame.iter().filter(|b| near_finding(b.line)) {
+        if !b.author.is_empty() && b.author != "not.committed.yet" {
+            *counts.entry(b.author.as_str()).or_default() += 1;
+        }
+    }
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+    ranked.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    ranked.into_iter().take(MAX_SUGGESTED_REVIEWERS).map(|(author, _)| author.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "\
+1111111111111111111111111111111111111111 1 1 2
+author Alice
+author-mail <alice@example.com>
+summary init
+filename src/db.rs
+\tfn query() {
+1111111111111111111111111111111111111111 2 2
+\t    run();
+2222222222222222222222222222222222222222 3 3 1
+author Bob
+author-mail <bob@example.com>
+summary fix
+filename src/db.rs
+\t}
+";
+
+    #[test]
+    fn test_parse_porcelain_reuses_commit_authors() {
+        let lines = parse_porcelain(PORCELAIN);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], BlameLine { line: 2, author: "alice@example.com".to_string() });
+        assert_eq!(lines[2].author, "bob@example.com");
+    }
+
+    #[test]
+    fn test_top_authors_ranks_lines_near_findings() {
+        let mut blame: Vec<BlameLine> = (1..=30).map(|line| BlameLine { line, author: "carol@example.com".to_string() }).collect();
+        for b in blame.iter_mut().filter(|b| b.line <= 3) {
+            b.author = "dave@example.com".to_string();
+        }
+        assert_eq!(top_authors(&blame, &[Some(25)]), vec!["carol@example.com"]);
+        assert_eq!(top_authors(&blame, &[Some(1)]), vec!["carol@example.com", "dave@example.com"]);
+    }
+}
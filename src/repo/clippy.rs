@@ -0,0 +1,136 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+use tracing::{debug, info};
+
+use super::{Checkout, RepoError};
+
+/// A compiler or clippy diagnostic, located in the checkout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Repository-relative path of the primary span
+    pub file: String,
+    pub line: usize,
+    /// `warning` or `error`
+    pub level: String,
+    /// Lint name (e.g. `clippy::redundant_clone`), when there is one
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl Checkout {
+    /// Run `cargo clippy --message-format json` on every crate containing one
+    /// of `changed_files` and collect the diagnostics. The process is killed
+    /// if the returned future is dropped, so Ctrl-C cancels it.
+    pub async fn clippy(&self, changed_files: &[&str]) -> Result<Vec<Diagnostic>, RepoError> {
+        let mut diagnostics = Vec::new();
+        for crate_dir in self.crate_dirs(changed_files) {
+            info!(dir = %crate_dir.display(), "running cargo clippy");
+            let output = Command::new("cargo")
+                .args(["clippy", "--quiet", "--message-format=json"])
+                .current_dir(&crate_dir)
+                .kill_on_drop(true)
+                .output()
+                .await?;
+            // A non-zero exit just means error-level diagnostics; they are in stdout.
+            debug!(status = %output.status, "cargo clippy finished");
+            diagnostics.extend(parse_messages(&String::from_utf8_lossy(&output.stdout), &crate_dir, &self.root));
+        }
+        Ok(diagnostics)
+    }
+
+    /// Directories of the nearest `Cargo.toml` above each changed `.rs` file.
+    fn crate_dirs(&self, changed_files: &[&str]) -> BTreeSet<PathBuf> {
+        changed_files
+            .iter()
+            .filter(|f| f.ends_with(".rs"))
+            .filter_map(|f| {
+                self.root
+                    .join(f)
+                    .ancestors()
+                    .skip(1)
+                    .take_while(|dir| dir.starts_with(&self.root))
+                    .find(|dir| dir.join("Cargo.toml").is_file())
+                    .map(Path::to_path_buf)
+            })
+            .collect()
+    }
+}
+
+/// Parse cargo's JSON message stream. Span paths are relative to the
+/// workspace root, which may be any ancestor of `crate_dir`, so each
+/// ancestor is tried until the file exists.
+fn parse_messages(stdout: &str, crate_dir: &Path, root: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for line in stdout.lines() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if value["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &value["message"];
+        let level = message["level"].as_str().unwrap_or_default();
+        if level != "warning" && level != "error" {
+            continue;
+        }
+        let Some(span) = message["spans"].as_array().and_then(|spans| spans.iter().find(|s| s["is_primary"] == true)) else {
+            continue;
+        };
+        let (Some(file_name), Some(line)) = (span["file_name"].as_str(), span["line_start"].as_u64()) else {
+            continue;
+        };
+        let Some(file) = crate_dir
+            .ancestors()
+            .take_while(|dir| dir.starts_with(root))
+            .map(|dir| dir.join(file_name))
+            .find(|path| path.is_file())
+            .and_then(|path| path.strip_prefix(root).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        else {
+            continue;
+        };
+        diagnostics.push(Diagnostic {
+            file,
+            line: line as usize,
+            level: level.to_string(),
+            code: message["code"]["code"].as_str().map(str::to_string),
+            message: message["message"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_messages_resolves_workspace_relative_paths() {
+        let root = std::env::temp_dir().join("pr-analyzer-test-clippy");
+        let crate_dir = root.join("crates/core");
+        std::fs::create_dir_all(crate_dir.join("src")).unwrap();
+        std::fs::write(crate_dir.join("src/lib.rs"), "").unwrap();
+
+        let stdout = [
+            r#"{"reason":"compiler-artifact","target":{}}"#,
+            r#"{"reason":"compiler-message","message":{"level":"warning","message":"redundant clone","code":{"code":"clippy::redundant_clone"},"spans":[{"file_name":"crates/core/src/lib.rs","line_start":7,"is_primary":true}]}}"#,
+            r#"{"reason":"compiler-message","message":{"level":"note","message":"ignored","code":null,"spans":[]}}"#,
+            r#"{"reason":"build-finished","success":true}"#,
+        ]
+        .join("\n");
+        let diagnostics = parse_messages(&stdout, &crate_dir, &root);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                file: "crates/core/src/lib.rs".to_string(),
+                line: 7,
+                level: "warning".to_string(),
+                code: Some("clippy::redundant_clone".to_string()),
+                message: "redundant clone".to_string(),
+            }]
+        );
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,272 @@
+pub mod blame;
+pub mod clippy;
+
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Arc;
+
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::pr::PrUrl;
+
+#[derive(Debug, Error)]
+pub enum RepoError {
+    #[error("Failed to run command: {0}")]
+    Spawn(#[from] std::io::Error),
+
+    #[error("git {command} failed: {stderr}")]
+    Git { command: String, stderr: String },
+
+    #[error("Path escapes the checkout: {0}")]
+    PathOutsideCheckout(String),
+
+    #[error("Not a text file: {0}")]
+    Binary(String),
+}
+
+/// A local checkout of the analyzed repository, used by checks that need
+/// whole files or history rather than the diff (blame, file length, ...).
+///
+/// Either an existing working tree (`--repo`) or a shallow clone of the PR
+/// head made for this run (`--clone`), which is deleted once the last copy
+/// of its `Checkout` is dropped, however the run ends.
+#[derive(Debug, Clone)]
+pub struct Checkout {
+    root: PathBuf,
+    /// Held, never read: set when this run created the directory, which
+    /// goes when the last copy of the guard does
+    _clone: Option<Arc<CloneDir>>,
+}
+
+/// A directory cloned for this run; dropping it deletes the directory.
+#[derive(Debug)]
+struct CloneDir(PathBuf);
+
+impl Drop for CloneDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            debug!(dir = %self.0.display(), error = %e, "could not remove clone");
+        }
+    }
+}
+
+impl Checkout {
+    /// Use an existing working tree.
+    pub fn open(root: &Path) -> Result<Self, RepoError> {
+        let checkout = Self { root: root.to_path_buf(), _clone: None };
+        checkout.git(&["rev-parse", "--is-inside-work-tree"])?;
+        Ok(checkout)
+    }
+
+    /// A checkout rooted at `root` without verifying it is a git work tree.
+    #[cfg(test)]
+    pub fn unchecked(root: &Path) -> Self {
+        Self { root: root.to_path_buf(), _clone: None }
+    }
+
+    /// Shallow-clone the PR head from `host` (github.com or a GitHub
+    /// Enterprise Server) into a fresh temporary directory. `depth` commits
+    /// of history are fetched so blame has something to attribute. Dropping
+    /// the future kills git and deletes the directory, so a clone can be
+    /// interrupted.
+    pub async fn clone_pull_request(pr_url: &PrUrl, host: &str, token: Option<&str>, depth: usize) -> Result<Self, RepoError> {
+        let root = std::env::temp_dir().join(format!("pr-analyzer-{}-{}-{}-{}", pr_url.owner, pr_url.repo, pr_url.pr_number, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root)?;
+        let checkout = Self { _clone: Some(Arc::new(CloneDir(root.clone()))), root };
+        info!(dir = %checkout.root.display(), depth, "cloning PR head");
+
+        let remote = format!("https://{}/{}/{}.git", host, pr_url.owner, pr_url.repo);
+        let refspec = format!("pull/{}/head", pr_url.pr_number);
+        let depth = format!("--depth={}", depth.max(1));
+        // On failure the checkout is dropped, which deletes the directory.
+        checkout.git_async(&["init", "--quiet"], None).await?;
+        checkout.git_async(&["fetch", "--quiet", "--no-tags", &depth, &remote, &refspec], token.map(|token| (host, token))).await?;
+        checkout.git_async(&["checkout", "--quiet", "--detach", "FETCH_HEAD"], None).await?;
+        Ok(checkout)
+    }
+
+    /// Commit currently checked out.
+    pub fn head_sha(&self) -> Result<String, RepoError> {
+        Ok(self.git(&["rev-parse", "HEAD"])?.trim().to_string())
+    }
+
+    /// Root of the working tree, which diff paths are relative to.
+    pub fn top_level(&self) -> Result<PathBuf, RepoError> {
+        Ok(PathBuf::from(self.git(&["rev-parse", "--show-toplevel"])?.trim()))
+    }
+
+    /// Staged and unstaged changes to tracked files, as a unified diff
+    /// against `HEAD`. Renames are detected whatever `diff.renames` says, so
+    /// a moved file is not reported as a full deletion and addition.
+    pub fn uncommitted_diff(&self) -> Result<String, RepoError> {
+        self.git(&["diff", "--no-color", "--no-ext-diff", "--find-renames", "HEAD"])
+    }
+
+    /// Staged changes only, as a unified diff against `HEAD`.
+    pub fn staged_diff(&self) -> Result<String, RepoError> {
+        self.git(&["diff", "--no-color", "--no-ext-diff", "--find-renames", "--cached", "HEAD"])
+    }
+
+    /// Changes between the two ends of a `base..head` or `base...head`
+    /// range, as a unified diff.
+    pub fn range_diff(&self, range: &str) -> Result<String, RepoError> {
+        self.git(&["diff", "--no-color", "--no-ext-diff", "--find-renames", range, "--"])
+    }
+
+    /// Full SHA of a revision.
+    pub fn resolve(&self, revision: &str) -> Result<String, RepoError> {
+        Ok(self.git(&["rev-parse", "--verify", revision])?.trim().to_string())
+    }
+
+    /// Author name of a commit.
+    pub fn commit_author(&self, revision: &str) -> Result<String, RepoError> {
+        Ok(self.git(&["log", "-1", "--format=%an", revision, "--"])?.trim().to_string())
+    }
+
+    /// Read a text file from the working tree by its repository-relative
+    /// path. Text in a legacy encoding (Latin-1, Shift-JIS) is read with its
+    /// invalid UTF-8 replaced; a file with a NUL byte in its first 8000
+    /// bytes is binary, as git decides, and an error.
+    pub fn read_file(&self, path: &str) -> Result<String, RepoError> {
+        let relative = Path::new(path);
+        if relative.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err(RepoError::PathOutsideCheckout(path.to_string()));
+        }
+        let bytes = std::fs::read(self.root.join(relative))?;
+        if bytes.iter().take(8000).any(|b| *b == 0) {
+            return Err(RepoError::Binary(path.to_string()));
+        }
+        Ok(match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        })
+    }
+
+    /// Whether a repository-relative path is a file in the working tree.
+    pub fn has_file(&self, path: &str) -> bool {
+        let relative = Path::new(path);
+        relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) && self.root.join(relative).is_file()
+    }
+
+    /// Run `git <args>` in the checkout and return stdout.
+    fn git(&self, args: &[&str]) -> Result<String, RepoError> {
+        self.git_with_token(args, None)
+    }
+
+    /// Like `git`, authenticating HTTPS requests to `auth`'s host with its
+    /// GitHub token. The header goes through `GIT_CONFIG_*` variables so the
+    /// token never appears in the process arguments.
+    fn git_with_token(&self, args: &[&str], auth: Option<(&str, &str)>) -> Result<String, RepoError> {
+        let output = self.git_command(args, auth).output()?;
+        git_output(args, output)
+    }
+
+    /// Like `git_with_token`, without blocking the runtime. git is killed
+    /// if the future is dropped before it exits.
+    async fn git_async(&self, args: &[&str], auth: Option<(&str, &str)>) -> Result<String, RepoError> {
+        let output = tokio::process::Command::from(self.git_command(args, auth)).kill_on_drop(true).output().await?;
+        git_output(args, output)
+    }
+
+    fn git_command(&self, args: &[&str], auth: Option<(&str, &str)>) -> Command {
+        debug!(root = %self.root.display(), ?args, "running git");
+        let mut command = Command::new("git");
+        command.arg("-C").arg(&self.root).args(args);
+        if let Some((host, token)) = auth {
+            let credentials = base64(format!("x-access-token:{}", token).as_bytes());
+            command
+                .env("GIT_CONFIG_COUNT", "1")
+                .env("GIT_CONFIG_KEY_0", format!("http.https://{}/.extraheader", host))
+                .env("GIT_CONFIG_VALUE_0", format!("Authorization: Basic {}", credentials));
+        }
+        command
+    }
+}
+
+/// Stdout of a finished git command, or its stderr as the error.
+fn git_output(args: &[&str], output: Output) -> Result<String, RepoError> {
+    if !output.status.success() {
+        return Err(RepoError::Git {
+            command: args.first().copied().unwrap_or_default().to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Standard base64 with padding (RFC 4648), for the HTTP Basic header.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (u32::from(*b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"x-access-token:abc"), "eC1hY2Nlc3MtdG9rZW46YWJj");
+    }
+
+    #[test]
+    fn test_read_file_rejects_escaping_paths() {
+        let checkout = Checkout::unchecked(&std::env::temp_dir());
+        assert!(matches!(checkout.read_file("../etc/passwd"), Err(RepoError::PathOutsideCheckout(_))));
+        assert!(matches!(checkout.read_file("/etc/passwd"), Err(RepoError::PathOutsideCheckout(_))));
+    }
+
+    #[test]
+    fn test_read_file_replaces_legacy_encodings_and_rejects_binaries() {
+        let root = std::env::temp_dir().join(format!("pr-analyzer-read-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("latin1.rs"), b"// caf\xe9\nfn main() {}\n").unwrap();
+        std::fs::write(root.join("image.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let checkout = Checkout::unchecked(&root);
+        assert_eq!(checkout.read_file("latin1.rs").unwrap(), "// caf\u{fffd}\nfn main() {}\n");
+        assert!(matches!(checkout.read_file("image.png"), Err(RepoError::Binary(_))));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_clone_dir_removed_when_last_checkout_dropped() {
+        let root = std::env::temp_dir().join("pr-analyzer-test-clone-guard");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let checkout = Checkout { root: root.clone(), _clone: Some(Arc::new(CloneDir(root.clone()))) };
+        let copy = checkout.clone();
+        drop(checkout);
+        assert!(root.join("src").is_dir());
+        drop(copy);
+        assert!(!root.exists());
+
+        let existing = std::env::temp_dir().join("pr-analyzer-test-open-kept");
+        std::fs::create_dir_all(&existing).unwrap();
+        drop(Checkout::unchecked(&existing));
+        assert!(existing.is_dir());
+    }
+
+    #[test]
+    fn test_open_rejects_non_repository() {
+        let dir = std::env::temp_dir().join("pr-analyzer-test-not-a-repo");
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(Checkout::open(&dir).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
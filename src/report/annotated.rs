@@ -0,0 +1,104 @@
+//! `--format annotated-diff`: the unified diff again, with each finding
+//! printed under the line it is about, the way `cargo clippy` points at
+//! source lines.
+//!
+//! Marker lines start with `^` (a finding on the line above), `=` (a
+//! finding about the file or the whole change) or `!` (an analysis
+//! warning), never with a diff prefix, so they cannot be mistaken for diff
+//! content.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::{rerun_tag, Catalog, Finding, Report};
+use crate::pr::types::DiffFile;
+
+/// The annotated diff of `diff`, the files the report was built from.
+pub(crate) fn render(report: &Report, diff: &[DiffFile], catalog: &Catalog) -> String {
+    let mut by_file: BTreeMap<&str, Vec<(&str, &Finding)>> = BTreeMap::new();
+    let mut change_wide = Vec::new();
+    for result in &report.results {
+        for finding in &result.findings {
+            match finding.file.as_deref().filter(|path| diff.iter().any(|f| f.path() == *path)) {
+                Some(path) => by_file.entry(path).or_default().push((result.analyzer_name.as_str(), finding)),
+                None => change_wide.push((result.analyzer_name.as_str(), finding)),
+            }
+        }
+    }
+    let marker = |out: &mut String, mark: char, analyzer: &str, finding: &Finding| {
+        let tag = rerun_tag(report, analyzer, finding).map_or(String::new(), |t| format!("[{}] ", t));
+        let _ = writeln!(out, "{} {}{}: {} ({})", mark, tag, finding.severity, catalog.message(finding), analyzer);
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "PR #{}: \"{}\"", report.pr_number, report.pr_title);
+    for warning in &report.warnings {
+        let file = warning.file.as_deref().map_or(String::new(), |f| format!(" ({})", f));
+        let _ = writeln!(out, "! {}{}", catalog.warning(warning), file);
+    }
+    for (analyzer, finding) in &change_wide {
+        marker(&mut out, '=', analyzer, finding);
+    }
+    for file in diff {
+        let findings = by_file.remove(file.path()).unwrap_or_default();
+        // Lines are numbered as the analyzers number them: new_start plus the index in the hunk.
+        let shown = |line: usize| file.hunks().iter().any(|h| h.lines.iter().enumerate().any(|(i, l)| h.new_start + i == line && !l.starts_with('-')));
+        let _ = writeln!(out);
+        let _ = writeln!(out, "--- {}", if file.is_new() { "/dev/null".to_string() } else { format!("a/{}", file.previous_path().unwrap_or(file.path())) });
+        let _ = writeln!(out, "+++ {}", if file.is_deleted() { "/dev/null".to_string() } else { format!("b/{}", file.path()) });
+        for (analyzer, finding) in findings.iter().filter(|(_, f)| !f.line.is_some_and(shown)) {
+            marker(&mut out, '=', analyzer, finding);
+        }
+        for hunk in file.hunks() {
+            let _ = writeln!(out, "@@ -{},{} +{},{} @@", hunk.old_start, hunk.old_count, hunk.new_start, hunk.new_count);
+            let mut marked = None;
+            for (i, line) in hunk.lines.iter().enumerate() {
+                let _ = writeln!(out, "{}", line);
+                let number = hunk.new_start + i;
+                if line.starts_with('-') || marked == Some(number) {
+                    continue;
+                }
+                marked = Some(number);
+                for (analyzer, finding) in findings.iter().filter(|(_, f)| f.line == Some(number)) {
+                    marker(&mut out, '^', analyzer, finding);
+                }
+            }
+        }
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}: {}", catalog.chrome("overall_risk"), report.overall_risk);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::sample_pr;
+    use crate::report::{build, AnalysisResult, RiskLevel};
+
+    #[test]
+    fn test_findings_follow_their_lines() {
+        let mut pr = sample_pr();
+        let file = DiffFile::builder("src/db.rs")
+            .hunk(10, 10, [" fn query() {", "-    old()", "+    let q = format!(\"SELECT {}\", id);", "+    run(q)"])
+            .build()
+            .unwrap();
+        pr.set_files(vec![file]);
+        let results = vec![AnalysisResult {
+            analyzer_name: "Security Risk Assessment".to_string(),
+            risk_level: RiskLevel::High,
+            findings: vec![
+                Finding::new("security.sql_construction", RiskLevel::High).in_file("src/db.rs").at_line(12),
+                Finding::new("security.new_dependencies", RiskLevel::Low).param("count", 1).param("file", "src/db.rs").param("dependencies", "x").in_file("src/db.rs"),
+                Finding::new("security.auth_surfaces", RiskLevel::Medium).param("count", 1).param("files", "src/auth.rs"),
+            ],
+        }];
+        let report = build(results, &pr);
+        let text = render(&report, pr.files(), Catalog::english());
+        let sql = "^ HIGH: Possible SQL injection: raw SQL query construction with string interpolation (Security Risk Assessment)";
+        assert!(text.contains(&format!("+    let q = format!(\"SELECT {{}}\", id);\n{}\n+    run(q)\n", sql)), "{text}");
+        assert!(text.contains("+++ b/src/db.rs\n= LOW: "), "{text}");
+        assert!(text.contains("\"\n= MEDIUM: Authentication/authorization code changed"), "{text}");
+        assert!(text.ends_with("Overall Risk: HIGH\n"), "{text}");
+    }
+}
@@ -0,0 +1,388 @@
+//! Report formats CI servers and review bots read natively: TeamCity service
+//! messages (`--format teamcity`), GitLab Code Quality JSON (`--format
+//! codequality`), Checkstyle XML (`--format checkstyle`, read by reviewdog
+//! and Jenkins warnings-ng, among others), the Reviewdog Diagnostic
+//! Format (`--format rdjson`) and GitHub Actions workflow commands
+//! (`--format github`).
+//!
+//! All of them but the last need a file for every finding; findings about
+//! the change as a whole are attached to the repository root (`.`).
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::html::escape as xml_escape;
+use super::{Catalog, Finding, FindingKey, Report, ReportError, RiskLevel};
+use crate::pr::types::DiffFile;
+
+/// Path given to findings without a file.
+const CHANGE_PATH: &str = ".";
+
+/// Every finding with its analyzer, in report order.
+fn findings(report: &Report) -> impl Iterator<Item = (&str, &Finding)> {
+    report.results.iter().flat_map(|r| r.findings.iter().map(move |f| (r.analyzer_name.as_str(), f)))
+}
+
+/// Line of `finding` in its file as saved. Analyzers number a finding by its
+/// place in the hunk, removed lines included, so the line is mapped through
+/// `diff`; a line past the diff is kept as is.
+fn file_line(diff: &[DiffFile], finding: &Finding) -> Option<usize> {
+    let line = finding.line?;
+    let file = finding.file.as_deref().and_then(|path| diff.iter().find(|f| f.path() == path));
+    Some(file.and_then(|f| f.file_line(line)).unwrap_or(line))
+}
+
+/// Inspection type of a finding: its message id, or the analyzer for free text.
+fn check_name<'a>(analyzer: &'a str, finding: &'a Finding) -> &'a str {
+    finding.message_id.as_deref().unwrap_or(analyzer)
+}
+
+/// TeamCity service messages: one `inspectionType` per check, then one
+/// `inspection` per finding, which TeamCity lists on the build's
+/// Inspections tab. Analysis warnings go to the build log as warning
+/// messages. Lines are mapped through `diff` to the file as saved.
+pub(crate) fn teamcity(report: &Report, diff: &[DiffFile], catalog: &Catalog) -> String {
+    let mut out = String::new();
+    for warning in &report.warnings {
+        let file = warning.file.as_deref().map_or(String::new(), |f| format!(" ({})", f));
+        out.push_str(&format!("##teamcity[message text='{}' status='WARNING']\n", escape(&format!("{}{}", catalog.warning(warning), file))));
+    }
+    let mut types = BTreeMap::new();
+    for (analyzer, finding) in findings(report) {
+        types.entry(check_name(analyzer, finding)).or_insert(analyzer);
+    }
+    for (id, analyzer) in &types {
+        out.push_str(&format!("##teamcity[inspectionType id='{}' name='{}' category='{}' description='{}']\n", escape(id), escape(id), escape(analyzer), escape(analyzer)));
+    }
+    for (analyzer, finding) in findings(report) {
+        let severity = match finding.severity {
+            RiskLevel::High => "ERROR",
+            RiskLevel::Medium => "WARNING",
+            RiskLevel::Low => "WEAK WARNING",
+        };
+        let line = file_line(diff, finding).map_or(String::new(), |l| format!(" line='{}'", l));
+        out.push_str(&format!(
+            "##teamcity[inspection typeId='{}' message='{}' file='{}'{} SEVERITY='{}']\n",
+            escape(check_name(analyzer, finding)),
+            escape(&catalog.message(finding)),
+            escape(finding.file.as_deref().unwrap_or(CHANGE_PATH)),
+            line,
+            severity
+        ));
+    }
+    out
+}
+
+/// Escape a service message attribute value (`|` is TeamCity's escape character).
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '|' => escaped.push_str("||"),
+            '\'' => escaped.push_str("|'"),
+            '\n' => escaped.push_str("|n"),
+            '\r' => escaped.push_str("|r"),
+            '[' => escaped.push_str("|["),
+            ']' => escaped.push_str("|]"),
+            '\u{0085}' => escaped.push_str("|x"),
+            '\u{2028}' => escaped.push_str("|l"),
+            '\u{2029}' => escaped.push_str("|p"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// One issue of a GitLab Code Quality report (the Code Climate issue format).
+#[derive(Serialize)]
+struct Issue<'a> {
+    description: String,
+    check_name: &'a str,
+    fingerprint: String,
+    severity: &'static str,
+    location: Location<'a>,
+}
+
+#[derive(Serialize)]
+struct Location<'a> {
+    path: &'a str,
+    lines: Lines,
+}
+
+#[derive(Serialize)]
+struct Lines {
+    begin: usize,
+}
+
+/// GitLab Code Quality JSON, for `artifacts: reports: codequality`. GitLab
+/// compares fingerprints between the merge request and its target branch to
+/// show new and fixed issues, so a fingerprint leaves out the line (as
+/// `FindingKey` does) and numbers repeats of the same finding in a file.
+/// Every issue needs a line, so findings without one (about the change as a
+/// whole, or a whole file) are left out; lines are mapped through `diff`.
+pub(crate) fn code_quality(report: &Report, diff: &[DiffFile], catalog: &Catalog) -> Result<String, ReportError> {
+    let mut seen: BTreeMap<FindingKey, usize> = BTreeMap::new();
+    let issues: Vec<Issue> = findings(report)
+        .filter_map(|(analyzer, finding)| Some((analyzer, finding, file_line(diff, finding)?)))
+        .map(|(analyzer, finding, line)| {
+            let key = FindingKey::new(analyzer, finding);
+            let occurrence = seen.entry(key.clone()).or_insert(0);
+            *occurrence += 1;
+            let identity = format!("{}\n{}\n{}\n{}", key.analyzer, key.file.as_deref().unwrap_or(""), key.message, occurrence);
+            Issue {
+                description: catalog.message(finding).into_owned(),
+                check_name: check_name(analyzer, finding),
+                fingerprint: Sha256::digest(identity.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect(),
+                severity: match finding.severity {
+                    RiskLevel::High => "critical",
+                    RiskLevel::Medium => "major",
+                    RiskLevel::Low => "minor",
+                },
+                location: Location { path: finding.file.as_deref().unwrap_or(CHANGE_PATH), lines: Lines { begin: line } },
+            }
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&issues)?)
+}
+
+/// Checkstyle XML: a `<file>` per path, in path order, with an `<error>` per
+/// finding. `source` is `pr-analyzer.` plus the check name, so tools can
+/// filter by rule. Lines are mapped through `diff` to the file as saved.
+pub(crate) fn checkstyle(report: &Report, diff: &[DiffFile], catalog: &Catalog) -> String {
+    let mut by_file: BTreeMap<&str, Vec<(&str, &Finding)>> = BTreeMap::new();
+    for (analyzer, finding) in findings(report) {
+        by_file.entry(finding.file.as_deref().unwrap_or(CHANGE_PATH)).or_default().push((analyzer, finding));
+    }
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checkstyle version=\"4.3\">\n");
+    for (path, findings) in by_file {
+        out.push_str(&format!("  <file name=\"{}\">\n", xml_escape(path)));
+        for (analyzer, finding) in findings {
+            let severity = match finding.severity {
+                RiskLevel::High => "error",
+                RiskLevel::Medium => "warning",
+                RiskLevel::Low => "info",
+            };
+            let line = file_line(diff, finding).map_or(String::new(), |l| format!(" line=\"{}\"", l));
+            out.push_str(&format!(
+                "    <error{} severity=\"{}\" message=\"{}\" source=\"pr-analyzer.{}\"/>\n",
+                line,
+                severity,
+                xml_escape(&catalog.message(finding)),
+                xml_escape(check_name(analyzer, finding))
+            ));
+        }
+        out.push_str("  </file>\n");
+    }
+    out.push_str("</checkstyle>\n");
+    out
+}
+
+/// A Reviewdog Diagnostic Format result.
+#[derive(Serialize)]
+struct DiagnosticResult<'a> {
+    source: Source,
+    diagnostics: Vec<Diagnostic<'a>>,
+}
+
+#[derive(Serialize)]
+struct Source {
+    name: &'static str,
+}
+
+#[derive(Serialize)]
+struct Diagnostic<'a> {
+    message: String,
+    location: DiagnosticLocation<'a>,
+    severity: &'static str,
+    code: Code<'a>,
+}
+
+#[derive(Serialize)]
+struct DiagnosticLocation<'a> {
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<Range>,
+}
+
+#[derive(Serialize)]
+struct Range {
+    start: Position,
+}
+
+#[derive(Serialize)]
+struct Position {
+    line: usize,
+}
+
+#[derive(Serialize)]
+struct Code<'a> {
+    value: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+}
+
+/// Reviewdog Diagnostic Format (RDJSON), for `reviewdog -f=rdjson`. Findings
+/// without a line have no range; reviewdog only comments on them when its
+/// filter mode lets file-level diagnostics through. Lines are mapped through
+/// `diff` to the file as saved, which is what reviewdog matches against.
+pub(crate) fn rdjson(report: &Report, diff: &[DiffFile], catalog: &Catalog) -> Result<String, ReportError> {
+    let diagnostics = findings(report)
+        .map(|(analyzer, finding)| Diagnostic {
+            message: catalog.message(finding).into_owned(),
+            location: DiagnosticLocation {
+                path: finding.file.as_deref().unwrap_or(CHANGE_PATH),
+                range: file_line(diff, finding).map(|line| Range { start: Position { line } }),
+            },
+            severity: match finding.severity {
+                RiskLevel::High => "ERROR",
+                RiskLevel::Medium => "WARNING",
+                RiskLevel::Low => "INFO",
+            },
+            code: Code { value: check_name(analyzer, finding), url: finding.docs_url.as_deref() },
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&DiagnosticResult { source: Source { name: "pr-analyzer" }, diagnostics })?)
+}
+
+/// GitHub Actions workflow commands: one `::error`, `::warning` or
+/// `::notice` per finding (HIGH, MEDIUM, LOW), which Actions shows as an
+/// annotation on the PR's diff, or on the workflow run for findings without
+/// a file. Lines are mapped through `diff` to the file as saved. Analysis
+/// warnings become `::warning` commands too.
+pub(crate) fn github(report: &Report, diff: &[DiffFile], catalog: &Catalog) -> String {
+    let mut out = String::new();
+    for warning in &report.warnings {
+        let file = warning.file.as_deref().map_or(String::new(), |f| format!(" file={},", github_property(f)));
+        out.push_str(&format!("::warning{}title=pr-analyzer::{}\n", file, github_data(&catalog.warning(warning))));
+    }
+    for (analyzer, finding) in findings(report) {
+        let command = match finding.severity {
+            RiskLevel::High => "error",
+            RiskLevel::Medium => "warning",
+            RiskLevel::Low => "notice",
+        };
+        let mut properties = Vec::new();
+        if let Some(path) = &finding.file {
+            properties.push(format!("file={}", github_property(path)));
+            if let Some(line) = file_line(diff, finding) {
+                properties.push(format!("line={}", line));
+            }
+        }
+        let title = finding.message_id.as_ref().map_or(analyzer.to_string(), |id| format!("{} ({})", id, analyzer));
+        properties.push(format!("title={}", github_property(&title)));
+        out.push_str(&format!("::{} {}::{}\n", command, properties.join(","), github_data(&catalog.message(finding))));
+    }
+    out
+}
+
+/// Escape a workflow command's message.
+fn github_data(value: &str) -> String {
+    value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value, which also ends at `,` and `:`.
+fn github_property(value: &str) -> String {
+    github_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::sample_pr;
+    use crate::report::{build, AnalysisResult};
+
+    fn sample_report() -> Report {
+        let results = vec![AnalysisResult {
+            analyzer_name: "Style & Architecture Assessment".to_string(),
+            risk_level: RiskLevel::Medium,
+            findings: vec![
+                Finding::new("style.unwrap", RiskLevel::Medium).in_file("src/a.rs").at_line(3),
+                Finding::new("style.unwrap", RiskLevel::Medium).in_file("src/a.rs").at_line(9),
+                Finding::text("it's [odd] | here", RiskLevel::Low),
+            ],
+        }];
+        build(results, &sample_pr())
+    }
+
+    /// `src/a.rs` with a removed line: hunk line 3 is line 2 of the saved file.
+    fn sample_diff() -> [DiffFile; 1] {
+        [DiffFile::builder("src/a.rs").hunk(1, 1, [" a", "-b", "+c"]).build().unwrap()]
+    }
+
+    #[test]
+    fn test_teamcity_service_messages() {
+        let text = teamcity(&sample_report(), &sample_diff(), Catalog::english());
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "##teamcity[inspectionType id='Style & Architecture Assessment' name='Style & Architecture Assessment' category='Style & Architecture Assessment' description='Style & Architecture Assessment']");
+        assert_eq!(lines[1], "##teamcity[inspectionType id='style.unwrap' name='style.unwrap' category='Style & Architecture Assessment' description='Style & Architecture Assessment']");
+        assert!(lines[2].starts_with("##teamcity[inspection typeId='style.unwrap' message='Use of .unwrap()"), "{}", lines[2]);
+        assert!(lines[2].ends_with(" file='src/a.rs' line='2' SEVERITY='WARNING']"), "{}", lines[2]);
+        assert_eq!(lines[4], "##teamcity[inspection typeId='Style & Architecture Assessment' message='it|'s |[odd|] || here' file='.' SEVERITY='WEAK WARNING']");
+    }
+
+    #[test]
+    fn test_code_quality_issues() {
+        let json: serde_json::Value = serde_json::from_str(&code_quality(&sample_report(), &sample_diff(), Catalog::english()).unwrap()).unwrap();
+        let issues = json.as_array().unwrap();
+        // The free-text finding has no line, so it is left out.
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0]["check_name"], "style.unwrap");
+        assert_eq!(issues[0]["severity"], "major");
+        assert_eq!(issues[0]["location"]["path"], "src/a.rs");
+        assert_eq!(issues[0]["location"]["lines"]["begin"], 2);
+        assert_eq!(issues[1]["location"]["lines"]["begin"], 9);
+        assert_ne!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+        // Moving a finding to another line keeps its fingerprint.
+        let mut moved = sample_report();
+        moved.results[0].findings[0].line = Some(30);
+        let moved: serde_json::Value = serde_json::from_str(&code_quality(&moved, &sample_diff(), Catalog::english()).unwrap()).unwrap();
+        assert_eq!(moved[0]["fingerprint"], issues[0]["fingerprint"]);
+    }
+
+    #[test]
+    fn test_rdjson_diagnostics() {
+        let json: serde_json::Value = serde_json::from_str(&rdjson(&sample_report(), &sample_diff(), Catalog::english()).unwrap()).unwrap();
+        assert_eq!(json["source"]["name"], "pr-analyzer");
+        let diagnostics = json["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0]["location"], serde_json::json!({ "path": "src/a.rs", "range": { "start": { "line": 2 } } }));
+        assert_eq!(diagnostics[1]["location"]["range"]["start"]["line"], 9);
+        assert_eq!(diagnostics[0]["severity"], "WARNING");
+        assert_eq!(diagnostics[0]["code"]["value"], "style.unwrap");
+        assert_eq!(diagnostics[2]["location"], serde_json::json!({ "path": "." }));
+        assert_eq!(diagnostics[2]["severity"], "INFO");
+        assert!(diagnostics[0]["code"].get("url").is_none());
+
+        let mut report = sample_report();
+        report.results[0].findings[0].docs_url = Some("https://docs.example.com/rules/style.unwrap".to_string());
+        let json: serde_json::Value = serde_json::from_str(&rdjson(&report, &sample_diff(), Catalog::english()).unwrap()).unwrap();
+        assert_eq!(json["diagnostics"][0]["code"]["url"], "https://docs.example.com/rules/style.unwrap");
+    }
+
+    #[test]
+    fn test_checkstyle_groups_findings_by_file() {
+        let xml = checkstyle(&sample_report(), &sample_diff(), Catalog::english());
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checkstyle version=\"4.3\">\n  <file name=\".\">\n"), "{xml}");
+        assert!(xml.contains("    <error severity=\"info\" message=\"it&#39;s [odd] | here\" source=\"pr-analyzer.Style &amp; Architecture Assessment\"/>\n  </file>\n  <file name=\"src/a.rs\">\n"), "{xml}");
+        assert!(xml.contains("  <file name=\"src/a.rs\">\n    <error line=\"2\" severity=\"warning\" message=\"Use of .unwrap()"), "{xml}");
+        assert!(xml.contains("    <error line=\"9\" severity=\"warning\" message=\"Use of .unwrap()"), "{xml}");
+        assert_eq!(xml.matches("source=\"pr-analyzer.style.unwrap\"").count(), 2);
+        assert!(xml.ends_with("  </file>\n</checkstyle>\n"));
+    }
+
+    #[test]
+    fn test_github_workflow_commands() {
+        let mut report = sample_report();
+        report.results[0].findings[2].message = "100% odd,\nreally".to_string();
+        let text = github(&report, &sample_diff(), Catalog::english());
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        // Line 3 of the hunk is the file's line 2; line 9 is past the diff and kept.
+        assert!(lines[0].starts_with("::warning file=src/a.rs,line=2,title=style.unwrap (Style & Architecture Assessment)::Use of .unwrap()"), "{}", lines[0]);
+        assert!(lines[1].starts_with("::warning file=src/a.rs,line=9,"), "{}", lines[1]);
+        assert_eq!(lines[2], "::notice title=Style & Architecture Assessment::100%25 odd,%0Areally");
+        assert_eq!(github_property("a,b: c"), "a%2Cb%3A c");
+    }
+}
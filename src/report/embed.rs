@@ -0,0 +1,59 @@
+//! Machine-readable copy of a report inside its markdown.
+//!
+//! The markdown report ends with an HTML comment holding the report as
+//! deflated, base64-encoded JSON. The comment is invisible when the markdown
+//! is rendered (e.g. as a PR comment), and `extract` reads it back, so a CI
+//! job without a `--history` file can compare against the report it posted
+//! last time (`--previous`).
+
+use std::io::Read;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use super::{Report, ReportError};
+
+/// Opening of the comment; the version changes if the payload encoding does.
+const MARKER: &str = "<!-- pr-analyzer:report v1 ";
+
+/// The comment line embedding `report`. The rules appendix and the previous
+/// comparison are left out: neither is needed to compare against a later run.
+pub(crate) fn footer(report: &Report) -> Result<String, ReportError> {
+    let mut value = serde_json::to_value(report)?;
+    if let Some(fields) = value.as_object_mut() {
+        fields.remove("rules");
+        fields.remove("since_last_run");
+    }
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    serde_json::to_writer(&mut encoder, &value)?;
+    Ok(format!("{}{} -->\n", MARKER, STANDARD.encode(encoder.finish()?)))
+}
+
+/// The report embedded in `markdown` by `footer`, or None when there is none.
+/// With several (e.g. a comment quoting an older one) the last wins.
+pub fn extract(markdown: &str) -> Result<Option<Report>, ReportError> {
+    let Some(start) = markdown.rfind(MARKER).map(|i| i + MARKER.len()) else {
+        return Ok(None);
+    };
+    let invalid = |reason: String| ReportError::Embedded(reason);
+    let end = markdown[start..].find("-->").ok_or_else(|| invalid("unterminated comment".to_string()))?;
+    let compressed = STANDARD.decode(markdown[start..start + end].trim()).map_err(|e| invalid(e.to_string()))?;
+    let mut json = Vec::new();
+    DeflateDecoder::new(compressed.as_slice()).read_to_end(&mut json).map_err(|e| invalid(e.to_string()))?;
+    serde_json::from_slice(&json).map(Some).map_err(|e| invalid(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_without_or_with_broken_footer() {
+        assert!(extract("# PR #42\n\nNo findings.\n").unwrap().is_none());
+        assert!(matches!(extract("<!-- pr-analyzer:report v1 not base64! -->"), Err(ReportError::Embedded(_))));
+        assert!(matches!(extract("<!-- pr-analyzer:report v1 AAAA"), Err(ReportError::Embedded(_))));
+    }
+}
@@ -0,0 +1,372 @@
+//! Self-contained HTML report with a risk heatmap of changed files.
+//!
+//! The heatmap is a treemap: each changed file is a tile whose area is its
+//! lines changed and whose color is the highest severity found in it. Tiles
+//! link to that file's findings further down the page.
+
+use std::collections::BTreeMap;
+
+use super::messages::Catalog;
+use super::rerun_tag;
+use super::types::{Finding, Report, RiskLevel};
+
+pub(super) const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem;color:#222}\
+.heatmap{position:relative;width:100%;height:360px;border:1px solid #ccc}\
+.tile{position:absolute;box-sizing:border-box;border:1px solid #fff;overflow:hidden;color:#111;font-size:12px;padding:2px 4px;text-decoration:none}\
+.tile:hover{outline:2px solid #000;z-index:1}\
+.risk-low{background:#b7e4c7}.risk-medium{background:#ffd166}.risk-high{background:#ef476f;color:#fff}\
+.clean{background:#e9ecef}\
+.notice{background:#fff3cd;padding:.5rem 1rem;border-left:4px solid #ffb703}";
+
+/// Position of a tile, in percent of the heatmap box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Render the full report as one HTML document, with text from `catalog`.
+pub fn render(report: &Report, catalog: &Catalog) -> String {
+    let t = |key| escape(catalog.chrome(key));
+    let mut by_file: BTreeMap<&str, Vec<(&str, &Finding)>> = BTreeMap::new();
+    let mut general = Vec::new();
+    for result in &report.results {
+        for finding in &result.findings {
+            match &finding.file {
+                Some(file) => by_file.entry(file.as_str()).or_default().push((result.analyzer_name.as_str(), finding)),
+                None => general.push((result.analyzer_name.as_str(), finding)),
+            }
+        }
+    }
+    let file_risk = |path: &str| by_file.get(path).and_then(|f| f.iter().map(|(_, f)| f.severity).max());
+
+    let mut html = String::new();
+    html.push_str(&format!("<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n", escape(&catalog.language)));
+    html.push_str(&format!("<title>PR #{}: {}</title>\n", report.pr_number, escape(&report.pr_title)));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str(&format!("<h1>PR #{}: {}</h1>\n", report.pr_number, escape(&report.pr_title)));
+    html.push_str(&format!(
+        "<p><strong>{}:</strong> {} | <strong>{}:</strong> {} | <strong>+{} -{}</strong> | <strong>{}:</strong> {}</p>\n",
+        t("author"),
+        escape(&report.author),
+        t("files_changed"),
+        report.files_changed,
+        report.additions,
+        report.deletions,
+        t("overall_risk"),
+        report.overall_risk
+    ));
+    if let Some(effort) = &report.review_effort {
+        html.push_str(&format!("<p><strong>{}:</strong> ~{} {}</p>\n", t("review_effort"), effort.minutes, t("minutes")));
+    }
+    if report.baselined > 0 {
+        html.push_str(&format!("<p><strong>{}:</strong> {} {}</p>\n", t("baseline"), report.baselined, t("baselined_findings")));
+    }
+    if report.interrupted {
+        html.push_str(&format!("<p class=\"notice\"><strong>{}</strong></p>\n", t("interrupted")));
+    }
+    if report.quick {
+        html.push_str(&format!("<p class=\"notice\"><strong>{}</strong></p>\n", t("quick")));
+    }
+    if report.fork {
+        html.push_str(&format!("<p class=\"notice\"><strong>{}</strong></p>\n", t("fork")));
+    }
+    if !report.warnings.is_empty() {
+        html.push_str(&format!("<div class=\"notice\"><strong>{}</strong>\n<ul>\n", t("warnings")));
+        for warning in &report.warnings {
+            let file = warning.file.as_deref().map_or(String::new(), |f| format!(" (<code>{}</code>)", escape(f)));
+            html.push_str(&format!("<li>{}{}</li>\n", escape(&catalog.warning(warning)), file));
+        }
+        html.push_str("</ul>\n</div>\n");
+    }
+    if !report.summary.is_empty() {
+        html.push_str(&format!("<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th><th>{}</th></tr>\n", t("summary"), t("analyzer"), t("risk"), t("findings_column")));
+        for row in &report.summary {
+            html.push_str(&format!("<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>\n", escape(&row.analyzer), risk_class(row.risk), row.risk, row.findings));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str(&format!("<h2>{}</h2>\n<div class=\"heatmap\">\n", t("risk_heatmap")));
+    let weights: Vec<f64> = report.files.iter().map(|f| (f.additions + f.deletions).max(1) as f64).collect();
+    let full = Rect { x: 0.0, y: 0.0, w: 100.0, h: 100.0 };
+    for (i, (file, rect)) in report.files.iter().zip(treemap(&weights, full)).enumerate() {
+        let risk = file_risk(&file.path);
+        html.push_str(&format!(
+            "<a class=\"tile {}\" style=\"left:{:.2}%;top:{:.2}%;width:{:.2}%;height:{:.2}%\" href=\"#{}\" title=\"{} (+{} -{}) risk: {}\">{}</a>\n",
+            risk.map_or("clean", risk_class),
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h,
+            anchor(&file.path, i),
+            escape(&file.path),
+            file.additions,
+            file.deletions,
+            risk.map_or("none".to_string(), |r| r.to_string()),
+            escape(crate::paths::file_name(&file.path)),
+        ));
+    }
+    html.push_str("</div>\n");
+
+    html.push_str(&format!("<h2>{}</h2>\n", t("findings_by_file")));
+    for (i, file) in report.files.iter().enumerate() {
+        let Some(findings) = by_file.get(file.path.as_str()) else {
+            continue;
+        };
+        html.push_str(&format!("<h3 id=\"{}\"><code>{}</code></h3>\n<ul>\n", anchor(&file.path, i), escape(&file.path)));
+        for (analyzer, finding) in findings {
+            html.push_str(&finding_item(report, catalog, analyzer, finding));
+        }
+        html.push_str("</ul>\n");
+    }
+    // Findings on files outside the diff listing (e.g. from a checkout)
+    for (path, findings) in by_file.iter().filter(|(path, _)| !report.files.iter().any(|f| f.path == **path)) {
+        html.push_str(&format!("<h3><code>{}</code></h3>\n<ul>\n", escape(path)));
+        for (analyzer, finding) in findings {
+            html.push_str(&finding_item(report, catalog, analyzer, finding));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if !general.is_empty() {
+        html.push_str(&format!("<h2>{}</h2>\n<ul>\n", t("general_findings")));
+        for (analyzer, finding) in general {
+            html.push_str(&format!(
+                "<li><span class=\"{}\">[{}]</span> {}{}: {}{}</li>\n",
+                risk_class(finding.severity),
+                finding.severity,
+                tag_html(report, analyzer, finding),
+                escape(analyzer),
+                escape(&catalog.message(finding)),
+                docs_link(catalog, finding)
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if let Some(comparison) = report.since_last_run.as_ref().filter(|c| !c.resolved.is_empty()) {
+        html.push_str(&format!("<h2>{} ({})</h2>\n<ul>\n", t("resolved_since_last_run"), escape(&comparison.previous_run)));
+        for resolved in &comparison.resolved {
+            let file = resolved.finding.file.as_deref().map_or(String::new(), |f| format!(" (<code>{}</code>)", escape(f)));
+            html.push_str(&format!("<li><s>{}</s> ({}){}</li>\n", escape(&catalog.message(&resolved.finding)), escape(&resolved.analyzer), file));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if !report.patches.is_empty() {
+        html.push_str(&format!("<h2>{}</h2>\n<ol>\n", t("patch_series")));
+        for patch in &report.patches {
+            html.push_str(&format!(
+                "<li><span class=\"{}\">{}</span> (+{} -{}, {}): {} {}</li>\n",
+                patch.risk.map_or("clean", risk_class),
+                escape(&patch.subject),
+                patch.additions,
+                patch.deletions,
+                escape(&patch.author),
+                patch.findings,
+                t("findings")
+            ));
+        }
+        html.push_str("</ol>\n");
+    }
+
+    for (heading, changes) in super::surface_sections(report) {
+        html.push_str(&format!("<h2>{}</h2>\n<ul>\n", t(heading)));
+        for change in changes {
+            html.push_str(&format!(
+                "<li>{}: <code>{}</code> (<code>{}:{}</code>)</li>\n",
+                t(change.kind.label_key()),
+                escape(&change.name),
+                escape(&change.file),
+                change.line
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if !report.split.is_empty() {
+        html.push_str(&format!("<h2>{}</h2>\n<ol>\n", t("suggested_split")));
+        for group in &report.split {
+            let files: Vec<String> = group.files.iter().map(|f| format!("<code>{}</code>", escape(f))).collect();
+            html.push_str(&format!(
+                "<li><strong>{}</strong> ({} {}, {} {}): {}</li>\n",
+                escape(&group.components.join(" + ")),
+                group.files.len(),
+                t("split_files"),
+                group.lines,
+                t("split_lines"),
+                files.join(", ")
+            ));
+        }
+        html.push_str("</ol>\n");
+    }
+
+    if !report.reviewers.is_empty() {
+        html.push_str(&format!("<h2>{}</h2>\n<ul>\n", t("suggested_reviewers")));
+        for hint in &report.reviewers {
+            html.push_str(&format!("<li><code>{}</code>: {}</li>\n", escape(&hint.file), escape(&hint.reviewers.join(", "))));
+        }
+        html.push_str("</ul>\n");
+    }
+    if !report.rules.is_empty() {
+        html.push_str(&format!(
+            "<details>\n<summary>{}: {}</summary>\n<table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th></th></tr>\n",
+            t("rules_run"),
+            escape(&super::rules_summary(report, catalog)),
+            t("rule"),
+            t("status"),
+            t("findings_column")
+        ));
+        for rule in &report.rules {
+            html.push_str(&format!(
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&rule.rule),
+                t(super::status_key(rule.status)),
+                rule.findings,
+                escape(rule.reason.as_deref().unwrap_or(""))
+            ));
+        }
+        html.push_str("</table>\n</details>\n");
+    }
+    html.push_str(&format!(
+        "<footer><small>pr-analyzer {} | generated {}</small></footer>\n</body>\n</html>\n",
+        escape(&report.provenance.tool_version),
+        escape(&report.provenance.generated_at)
+    ));
+    html
+}
+
+fn finding_item(report: &Report, catalog: &Catalog, analyzer: &str, finding: &Finding) -> String {
+    let line = finding.line.map(|l| format!(" ({} {})", escape(catalog.chrome("line")), l)).unwrap_or_default();
+    format!(
+        "<li><span class=\"{}\">[{}]</span> {}{}{}{}</li>\n",
+        risk_class(finding.severity),
+        finding.severity,
+        tag_html(report, analyzer, finding),
+        escape(&catalog.message(finding)),
+        line,
+        docs_link(catalog, finding)
+    )
+}
+
+/// ` <code>SEC002</code> <a href=...>docs</a>` for a finding with a rule
+/// code, linked to its rule's documentation.
+fn docs_link(catalog: &Catalog, finding: &Finding) -> String {
+    let code = finding.rule_code.as_deref().map_or(String::new(), |c| format!(" <code>{}</code>", escape(c)));
+    let link = finding.docs_url.as_deref().map_or(String::new(), |url| format!(" <a href=\"{}\">{}</a>", escape(url), escape(catalog.chrome("docs"))));
+    code + &link
+}
+
+fn tag_html(report: &Report, analyzer: &str, finding: &Finding) -> String {
+    rerun_tag(report, analyzer, finding).map_or(String::new(), |tag| format!("<strong>{}</strong> ", tag))
+}
+
+pub(super) fn risk_class(level: RiskLevel) -> &'static str {
+    match level {
+        RiskLevel::Low => "risk-low",
+        RiskLevel::Medium => "risk-medium",
+        RiskLevel::High => "risk-high",
+    }
+}
+
+/// Stable in-page anchor for a file's findings.
+fn anchor(path: &str, index: usize) -> String {
+    let slug: String = path.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' }).collect();
+    format!("file-{}-{}", index, slug)
+}
+
+/// Lay out weights as a treemap inside `area`, splitting the list in two
+/// halves of similar weight along the longer side until one item is left.
+fn treemap(weights: &[f64], area: Rect) -> Vec<Rect> {
+    match weights.len() {
+        0 => Vec::new(),
+        1 => vec![area],
+        n => {
+            let total: f64 = weights.iter().sum();
+            let mut split = 1;
+            let mut acc = weights[0];
+            while split < n - 1 && (acc + weights[split]) * 2.0 <= total {
+                acc += weights[split];
+                split += 1;
+            }
+            let share = if total > 0.0 { acc / total } else { split as f64 / n as f64 };
+            let (first, second) = if area.w >= area.h {
+                let w = area.w * share;
+                (Rect { w, ..area }, Rect { x: area.x + w, w: area.w - w, ..area })
+            } else {
+                let h = area.h * share;
+                (Rect { h, ..area }, Rect { y: area.y + h, h: area.h - h, ..area })
+            };
+            let mut rects = treemap(&weights[..split], first);
+            rects.extend(treemap(&weights[split..], second));
+            rects
+        }
+    }
+}
+
+pub(super) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pr::PullRequest;
+    use crate::report::types::{AnalysisResult, ChangedFile};
+
+    fn sample_report() -> Report {
+        let pr = PullRequest::builder("Rotate <keys>").number(7).author("alice").totals(2, 32, 10).build().unwrap();
+        let mut report = crate::report::build(
+            vec![AnalysisResult {
+                analyzer_name: "Security".to_string(),
+                risk_level: RiskLevel::High,
+                findings: vec![
+                    Finding::text("Hardcoded <secret>", RiskLevel::High).in_file("src/auth.rs").at_line(3),
+                    Finding::text("2 new dependencies", RiskLevel::Low),
+                ],
+            }],
+            &pr,
+        );
+        report.files = vec![
+            ChangedFile { path: "src/auth.rs".to_string(), additions: 30, deletions: 10 },
+            ChangedFile { path: "README.md".to_string(), additions: 2, deletions: 0 },
+        ];
+        report
+    }
+
+    #[test]
+    fn test_heatmap_tiles_are_colored_and_link_to_findings() {
+        let html = render(&sample_report(), Catalog::english());
+        assert!(html.contains("class=\"tile risk-high\""));
+        assert!(html.contains("class=\"tile clean\""));
+        assert!(html.contains("href=\"#file-0-src-auth-rs\""));
+        assert!(html.contains("<h3 id=\"file-0-src-auth-rs\">"));
+        assert!(html.contains("Hardcoded &lt;secret&gt;"));
+        assert!(html.contains("<h2>General Findings</h2>"));
+        assert!(html.contains("<h1>PR #7: Rotate &lt;keys&gt;</h1>"));
+    }
+
+    #[test]
+    fn test_treemap_tiles_fill_the_area_proportionally() {
+        let rects = treemap(&[60.0, 30.0, 10.0], Rect { x: 0.0, y: 0.0, w: 100.0, h: 100.0 });
+        assert_eq!(rects.len(), 3);
+        let areas: Vec<f64> = rects.iter().map(|r| r.w * r.h / 100.0).collect();
+        for (area, expected) in areas.iter().zip([60.0, 30.0, 10.0]) {
+            assert!((area - expected).abs() < 1e-9, "{} != {}", area, expected);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! `[report] sections` and `hide_empty`: which parts of a report are shown,
+//! and in what order.
+//!
+//! The layout is applied to a copy of the report before it reaches the
+//! sinks, so every format shows the same shape. Analyzer sections follow the
+//! listed order; the summary, when listed, comes first, and the other
+//! sections keep their usual place after the findings.
+
+use super::{AnalyzerSummary, Report};
+
+/// A report section that `[report] sections` can name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Part {
+    /// Risk and finding count of every analyzer, shown or not
+    Summary,
+    /// One analyzer's findings, by full analyzer name
+    Analyzer(String),
+    Resolved,
+    Patches,
+    /// What the org policy enforced; shown whenever there is any, listed or
+    /// not, so a local layout cannot hide it
+    Policy,
+    /// Attack surface changes and new configuration
+    Surface,
+    Split,
+    Reviewers,
+    Rules,
+}
+
+impl Part {
+    /// Names of the sections that are not analyzers, as written in config.
+    pub const NAMES: &'static [&'static str] = &["summary", "resolved", "patches", "policy", "surface", "split", "reviewers", "rules"];
+
+    /// A non-analyzer section by its config name.
+    pub fn named(name: &str) -> Option<Part> {
+        Some(match name {
+            "summary" => Part::Summary,
+            "resolved" => Part::Resolved,
+            "patches" => Part::Patches,
+            "policy" => Part::Policy,
+            "surface" => Part::Surface,
+            "split" => Part::Split,
+            "reviewers" => Part::Reviewers,
+            "rules" => Part::Rules,
+            _ => return None,
+        })
+    }
+}
+
+/// The shape of the rendered report. The default shows everything.
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    /// Sections in order; empty shows every section but the summary
+    pub sections: Vec<Part>,
+    /// Leave out analyzers with no findings
+    pub hide_empty: bool,
+}
+
+impl Layout {
+    /// A copy of `report` with only the selected sections. The overall risk
+    /// still covers every analyzer.
+    pub fn apply(&self, report: &Report) -> Report {
+        let mut shaped = report.clone();
+        if !self.sections.is_empty() {
+            shaped.results = self
+                .sections
+                .iter()
+                .filter_map(|part| match part {
+                    Part::Analyzer(name) => report.results.iter().find(|r| r.analyzer_name == *name).cloned(),
+                    _ => None,
+                })
+                .collect();
+            if self.shows(&Part::Summary) {
+                shaped.summary = report.results.iter().map(|r| AnalyzerSummary { analyzer: r.analyzer_name.clone(), risk: r.risk_level, findings: r.findings.len() }).collect();
+            }
+            if !self.shows(&Part::Resolved) {
+                if let Some(comparison) = &mut shaped.since_last_run {
+                    comparison.resolved.clear();
+                }
+            }
+            if !self.shows(&Part::Patches) {
+                shaped.patches.clear();
+            }
+            if !self.shows(&Part::Surface) {
+                shaped.attack_surface.clear();
+            }
+            if !self.shows(&Part::Split) {
+                shaped.split.clear();
+            }
+            if !self.shows(&Part::Reviewers) {
+                shaped.reviewers.clear();
+            }
+            if !self.shows(&Part::Rules) {
+                shaped.rules.clear();
+            }
+        }
+        if self.hide_empty {
+            shaped.results.retain(|r| !r.findings.is_empty());
+        }
+        shaped
+    }
+
+    fn shows(&self, part: &Part) -> bool {
+        self.sections.contains(part)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::sample_pr;
+    use crate::report::{build, AnalysisResult, Finding, RiskLevel};
+
+    fn result(analyzer: &str, findings: Vec<Finding>) -> AnalysisResult {
+        let risk_level = findings.iter().map(|f| f.severity).max().unwrap_or(RiskLevel::Low);
+        AnalysisResult { analyzer_name: analyzer.to_string(), risk_level, findings }
+    }
+
+    #[test]
+    fn test_layout_selects_and_orders_sections() {
+        let mut report = build(
+            vec![
+                result("Security Risk Assessment", vec![Finding::text("a", RiskLevel::High)]),
+                result("Complexity Assessment", vec![Finding::text("b", RiskLevel::Low)]),
+                result("Style & Architecture Assessment", vec![]),
+            ],
+            &sample_pr(),
+        );
+        report.provenance.policy = vec!["held".to_string()];
+
+        let layout = Layout {
+            sections: vec![Part::Summary, Part::Analyzer("Complexity Assessment".to_string()), Part::Analyzer("Style & Architecture Assessment".to_string()), Part::Analyzer("Security Risk Assessment".to_string())],
+            hide_empty: true,
+        };
+        let shaped = layout.apply(&report);
+        let names: Vec<_> = shaped.results.iter().map(|r| r.analyzer_name.as_str()).collect();
+        assert_eq!(names, ["Complexity Assessment", "Security Risk Assessment"]);
+        assert_eq!(shaped.summary.len(), 3);
+        assert_eq!(shaped.summary[0], AnalyzerSummary { analyzer: "Security Risk Assessment".to_string(), risk: RiskLevel::High, findings: 1 });
+        // The policy section is not listed, but a layout cannot hide it.
+        assert_eq!(shaped.provenance.policy, ["held"]);
+        assert_eq!(shaped.overall_risk, RiskLevel::High);
+
+        let everything = Layout::default().apply(&report);
+        assert_eq!(everything.results.len(), 3);
+        assert!(everything.summary.is_empty());
+        assert_eq!(everything.provenance.policy, ["held"]);
+    }
+}
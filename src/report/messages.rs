@@ -0,0 +1,190 @@
+//! Message catalogs: the text of findings and of the report around them.
+//!
+//! Analyzers report a message id and named parameters (see `Finding::new`),
+//! not sentences. A `Catalog` maps each id to a template and fills in the
+//! parameters when a report is rendered, and also supplies the report's
+//! headings and labels ("chrome"). The built-in catalog is English
+//! (`messages.toml`); `Catalog::load` overlays a translation on it.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use serde::Deserialize;
+use tracing::warn;
+
+use super::types::{Finding, Warning};
+use super::ReportError;
+
+static ENGLISH: LazyLock<Catalog> =
+    LazyLock::new(|| Catalog::parse(include_str!("messages.toml")).unwrap_or_else(|e| panic!("built-in message catalog is invalid: {}", e)));
+
+/// Templates for finding messages and report chrome in one language.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    /// BCP 47 tag of the catalog's language (e.g. `en`, `de`)
+    pub language: String,
+    findings: BTreeMap<String, String>,
+    chrome: BTreeMap<String, String>,
+}
+
+/// On-disk layout of a catalog.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CatalogFile {
+    language: Option<String>,
+    #[serde(default)]
+    findings: BTreeMap<String, BTreeMap<String, String>>,
+    #[serde(default)]
+    chrome: BTreeMap<String, String>,
+}
+
+impl Catalog {
+    /// The built-in English catalog, which defines every message id.
+    pub fn english() -> &'static Catalog {
+        &ENGLISH
+    }
+
+    /// Read a catalog file and fill the entries it leaves out from English.
+    /// Entries English does not have are ignored with a warning, since they
+    /// usually mean the translation was written for another version.
+    pub fn load(path: &Path) -> Result<Catalog, ReportError> {
+        let invalid = |reason: String| ReportError::Catalog { path: path.to_path_buf(), reason };
+        let contents = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        let translation = Catalog::parse(&contents).map_err(|e| invalid(e.to_string()))?;
+        let mut catalog = Catalog::english().clone();
+        catalog.language = translation.language;
+        for (table, own, entries) in [("findings", &mut catalog.findings, translation.findings), ("chrome", &mut catalog.chrome, translation.chrome)] {
+            for (id, template) in entries {
+                match own.get_mut(&id) {
+                    Some(slot) => *slot = template,
+                    None => warn!(path = %path.display(), table, id = %id, "unknown message id in catalog; ignored"),
+                }
+            }
+        }
+        Ok(catalog)
+    }
+
+    fn parse(contents: &str) -> Result<Catalog, toml::de::Error> {
+        let file: CatalogFile = toml::from_str(contents)?;
+        let findings = file
+            .findings
+            .into_iter()
+            .flat_map(|(table, entries)| entries.into_iter().map(move |(key, template)| (format!("{}.{}", table, key), template)))
+            .collect();
+        Ok(Catalog { language: file.language.unwrap_or_else(|| "en".to_string()), findings, chrome: file.chrome })
+    }
+
+    /// Whether `id` names a finding message in this catalog.
+    pub fn has_message(&self, id: &str) -> bool {
+        self.findings.contains_key(id)
+    }
+
+    /// Every finding message id, in order.
+    #[cfg(test)]
+    pub(crate) fn message_ids(&self) -> impl Iterator<Item = &str> {
+        self.findings.keys().map(String::as_str)
+    }
+
+    /// Render the template `id` with `params`; None for an unknown id.
+    pub fn render(&self, id: &str, params: &BTreeMap<String, String>) -> Option<String> {
+        self.findings.get(id).map(|template| fill(template, params))
+    }
+
+    /// Text of a finding in this catalog's language. Findings without a
+    /// message id (free text, or records written before ids existed) keep
+    /// their stored message.
+    pub fn message<'a>(&self, finding: &'a Finding) -> Cow<'a, str> {
+        match finding.message_id.as_deref().and_then(|id| self.render(id, &finding.params)) {
+            Some(text) => Cow::Owned(text),
+            None => Cow::Borrowed(&finding.message),
+        }
+    }
+
+    /// Text of a warning in this catalog's language.
+    pub fn warning<'a>(&self, warning: &'a Warning) -> Cow<'a, str> {
+        match self.render(&warning.message_id, &warning.params) {
+            Some(text) => Cow::Owned(text),
+            None => Cow::Borrowed(&warning.message),
+        }
+    }
+
+    /// A heading or label of the report, e.g. `chrome("overall_risk")`.
+    /// Unknown keys are returned as-is.
+    pub fn chrome<'a>(&'a self, key: &'a str) -> &'a str {
+        self.chrome.get(key).map_or(key, String::as_str)
+    }
+}
+
+/// Replace each `{name}` in `template` with `params[name]`. Placeholders
+/// without a parameter are left in place, so a gap is visible rather than
+/// silently dropped. Parameter values are not scanned for placeholders.
+fn fill(template: &str, params: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let tail = &rest[open..];
+        match tail.find('}').and_then(|close| params.get(&tail[1..close]).map(|value| (close, value))) {
+            Some((close, value)) => {
+                out.push_str(value);
+                rest = &tail[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::types::RiskLevel;
+
+    #[test]
+    fn test_fill_substitutes_known_placeholders_only() {
+        let params = BTreeMap::from([("item".to_string(), "path /users/{id}".to_string())]);
+        assert_eq!(fill("removed {item}", &params), "removed path /users/{id}");
+        assert_eq!(fill("{missing} and {item}", &params), "{missing} and path /users/{id}");
+        assert_eq!(fill("unclosed {item", &params), "unclosed {item");
+    }
+
+    #[test]
+    fn test_translation_overrides_and_falls_back_to_english() {
+        let path = std::env::temp_dir().join("pr-analyzer-test-catalog-de.toml");
+        std::fs::write(
+            &path,
+            "language = \"de\"\n[findings.complexity]\nmany_files = \"Viele geänderte Dateien: {files}\"\nno_such_id = \"x\"\n[chrome]\noverall_risk = \"Gesamtrisiko\"\n",
+        )
+        .unwrap();
+        let catalog = Catalog::load(&path).unwrap();
+
+        let many = Finding::new("complexity.many_files", RiskLevel::Medium).param("files", 40);
+        let large = Finding::new("complexity.large_change", RiskLevel::Medium).param("lines", 600).param("additions", 500).param("deletions", 100);
+        assert_eq!(many.message, "High number of files changed: 40");
+        assert_eq!(catalog.language, "de");
+        assert_eq!(catalog.message(&many), "Viele geänderte Dateien: 40");
+        assert_eq!(catalog.message(&large), large.message);
+        assert_eq!(catalog.chrome("overall_risk"), "Gesamtrisiko");
+        assert_eq!(catalog.chrome("risk_level"), "Risk Level");
+        assert!(!catalog.has_message("complexity.no_such_id"));
+    }
+
+    #[test]
+    fn test_free_text_findings_keep_their_message() {
+        let finding = Finding::text("clippy says hi", RiskLevel::Low);
+        assert_eq!(Catalog::english().message(&finding), "clippy says hi");
+    }
+
+    #[test]
+    fn test_load_rejects_malformed_catalog() {
+        let path = std::env::temp_dir().join("pr-analyzer-test-catalog-bad.toml");
+        std::fs::write(&path, "[headings]\ntitle = \"x\"\n").unwrap();
+        assert!(matches!(Catalog::load(&path), Err(ReportError::Catalog { .. })));
+    }
+}
@@ -1,19 +1,107 @@
+mod annotated;
+mod ci;
+mod embed;
+mod html;
+mod layout;
+mod messages;
+mod sink;
+mod site;
 pub mod types;
 
-pub use types::{AnalysisResult, Report, RiskLevel};
-#[cfg(test)]
-pub use types::Finding;
+pub use embed::extract as extract_embedded;
+pub use layout::{Layout, Part};
+pub use messages::Catalog;
+pub use sink::{emit, sinks, CommentSink, FileSink, Output, Sink, StdoutSink};
+pub use site::build_site;
+pub use types::{AnalysisResult, AnalyzerSummary, Category, ChangedFile, Finding, FindingKey, PatchSummary, Provenance, Report, ReviewEffort, RiskLevel, RuleRun, RuleStatus, RunComparison, SplitGroup, SurfaceChange, SurfaceKind, Warning};
 
 use crate::pr::PullRequest;
 use colored::Colorize;
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
 use thiserror::Error;
-use tracing::{debug, instrument};
+use tracing::instrument;
 
 #[derive(Debug, Error)]
 pub enum ReportError {
     #[error("Failed to write report file: {0}")]
     FileWrite(#[from] std::io::Error),
+
+    #[error("Failed to serialize report: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    #[error("Failed to sign report: {0}")]
+    Sign(String),
+
+    #[error("Invalid message catalog {}: {reason}", path.display())]
+    Catalog { path: PathBuf, reason: String },
+
+    #[error("Invalid report embedded in markdown: {0}")]
+    Embedded(String),
+
+    #[error("Failed to post report: {0}")]
+    Post(#[from] crate::pr::PrError),
+}
+
+/// Report output format. Defaults to terminal, or markdown when `--output` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Terminal,
+    Markdown,
+    Json,
+    /// Standalone page with a per-file risk heatmap
+    Html,
+    /// The unified diff with findings under the lines they flag
+    AnnotatedDiff,
+    /// TeamCity service messages (build inspections)
+    #[value(name = "teamcity")]
+    TeamCity,
+    /// GitLab Code Quality JSON
+    #[value(name = "codequality")]
+    CodeQuality,
+    /// Checkstyle XML (reviewdog, Jenkins warnings-ng)
+    Checkstyle,
+    /// Reviewdog Diagnostic Format JSON
+    Rdjson,
+    /// GitHub Actions workflow commands (annotations on the PR)
+    #[value(name = "github")]
+    GitHub,
+}
+
+/// Order of analyzers and findings in every output format (`--sort`).
+///
+/// Findings at the same location are ordered by message, which names the
+/// rule, so the same input always renders the same way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortOrder {
+    /// Analyzers by risk level, findings by severity (highest first), then file and line
+    Severity,
+    /// Analyzers in report order, findings by file and line
+    #[default]
+    File,
+    /// Analyzers by name, findings by file and line
+    Analyzer,
+}
+
+/// How the terminal and markdown reports divide findings into sections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// One section per analyzer
+    #[default]
+    Analyzer,
+    /// One section per finding category (injection, secrets, supply chain, ...); empty ones are left out
+    Category,
+}
+
+/// External tool used to produce a detached signature of a written report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Signer {
+    /// `minisign -S`, writing `<report>.minisig`
+    Minisign,
+    /// `cosign sign-blob` (sigstore), writing `<report>.sig`
+    Cosign,
 }
 
 /// Build a Report from analyzer results and PR metadata.
@@ -28,38 +116,234 @@ pub fn build(results: Vec<AnalysisResult>, pr: &PullRequest) -> Report {
         .max()
         .unwrap_or(RiskLevel::Low);
 
+    let patches = patch_breakdown(&results, pr);
     Report {
-        pr_number: pr.number,
-        pr_title: pr.title.clone(),
-        author: pr.author.clone(),
-        files_changed: pr.files_changed,
-        additions: pr.additions,
-        deletions: pr.deletions,
+        pr_number: pr.number(),
+        pr_title: pr.title().to_string(),
+        author: pr.author().to_string(),
+        files_changed: pr.files_changed(),
+        additions: pr.additions(),
+        deletions: pr.deletions(),
+        files: pr
+            .files()
+            .iter()
+            .map(|f| ChangedFile { path: f.path().to_string(), additions: f.additions(), deletions: f.deletions() })
+            .collect(),
         results,
         overall_risk,
+        interrupted: false,
+        quick: false,
+        fork: pr.is_fork(),
+        stale_head: None,
+        summary: Vec::new(),
+        provenance: Provenance {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            config_hash: None,
+            rule_versions: Default::default(),
+            head_sha: pr.head_sha().map(str::to_string),
+            generated_at: rfc3339_utc(SystemTime::now()),
+            policy: Vec::new(),
+        },
+        baselined: 0,
+        warnings: Vec::new(),
+        split: Vec::new(),
+        review_effort: None,
+        reviewers: Vec::new(),
+        rules: Vec::new(),
+        attack_surface: Vec::new(),
+        patches,
+        since_last_run: None,
     }
 }
 
-/// Output the report to terminal (default) or to a markdown file.
-///
-/// Claude: Implement both formatters.
-/// - If output_path is None, print to stdout using colored terminal output
-/// - If output_path is Some, write markdown to the specified file
-#[instrument(skip(report), fields(pr = report.pr_number, overall_risk = %report.overall_risk))]
-pub fn output(report: &Report, output_path: Option<&Path>) -> Result<(), ReportError> {
-    match output_path {
-        None => {
-            debug!("writing report to terminal");
-            print_terminal_report(report);
-            Ok(())
+/// Put analyzers and their findings in a deterministic order.
+/// Findings without a file (change-wide notes) come first.
+pub fn sort(report: &mut Report, order: SortOrder) {
+    let by_location = |a: &Finding, b: &Finding| (&a.file, a.line, &a.message).cmp(&(&b.file, b.line, &b.message));
+    match order {
+        SortOrder::Severity => report.results.sort_by_key(|r| std::cmp::Reverse(r.risk_level)),
+        SortOrder::File => {}
+        SortOrder::Analyzer => report.results.sort_by(|a, b| a.analyzer_name.cmp(&b.analyzer_name)),
+    }
+    for result in &mut report.results {
+        match order {
+            SortOrder::Severity => result.findings.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| by_location(a, b))),
+            SortOrder::File | SortOrder::Analyzer => result.findings.sort_by(by_location),
+        }
+    }
+}
+
+/// Attribute each located finding to the patch whose hunk contains it.
+/// When several patches touch the same lines the latest one wins.
+/// Compare `report` with the previous run of the same PR: findings it also
+/// reported are persisting, the rest are new, and its findings missing from
+/// `report` are resolved.
+pub fn compare_with_previous(report: &mut Report, previous: &Report) {
+    let current: BTreeSet<FindingKey> =
+        report.results.iter().flat_map(|r| r.findings.iter().map(|f| FindingKey::new(&r.analyzer_name, f))).collect();
+    let mut before = BTreeSet::new();
+    let mut resolved = Vec::new();
+    for result in &previous.results {
+        for finding in &result.findings {
+            let key = FindingKey::new(&result.analyzer_name, finding);
+            if !current.contains(&key) && !before.contains(&key) {
+                resolved.push(types::ResolvedFinding { analyzer: result.analyzer_name.clone(), finding: finding.clone() });
+            }
+            before.insert(key);
         }
-        Some(path) => {
-            debug!(path = %path.display(), "writing report to file");
-            write_markdown_report(report, path)
+    }
+    report.since_last_run = Some(RunComparison {
+        previous_run: previous.provenance.generated_at.clone(),
+        previous_head_sha: previous.provenance.head_sha.clone(),
+        persisting: current.intersection(&before).cloned().collect(),
+        resolved,
+    });
+}
+
+/// Link every finding that has a rule id to that rule's documentation:
+/// `template` with `{rule_id}` replaced.
+pub fn link_docs(report: &mut Report, template: &str) {
+    for finding in report.results.iter_mut().flat_map(|r| &mut r.findings) {
+        finding.docs_url = finding.rule.as_deref().map(|rule| template.replace("{rule_id}", rule));
+    }
+}
+
+/// A findings section of the terminal or markdown report.
+struct Section<'a> {
+    title: &'a str,
+    risk: RiskLevel,
+    /// (analyzer, finding) pairs in report order
+    findings: Vec<(&'a str, &'a Finding)>,
+}
+
+/// The report's findings divided per `group_by`. By category, sections
+/// follow `Category` order with uncategorized findings last, and a section's
+/// risk is its highest severity.
+fn sections<'a>(report: &'a Report, group_by: GroupBy, catalog: &'a Catalog) -> Vec<Section<'a>> {
+    let pairs = |result: &'a AnalysisResult| result.findings.iter().map(move |f| (result.analyzer_name.as_str(), f));
+    match group_by {
+        GroupBy::Analyzer => report
+            .results
+            .iter()
+            .map(|r| Section { title: &r.analyzer_name, risk: r.risk_level, findings: pairs(r).collect() })
+            .collect(),
+        GroupBy::Category => {
+            let mut by_category = BTreeMap::new();
+            for (analyzer, finding) in report.results.iter().flat_map(pairs) {
+                by_category.entry((finding.category.is_none(), finding.category)).or_insert_with(Vec::new).push((analyzer, finding));
+            }
+            by_category
+                .into_iter()
+                .map(|((_, category), findings)| Section {
+                    title: catalog.chrome(category.map_or("uncategorized", Category::label_key)),
+                    risk: findings.iter().map(|(_, f)| f.severity).max().unwrap_or(RiskLevel::Low),
+                    findings,
+                })
+                .collect()
         }
     }
 }
 
+/// The non-empty surface sections, as (chrome heading key, entries): the
+/// attack surface for security review, then new configuration for deployment.
+/// Environment variables belong to both.
+fn surface_sections(report: &Report) -> Vec<(&'static str, Vec<&SurfaceChange>)> {
+    let section = |heading, include: fn(SurfaceKind) -> bool| (heading, report.attack_surface.iter().filter(|c| include(c.kind)).collect::<Vec<_>>());
+    [section("attack_surface_changes", SurfaceKind::is_attack_surface), section("new_configuration", SurfaceKind::is_deployment_config)]
+        .into_iter()
+        .filter(|(_, changes)| !changes.is_empty())
+        .collect()
+}
+
+/// Chrome key of a rule status label.
+fn status_key(status: RuleStatus) -> &'static str {
+    match status {
+        RuleStatus::Executed => "executed",
+        RuleStatus::Skipped => "skipped",
+        RuleStatus::Errored => "errored",
+    }
+}
+
+/// "41 executed, 2 skipped, 0 errored"
+fn rules_summary(report: &Report, catalog: &Catalog) -> String {
+    [RuleStatus::Executed, RuleStatus::Skipped, RuleStatus::Errored]
+        .iter()
+        .map(|&status| format!("{} {}", report.rules.iter().filter(|r| r.status == status).count(), catalog.chrome(status_key(status))))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// "NEW" or "PERSISTING" when the report was compared with a previous run.
+fn rerun_tag(report: &Report, analyzer: &str, finding: &Finding) -> Option<&'static str> {
+    let comparison = report.since_last_run.as_ref()?;
+    Some(if comparison.is_persisting(analyzer, finding) { "PERSISTING" } else { "NEW" })
+}
+
+fn patch_breakdown(results: &[AnalysisResult], pr: &PullRequest) -> Vec<PatchSummary> {
+    let mut summaries: Vec<PatchSummary> = pr
+        .patches()
+        .iter()
+        .map(|p| PatchSummary {
+            subject: p.subject.clone(),
+            author: p.author.clone(),
+            additions: p.files.iter().map(|f| f.additions()).sum(),
+            deletions: p.files.iter().map(|f| f.deletions()).sum(),
+            findings: 0,
+            risk: None,
+        })
+        .collect();
+    for finding in results.iter().flat_map(|r| &r.findings) {
+        let Some(path) = finding.file.as_deref() else {
+            continue;
+        };
+        let owner = pr.patches().iter().rposition(|patch| {
+            patch.files.iter().filter(|f| f.path() == path).any(|f| match finding.line {
+                Some(line) => f.hunks().iter().any(|h| line >= h.new_start && line < h.new_start + h.new_count.max(1)),
+                None => true,
+            })
+        });
+        if let Some(summary) = owner.and_then(|i| summaries.get_mut(i)) {
+            summary.findings += 1;
+            summary.risk = summary.risk.max(Some(finding.severity));
+        }
+    }
+    summaries
+}
+
+/// Produce a detached signature for a written report file and return the
+/// signature's path. Minisign needs a secret key; cosign uses keyless
+/// signing unless a key is given.
+#[instrument]
+pub fn sign(path: &Path, signer: Signer, key: Option<&Path>) -> Result<PathBuf, ReportError> {
+    let mut sig_path = path.as_os_str().to_owned();
+    let mut command = match signer {
+        Signer::Minisign => {
+            let key = key.ok_or_else(|| ReportError::Sign("minisign requires --sign-key".to_string()))?;
+            sig_path.push(".minisig");
+            let mut command = Command::new("minisign");
+            command.arg("-S").arg("-s").arg(key).arg("-m").arg(path).arg("-x").arg(&sig_path);
+            command
+        }
+        Signer::Cosign => {
+            sig_path.push(".sig");
+            let mut command = Command::new("cosign");
+            command.arg("sign-blob").arg("--yes").arg("--output-signature").arg(&sig_path);
+            if let Some(key) = key {
+                command.arg("--key").arg(key);
+            }
+            command.arg(path);
+            command
+        }
+    };
+    let status = command
+        .status()
+        .map_err(|e| ReportError::Sign(format!("could not run {:?}: {}", command.get_program(), e)))?;
+    if !status.success() {
+        return Err(ReportError::Sign(format!("{:?} exited with {}", command.get_program(), status)));
+    }
+    Ok(PathBuf::from(sig_path))
+}
+
 /// Format and print the report to the terminal with colors.
 ///
 /// Claude: Implement terminal formatting.
@@ -75,41 +359,141 @@ pub fn output(report: &Report, output_path: Option<&Path>) -> Result<(), ReportE
 /// ...
 ///
 /// ═══ Overall Risk: HIGH ═══
-fn print_terminal_report(report: &Report) {
+fn print_terminal_report(report: &Report, group_by: GroupBy, catalog: &Catalog) {
+    let t = |key| catalog.chrome(key);
     println!();
     println!(
         "PR #{}: \"{}\"",
         report.pr_number, report.pr_title
     );
     println!(
-        "Author: {} | Files changed: {} | +{} -{}",
-        report.author, report.files_changed, report.additions, report.deletions
+        "{}: {} | {}: {} | +{} -{}",
+        t("author"), report.author, t("files_changed"), report.files_changed, report.additions, report.deletions
     );
+    if let Some(effort) = &report.review_effort {
+        println!("{}: ~{} {}", t("review_effort").bold(), effort.minutes, t("minutes"));
+    }
+    if report.baselined > 0 {
+        println!("{}: {} {}", t("baseline").bold(), report.baselined, t("baselined_findings"));
+    }
     println!();
+    if report.interrupted {
+        println!("{}", t("interrupted").yellow().bold());
+        println!();
+    }
+    if report.quick {
+        println!("{}", t("quick").yellow().bold());
+        println!();
+    }
+    if report.fork {
+        println!("{}", t("fork").yellow().bold());
+        println!();
+    }
+    if !report.warnings.is_empty() {
+        println!("{}", t("warnings").yellow().bold());
+        for warning in &report.warnings {
+            let file = warning.file.as_deref().map_or(String::new(), |f| format!(" ({})", f));
+            println!("  ⚠ {}{}", catalog.warning(warning), file);
+        }
+        println!();
+    }
+    if !report.summary.is_empty() {
+        println!("═══ {} ═══", t("summary"));
+        for row in &report.summary {
+            println!("  • {}: {} ({} {})", row.analyzer, colorize_risk(row.risk), row.findings, t("findings"));
+        }
+        println!();
+    }
 
-    for result in &report.results {
-        println!("═══ {} ═══", result.analyzer_name);
-        println!("Risk Level: {}", colorize_risk(result.risk_level));
-        if result.findings.is_empty() {
-            println!("  No findings.");
+    let sections = sections(report, group_by, catalog);
+    if sections.is_empty() {
+        println!("{}", t("no_findings"));
+        println!();
+    }
+    for section in sections {
+        println!("═══ {} ═══", section.title);
+        println!("{}: {}", t("risk_level"), colorize_risk(section.risk));
+        if section.findings.is_empty() {
+            println!("  {}", t("no_findings"));
         } else {
-            for finding in &result.findings {
+            for (analyzer, finding) in section.findings {
                 let location = match (&finding.file, finding.line) {
                     (Some(f), Some(l)) => format!(" ({}:{})", f, l),
                     (Some(f), None) => format!(" ({})", f),
                     _ => String::new(),
                 };
-                println!("  • {}{}", finding.message, location);
+                let tag = rerun_tag(report, analyzer, finding).map_or(String::new(), |t| format!("{} ", format!("[{}]", t).bold()));
+                let code = finding.rule_code.as_deref().map_or(String::new(), |c| format!(" {}", format!("[{}]", c).dimmed()));
+                println!("  • {}{}{}{}", tag, catalog.message(finding), location, code);
             }
         }
         println!();
     }
 
-    println!("═══ Overall Risk: {} ═══", colorize_risk(report.overall_risk));
+    if let Some(comparison) = report.since_last_run.as_ref().filter(|c| !c.resolved.is_empty()) {
+        println!("═══ {} ({}) ═══", t("resolved_since_last_run"), comparison.previous_run);
+        for resolved in &comparison.resolved {
+            let file = resolved.finding.file.as_deref().map_or(String::new(), |f| format!(" ({})", f));
+            println!("  ✓ {}: {}{}", resolved.analyzer, catalog.message(&resolved.finding), file);
+        }
+        println!();
+    }
+
+    if !report.patches.is_empty() {
+        println!("═══ {} ═══", t("patch_series"));
+        let total = report.patches.len();
+        for (i, patch) in report.patches.iter().enumerate() {
+            let risk = patch.risk.map_or(t("clean").normal(), colorize_risk);
+            println!("  [{}/{}] {} (+{} -{}): {} {}, {}", i + 1, total, patch.subject, patch.additions, patch.deletions, patch.findings, t("findings"), risk);
+        }
+        println!();
+    }
+
+    if !report.provenance.policy.is_empty() {
+        println!("═══ {} ═══", t("enforced_by_policy"));
+        for item in &report.provenance.policy {
+            println!("  • {}", item);
+        }
+        println!();
+    }
+
+    for (heading, changes) in surface_sections(report) {
+        println!("═══ {} ═══", t(heading));
+        for change in changes {
+            println!("  • {}: {} ({}:{})", t(change.kind.label_key()), change.name, change.file, change.line);
+        }
+        println!();
+    }
+
+    if !report.split.is_empty() {
+        println!("═══ {} ═══", t("suggested_split"));
+        for (i, group) in report.split.iter().enumerate() {
+            println!("  {}. {} ({} {}, {} {}): {}", i + 1, group.components.join(" + "), group.files.len(), t("split_files"), group.lines, t("split_lines"), group.files.join(", "));
+        }
+        println!();
+    }
+
+    if !report.reviewers.is_empty() {
+        println!("═══ {} ═══", t("suggested_reviewers"));
+        for hint in &report.reviewers {
+            println!("  • {}: {}", hint.file, hint.reviewers.join(", "));
+        }
+        println!();
+    }
+
+    if !report.rules.is_empty() {
+        println!("═══ {}: {} ═══", t("rules_run"), rules_summary(report, catalog));
+        for rule in report.rules.iter().filter(|r| r.status != RuleStatus::Executed) {
+            println!("  • {}: {} ({})", rule.rule, t(status_key(rule.status)), rule.reason.as_deref().unwrap_or(""));
+        }
+        println!();
+    }
+
+    println!("═══ {}: {} ═══", t("overall_risk"), colorize_risk(report.overall_risk));
     println!();
 }
 
-/// Write the report as a markdown file.
+/// The report as markdown, with the embedded JSON footer.
 ///
 /// Claude: Implement markdown formatting.
 /// Similar structure to terminal but using markdown syntax:
@@ -120,38 +504,204 @@ fn print_terminal_report(report: &Report) {
 /// **Risk Level: HIGH**
 /// - Finding 1
 /// - Finding 2
-fn write_markdown_report(report: &Report, path: &Path) -> Result<(), ReportError> {
+fn render_markdown(report: &Report, group_by: GroupBy, catalog: &Catalog) -> Result<String, ReportError> {
+    let t = |key| catalog.chrome(key);
     let mut md = String::new();
     md.push_str(&format!("# PR #{}: \"{}\"\n\n", report.pr_number, report.pr_title));
     md.push_str(&format!(
-        "**Author:** {} | **Files changed:** {} | **+{} -{}**\n\n",
-        report.author, report.files_changed, report.additions, report.deletions
+        "**{}:** {} | **{}:** {} | **+{} -{}**\n\n",
+        t("author"), report.author, t("files_changed"), report.files_changed, report.additions, report.deletions
     ));
+    if let Some(effort) = &report.review_effort {
+        md.push_str(&format!("**{}:** ~{} {}\n\n", t("review_effort"), effort.minutes, t("minutes")));
+    }
+    if report.baselined > 0 {
+        md.push_str(&format!("**{}:** {} {}\n\n", t("baseline"), report.baselined, t("baselined_findings")));
+    }
+    if let Some(head) = &report.stale_head {
+        let analyzed = report.provenance.head_sha.as_deref().map_or("?", short_sha);
+        md.push_str(&format!("> **{}** (`{}` → `{}`)\n\n", t("stale"), analyzed, short_sha(head)));
+    }
+    if report.interrupted {
+        md.push_str(&format!("> **{}**\n\n", t("interrupted")));
+    }
+    if report.quick {
+        md.push_str(&format!("> **{}**\n\n", t("quick")));
+    }
+    if report.fork {
+        md.push_str(&format!("> **{}**\n\n", t("fork")));
+    }
+    if !report.warnings.is_empty() {
+        md.push_str(&format!("> **{}**\n>\n", t("warnings")));
+        for warning in &report.warnings {
+            let file = warning.file.as_deref().map_or(String::new(), |f| format!(" (`{}`)", f));
+            md.push_str(&format!("> - {}{}\n", catalog.warning(warning), file));
+        }
+        md.push('\n');
+    }
+    if let Some(sha) = &report.provenance.head_sha {
+        md.push_str(&format!("**{}:** `{}` | **pr-analyzer** {}\n\n", t("head"), sha, report.provenance.tool_version));
+    }
+    if !report.summary.is_empty() {
+        md.push_str(&format!("## {}\n\n| {} | {} | {} |\n|---|---|---|\n", t("summary"), t("analyzer"), t("risk"), t("findings_column")));
+        for row in &report.summary {
+            md.push_str(&format!("| {} | {} | {} |\n", row.analyzer, row.risk, row.findings));
+        }
+        md.push('\n');
+    }
 
-    for result in &report.results {
-        md.push_str(&format!("## {}\n\n", result.analyzer_name));
-        md.push_str(&format!("**Risk Level: {}**\n\n", result.risk_level));
-        if result.findings.is_empty() {
-            md.push_str("No findings.\n\n");
+    let sections = sections(report, group_by, catalog);
+    if sections.is_empty() {
+        md.push_str(&format!("{}\n\n", t("no_findings")));
+    }
+    for section in sections {
+        md.push_str(&format!("## {}\n\n", section.title));
+        md.push_str(&format!("**{}: {}**\n\n", t("risk_level"), section.risk));
+        if section.findings.is_empty() {
+            md.push_str(&format!("{}\n\n", t("no_findings")));
         } else {
-            for finding in &result.findings {
+            for (analyzer, finding) in section.findings {
                 let location = match (&finding.file, finding.line) {
                     (Some(f), Some(l)) => format!(" (`{}:{}`)", f, l),
                     (Some(f), None) => format!(" (`{}`)", f),
                     _ => String::new(),
                 };
-                md.push_str(&format!("- **[{}]** {}{}\n", finding.severity, finding.message, location));
+                let tag = rerun_tag(report, analyzer, finding).map_or(String::new(), |t| format!("`{}` ", t));
+                let code = finding.rule_code.as_deref().map_or(String::new(), |c| format!(" `{}`", c));
+                let docs = finding.docs_url.as_deref().map_or(String::new(), |url| format!(" [{}]({})", t("docs"), url));
+                md.push_str(&format!("- **[{}]** {}{}{}{}{}\n", finding.severity, tag, catalog.message(finding), location, code, docs));
             }
             md.push('\n');
         }
     }
 
-    md.push_str(&format!("## Overall Risk: {}\n", report.overall_risk));
+    if let Some(comparison) = report.since_last_run.as_ref().filter(|c| !c.resolved.is_empty()) {
+        md.push_str(&format!("## {} ({})\n\n", t("resolved_since_last_run"), comparison.previous_run));
+        for resolved in &comparison.resolved {
+            let file = resolved.finding.file.as_deref().map_or(String::new(), |f| format!(" (`{}`)", f));
+            md.push_str(&format!("- ~~{}~~ ({}){}\n", catalog.message(&resolved.finding), resolved.analyzer, file));
+        }
+        md.push('\n');
+    }
+
+    if !report.patches.is_empty() {
+        md.push_str(&format!(
+            "## {}\n\n| # | {} | {} | {} | {} | {} |\n|---|---|---|---|---|---|\n",
+            t("patch_series"),
+            t("patch"),
+            t("author"),
+            t("lines"),
+            t("findings_column"),
+            t("risk")
+        ));
+        for (i, patch) in report.patches.iter().enumerate() {
+            md.push_str(&format!(
+                "| {} | {} | {} | +{} -{} | {} | {} |\n",
+                i + 1,
+                patch.subject.replace('|', "\\|"),
+                patch.author,
+                patch.additions,
+                patch.deletions,
+                patch.findings,
+                patch.risk.map_or("-".to_string(), |r| r.to_string())
+            ));
+        }
+        md.push('\n');
+    }
+
+    if !report.provenance.policy.is_empty() {
+        md.push_str(&format!("## {}\n\n", t("enforced_by_policy")));
+        for item in &report.provenance.policy {
+            md.push_str(&format!("- {}\n", item));
+        }
+        md.push('\n');
+    }
+
+    for (heading, changes) in surface_sections(report) {
+        md.push_str(&format!("## {}\n\n", t(heading)));
+        for change in changes {
+            md.push_str(&format!("- {}: `{}` (`{}:{}`)\n", t(change.kind.label_key()), change.name, change.file, change.line));
+        }
+        md.push('\n');
+    }
+
+    if !report.split.is_empty() {
+        md.push_str(&format!("## {}\n\n", t("suggested_split")));
+        for (i, group) in report.split.iter().enumerate() {
+            let files: Vec<String> = group.files.iter().map(|f| format!("`{}`", f)).collect();
+            md.push_str(&format!("{}. **{}** ({} {}, {} {}): {}\n", i + 1, group.components.join(" + "), group.files.len(), t("split_files"), group.lines, t("split_lines"), files.join(", ")));
+        }
+        md.push('\n');
+    }
+
+    if !report.reviewers.is_empty() {
+        md.push_str(&format!("## {}\n\n", t("suggested_reviewers")));
+        for hint in &report.reviewers {
+            md.push_str(&format!("- `{}`: {}\n", hint.file, hint.reviewers.join(", ")));
+        }
+        md.push('\n');
+    }
+
+    if !report.rules.is_empty() {
+        md.push_str(&format!(
+            "<details>\n<summary>{}: {}</summary>\n\n| {} | {} | {} | |\n|---|---|---|---|\n",
+            t("rules_run"),
+            rules_summary(report, catalog),
+            t("rule"),
+            t("status"),
+            t("findings_column")
+        ));
+        for rule in &report.rules {
+            let findings = if rule.suppressed > 0 { format!("{} (+{} {})", rule.findings, rule.suppressed, t("suppressed")) } else { rule.findings.to_string() };
+            md.push_str(&format!(
+                "| `{}` | {} | {} | {} |\n",
+                rule.rule,
+                t(status_key(rule.status)),
+                findings,
+                rule.reason.as_deref().unwrap_or("").replace('|', "\\|")
+            ));
+        }
+        md.push_str("\n</details>\n\n");
+    }
+
+    md.push_str(&format!("## {}: {}\n", t("overall_risk"), report.overall_risk));
+    md.push('\n');
+    md.push_str(&embed::footer(report)?);
+    Ok(md)
+}
 
-    std::fs::write(path, md)?;
+/// Write to a sibling file and rename so an interrupt never leaves a
+/// half-written report at `path`.
+fn write_atomically(path: &Path, contents: &str) -> Result<(), ReportError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)?;
     Ok(())
 }
 
+/// Format a time as RFC 3339 in UTC (e.g. `2026-02-17T09:30:00Z`).
+pub(crate) fn rfc3339_utc(time: SystemTime) -> String {
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Civil-from-days (Howard Hinnant), valid for any date after 1970.
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// First 7 characters of a commit SHA, as git abbreviates it.
+fn short_sha(sha: &str) -> &str {
+    sha.get(..7).unwrap_or(sha)
+}
+
 /// Helper to colorize a risk level string for terminal output.
 fn colorize_risk(level: RiskLevel) -> colored::ColoredString {
     match level {
@@ -166,16 +716,8 @@ mod tests {
     use super::*;
     use crate::pr::types::PullRequest;
 
-    fn sample_pr() -> PullRequest {
-        PullRequest {
-            number: 42,
-            title: "Add OAuth2 login flow".to_string(),
-            author: "alice".to_string(),
-            files_changed: 7,
-            additions: 320,
-            deletions: 45,
-            files: vec![],
-        }
+    pub(crate) fn sample_pr() -> PullRequest {
+        PullRequest::builder("Add OAuth2 login flow").number(42).author("alice").totals(7, 320, 45).build().unwrap()
     }
 
     #[test]
@@ -216,19 +758,14 @@ mod tests {
             AnalysisResult {
                 analyzer_name: "Security".to_string(),
                 risk_level: RiskLevel::High,
-                findings: vec![Finding {
-                    message: "SQL injection detected".to_string(),
-                    file: Some("db/query.rs".to_string()),
-                    line: Some(42),
-                    severity: RiskLevel::High,
-                }],
+                findings: vec![Finding::text("SQL injection detected", RiskLevel::High).in_file("db/query.rs").at_line(42)],
             },
         ];
         let report = build(results, &sample_pr());
 
         let dir = std::env::temp_dir();
         let path = dir.join("test_report.md");
-        write_markdown_report(&report, &path).unwrap();
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
 
         let content = std::fs::read_to_string(&path).unwrap();
         assert!(content.contains("# PR #42"));
@@ -240,6 +777,314 @@ mod tests {
         std::fs::remove_file(&path).ok();
     }
 
+    #[test]
+    fn test_sort_orders_are_deterministic() {
+        let finding = |file: Option<&str>, line, severity, message: &str| Finding { file: file.map(str::to_string), line, ..Finding::text(message, severity) };
+        let results = vec![
+            AnalysisResult {
+                analyzer_name: "Style".to_string(),
+                risk_level: RiskLevel::Medium,
+                findings: vec![
+                    finding(Some("src/b.rs"), Some(3), RiskLevel::Low, "clone"),
+                    finding(Some("src/a.rs"), Some(9), RiskLevel::Medium, "unwrap"),
+                    finding(Some("src/a.rs"), Some(2), RiskLevel::Low, "todo"),
+                    finding(None, None, RiskLevel::Low, "summary"),
+                ],
+            },
+            AnalysisResult { analyzer_name: "Security".to_string(), risk_level: RiskLevel::High, findings: vec![] },
+        ];
+        let messages = |report: &Report| report.results[0].findings.iter().map(|f| f.message.as_str()).collect::<Vec<_>>().join(",");
+        let names = |report: &Report| report.results.iter().map(|r| r.analyzer_name.as_str()).collect::<Vec<_>>().join(",");
+
+        let mut report = build(results.clone(), &sample_pr());
+        sort(&mut report, SortOrder::File);
+        assert_eq!((names(&report), messages(&report)), ("Style,Security".to_string(), "summary,todo,unwrap,clone".to_string()));
+
+        let mut report = build(results.clone(), &sample_pr());
+        sort(&mut report, SortOrder::Analyzer);
+        assert_eq!(names(&report), "Security,Style");
+
+        let mut report = build(results, &sample_pr());
+        sort(&mut report, SortOrder::Severity);
+        assert_eq!(names(&report), "Security,Style");
+        assert_eq!(report.results[1].findings[0].message, "unwrap");
+        assert_eq!(report.results[1].findings[1].message, "summary");
+    }
+
+    #[test]
+    fn test_patch_breakdown_attributes_findings_to_patches() {
+        use crate::pr::types::{DiffFile, Hunk, Patch};
+        let patch = |subject: &str, new_start: usize| {
+            let mut file = DiffFile::empty("src/net.rs");
+            file.set_counts(2, 0);
+            file.hunks_mut().push(Hunk { old_start: new_start, old_count: 1, new_start, new_count: 3, lines: vec![] });
+            Patch { subject: subject.to_string(), author: "jane".to_string(), files: vec![file] }
+        };
+        let mut pr = sample_pr();
+        *pr.patches_mut() = vec![patch("net: add retry", 1), patch("net: use retry", 20)];
+        let finding = |line| Finding::text("x", RiskLevel::Medium).in_file("src/net.rs").at_line(line);
+        let results = vec![AnalysisResult {
+            analyzer_name: "Style".to_string(),
+            risk_level: RiskLevel::Medium,
+            findings: vec![finding(21), finding(22), finding(100)],
+        }];
+        let report = build(results, &pr);
+        assert_eq!(report.patches.len(), 2);
+        assert_eq!((report.patches[0].findings, report.patches[0].risk), (0, None));
+        assert_eq!((report.patches[1].findings, report.patches[1].risk), (2, Some(RiskLevel::Medium)));
+
+        let path = std::env::temp_dir().join("test_patch_series_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("## Patch Series"));
+        assert!(content.contains("| 2 | net: use retry | jane | +2 -0 | 2 | MEDIUM |"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_lists_suggested_reviewers() {
+        let mut report = build(vec![], &sample_pr());
+        report.reviewers.push(types::ReviewerHint {
+            file: "src/db.rs".to_string(),
+            reviewers: vec!["alice@example.com".to_string()],
+        });
+        let path = std::env::temp_dir().join("test_reviewers_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("## Suggested Reviewers"));
+        assert!(content.contains("- `src/db.rs`: alice@example.com"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_lists_attack_surface_changes() {
+        let mut report = build(vec![], &sample_pr());
+        report.attack_surface.push(SurfaceChange { kind: SurfaceKind::Route, name: "POST /upload".to_string(), file: "web/app.js".to_string(), line: 12 });
+        let path = std::env::temp_dir().join("test_attack_surface_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("## Attack Surface Changes\n\n- HTTP route: `POST /upload` (`web/app.js:12`)\n"));
+        assert!(!content.contains("## New Configuration"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_appends_rules_run() {
+        let mut report = build(vec![], &sample_pr());
+        let rule = |rule: &str, status, findings, reason: Option<&str>| RuleRun {
+            analyzer: "Security Risk Assessment".to_string(),
+            rule: rule.to_string(),
+            status,
+            findings,
+            suppressed: 0,
+            reason: reason.map(str::to_string),
+        };
+        report.rules = vec![
+            rule("security.hardcoded_secret", RuleStatus::Executed, 1, None),
+            rule("security.custom_patterns", RuleStatus::Skipped, 0, Some("no [security] patterns configured")),
+        ];
+        let path = std::env::temp_dir().join("test_rules_run_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("<summary>Rules Run: 1 executed, 1 skipped, 0 errored</summary>"));
+        assert!(content.contains("| `security.hardcoded_secret` | executed | 1 |  |\n"));
+        assert!(content.contains("| `security.custom_patterns` | skipped | 0 | no [security] patterns configured |\n"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_compare_with_previous_marks_new_persisting_and_resolved() {
+        let finding = |message: &str, line| Finding::text(message, RiskLevel::High).in_file("src/db.rs").at_line(line);
+        let result = |findings| AnalysisResult { analyzer_name: "Security".to_string(), risk_level: RiskLevel::High, findings };
+        let previous = build(vec![result(vec![finding("SQL built from input", 10), finding("Hardcoded secret", 12)])], &sample_pr());
+        // The SQL finding moved down two lines; the secret is gone; unwrap is new
+        let mut report = build(vec![result(vec![finding("SQL built from input", 12), finding("unwrap() in library code", 3)])], &sample_pr());
+        compare_with_previous(&mut report, &previous);
+
+        let comparison = report.since_last_run.as_ref().unwrap();
+        assert_eq!(comparison.resolved.len(), 1);
+        assert_eq!(comparison.resolved[0].finding.message, "Hardcoded secret");
+        assert_eq!(rerun_tag(&report, "Security", &report.results[0].findings[0]), Some("PERSISTING"));
+        assert_eq!(rerun_tag(&report, "Security", &report.results[0].findings[1]), Some("NEW"));
+
+        let path = std::env::temp_dir().join("test_rerun_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("- **[HIGH]** `NEW` unwrap() in library code"));
+        assert!(content.contains("## Resolved Since Last Run"));
+        assert!(content.contains("- ~~Hardcoded secret~~ (Security) (`src/db.rs`)"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_embeds_report_for_later_comparison() {
+        let finding = |message: &str| Finding::text(message, RiskLevel::High).in_file("src/db.rs").at_line(10);
+        let result = |findings| AnalysisResult { analyzer_name: "Security".to_string(), risk_level: RiskLevel::High, findings };
+        let previous = build(vec![result(vec![finding("SQL built from input"), finding("Hardcoded secret")])], &sample_pr());
+        let path = std::env::temp_dir().join("test_embedded_report.md");
+        write_atomically(&path, &render_markdown(&previous, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.lines().last().unwrap().starts_with("<!-- pr-analyzer:report v1 "));
+
+        let embedded = extract_embedded(&content).unwrap().unwrap();
+        assert_eq!(embedded.provenance.generated_at, previous.provenance.generated_at);
+        let mut report = build(vec![result(vec![finding("SQL built from input")])], &sample_pr());
+        compare_with_previous(&mut report, &embedded);
+        let comparison = report.since_last_run.as_ref().unwrap();
+        assert_eq!(comparison.persisting.len(), 1);
+        assert_eq!(comparison.resolved[0].finding.message, "Hardcoded secret");
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_groups_by_category() {
+        let finding = |message: &str, severity, category| Finding { category, ..Finding::text(message, severity) };
+        let report = build(
+            vec![
+                AnalysisResult {
+                    analyzer_name: "Security".to_string(),
+                    risk_level: RiskLevel::High,
+                    findings: vec![finding("Hardcoded token", RiskLevel::High, Some(Category::Secrets)), finding("New dependency", RiskLevel::Low, Some(Category::SupplyChain))],
+                },
+                AnalysisResult {
+                    analyzer_name: "Container".to_string(),
+                    risk_level: RiskLevel::Medium,
+                    findings: vec![finding("Unpinned base image", RiskLevel::Medium, Some(Category::SupplyChain)), finding("Note", RiskLevel::Low, None)],
+                },
+            ],
+            &sample_pr(),
+        );
+        let path = std::env::temp_dir().join("test_category_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Category, Catalog::english()).unwrap()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let headings: Vec<&str> = content.lines().filter(|l| l.starts_with("## ")).collect();
+        assert_eq!(headings, vec!["## Secrets", "## Supply Chain", "## Other", "## Overall Risk: HIGH"]);
+        assert!(content.contains("## Supply Chain\n\n**Risk Level: MEDIUM**\n\n- **[LOW]** New dependency\n- **[MEDIUM]** Unpinned base image\n"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_lists_policy_enforcement() {
+        let mut report = build(vec![], &sample_pr());
+        report.provenance.policy.push("security.patterns: local value ignored (locked by policy)".to_string());
+        let path = std::env::temp_dir().join("test_policy_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("## Enforced by Org Policy\n\n- security.patterns: local value ignored"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_uses_translated_catalog() {
+        let catalog_path = std::env::temp_dir().join("test_catalog_fr.toml");
+        std::fs::write(&catalog_path, "language = \"fr\"\n[findings.style]\nunwrap = \"Appel à .unwrap()\"\n[chrome]\noverall_risk = \"Risque global\"\n").unwrap();
+        let catalog = Catalog::load(&catalog_path).unwrap();
+        let result = AnalysisResult {
+            analyzer_name: "Style".to_string(),
+            risk_level: RiskLevel::Medium,
+            findings: vec![Finding::new("style.unwrap", RiskLevel::Medium).in_file("src/lib.rs").at_line(3)],
+        };
+        let report = build(vec![result], &sample_pr());
+        let path = std::env::temp_dir().join("test_translated_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, &catalog).unwrap()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("- **[MEDIUM]** Appel à .unwrap() (`src/lib.rs:3`)"));
+        assert!(content.contains("## Risque global: MEDIUM"));
+        assert!(content.contains("**Risk Level: MEDIUM**"));
+        assert!(report.results[0].findings[0].message.starts_with("Use of .unwrap()"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_markdown_report_marks_quick_run() {
+        let mut report = build(vec![], &sample_pr());
+        report.quick = true;
+        let md = render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap();
+        assert!(md.contains("> **QUICK: diff-only analysis (--quick)"), "{md}");
+        assert!(!md.contains("INTERRUPTED"));
+    }
+
+    #[test]
+    fn test_markdown_report_links_rule_docs() {
+        let mut finding = Finding::new("style.unwrap", RiskLevel::Medium).in_file("src/a.rs").at_line(3);
+        finding.rule = Some("style.unwrap".to_string());
+        finding.rule_code = Some("STY001".to_string());
+        let results = vec![AnalysisResult { analyzer_name: "Style".to_string(), risk_level: RiskLevel::Medium, findings: vec![finding, Finding::text("free text", RiskLevel::Low)] }];
+        let mut report = build(results, &sample_pr());
+        link_docs(&mut report, "https://docs.example.com/pr-analyzer/rules/{rule_id}");
+        let md = render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap();
+        assert!(md.contains("(`src/a.rs:3`) `STY001` [docs](https://docs.example.com/pr-analyzer/rules/style.unwrap)\n"), "{md}");
+        assert!(md.contains("free text\n"), "{md}");
+    }
+
+    #[test]
+    fn test_reports_list_analysis_warnings() {
+        let mut report = build(vec![], &sample_pr());
+        report.warnings = vec![Warning::new("warning.files_missing").param("listed", 2).param("files", 5)];
+        let md = render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap();
+        assert!(md.contains("> **Analysis warnings**\n>\n> - The diff lists 2 of 5 changed files; the others were not analyzed\n"), "{md}");
+        let html = html::render(&report, Catalog::english());
+        assert!(html.contains("<li>The diff lists 2 of 5 changed files; the others were not analyzed</li>"), "{html}");
+    }
+
+    #[test]
+    fn test_markdown_report_marks_fork() {
+        let mut pr = sample_pr();
+        pr.set_fork(true);
+        let md = render_markdown(&build(vec![], &pr), GroupBy::Analyzer, Catalog::english()).unwrap();
+        assert!(md.contains("> **FORK: this PR comes from a fork;"), "{md}");
+    }
+
+    #[test]
+    fn test_markdown_report_shows_review_effort() {
+        let mut report = build(vec![], &sample_pr());
+        report.review_effort = Some(ReviewEffort { minutes: 45, weighted_lines: 180, complexity_findings: 2, test_ratio: 0.3 });
+        let md = render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap();
+        assert!(md.contains("**Review effort:** ~45 min\n"), "{md}");
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["review_effort"]["minutes"], 45);
+    }
+
+    #[test]
+    fn test_markdown_report_marks_stale_report() {
+        let mut pr = sample_pr();
+        pr.set_head_sha(Some("0123456789abcdef".to_string()));
+        let mut report = build(vec![], &pr);
+        assert!(!render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap().contains("STALE"));
+        report.stale_head = Some("fedcba9876543210".to_string());
+        let md = render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap();
+        assert!(md.contains("> **STALE: "), "{md}");
+        assert!(md.contains("(`0123456` → `fedcba9`)"), "{md}");
+    }
+
+    #[test]
+    fn test_markdown_report_marks_interrupted_run() {
+        let mut report = build(vec![], &sample_pr());
+        report.interrupted = true;
+        let path = std::env::temp_dir().join("test_interrupted_report.md");
+        write_atomically(&path, &render_markdown(&report, GroupBy::Analyzer, Catalog::english()).unwrap()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("INTERRUPTED"));
+        assert!(!content.contains("QUICK"));
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        assert!(!std::path::Path::new(&tmp).exists());
+
+        std::fs::remove_file(&path).ok();
+    }
+
     #[test]
     fn test_terminal_report_does_not_panic() {
         let results = vec![
@@ -251,23 +1096,56 @@ mod tests {
         ];
         let report = build(results, &sample_pr());
         // Just ensure it doesn't panic
-        print_terminal_report(&report);
+        print_terminal_report(&report, GroupBy::Analyzer, Catalog::english());
     }
 
-    #[test]
-    fn test_output_to_file() {
+    #[tokio::test]
+    async fn test_output_to_file() {
         let report = build(vec![], &sample_pr());
         let dir = std::env::temp_dir();
         let path = dir.join("test_output.md");
-        output(&report, Some(&path)).unwrap();
+        let outputs = [Output { format: None, path: path.clone() }];
+        emit(&sinks(&outputs, None, GroupBy::Analyzer), &report, &[], Catalog::english()).await.unwrap();
         assert!(path.exists());
         std::fs::remove_file(&path).ok();
     }
 
+    #[tokio::test]
+    async fn test_json_report_includes_provenance() {
+        let mut pr = sample_pr();
+        pr.set_head_sha(Some("abc123".to_string()));
+        let mut report = build(vec![], &pr);
+        report.provenance.config_hash = Some("deadbeef".to_string());
+        let path = std::env::temp_dir().join("test_report_provenance.json");
+        let outputs = [Output { format: None, path: path.clone() }];
+        emit(&sinks(&outputs, Some(Format::Json), GroupBy::Analyzer), &report, &[], Catalog::english()).await.unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["provenance"]["head_sha"], "abc123");
+        assert_eq!(json["provenance"]["config_hash"], "deadbeef");
+        assert_eq!(json["provenance"]["tool_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["overall_risk"], "low");
+
+        std::fs::remove_file(&path).ok();
+    }
+
     #[test]
-    fn test_output_to_terminal() {
+    fn test_rfc3339_utc() {
+        let t = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_771_320_600);
+        assert_eq!(rfc3339_utc(t), "2026-02-17T09:30:00Z");
+        assert_eq!(rfc3339_utc(SystemTime::UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_minisign_requires_key() {
+        let err = sign(Path::new("report.json"), Signer::Minisign, None).unwrap_err();
+        assert!(err.to_string().contains("--sign-key"));
+    }
+
+    #[tokio::test]
+    async fn test_output_to_terminal() {
         let report = build(vec![], &sample_pr());
         // Should not panic
-        output(&report, None).unwrap();
+        emit(&sinks(&[], None, GroupBy::Analyzer), &report, &[], Catalog::english()).await.unwrap();
     }
 }
@@ -0,0 +1,276 @@
+//! Where a finished report goes.
+//!
+//! `sinks` turns `--output` (repeatable), `--format` and `[output] files`
+//! into a list of `Sink`s, and `emit` hands the report to each. Writing
+//! files no longer replaces the terminal summary, which is still printed.
+//! Destinations that post the report somewhere (PR comments, check runs,
+//! chat) are further `Sink` implementations.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use clap::ValueEnum;
+use serde::Deserialize;
+use tracing::{debug, instrument, warn};
+
+use super::{annotated, ci, html, print_terminal_report, render_markdown, write_atomically, Catalog, Format, GroupBy, Report, ReportError};
+use crate::config::AuditConfig;
+use crate::pr::types::DiffFile;
+use crate::pr::{self, comment, GitHubClient, PrUrl};
+
+/// One `--output` value: `[FORMAT=]PATH`, e.g. `report.md` or
+/// `codequality=gl-code-quality-report.json`. Also the form of the
+/// `[output] files` entries.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Output {
+    pub format: Option<Format>,
+    pub path: PathBuf,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let Some((prefix, path)) = value.split_once('=') else {
+            return Ok(Self { format: None, path: PathBuf::from(value) });
+        };
+        match Format::from_str(prefix, true) {
+            Ok(Format::Terminal) => Err("terminal output goes to stdout, not to a file".to_string()),
+            Ok(format) if !path.is_empty() => Ok(Self { format: Some(format), path: PathBuf::from(path) }),
+            Ok(_) => Err(format!("missing path after '{}='", prefix)),
+            // Not a format: an `=` that is part of the path.
+            Err(_) => Ok(Self { format: None, path: PathBuf::from(value) }),
+        }
+    }
+}
+
+impl TryFrom<String> for Output {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Format implied by a file extension, if any.
+fn format_for_extension(path: &Path) -> Option<Format> {
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "md" | "markdown" => Some(Format::Markdown),
+        "json" => Some(Format::Json),
+        "html" | "htm" => Some(Format::Html),
+        "xml" => Some(Format::Checkstyle),
+        "rdjson" => Some(Format::Rdjson),
+        "diff" | "patch" => Some(Format::AnnotatedDiff),
+        _ => None,
+    }
+}
+
+/// A destination for the finished report.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// What the sink does, for logs (e.g. "markdown to report.md").
+    fn describe(&self) -> String;
+
+    /// The file the sink writes, if it writes one (for `--sign`).
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    async fn emit(&self, report: &Report, diff: &[DiffFile], catalog: &Catalog) -> Result<(), ReportError>;
+}
+
+/// The report in `format` written to a file, replaced atomically.
+pub struct FileSink {
+    format: Format,
+    path: PathBuf,
+    group_by: GroupBy,
+}
+
+impl FileSink {
+    pub fn new(format: Format, path: impl Into<PathBuf>, group_by: GroupBy) -> Self {
+        Self { format, path: path.into(), group_by }
+    }
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    fn describe(&self) -> String {
+        format!("{:?} to {}", self.format, self.path.display()).to_lowercase()
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    async fn emit(&self, report: &Report, diff: &[DiffFile], catalog: &Catalog) -> Result<(), ReportError> {
+        write_atomically(&self.path, &render(self.format, report, diff, self.group_by, catalog)?)
+    }
+}
+
+/// The report in `format` printed to stdout.
+pub struct StdoutSink {
+    format: Format,
+    group_by: GroupBy,
+}
+
+impl StdoutSink {
+    pub fn new(format: Format, group_by: GroupBy) -> Self {
+        Self { format, group_by }
+    }
+}
+
+#[async_trait]
+impl Sink for StdoutSink {
+    fn describe(&self) -> String {
+        format!("{:?} to stdout", self.format).to_lowercase()
+    }
+
+    async fn emit(&self, report: &Report, diff: &[DiffFile], catalog: &Catalog) -> Result<(), ReportError> {
+        match self.format {
+            Format::Terminal => print_terminal_report(report, self.group_by, catalog),
+            format => print!("{}", render(format, report, diff, self.group_by, catalog)?),
+        }
+        Ok(())
+    }
+}
+
+/// The markdown report as a comment on a GitHub PR (`--comment`), edited
+/// in place on later runs.
+pub struct CommentSink {
+    url: PrUrl,
+    client: GitHubClient,
+    audit: AuditConfig,
+    group_by: GroupBy,
+}
+
+impl CommentSink {
+    pub fn new(url: PrUrl, client: GitHubClient, audit: AuditConfig, group_by: GroupBy) -> Self {
+        Self { url, client, audit, group_by }
+    }
+}
+
+#[async_trait]
+impl Sink for CommentSink {
+    fn describe(&self) -> String {
+        format!("comment on {}/{}#{}", self.url.owner, self.url.repo, self.url.pr_number)
+    }
+
+    /// Marks the report stale when the PR head moved past the analyzed
+    /// commit while the analysis ran; a report that looks current but is
+    /// not is worse than none.
+    async fn emit(&self, report: &Report, _diff: &[DiffFile], catalog: &Catalog) -> Result<(), ReportError> {
+        let stale_head = match (&report.provenance.head_sha, pr::current_head_sha(&self.client, &self.url).await) {
+            (Some(analyzed), Ok(head)) if *analyzed != head => Some(head),
+            (_, Err(e)) => {
+                warn!(error = %e, "could not read the PR head; posting without a staleness check");
+                None
+            }
+            _ => None,
+        };
+        let markdown = match stale_head {
+            Some(head) => {
+                warn!(analyzed = ?report.provenance.head_sha, head = %head, "new commits since the analysis; report marked STALE");
+                render_markdown(&Report { stale_head: Some(head), ..report.clone() }, self.group_by, catalog)?
+            }
+            None => render_markdown(report, self.group_by, catalog)?,
+        };
+        Ok(comment::upsert(&self.client, &self.url, &markdown, &self.audit).await?)
+    }
+}
+
+/// The report as text in `format`. Terminal output has no file form and is
+/// rendered as markdown.
+fn render(format: Format, report: &Report, diff: &[DiffFile], group_by: GroupBy, catalog: &Catalog) -> Result<String, ReportError> {
+    Ok(match format {
+        Format::Terminal | Format::Markdown => render_markdown(report, group_by, catalog)?,
+        Format::Json => serde_json::to_string_pretty(report)? + "\n",
+        Format::Html => html::render(report, catalog),
+        Format::AnnotatedDiff => annotated::render(report, diff, catalog),
+        Format::TeamCity => ci::teamcity(report, diff, catalog),
+        Format::CodeQuality => ci::code_quality(report, diff, catalog)? + "\n",
+        Format::Checkstyle => ci::checkstyle(report, diff, catalog),
+        Format::Rdjson => ci::rdjson(report, diff, catalog)? + "\n",
+        Format::GitHub => ci::github(report, diff, catalog),
+    })
+}
+
+/// The sinks for one run. Each of `outputs` is written in its own format,
+/// else in `format`, else in the format its extension implies, else as
+/// markdown. Without outputs, `format` (terminal by default) goes to stdout;
+/// with outputs, stdout gets the terminal summary.
+pub fn sinks(outputs: &[Output], format: Option<Format>, group_by: GroupBy) -> Vec<Box<dyn Sink>> {
+    let file_format = format.filter(|f| *f != Format::Terminal);
+    let mut sinks: Vec<Box<dyn Sink>> = outputs
+        .iter()
+        .map(|o| {
+            let format = o.format.or(file_format).or_else(|| format_for_extension(&o.path)).unwrap_or(Format::Markdown);
+            Box::new(FileSink::new(format, &o.path, group_by)) as Box<dyn Sink>
+        })
+        .collect();
+    let stdout_format = if outputs.is_empty() { format.unwrap_or(Format::Terminal) } else { Format::Terminal };
+    sinks.push(Box::new(StdoutSink::new(stdout_format, group_by)));
+    sinks
+}
+
+/// Hand the report to every sink, in order. A failing sink does not stop
+/// the rest; the first error is returned once all have run.
+#[instrument(skip_all, fields(pr = report.pr_number, overall_risk = %report.overall_risk))]
+pub async fn emit(sinks: &[Box<dyn Sink>], report: &Report, diff: &[DiffFile], catalog: &Catalog) -> Result<(), ReportError> {
+    let mut first_error = None;
+    for sink in sinks {
+        debug!(sink = %sink.describe(), "writing report");
+        if let Err(e) = sink.emit(report, diff, catalog).await {
+            warn!(sink = %sink.describe(), error = %e, "report output failed");
+            first_error.get_or_insert(e);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::build;
+    use crate::report::tests::sample_pr;
+
+    #[test]
+    fn test_parse_output() {
+        assert_eq!("report.md".parse(), Ok(Output { format: None, path: PathBuf::from("report.md") }));
+        assert_eq!("codequality=gl.json".parse(), Ok(Output { format: Some(Format::CodeQuality), path: PathBuf::from("gl.json") }));
+        assert_eq!("out/a=b.md".parse(), Ok(Output { format: None, path: PathBuf::from("out/a=b.md") }));
+        assert!("terminal=x.txt".parse::<Output>().is_err());
+        assert!("json=".parse::<Output>().is_err());
+    }
+
+    #[test]
+    fn test_sink_formats() {
+        let outputs: Vec<Output> = ["report.md", "report.json", "notes.txt", "html=page"].iter().map(|o| o.parse().unwrap()).collect();
+        let described: Vec<String> = sinks(&outputs, None, GroupBy::Analyzer).iter().map(|s| s.describe()).collect();
+        assert_eq!(described, ["markdown to report.md", "json to report.json", "markdown to notes.txt", "html to page", "terminal to stdout"]);
+
+        let described: Vec<String> = sinks(&outputs[1..2], Some(Format::CodeQuality), GroupBy::Analyzer).iter().map(|s| s.describe()).collect();
+        assert_eq!(described, ["codequality to report.json", "terminal to stdout"]);
+        let described: Vec<String> = sinks(&[], Some(Format::Json), GroupBy::Analyzer).iter().map(|s| s.describe()).collect();
+        assert_eq!(described, ["json to stdout"]);
+    }
+
+    #[tokio::test]
+    async fn test_emit_writes_every_file_despite_a_failure() {
+        let report = build(vec![], &sample_pr());
+        let dir = std::env::temp_dir();
+        let (md, json) = (dir.join("test_sinks.md"), dir.join("test_sinks.json"));
+        let sinks: Vec<Box<dyn Sink>> = vec![
+            Box::new(FileSink::new(Format::Markdown, dir.join("no-such-dir").join("x.md"), GroupBy::Analyzer)),
+            Box::new(FileSink::new(Format::Markdown, &md, GroupBy::Analyzer)),
+            Box::new(FileSink::new(Format::Json, &json, GroupBy::Analyzer)),
+        ];
+        assert!(matches!(emit(&sinks, &report, &[], Catalog::english()).await, Err(ReportError::FileWrite(_))));
+        assert!(std::fs::read_to_string(&md).unwrap().starts_with("# PR #42"));
+        let parsed: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&json).unwrap()).unwrap();
+        assert_eq!(parsed["overall_risk"], "low");
+        std::fs::remove_file(&md).ok();
+        std::fs::remove_file(&json).ok();
+    }
+}
@@ -0,0 +1,152 @@
+//! Static dashboard built from the run history (`pr-analyzer site build`).
+//!
+//! Layout of the output directory, ready for GitHub Pages:
+//! - `index.html`: daily risk trend and one row per PR (its latest run)
+//! - `prs/<repo>-<number>.html`: the latest HTML report of each PR
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use super::html::{self, escape, risk_class};
+use super::messages::Catalog;
+use super::types::RiskLevel;
+use super::{write_atomically, ReportError};
+use crate::history::Entry;
+
+/// Write the site into `out` and return the path of its index page.
+pub fn build_site(entries: &[Entry], out: &Path) -> Result<PathBuf, ReportError> {
+    std::fs::create_dir_all(out.join("prs"))?;
+
+    // Latest run per PR, keyed so the index lists repos and PRs in order.
+    let mut latest: BTreeMap<(String, u64), (&Entry, usize)> = BTreeMap::new();
+    for entry in entries {
+        let key = (entry.repo.clone().unwrap_or_else(|| "local".to_string()), entry.report.pr_number);
+        let runs = latest.get(&key).map_or(0, |(_, runs)| *runs) + 1;
+        let newer = latest.get(&key).is_none_or(|(seen, _)| entry.report.provenance.generated_at >= seen.report.provenance.generated_at);
+        if newer {
+            latest.insert(key, (entry, runs));
+        } else if let Some(slot) = latest.get_mut(&key) {
+            slot.1 = runs;
+        }
+    }
+
+    let mut rows = String::new();
+    for ((repo, number), (entry, runs)) in &latest {
+        let report = &entry.report;
+        let page = format!("prs/{}.html", page_name(repo, *number));
+        let back = "<body>\n<p><a href=\"../index.html\">&larr; All pull requests</a></p>\n";
+        write_atomically(&out.join(&page), &html::render(report, Catalog::english()).replacen("<body>\n", back, 1))?;
+        let findings: usize = report.results.iter().map(|r| r.findings.len()).sum();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td><a href=\"{}\">#{}</a></td><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape(repo),
+            page,
+            number,
+            escape(&report.pr_title),
+            escape(&report.author),
+            risk_class(report.overall_risk),
+            report.overall_risk,
+            findings,
+            runs,
+            escape(&report.provenance.generated_at),
+        ));
+    }
+
+    let mut index = String::new();
+    index.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>PR risk dashboard</title>\n");
+    index.push_str(&format!("<style>{}{}</style>\n</head>\n<body>\n", html::STYLE, STYLE));
+    index.push_str(&format!("<h1>PR risk dashboard</h1>\n<p>{} runs across {} pull requests.</p>\n", entries.len(), latest.len()));
+    index.push_str("<h2>Trend</h2>\n");
+    index.push_str(&trend_table(entries));
+    index.push_str("<h2>Pull Requests</h2>\n<table>\n<tr><th>Repository</th><th>PR</th><th>Title</th><th>Author</th><th>Risk</th><th>Findings</th><th>Runs</th><th>Last analyzed</th></tr>\n");
+    index.push_str(&rows);
+    index.push_str("</table>\n</body>\n</html>\n");
+
+    let index_path = out.join("index.html");
+    write_atomically(&index_path, &index)?;
+    info!(pages = latest.len(), path = %index_path.display(), "static site written");
+    Ok(index_path)
+}
+
+const STYLE: &str = "table{border-collapse:collapse}td,th{padding:4px 8px;border-bottom:1px solid #ddd;text-align:left}\
+.bar{display:flex;height:14px;min-width:2px}.bar span{display:block}";
+
+/// Runs per day, stacked by overall risk.
+fn trend_table(entries: &[Entry]) -> String {
+    let mut days: BTreeMap<&str, [usize; 3]> = BTreeMap::new();
+    for entry in entries {
+        let day = entry.report.provenance.generated_at.get(..10).unwrap_or("unknown");
+        let counts = days.entry(day).or_default();
+        counts[entry.report.overall_risk as usize] += 1;
+    }
+    let busiest = days.values().map(|c| c.iter().sum::<usize>()).max().unwrap_or(1).max(1);
+    let mut table = String::from("<table>\n<tr><th>Day</th><th>Runs</th><th>High</th><th>Medium</th><th>Low</th><th></th></tr>\n");
+    for (day, counts) in days {
+        let total: usize = counts.iter().sum();
+        let mut bar = String::new();
+        for level in [RiskLevel::High, RiskLevel::Medium, RiskLevel::Low] {
+            let n = counts[level as usize];
+            if n > 0 {
+                bar.push_str(&format!("<span class=\"{}\" style=\"width:{}px\"></span>", risk_class(level), n * 300 / busiest));
+            }
+        }
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td><div class=\"bar\">{}</div></td></tr>\n",
+            escape(day),
+            total,
+            counts[RiskLevel::High as usize],
+            counts[RiskLevel::Medium as usize],
+            counts[RiskLevel::Low as usize],
+            bar
+        ));
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+/// File name for a PR page: `org-repo-42`.
+fn page_name(repo: &str, number: u64) -> String {
+    let slug: String = repo.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' }).collect();
+    format!("{}-{}", slug, number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pr::PullRequest;
+    use crate::report::types::{AnalysisResult, Finding};
+
+    fn entry(repo: Option<&str>, number: u64, risk: RiskLevel, generated_at: &str) -> Entry {
+        let pr = PullRequest::builder(format!("Change {}", number)).number(number).author("alice").totals(1, 5, 1).build().unwrap();
+        let result = AnalysisResult {
+            analyzer_name: "Security".to_string(),
+            risk_level: risk,
+            findings: vec![Finding::text("x", risk)],
+        };
+        let mut report = crate::report::build(vec![result], &pr);
+        report.provenance.generated_at = generated_at.to_string();
+        Entry { repo: repo.map(str::to_string), report }
+    }
+
+    #[test]
+    fn test_build_site_writes_index_and_latest_pr_pages() {
+        let out = std::env::temp_dir().join("pr-analyzer-test-site");
+        std::fs::remove_dir_all(&out).ok();
+        let entries = vec![
+            entry(Some("org/api"), 7, RiskLevel::High, "2026-10-01T10:00:00Z"),
+            entry(Some("org/api"), 7, RiskLevel::Low, "2026-10-02T10:00:00Z"),
+            entry(None, 42, RiskLevel::Medium, "2026-10-02T11:00:00Z"),
+        ];
+        let index_path = build_site(&entries, &out).unwrap();
+        let index = std::fs::read_to_string(&index_path).unwrap();
+        assert!(index.contains("3 runs across 2 pull requests"));
+        assert!(index.contains("<a href=\"prs/org-api-7.html\">#7</a>"));
+        assert!(index.contains("<td class=\"risk-low\">LOW</td><td>1</td><td>2</td>"));
+        assert!(index.contains("<tr><td>2026-10-02</td><td>2</td><td>0</td><td>1</td><td>1</td>"));
+        let page = std::fs::read_to_string(out.join("prs/local-42.html")).unwrap();
+        assert!(page.contains("href=\"../index.html\""));
+        std::fs::remove_dir_all(&out).ok();
+    }
+}
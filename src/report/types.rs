@@ -1,5 +1,12 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::messages::Catalog;
+
 /// Risk level for an analysis finding or overall assessment.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Deserializes from lowercase names (`"low"`, `"medium"`, `"high"`) in config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RiskLevel {
     Low,
     Medium,
@@ -17,20 +24,214 @@ impl std::fmt::Display for RiskLevel {
 }
 
 /// A single finding from an analyzer.
-#[derive(Debug, Clone)]
+///
+/// Analyzers build findings from a message id and parameters (`new`,
+/// `param`); `message` is that message in the built-in English catalog.
+/// Reports render the id through the selected `Catalog` instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Finding {
-    /// Human-readable description of the finding
+    /// Human-readable description of the finding, in English
     pub message: String,
+    /// Catalog id of the message template, e.g. `complexity.many_files`
+    /// (None for free-text messages such as clippy diagnostics)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    /// Values of the template's placeholders, e.g. `files` = `40`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
     /// File path where the finding was detected (if applicable)
     pub file: Option<String>,
     /// Line number in the file (if applicable)
     pub line: Option<usize>,
     /// Severity of this individual finding
     pub severity: RiskLevel,
+    /// Kind of risk, from the rule that reported it (None for notes and
+    /// free-text findings, and in reports written before categories existed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<Category>,
+    /// Id of the rule that reported it, e.g. `security.hardcoded_secret`
+    /// (None where `category` is)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// Stable short code of `rule`, e.g. `SEC002`, for suppressions and
+    /// baselines that should survive a rule being renamed (None where `rule` is)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_code: Option<String>,
+    /// Documentation page of `rule`, from `[report] docs_url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+    /// Labels for filtering in integrations, e.g. from `[security] patterns`
+    /// entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Structured extras that are not part of the message, e.g. `cwe` =
+    /// `CWE-89`, for JSON consumers such as issue trackers and dashboards
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Replacement for the whole flagged line, without its line ending, when
+    /// the rule knows the fix; applied by `pr-analyzer fix`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    /// Why the rule chose this severity, when that depends on the change;
+    /// shown by `--explain-matches`, not stored in reports
+    #[serde(skip)]
+    pub severity_reason: Option<String>,
+}
+
+impl Finding {
+    /// A finding with the catalog message `message_id` and no location.
+    pub fn new(message_id: &str, severity: RiskLevel) -> Self {
+        debug_assert!(Catalog::english().has_message(message_id), "unknown message id {}", message_id);
+        let mut finding = Self::text(String::new(), severity);
+        finding.message_id = Some(message_id.to_string());
+        finding.render();
+        finding
+    }
+
+    /// A finding whose message is not in any catalog.
+    pub fn text(message: impl Into<String>, severity: RiskLevel) -> Self {
+        Self { message: message.into(), message_id: None, params: BTreeMap::new(), file: None, line: None, severity, category: None, rule: None, rule_code: None, docs_url: None, tags: Vec::new(), metadata: BTreeMap::new(), suggestion: None, severity_reason: None }
+    }
+
+    /// Set the placeholder `name` of the message template.
+    pub fn param(mut self, name: &str, value: impl std::fmt::Display) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self.render();
+        self
+    }
+
+    pub fn in_file(mut self, path: impl Into<String>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    pub fn at_line(mut self, line: impl Into<Option<usize>>) -> Self {
+        self.line = line.into();
+        self
+    }
+
+    /// Add a tag, once.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+
+    /// Set the metadata entry `key`; unlike `param`, it is not part of the
+    /// message.
+    pub fn meta(mut self, key: &str, value: impl std::fmt::Display) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Suggest `line` as the replacement of the flagged line (see `suggestion`).
+    pub fn suggest(mut self, line: impl Into<String>) -> Self {
+        self.suggestion = Some(line.into());
+        self
+    }
+
+    /// Record why the rule picked this severity (see `severity_reason`).
+    pub fn because(mut self, reason: impl Into<String>) -> Self {
+        self.severity_reason = Some(reason.into());
+        self
+    }
+
+    fn render(&mut self) {
+        if let Some(text) = self.message_id.as_deref().and_then(|id| Catalog::english().render(id, &self.params)) {
+            self.message = text;
+        }
+    }
+}
+
+/// Something that kept the analysis from seeing the whole change, such as
+/// a diff cut off mid-hunk. Unlike a finding it says nothing about the code
+/// and has no severity; it says the findings may be incomplete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Warning {
+    /// Description in English
+    pub message: String,
+    /// Catalog id of the message template, e.g. `warning.incomplete_hunk`
+    pub message_id: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl Warning {
+    pub fn new(message_id: &str) -> Self {
+        debug_assert!(Catalog::english().has_message(message_id), "unknown message id {}", message_id);
+        let message = Catalog::english().render(message_id, &BTreeMap::new()).unwrap_or_default();
+        Self { message, message_id: message_id.to_string(), params: BTreeMap::new(), file: None }
+    }
+
+    pub fn param(mut self, name: &str, value: impl std::fmt::Display) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self.message = Catalog::english().render(&self.message_id, &self.params).unwrap_or_default();
+        self
+    }
+
+    pub fn in_file(mut self, path: impl Into<String>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+}
+
+/// Kind of risk a finding represents, whichever analyzer reported it.
+/// Declaration order is the order of `--group-by category` sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// SQL, command, code and HTML injection, SSRF, open redirects
+    Injection,
+    /// Credentials in code or images
+    Secrets,
+    /// Authentication, authorization, privileges and IAM
+    AccessControl,
+    /// Access to personal data and device sensors
+    Privacy,
+    /// Dependencies, base images, actions and code run at build time
+    SupplyChain,
+    /// Memory safety, resource exhaustion, timeouts
+    Reliability,
+    /// Page weight and heavyweight packages
+    Performance,
+    /// Size, nesting, naming and code hygiene
+    Maintainability,
+    /// Breaking changes to published APIs
+    Compatibility,
+    /// Infrastructure-as-code changes
+    Infrastructure,
+    /// How code is reviewed, released and deployed
+    Process,
+    /// `[security] patterns`, whatever they look for
+    Custom,
+}
+
+impl Category {
+    /// Catalog chrome key of the category's heading.
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Category::Injection => "category_injection",
+            Category::Secrets => "category_secrets",
+            Category::AccessControl => "category_access_control",
+            Category::Privacy => "category_privacy",
+            Category::SupplyChain => "category_supply_chain",
+            Category::Reliability => "category_reliability",
+            Category::Performance => "category_performance",
+            Category::Maintainability => "category_maintainability",
+            Category::Compatibility => "category_compatibility",
+            Category::Infrastructure => "category_infrastructure",
+            Category::Process => "category_process",
+            Category::Custom => "category_custom",
+        }
+    }
 }
 
 /// Result from a single analyzer run.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisResult {
     /// Name of the analyzer that produced this result
     pub analyzer_name: String,
@@ -40,8 +241,207 @@ pub struct AnalysisResult {
     pub findings: Vec<Finding>,
 }
 
+/// One analyzer's line in the `[report]` summary section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalyzerSummary {
+    pub analyzer: String,
+    pub risk: RiskLevel,
+    pub findings: usize,
+}
+
+/// Likely owners of a file with findings, from blame of the surrounding lines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewerHint {
+    pub file: String,
+    /// Author emails, most lines first
+    pub reviewers: Vec<String>,
+}
+
+/// Whether a rule ran on this PR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleStatus {
+    Executed,
+    /// Disabled by configuration, not applicable to this run, or interrupted
+    Skipped,
+    /// Ran but could not complete (e.g. `cargo clippy` failed)
+    Errored,
+}
+
+/// One rule's status in a run, for the rules appendix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleRun {
+    pub analyzer: String,
+    /// `<table>.<rule>`, e.g. `security.hardcoded_secret`
+    pub rule: String,
+    pub status: RuleStatus,
+    /// Findings of this rule in the report
+    pub findings: usize,
+    /// Findings it raised that `min_severity` or the findings budget dropped
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub suppressed: usize,
+    /// Why it was skipped, or the error it hit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Kind of externally reachable surface a change adds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurfaceKind {
+    /// A socket or server bound to an address
+    Listener,
+    /// An HTTP route or handler
+    Route,
+    /// A command-line argument
+    CliArgument,
+    /// An environment variable read, or a key added to a `.env` file
+    EnvVar,
+    /// A key added to a configuration file
+    ConfigKey,
+}
+
+impl SurfaceKind {
+    /// Catalog `chrome` key of the kind's label.
+    pub fn label_key(self) -> &'static str {
+        match self {
+            SurfaceKind::Listener => "surface_listener",
+            SurfaceKind::Route => "surface_route",
+            SurfaceKind::CliArgument => "surface_cli_argument",
+            SurfaceKind::EnvVar => "surface_env_var",
+            SurfaceKind::ConfigKey => "surface_config_key",
+        }
+    }
+
+    /// Whether the kind is listed under "Attack Surface Changes".
+    pub fn is_attack_surface(self) -> bool {
+        self != SurfaceKind::ConfigKey
+    }
+
+    /// Whether the kind is listed under "New Configuration", for deployments.
+    pub fn is_deployment_config(self) -> bool {
+        matches!(self, SurfaceKind::EnvVar | SurfaceKind::ConfigKey)
+    }
+}
+
+/// One addition to the attack surface (see `analysis::surface`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurfaceChange {
+    pub kind: SurfaceKind,
+    /// e.g. `POST /upload`, `--api-token`, `DATABASE_URL`
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Files that could land as a PR of their own (see `analysis::split`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitGroup {
+    /// Components in the group, e.g. `src/auth`; several when imports tie them together
+    pub components: Vec<String>,
+    pub files: Vec<String>,
+    /// Lines added and deleted in those files
+    pub lines: usize,
+}
+
+/// Estimated reviewer time for the change (see `analysis::effort`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewEffort {
+    /// Estimate in minutes, rounded to 5
+    pub minutes: u32,
+    /// Changed lines weighted by how closely each kind of file is read
+    pub weighted_lines: usize,
+    /// Findings of the complexity analyzer, each adding time
+    pub complexity_findings: usize,
+    /// Share of changed code and test lines that are in tests, 0 to 1
+    pub test_ratio: f64,
+}
+
+/// Size of one changed file's diff, for per-file views such as the heatmap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// One patch of a series with the findings that fall inside its hunks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchSummary {
+    pub subject: String,
+    pub author: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub findings: usize,
+    /// Highest severity among those findings (None when there are none)
+    pub risk: Option<RiskLevel>,
+}
+
+/// Identity of a finding across runs of the same PR. Line numbers shift
+/// between pushes, so they are not part of it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FindingKey {
+    pub analyzer: String,
+    pub file: Option<String>,
+    pub message: String,
+}
+
+impl FindingKey {
+    pub fn new(analyzer: &str, finding: &Finding) -> Self {
+        Self { analyzer: analyzer.to_string(), file: finding.file.clone(), message: finding.message.clone() }
+    }
+}
+
+/// A finding of the previous run that this run no longer reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedFinding {
+    pub analyzer: String,
+    #[serde(flatten)]
+    pub finding: Finding,
+}
+
+/// How this run compares to the previous run of the same PR in the history file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunComparison {
+    /// `generated_at` of the previous run
+    pub previous_run: String,
+    pub previous_head_sha: Option<String>,
+    /// Findings of this run the previous run also reported; all others are new
+    pub persisting: BTreeSet<FindingKey>,
+    pub resolved: Vec<ResolvedFinding>,
+}
+
+impl RunComparison {
+    pub fn is_persisting(&self, analyzer: &str, finding: &Finding) -> bool {
+        self.persisting.contains(&FindingKey::new(analyzer, finding))
+    }
+}
+
+/// Where a report came from: the tool, rules, and code it describes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Provenance {
+    /// pr-analyzer version that produced the report
+    pub tool_version: String,
+    /// SHA-256 of the effective configuration (see `Config::fingerprint`)
+    pub config_hash: Option<String>,
+    /// Rules version per analyzer (see `Analyzer::rules_version`)
+    #[serde(default)]
+    pub rule_versions: BTreeMap<String, u32>,
+    /// Commit SHA of the analyzed PR head
+    pub head_sha: Option<String>,
+    /// Generation time, RFC 3339 in UTC
+    pub generated_at: String,
+    /// Settings the org policy enforced over the local config (see `Policy::enforced`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy: Vec<String>,
+}
+
 /// Complete report combining all analyzer results.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Report {
     /// PR number
     pub pr_number: u64,
@@ -55,10 +455,61 @@ pub struct Report {
     pub additions: usize,
     /// Lines deleted
     pub deletions: usize,
+    /// Per-file change sizes, in diff order
+    pub files: Vec<ChangedFile>,
     /// Results from each analyzer
     pub results: Vec<AnalysisResult>,
     /// Overall risk level (highest across all analyzers)
     pub overall_risk: RiskLevel,
+    /// True when the run was cancelled and `results` is partial
+    pub interrupted: bool,
+    /// True for a `--quick` run: diff-based checks only, within a time
+    /// budget, so `results` may be partial
+    #[serde(default)]
+    pub quick: bool,
+    /// True when the PR comes from a fork: checks that run its code were
+    /// skipped and nothing was posted back to it
+    #[serde(default)]
+    pub fork: bool,
+    /// The PR's head when the report was posted, if commits landed after
+    /// the analyzed `provenance.head_sha`: the results are stale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_head: Option<String>,
+    /// Risk and finding count per analyzer, including analyzers whose
+    /// sections are hidden (empty unless `[report] sections` lists `summary`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summary: Vec<AnalyzerSummary>,
+    /// Tool, config and commit identity for audit trails
+    pub provenance: Provenance,
+    /// Known findings left out because `--baseline` records them
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub baselined: usize,
+    /// What kept the analysis from seeing the whole change; empty when it saw everything
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// Suggested independent PRs, largest first (empty unless the change is
+    /// too large and spans separable components)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub split: Vec<SplitGroup>,
+    /// Estimated review time (None in reports written before it existed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review_effort: Option<ReviewEffort>,
+    /// Suggested reviewers per file (empty unless a local checkout was given)
+    pub reviewers: Vec<ReviewerHint>,
+    /// Every rule of every analyzer and whether it ran (empty in reports
+    /// written before rules were listed)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RuleRun>,
+    /// Listeners, routes, CLI arguments, environment variables and config keys the PR adds
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attack_surface: Vec<SurfaceChange>,
+    /// Per-patch breakdown (empty unless the input was a patch series)
+    #[serde(default)]
+    pub patches: Vec<PatchSummary>,
+    /// New, persisting and resolved findings against the previous run of
+    /// this PR (None unless `--history` holds an earlier run)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_last_run: Option<RunComparison>,
 }
 
 #[cfg(test)]
@@ -80,13 +531,20 @@ mod tests {
 
     #[test]
     fn test_finding_creation() {
-        let finding = Finding {
-            message: "SQL injection detected".to_string(),
-            file: Some("db/query.rs".to_string()),
-            line: Some(42),
-            severity: RiskLevel::High,
-        };
+        let finding = Finding::new("security.sql_construction", RiskLevel::High).in_file("db/query.rs").at_line(42);
         assert_eq!(finding.severity, RiskLevel::High);
         assert_eq!(finding.file.as_deref(), Some("db/query.rs"));
+        assert_eq!(finding.line, Some(42));
+        assert!(finding.message.starts_with("Possible SQL injection"));
+    }
+
+    #[test]
+    fn test_finding_params_render_and_serialize() {
+        let finding = Finding::new("complexity.new_dependencies", RiskLevel::Medium).param("count", 3).param("file", "Cargo.toml");
+        assert_eq!(finding.message, "3 new dependencies added in Cargo.toml");
+        let json = serde_json::to_value(&finding).unwrap();
+        assert_eq!(json["message_id"], "complexity.new_dependencies");
+        assert_eq!(json["params"]["count"], "3");
+        assert!(serde_json::to_value(Finding::text("note", RiskLevel::Low)).unwrap().get("params").is_none());
     }
 }
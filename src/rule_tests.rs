@@ -0,0 +1,178 @@
+//! `rules test`: runs the examples in `[[rule_tests]]` (from the config and
+//! from `*.toml` files in a directory) through the analyzers with the
+//! current config, and checks that each rule reports exactly the snippets
+//! it is expected to. Custom patterns can be tested before a rollout.
+
+use std::path::{Path, PathBuf};
+
+use pr_analyzer::analysis;
+use pr_analyzer::config::{Config, RuleTest};
+use pr_analyzer::pr::types::{BuildError, DiffFile};
+use pr_analyzer::pr::PullRequest;
+use pr_analyzer::report::types::{RiskLevel, RuleStatus};
+use serde::Deserialize;
+
+/// A `*.toml` file of examples in the test directory.
+#[derive(Debug, Deserialize)]
+struct RuleTestFile {
+    #[serde(default)]
+    rule_tests: Vec<RuleTest>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    /// The rule did the opposite of what the example expects
+    Fail(String),
+    /// The example could not run (unknown rule, rule turned off, ...)
+    Error(String),
+}
+
+#[derive(Debug)]
+pub struct TestResult {
+    pub name: String,
+    /// Where the example is defined
+    pub source: String,
+    pub outcome: Outcome,
+}
+
+/// Examples from `config` and, in file name order, from the `*.toml` files
+/// in `dir`, each with where it came from.
+pub fn collect(config: &Config, dir: Option<&Path>) -> Result<Vec<(String, RuleTest)>, Box<dyn std::error::Error>> {
+    let mut tests: Vec<(String, RuleTest)> = config.rule_tests.iter().map(|t| (".pr-analyzer.toml".to_string(), t.clone())).collect();
+    if let Some(dir) = dir {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?.filter_map(|e| e.ok().map(|e| e.path())).filter(|p| p.extension().is_some_and(|ext| ext == "toml")).collect();
+        files.sort();
+        for path in files {
+            let file: RuleTestFile = toml::from_str(&std::fs::read_to_string(&path)?).map_err(|e| format!("{}: {}", path.display(), e))?;
+            tests.extend(file.rule_tests.into_iter().map(|t| (path.display().to_string(), t)));
+        }
+    }
+    Ok(tests)
+}
+
+/// Run each example as a one-file change, with only its rule selected and
+/// no `min_severity` filtering.
+pub async fn run(config: &Config, tests: Vec<(String, RuleTest)>) -> Vec<TestResult> {
+    let mut results = Vec::with_capacity(tests.len());
+    for (source, test) in tests {
+        let name = test.name.clone().unwrap_or_else(|| format!("{} on {}", test.rule, test.file));
+        let outcome = check(config, &test).await;
+        results.push(TestResult { name, source, outcome });
+    }
+    results
+}
+
+async fn check(config: &Config, test: &RuleTest) -> Outcome {
+    let mut config = config.clone();
+    config.only.rule = Some(test.rule.clone());
+    // An example checks one rule in isolation and reports nothing, so the
+    // policy's required analyzers need not run with it.
+    config.policy.required_analyzers.clear();
+    config.thresholds.min_severity = RiskLevel::Low;
+    let pr = match snippet_pr(test) {
+        Ok(pr) => pr,
+        Err(e) => return Outcome::Error(e.to_string()),
+    };
+    let run = match analysis::run_all(&pr, &config, std::future::pending()).await {
+        Ok(run) => run,
+        Err(e) => return Outcome::Error(e.to_string()),
+    };
+    if let Some(rule) = run.rules.iter().find(|r| r.rule.eq_ignore_ascii_case(&test.rule) && r.status != RuleStatus::Executed) {
+        let status = format!("{:?}", rule.status).to_lowercase();
+        return Outcome::Error(format!("rule {}: {}", status, rule.reason.as_deref().unwrap_or("")));
+    }
+    let reported: Vec<String> = run
+        .results
+        .iter()
+        .flat_map(|r| &r.findings)
+        .filter(|f| test.pattern.as_ref().is_none_or(|p| f.params.get("pattern") == Some(p)))
+        .map(|f| f.line.map_or(f.message.clone(), |line| format!("line {}: {}", line, f.message)))
+        .collect();
+    match (test.matches, reported.is_empty()) {
+        (true, false) | (false, true) => Outcome::Pass,
+        (true, true) => Outcome::Fail("expected a match, got none".to_string()),
+        (false, false) => Outcome::Fail(format!("expected no match, got: {}", reported.join("; "))),
+    }
+}
+
+/// A change that adds `test.code` to `test.file`.
+fn snippet_pr(test: &RuleTest) -> Result<PullRequest, BuildError> {
+    let file = DiffFile::builder(&test.file).new_file().hunk(0, 1, test.code.lines().map(|l| format!("+{}", l))).build()?;
+    PullRequest::builder("rules test").file(file).build()
+}
+
+pub fn render(results: &[TestResult]) -> String {
+    let mut out = String::new();
+    for result in results {
+        let (tag, detail) = match &result.outcome {
+            Outcome::Pass => ("ok", None),
+            Outcome::Fail(detail) => ("FAIL", Some(detail)),
+            Outcome::Error(detail) => ("ERR", Some(detail)),
+        };
+        out.push_str(&format!("[{:<4}] {} ({})\n", tag, result.name, result.source));
+        if let Some(detail) = detail {
+            out.push_str(&format!("       {}\n", detail));
+        }
+    }
+    let passed = results.iter().filter(|r| r.outcome == Outcome::Pass).count();
+    out.push_str(&format!("{} passed, {} failed\n", passed, results.len() - passed));
+    out
+}
+
+pub fn has_failures(results: &[TestResult]) -> bool {
+    results.iter().any(|r| r.outcome != Outcome::Pass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(code: &str, matches: bool, pattern: Option<&str>) -> (String, RuleTest) {
+        let test = RuleTest {
+            name: None,
+            rule: "security.custom_patterns".to_string(),
+            file: "src/client.rs".to_string(),
+            code: code.to_string(),
+            matches,
+            pattern: pattern.map(str::to_string),
+        };
+        ("test".to_string(), test)
+    }
+
+    #[tokio::test]
+    async fn test_checks_expected_matches() {
+        let mut config = Config::default();
+        config.apply_profile(None).unwrap();
+        config.security.patterns = vec!["corp\\.internal".into(), "TODO".into()];
+        let results = run(
+            &config,
+            vec![
+                example("let url = \"http://corp.internal/api\";", true, Some("corp\\.internal")),
+                example("// see corp.internal", false, None),
+                example("let x = 1;", true, None),
+                example("let y = 2; // TODO", false, Some("corp\\.internal")),
+            ],
+        )
+        .await;
+        let outcomes: Vec<&Outcome> = results.iter().map(|r| &r.outcome).collect();
+        assert_eq!(outcomes[..2], [&Outcome::Pass, &Outcome::Pass]);
+        assert_eq!(outcomes[2], &Outcome::Fail("expected a match, got none".to_string()));
+        assert_eq!(outcomes[3], &Outcome::Pass);
+        let rendered = render(&results);
+        assert!(rendered.contains("[FAIL] security.custom_patterns on src/client.rs (test)\n       expected a match, got none\n"), "{rendered}");
+        assert!(rendered.ends_with("3 passed, 1 failed\n"));
+        assert!(has_failures(&results));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_or_disabled_rule_is_an_error() {
+        let mut config = Config::default();
+        config.apply_profile(None).unwrap();
+        let (source, mut unknown) = example("x", true, None);
+        unknown.rule = "security.nope".to_string();
+        let results = run(&config, vec![example("x", true, None), (source, unknown)]).await;
+        assert_eq!(results[0].outcome, Outcome::Error("rule skipped: no [security] patterns configured".to_string()));
+        assert!(matches!(&results[1].outcome, Outcome::Error(e) if e.starts_with("Unknown rule 'security.nope'")));
+    }
+}
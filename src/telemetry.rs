@@ -0,0 +1,104 @@
+//! Tracing setup: log lines on stderr (filtered by `RUST_LOG`), and with
+//! `--otel-endpoint` or the standard `OTEL_EXPORTER_OTLP_ENDPOINT` /
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` variables, the same spans exported
+//! to an OpenTelemetry collector over OTLP/HTTP.
+//!
+//! Export needs the `otel` cargo feature; without it an endpoint is an error
+//! rather than silently ignored.
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Flushes exported spans on `shutdown`. Spans still buffered when the
+/// process exits without it are lost.
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Telemetry {
+    /// Export any buffered spans and stop the exporter.
+    pub fn shutdown(self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("warning: could not flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global subscriber. `endpoint` is the collector's base URL
+/// (e.g. `http://localhost:4318`); traces go to `<endpoint>/v1/traces`.
+pub fn init(endpoint: Option<&str>) -> Result<Telemetry, Box<dyn std::error::Error>> {
+    let log = tracing_subscriber::fmt::layer().with_target(true).with_writer(std::io::stderr).with_filter(EnvFilter::from_default_env());
+    let from_env = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"].iter().any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()));
+    if endpoint.is_none() && !from_env {
+        tracing_subscriber::registry().with(log).init();
+        return Ok(Telemetry {
+            #[cfg(feature = "otel")]
+            provider: None,
+        });
+    }
+    export(log, endpoint)
+}
+
+#[cfg(feature = "otel")]
+fn export<L>(log: L, endpoint: Option<&str>) -> Result<Telemetry, Box<dyn std::error::Error>>
+where
+    L: Layer<tracing_subscriber::Registry> + Send + Sync,
+{
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use tracing_subscriber::filter::LevelFilter;
+
+    let mut exporter = SpanExporter::builder().with_http();
+    if let Some(base) = endpoint {
+        exporter = exporter.with_endpoint(traces_url(base));
+    }
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter.build()?)
+        .with_resource(Resource::builder().with_service_name("pr-analyzer").with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION"))).build())
+        .build();
+    // Spans are exported at INFO whatever RUST_LOG says about log lines.
+    let spans = tracing_opentelemetry::layer().with_tracer(provider.tracer("pr-analyzer")).with_filter(LevelFilter::INFO);
+    tracing_subscriber::registry().with(log).with(spans).init();
+    Ok(Telemetry { provider: Some(provider) })
+}
+
+#[cfg(not(feature = "otel"))]
+fn export<L>(log: L, _endpoint: Option<&str>) -> Result<Telemetry, Box<dyn std::error::Error>>
+where
+    L: Layer<tracing_subscriber::Registry> + Send + Sync,
+{
+    tracing_subscriber::registry().with(log).init();
+    Err("OpenTelemetry export needs a build with the `otel` feature (cargo install pr-analyzer --features otel)".into())
+}
+
+/// The OTLP/HTTP traces URL for a collector base URL, as the SDK derives it
+/// from `OTEL_EXPORTER_OTLP_ENDPOINT`.
+#[cfg(any(feature = "otel", test))]
+fn traces_url(base: &str) -> String {
+    let base = base.trim_end_matches('/');
+    if base.ends_with("/v1/traces") {
+        base.to_string()
+    } else {
+        format!("{}/v1/traces", base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(traces_url("http://localhost:4318"), "http://localhost:4318/v1/traces");
+        assert_eq!(traces_url("http://collector:4318/"), "http://collector:4318/v1/traces");
+        assert_eq!(traces_url("https://otel.example.com/v1/traces"), "https://otel.example.com/v1/traces");
+    }
+}